@@ -1,34 +1,35 @@
+use std::time::{Duration, Instant};
+
 use iced::widget::image;
 use iced::{
-    Border, Color, Element, Shadow, Task, Vector,
-    alignment::{Horizontal, Vertical},
+    Border, Color, Element, Shadow, Subscription, Task, Vector,
+    alignment::Horizontal,
     mouse::Cursor,
     widget::{button, column, container, text},
 };
 use slippery::{
-    Action, CacheMessage, Geodetic, GlobalElement, MapProgram, Mercator, Projector, TileCache,
-    Viewpoint, Zoom, location, sources::OpenStreetMap,
+    Action, CacheMessage, Geodetic, GlobalElement, MapProgram, Projector, TileCache, Viewpoint,
+    Zoom, location,
+    markers::{MarkerStore, MarkerStyle},
+    sources::OpenStreetMap,
 };
 
 fn main() {
     iced::application(StressTest::boot, StressTest::update, StressTest::view)
+        .subscription(StressTest::subscription)
         .title("Slippery - Diff Stress Test")
         .run()
         .unwrap();
 }
 
-struct PointData {
-    id: usize,
-    position: Mercator,
-    is_popup_open: bool,
-}
-
 struct StressTest {
     cache: TileCache,
     point_handle_red: image::Handle,
     point_handle_blue: image::Handle,
     viewpoint: Viewpoint,
-    points: Vec<PointData>,
+    /// The points are kept by the store, such that only the changed ones are touched
+    points: MarkerStore<usize, ()>,
+    open_popups: Vec<usize>,
     dragged_point: Option<usize>,
     drag_start: Option<iced::Point>,
 }
@@ -42,31 +43,30 @@ enum Message {
 
     // Interaction
     DragStart(usize, iced::Point),
-    DragMove(Mercator),
+    DragMove(Geodetic),
     DragEnd(iced::Point),
 
     // Popup controls
     ClosePopup(usize),
+    RemovePoint(usize),
+    Animate,
 }
 
 impl StressTest {
     fn boot() -> (Self, Task<Message>) {
         let center = location::paris();
-        let mut points = Vec::new();
+        let now = Instant::now();
+        let mut points = MarkerStore::new().transition(Duration::from_millis(250));
 
         // Create initial points around Paris
         let mut index = 0;
         for lat_offs in -500..=500 {
             for lon_offs in -500..=500 {
-                points.push(PointData {
-                    id: index,
-                    position: Geodetic::new(
-                        center.longitude() + lon_offs as f64 / 60.0,
-                        center.latitude() + lat_offs as f64 / 100.0,
-                    )
-                    .as_mercator(),
-                    is_popup_open: false,
-                });
+                let position = Geodetic::new(
+                    center.longitude() + lon_offs as f64 / 60.0,
+                    center.latitude() + lat_offs as f64 / 100.0,
+                );
+                points.insert(index, position, (), now);
                 index += 1;
             }
         }
@@ -81,6 +81,7 @@ impl StressTest {
                     zoom: Zoom::try_from(12.0).unwrap(),
                 },
                 points,
+                open_popups: Vec::new(),
                 dragged_point: None,
                 drag_start: None,
             },
@@ -102,9 +103,7 @@ impl StressTest {
             }
             Message::DragMove(geo) => {
                 if let Some(id) = self.dragged_point {
-                    if let Some(point) = self.points.iter_mut().find(|p| p.id == id) {
-                        point.position = geo;
-                    }
+                    self.points.set_position(&id, geo);
                 }
             }
             Message::DragEnd(point) => {
@@ -117,8 +116,10 @@ impl StressTest {
 
                         // If moved less than 5 pixels, treat as click to toggle popup
                         if dist < 5.0 {
-                            if let Some(p) = self.points.iter_mut().find(|p| p.id == id) {
-                                p.is_popup_open = !p.is_popup_open;
+                            if let Some(index) = self.open_popups.iter().position(|p| *p == id) {
+                                self.open_popups.remove(index);
+                            } else {
+                                self.open_popups.push(id);
                             }
                         }
                     }
@@ -127,45 +128,53 @@ impl StressTest {
                 }
             }
             Message::ClosePopup(id) => {
-                if let Some(p) = self.points.iter_mut().find(|p| p.id == id) {
-                    p.is_popup_open = false;
-                }
+                self.open_popups.retain(|p| *p != id);
+            }
+            Message::RemovePoint(id) => {
+                self.open_popups.retain(|p| *p != id);
+                self.points.remove(&id, Instant::now());
+            }
+            Message::Animate => {
+                self.points.prune(Instant::now());
             }
         }
 
         Task::none()
     }
 
+    fn subscription(&self) -> Subscription<Message> {
+        if self.points.is_animating(Instant::now()) {
+            iced::time::every(Duration::from_millis(16)).map(|_| Message::Animate)
+        } else {
+            Subscription::none()
+        }
+    }
+
     fn view(&self) -> Element<'_, Message> {
         let map = MapProgram::new(&self.cache)
             .on_cache(Message::Cache)
             .on_update(Message::Projector)
             .with_draw_layer({
-                let points: Vec<_> = self
-                    .points
+                let layer = self.points.layer(Instant::now()).style(MarkerStyle {
+                    icon: Some(self.point_handle_blue.clone()),
+                    radius: RADIUS,
+                    ..MarkerStyle::default()
+                });
+                let open: Vec<Geodetic> = self
+                    .open_popups
                     .iter()
-                    .map(|p| (p.position, p.is_popup_open, p.id))
+                    .filter_map(|id| self.points.get(id))
+                    .map(|(position, _)| position)
                     .collect();
-
                 let handle_red = self.point_handle_red.clone();
-                let handle_blue = self.point_handle_blue.clone();
 
                 move |projector, frame| {
-                    for (pos, is_open, _id) in &points {
-                        let screen_pos = projector.mercator_into_screen_space(*pos);
+                    layer.draw(projector, frame);
 
-                        // Skip adding the point to the frame if it is out of bounds
-                        if !projector.bounds.expand(RADIUS * 2.0).contains(screen_pos) {
-                            continue;
-                        }
-
-                        let handle = if *is_open {
-                            handle_red.clone()
-                        } else {
-                            handle_blue.clone()
-                        };
-
-                        let image = iced::widget::canvas::Image::new(handle);
+                    // Points with an open popup are drawn in red on top
+                    for position in &open {
+                        let screen_pos = projector.geodetic_into_screen_space(*position);
+                        let image = iced::widget::canvas::Image::new(handle_red.clone());
                         let bounds = iced::Rectangle::new(
                             screen_pos - Vector::new(RADIUS, RADIUS),
                             iced::Size::new(RADIUS * 2.0, RADIUS * 2.0),
@@ -175,7 +184,10 @@ impl StressTest {
                 }
             })
             .with_interaction({
-                let points: Vec<_> = self.points.iter().map(|p| (p.position, p.id)).collect();
+                let layer = self.points.layer(Instant::now()).style(MarkerStyle {
+                    radius: RADIUS * 2.0,
+                    ..MarkerStyle::default()
+                });
                 let dragged_point = self.dragged_point;
 
                 move |projector, cursor, event| {
@@ -190,18 +202,14 @@ impl StressTest {
 
                     match event {
                         Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
-                            // Find clicked point (reverse to pick top-most)
-                            for (pos, id) in points.iter().rev() {
-                                let screen_pos = projector.mercator_into_screen_space(*pos);
-                                if screen_pos.distance(cursor) < 10.0 {
-                                    return Action::Capture(Message::DragStart(*id, cursor));
-                                }
+                            if let Some(id) = layer.marker_at(projector, cursor) {
+                                return Action::Capture(Message::DragStart(*id, cursor));
                             }
                         }
                         Event::Mouse(mouse::Event::CursorMoved { .. }) => {
                             if dragged_point.is_some() {
-                                let mercator = projector.screen_space_into_mercator(cursor);
-                                return Action::Capture(Message::DragMove(mercator));
+                                let geodetic = projector.screen_space_into_geodetic(cursor);
+                                return Action::Capture(Message::DragMove(geodetic));
                             }
                         }
                         Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
@@ -215,22 +223,22 @@ impl StressTest {
                 }
             })
             .with_children(
-                self.points
+                self.open_popups
                     .iter()
-                    .filter(|p| p.is_popup_open)
-                    .map(|p| {
-                        GlobalElement::new(
+                    .filter_map(|id| self.points.get(id).map(|(position, _)| (*id, position)))
+                    .map(|(id, position)| {
+                        GlobalElement::popup(
                             container(
                                 column![
-                                    text(format!("Point #{}", p.id))
-                                        .font(iced::font::Font::MONOSPACE),
+                                    text(format!("Point #{id}")).font(iced::font::Font::MONOSPACE),
                                     text(format!(
                                         "{:.4}, {:.4}",
-                                        p.position.east_x(),
-                                        p.position.south_y()
+                                        position.longitude(),
+                                        position.latitude()
                                     )),
-                                    button("Close")
-                                        .on_press(Message::ClosePopup(p.id))
+                                    button("Close").on_press(Message::ClosePopup(id)).padding(2),
+                                    button("Remove")
+                                        .on_press(Message::RemovePoint(id))
                                         .padding(2)
                                 ]
                                 .align_x(Horizontal::Center)
@@ -246,9 +254,8 @@ impl StressTest {
                                     })
                                     .border(Border::default().rounded(5.0))
                             }),
-                            p.position,
+                            position.as_mercator(),
                         )
-                        .align(Horizontal::Left, Vertical::Top)
                     })
                     .collect::<Vec<_>>(),
             )
@@ -0,0 +1,122 @@
+use std::time::{Duration, Instant};
+
+use iced::widget::{container, stack, text};
+use iced::{Element, Length, Padding, Subscription, Task, alignment};
+use slippery::{
+    CacheMessage, MapProgram, Projector, TileCache, Viewpoint, Zoom,
+    feed::{FeedMessage, FeedStyle, GeoJsonFeed},
+    legend::{Legend, LegendMessage},
+    location,
+    sources::OpenStreetMap,
+};
+
+/// All earthquakes recorded by the USGS within the past day
+const FEED_URL: &str = "https://earthquake.usgs.gov/earthquakes/feed/v1.0/summary/all_day.geojson";
+
+fn main() {
+    env_logger::builder()
+        .filter_level(log::LevelFilter::Error)
+        .filter_module("slippery", log::LevelFilter::Debug)
+        .init();
+
+    iced::application(Application::boot, Application::update, Application::view)
+        .subscription(Application::subscription)
+        .title("Slippery - Earthquakes Example")
+        .run()
+        .unwrap();
+}
+
+#[derive(Debug, Clone)]
+enum Message {
+    Cache(CacheMessage),
+    Projector(Projector),
+    Feed(FeedMessage),
+    Legend(LegendMessage),
+    Animate,
+}
+
+struct Application {
+    cache: TileCache,
+    feed: GeoJsonFeed,
+    legend: Legend,
+    viewpoint: Viewpoint,
+}
+
+impl Application {
+    pub fn boot() -> (Self, Task<Message>) {
+        (
+            Application {
+                cache: TileCache::new(OpenStreetMap),
+                feed: GeoJsonFeed::new(FEED_URL, Duration::from_secs(60)),
+                legend: Legend::new("USGS").entry(FeedStyle::default().legend("Earthquake")),
+                viewpoint: Viewpoint {
+                    position: location::rome().as_mercator(),
+                    zoom: Zoom::try_from(2.0).unwrap(),
+                },
+            },
+            Task::done(Message::Feed(FeedMessage::Refresh)),
+        )
+    }
+
+    pub fn update(&mut self, message: Message) -> Task<Message> {
+        match message {
+            Message::Projector(projector) => {
+                self.viewpoint = projector.viewpoint;
+            }
+            Message::Cache(message) => {
+                return self.cache.update(message).map(Message::Cache);
+            }
+            Message::Feed(message) => {
+                return self.feed.update(message).map(Message::Feed);
+            }
+            Message::Legend(message) => self.legend.update(message),
+            Message::Animate => {}
+        }
+
+        Task::none()
+    }
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        if self.feed.is_animating(Instant::now()) {
+            iced::time::every(Duration::from_millis(16)).map(|_| Message::Animate)
+        } else {
+            Subscription::none()
+        }
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let layer = self.feed.layer();
+
+        let map = MapProgram::new(&self.cache)
+            .on_cache(Message::Cache)
+            .on_update(Message::Projector)
+            .with_draw_layer(move |projector, frame| layer.draw(projector, frame))
+            .with_children([self
+                .legend
+                .overlay(alignment::Horizontal::Left, alignment::Vertical::Bottom)
+                .map(Message::Legend)])
+            .build(self.viewpoint);
+
+        let status = match self.feed.last_updated() {
+            Some(updated) => format!(
+                "{} earthquakes, updated {} s ago",
+                self.feed.features().count(),
+                updated.elapsed().as_secs()
+            ),
+            None => "Loading earthquakes..".to_string(),
+        };
+
+        stack![
+            map,
+            container(
+                container(text(status))
+                    .padding(8)
+                    .style(container::rounded_box)
+            )
+            .padding(Padding::new(10.0))
+            .width(Length::Fill)
+            .align_x(alignment::Horizontal::Right)
+        ]
+        .into()
+    }
+}
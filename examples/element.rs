@@ -123,9 +123,9 @@ impl PopupExample {
                 } else {
                     return Action::None;
                 };
-                let screen_pos = projector.geodetic_into_screen_space(point_position);
-                let distance_to_point = screen_pos.distance(cursor);
-                let is_hovering = distance_to_point < 15.0; // slightly larger hit area
+                // Slightly larger hit area than the drawn point
+                let is_hovering =
+                    projector.is_within_radius(point_position.as_mercator(), cursor, 15.0);
 
                 match event {
                     Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
@@ -151,38 +151,35 @@ impl PopupExample {
                 Action::None
             })
             .with_children(if is_popup_open {
-                vec![
-                    GlobalElement::new(
-                        container(
-                            column![
-                                text("Movable Point")
-                                    .size(14)
-                                    .font(iced::font::Font::MONOSPACE),
-                                text(format!(
-                                    "{:.4}, {:.4}",
-                                    point_position.latitude(),
-                                    point_position.longitude()
-                                ))
-                                .size(12),
-                                button("Close").on_press(Message::ClosePopup).padding(5)
-                            ]
-                            .spacing(5)
-                            .align_x(alignment::Horizontal::Center),
-                        )
-                        .padding(10)
-                        .style(|theme| {
-                            container::rounded_box(theme)
-                                .shadow(Shadow {
-                                    color: Color::BLACK,
-                                    offset: Vector::new(0.0, 2.0),
-                                    blur_radius: 10.0,
-                                })
-                                .border(Border::default().rounded(10.0))
-                        }),
-                        point_position.as_mercator(),
+                vec![GlobalElement::popup(
+                    container(
+                        column![
+                            text("Movable Point")
+                                .size(14)
+                                .font(iced::font::Font::MONOSPACE),
+                            text(format!(
+                                "{:.4}, {:.4}",
+                                point_position.latitude(),
+                                point_position.longitude()
+                            ))
+                            .size(12),
+                            button("Close").on_press(Message::ClosePopup).padding(5)
+                        ]
+                        .spacing(5)
+                        .align_x(alignment::Horizontal::Center),
                     )
-                    .align(alignment::Horizontal::Left, alignment::Vertical::Top),
-                ]
+                    .padding(10)
+                    .style(|theme| {
+                        container::rounded_box(theme)
+                            .shadow(Shadow {
+                                color: Color::BLACK,
+                                offset: Vector::new(0.0, 2.0),
+                                blur_radius: 10.0,
+                            })
+                            .border(Border::default().rounded(10.0))
+                    }),
+                    point_position.as_mercator(),
+                )]
             } else {
                 vec![]
             })
@@ -0,0 +1,132 @@
+use iced::widget::{container, pick_list, row, stack, text};
+use iced::{Element, Length, Padding, Task, alignment, mouse, widget::canvas};
+use slippery::{
+    Action, CacheMessage, CoordinateFormat, Geodetic, MapProgram, Projector, TileCache, Viewpoint,
+    Zoom,
+    elevation::{ContourLayer, HillshadeLayer, Terrain, TerrainEncoding},
+    sources::{OpenStreetMap, Terrarium},
+};
+
+fn main() {
+    env_logger::builder()
+        .filter_level(log::LevelFilter::Error)
+        .filter_module("slippery", log::LevelFilter::Debug)
+        .init();
+
+    iced::application(Application::boot, Application::update, Application::view)
+        .title("Slippery - Elevation Example")
+        .run()
+        .unwrap();
+}
+
+#[derive(Debug, Clone)]
+enum Message {
+    Cache(CacheMessage),
+    Terrain(CacheMessage),
+    Projector(Projector),
+    CursorMoved(Geodetic),
+    Format(CoordinateFormat),
+}
+
+struct Application {
+    cache: TileCache,
+    terrain: Terrain,
+    viewpoint: Viewpoint,
+    cursor: Option<Geodetic>,
+    format: CoordinateFormat,
+}
+
+impl Application {
+    pub fn boot() -> (Self, Task<Message>) {
+        (
+            Application {
+                cache: TileCache::new(OpenStreetMap),
+                terrain: Terrain::new(Terrarium, TerrainEncoding::Terrarium, 12),
+                viewpoint: Viewpoint {
+                    // Start view centered on the Alps
+                    position: Geodetic::new(7.66, 45.98).as_mercator(),
+                    zoom: Zoom::try_from(11.0).unwrap(),
+                },
+                cursor: None,
+                format: CoordinateFormat::default(),
+            },
+            Task::none(),
+        )
+    }
+
+    pub fn update(&mut self, message: Message) -> Task<Message> {
+        match message {
+            Message::Projector(projector) => {
+                self.viewpoint = projector.viewpoint;
+
+                // The hillshade and contours are derived from the same elevation tiles
+                HillshadeLayer::new(&self.terrain)
+                    .request(&projector)
+                    .map(Message::Terrain)
+            }
+            Message::Cache(message) => self.cache.update(message).map(Message::Cache),
+            Message::Terrain(message) => self.terrain.update(message).map(Message::Terrain),
+            Message::CursorMoved(position) => {
+                self.cursor = Some(position);
+                self.terrain.request([position]).map(Message::Terrain)
+            }
+            Message::Format(format) => {
+                self.format = format;
+                Task::none()
+            }
+        }
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let hillshade = HillshadeLayer::new(&self.terrain).opacity(0.5);
+        let contours = ContourLayer::new(&self.terrain, 50.0);
+
+        let map = MapProgram::new(&self.cache)
+            .on_cache(Message::Cache)
+            .on_update(Message::Projector)
+            .with_draw_layer(move |projector, frame| {
+                hillshade.draw(projector, frame);
+                contours.draw(projector, frame);
+            })
+            .with_interaction(|projector, cursor, event| {
+                if let canvas::Event::Mouse(mouse::Event::CursorMoved { .. }) = event
+                    && let Some(position) = cursor.position()
+                {
+                    let position = projector.screen_space_into_geodetic(position);
+                    return Action::Publish(Message::CursorMoved(position));
+                }
+                Action::None
+            })
+            .build(self.viewpoint);
+
+        let readout = match self.cursor {
+            Some(position) => match self.terrain.elevation_at(position) {
+                Some(elevation) => {
+                    format!("{}: {elevation:.0} m", position.format(self.format))
+                }
+                None => "Loading elevation..".to_string(),
+            },
+            None => "Hover the map to show the elevation".to_string(),
+        };
+
+        stack![
+            map,
+            container(
+                container(
+                    row![
+                        text(readout),
+                        pick_list(CoordinateFormat::ALL, Some(self.format), Message::Format)
+                    ]
+                    .spacing(8)
+                    .align_y(alignment::Vertical::Center)
+                )
+                .padding(8)
+                .style(container::rounded_box)
+            )
+            .padding(Padding::new(10.0))
+            .width(Length::Fill)
+            .align_x(alignment::Horizontal::Right)
+        ]
+        .into()
+    }
+}
@@ -0,0 +1,125 @@
+use iced::widget::{button, container, stack, text};
+use iced::{Element, Length, Padding, Subscription, Task, alignment};
+use slippery::{
+    CacheMessage, MapProgram, Projector, TileCache, Viewpoint, Zoom,
+    gps::{self, Fix, Follow, GpsError, NmeaProvider, PositionLayer},
+    location,
+    sources::OpenStreetMap,
+};
+
+fn main() {
+    env_logger::builder()
+        .filter_level(log::LevelFilter::Error)
+        .filter_module("slippery", log::LevelFilter::Debug)
+        .init();
+
+    iced::application(Application::boot, Application::update, Application::view)
+        .subscription(Application::subscription)
+        .title("Slippery - GPS Example")
+        .run()
+        .unwrap();
+}
+
+#[derive(Debug, Clone)]
+enum Message {
+    Cache(CacheMessage),
+    Projector(Projector),
+    Position(Result<Fix, GpsError>),
+    Follow,
+}
+
+struct Application {
+    cache: TileCache,
+    provider: NmeaProvider,
+    fix: Option<Fix>,
+    status: String,
+    follow: Follow,
+    viewpoint: Viewpoint,
+}
+
+impl Application {
+    pub fn boot() -> (Self, Task<Message>) {
+        // Pass a serial device such as `/dev/ttyUSB0`, or the address of an NMEA TCP server
+        let provider = match std::env::args().nth(1) {
+            Some(device) if device.starts_with("/dev/") => NmeaProvider::Serial(device.into()),
+            Some(address) => NmeaProvider::Tcp(address),
+            None => NmeaProvider::Tcp("127.0.0.1:10110".to_string()),
+        };
+
+        (
+            Application {
+                cache: TileCache::new(OpenStreetMap),
+                provider,
+                fix: None,
+                status: "Waiting for a fix..".to_string(),
+                follow: Follow::new(),
+                viewpoint: Viewpoint {
+                    position: location::paris().as_mercator(),
+                    zoom: Zoom::try_from(15.0).unwrap(),
+                },
+            },
+            Task::none(),
+        )
+    }
+
+    pub fn update(&mut self, message: Message) -> Task<Message> {
+        match message {
+            Message::Projector(projector) => {
+                self.follow.update(&mut self.viewpoint, projector.viewpoint);
+            }
+            Message::Cache(message) => {
+                return self.cache.update(message).map(Message::Cache);
+            }
+            Message::Position(Ok(fix)) => {
+                self.follow.fix(&mut self.viewpoint, &fix);
+                self.status = match fix.accuracy {
+                    Some(accuracy) => format!("Accuracy: {accuracy:.0} m"),
+                    None => "Unknown accuracy".to_string(),
+                };
+                self.fix = Some(fix);
+            }
+            Message::Position(Err(error)) => {
+                self.status = error.to_string();
+            }
+            Message::Follow => {
+                self.follow.set_active(&mut self.viewpoint, true);
+            }
+        }
+
+        Task::none()
+    }
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        gps::subscription(self.provider.clone()).map(Message::Position)
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let position = PositionLayer::new(self.fix.as_ref());
+
+        let map = MapProgram::new(&self.cache)
+            .on_cache(Message::Cache)
+            .on_update(Message::Projector)
+            .with_draw_layer(move |projector, frame| position.draw(projector, frame))
+            .build(self.viewpoint);
+
+        let follow = button(text("Follow"))
+            .on_press_maybe((!self.follow.is_active()).then_some(Message::Follow));
+
+        stack![
+            map,
+            container(
+                container(
+                    iced::widget::row![text(&self.status), follow]
+                        .spacing(10)
+                        .align_y(alignment::Vertical::Center)
+                )
+                .padding(8)
+                .style(container::rounded_box)
+            )
+            .padding(Padding::new(10.0))
+            .width(Length::Fill)
+            .align_x(alignment::Horizontal::Right)
+        ]
+        .into()
+    }
+}
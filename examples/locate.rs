@@ -0,0 +1,114 @@
+use iced::widget::{column, container, text};
+use iced::{Element, Subscription, Task, Vector, alignment};
+use slippery::{
+    CacheMessage, GlobalElement, MapProgram, Projector, TileCache, Viewpoint, Zoom,
+    gps::{GpsdProvider, Locate, LocateMessage},
+    location,
+    sources::OpenStreetMap,
+};
+
+fn main() {
+    env_logger::builder()
+        .filter_level(log::LevelFilter::Error)
+        .filter_module("slippery", log::LevelFilter::Debug)
+        .init();
+
+    iced::application(Application::boot, Application::update, Application::view)
+        .subscription(Application::subscription)
+        .title("Slippery - Locate Example")
+        .run()
+        .unwrap();
+}
+
+#[derive(Debug, Clone)]
+enum Message {
+    Cache(CacheMessage),
+    Projector(Projector),
+    Locate(LocateMessage),
+}
+
+struct Application {
+    cache: TileCache,
+    locate: Locate<GpsdProvider>,
+    viewpoint: Viewpoint,
+}
+
+impl Application {
+    pub fn boot() -> (Self, Task<Message>) {
+        // Pass the address of a gpsd daemon, if it is not running locally
+        let provider = match std::env::args().nth(1) {
+            Some(address) => GpsdProvider::new(address),
+            None => GpsdProvider::default(),
+        };
+
+        (
+            Application {
+                cache: TileCache::new(OpenStreetMap),
+                locate: Locate::new(provider),
+                viewpoint: Viewpoint {
+                    position: location::paris().as_mercator(),
+                    zoom: Zoom::try_from(5.0).unwrap(),
+                },
+            },
+            Task::none(),
+        )
+    }
+
+    pub fn update(&mut self, message: Message) -> Task<Message> {
+        match message {
+            Message::Projector(projector) => {
+                self.viewpoint = projector.viewpoint;
+            }
+            Message::Cache(message) => {
+                return self.cache.update(message).map(Message::Cache);
+            }
+            Message::Locate(message) => {
+                return self
+                    .locate
+                    .update(&mut self.viewpoint, message)
+                    .map(Message::Locate);
+            }
+        }
+
+        Task::none()
+    }
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        self.locate.subscription().map(Message::Locate)
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let position = self.locate.layer();
+
+        let status = match (self.locate.error(), self.locate.fix()) {
+            (Some(error), _) => error.to_string(),
+            (None, Some(fix)) => match fix.accuracy {
+                Some(accuracy) => format!("Accuracy: {accuracy:.0} m"),
+                None => "Unknown accuracy".to_string(),
+            },
+            (None, None) => String::new(),
+        };
+
+        let controls = container(
+            column![self.locate.button().map(Message::Locate), text(status)]
+                .spacing(6)
+                .align_x(alignment::Horizontal::Right),
+        )
+        .padding(8)
+        .style(container::rounded_box);
+
+        // The controls stay in the top right corner of the map
+        MapProgram::new(&self.cache)
+            .on_cache(Message::Cache)
+            .on_update(Message::Projector)
+            .inertia(!self.locate.is_flying())
+            .with_draw_layer(move |projector, frame| position.draw(projector, frame))
+            .with_children([GlobalElement::screen(
+                controls,
+                alignment::Horizontal::Right,
+                alignment::Vertical::Top,
+            )
+            .offset(Vector::new(-10.0, 10.0))])
+            .build(self.viewpoint)
+    }
+}
@@ -0,0 +1,132 @@
+use iced::{Element, Task, mouse, widget::canvas};
+use slippery::{
+    Action, CacheMessage, MapProgram, Projector, TileCache, Viewpoint, Zoom, location,
+    markers::{MarkerIndex, MarkerStyle, Spiderfy},
+    sources::OpenStreetMap,
+};
+
+fn main() {
+    iced::application(Application::boot, Application::update, Application::view)
+        .title("Slippery - Markers Example")
+        .run()
+        .unwrap();
+}
+
+#[derive(Debug, Clone)]
+enum Message {
+    Cache(CacheMessage),
+    Projector(Projector),
+    Quality(bool),
+    Spiderfy(Option<Spiderfy>),
+    Selected(usize),
+}
+
+struct Application {
+    cache: TileCache,
+    markers: MarkerIndex<usize>,
+    viewpoint: Viewpoint,
+    reduced_quality: bool,
+    spider: Option<Spiderfy>,
+}
+
+impl Application {
+    pub fn boot() -> Self {
+        // Scatter a hundred thousand markers around Paris, using a simple hash as the
+        // source of randomness
+        let center = location::paris();
+        let markers = (0..100_000)
+            .map(|i| {
+                let hash = (i as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+                let bearing = (hash >> 32) as f64 / u32::MAX as f64 * 360.0;
+                let distance = (hash & 0xFFFF_FFFF) as f64 / u32::MAX as f64 * 50_000.0;
+                (center.destination(bearing, distance), i)
+            })
+            .collect::<MarkerIndex<usize>>();
+
+        Application {
+            cache: TileCache::new(OpenStreetMap),
+            markers,
+            viewpoint: Viewpoint {
+                position: location::paris().as_mercator(),
+                zoom: Zoom::try_from(10.0).unwrap(),
+            },
+            reduced_quality: false,
+            spider: None,
+        }
+    }
+
+    pub fn update(&mut self, message: Message) -> Task<Message> {
+        match message {
+            Message::Projector(projector) => {
+                self.viewpoint = projector.viewpoint;
+                Task::none()
+            }
+            Message::Quality(reduced) => {
+                self.reduced_quality = reduced;
+                Task::none()
+            }
+            Message::Spiderfy(spider) => {
+                self.spider = spider;
+                Task::none()
+            }
+            Message::Selected(index) => {
+                if let Some((position, id)) = self.markers.get(index) {
+                    println!("Selected marker {id} at {position:?}");
+                }
+                Task::none()
+            }
+            Message::Cache(message) => self.cache.update(message).map(Message::Cache),
+        }
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        // Draw fewer markers while the map can not keep up with the frame rate
+        let mut style = MarkerStyle::default();
+        if self.reduced_quality {
+            style.max_markers /= 4;
+        }
+        let markers = self
+            .markers
+            .layer()
+            .style(style)
+            .spiderfied(self.spider.as_ref());
+        let interact = markers.clone();
+
+        // Overlapping markers are fanned out when clicked, once the map can not be zoomed
+        // in any further
+        let max_zoom = self.cache.max_zoom() as f64;
+        let spider = self.spider.clone();
+
+        MapProgram::new(&self.cache)
+            .on_cache(Message::Cache)
+            .on_update(Message::Projector)
+            .on_quality(Message::Quality)
+            .with_draw_layer(move |projector, frame| markers.draw(projector, frame))
+            .with_interaction(move |projector, cursor, event| {
+                let canvas::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) = event
+                else {
+                    return Action::None;
+                };
+                let Some(cursor) = cursor.position() else {
+                    return Action::None;
+                };
+
+                match &spider {
+                    Some(spider) => match spider.hit(projector, cursor) {
+                        Some(index) => Action::Capture(Message::Selected(index)),
+                        None if !spider.contains(projector, cursor) => {
+                            Action::Publish(Message::Spiderfy(None))
+                        }
+                        None => Action::None,
+                    },
+                    None if projector.viewpoint.zoom.f64() >= max_zoom - 0.5 => interact
+                        .spiderfy(projector, cursor)
+                        .map_or(Action::None, |spider| {
+                            Action::Capture(Message::Spiderfy(Some(spider)))
+                        }),
+                    None => Action::None,
+                }
+            })
+            .build(self.viewpoint)
+    }
+}
@@ -1,54 +1,48 @@
-use iced::{self, Element, Task};
-use slippery::{
-    CacheMessage, MapWidget, Projector, TileCache, Viewpoint, Zoom, location,
-    sources::OpenStreetMap,
-};
+use iced::{self, Element, Subscription, Task};
+use slippery::{MapMessage, MapState, Viewpoint, Zoom, location, sources::OpenStreetMap};
 
 fn main() {
     iced::application(Application::boot, Application::update, Application::view)
         .title("Slippery minimal example")
+        .subscription(Application::subscription)
         .run()
         .unwrap();
 }
 
 struct Application {
-    cache: TileCache,
-    viewpoint: Viewpoint,
+    map: MapState,
 }
 
 #[derive(Debug, Clone)]
 enum Message {
-    Cache(CacheMessage),
-    Projector(Projector),
+    Map(MapMessage),
 }
 
 impl Application {
     pub fn boot() -> Self {
         Application {
-            cache: TileCache::new(OpenStreetMap),
-            viewpoint: Viewpoint {
-                position: location::paris().as_mercator(),
-                zoom: Zoom::try_from(12.0).unwrap(),
-            },
+            map: MapState::new(
+                OpenStreetMap,
+                Viewpoint {
+                    position: location::paris().as_mercator(),
+                    zoom: Zoom::try_from(12.0).unwrap(),
+                },
+            ),
         }
     }
 
     pub fn update(&mut self, message: Message) -> Task<Message> {
         match message {
-            // The updated projector contains the new viewpoint
-            Message::Projector(projector) => {
-                self.viewpoint = projector.viewpoint;
-            }
-            // Glue the cache update function into our application
-            Message::Cache(message) => {
-                return self.cache.update(message).map(Message::Cache);
-            }
+            // Glue the map update function into our application
+            Message::Map(message) => self.map.update(message).map(Message::Map),
         }
+    }
 
-        Task::none()
+    pub fn subscription(&self) -> Subscription<Message> {
+        self.map.subscription().map(Message::Map)
     }
 
     pub fn view(&self) -> impl Into<Element<'_, Message>> {
-        MapWidget::new(&self.cache, Message::Cache, self.viewpoint).on_update(Message::Projector)
+        self.map.view().map(Message::Map)
     }
 }
@@ -0,0 +1,104 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use iced::widget::{column, container};
+use iced::{Element, Length, Subscription, Task};
+use slippery::{
+    CacheMessage, MapProgram, Projector, TileCache, Viewpoint, Zoom, location,
+    sources::{OpenStreetMap, RainViewer},
+    timeline::{Timeline, TimelineMessage},
+};
+
+/// RainViewer publishes a radar frame every 10 minutes.
+const FRAME_INTERVAL: u64 = 600;
+
+fn main() {
+    env_logger::builder()
+        .filter_level(log::LevelFilter::Error)
+        .filter_module("slippery", log::LevelFilter::Debug)
+        .init();
+
+    iced::application(Application::boot, Application::update, Application::view)
+        .subscription(Application::subscription)
+        .title("Slippery - Radar Example")
+        .run()
+        .unwrap();
+}
+
+#[derive(Debug, Clone)]
+enum Message {
+    Cache(CacheMessage),
+    Projector(Projector),
+    Timeline(TimelineMessage),
+}
+
+struct Application {
+    cache: TileCache,
+    timeline: Timeline,
+    viewpoint: Viewpoint,
+}
+
+impl Application {
+    pub fn boot() -> (Self, Task<Message>) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        // The last two hours of radar, skipping the newest frame which may still be processing
+        let latest = now / FRAME_INTERVAL * FRAME_INTERVAL - FRAME_INTERVAL;
+        let frames = (0..12).rev().map(|i| {
+            let timestamp = latest - i * FRAME_INTERVAL;
+            let label = format!(
+                "{:02}:{:02} UTC",
+                timestamp % 86400 / 3600,
+                timestamp % 3600 / 60
+            );
+            (label, RainViewer::new(timestamp))
+        });
+
+        (
+            Application {
+                cache: TileCache::new(OpenStreetMap),
+                timeline: Timeline::new(frames),
+                viewpoint: Viewpoint {
+                    position: location::paris().as_mercator(),
+                    zoom: Zoom::try_from(5.0).unwrap(),
+                },
+            },
+            Task::done(Message::Timeline(TimelineMessage::Play)),
+        )
+    }
+
+    pub fn update(&mut self, message: Message) -> Task<Message> {
+        match message {
+            Message::Projector(projector) => {
+                self.viewpoint = projector.viewpoint;
+                self.timeline.request(projector).map(Message::Timeline)
+            }
+            Message::Cache(message) => self.cache.update(message).map(Message::Cache),
+            Message::Timeline(message) => self.timeline.update(message).map(Message::Timeline),
+        }
+    }
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        self.timeline.subscription().map(Message::Timeline)
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let radar = self.timeline.layer();
+
+        let map = MapProgram::new(&self.cache)
+            .on_cache(Message::Cache)
+            .on_update(Message::Projector)
+            .with_draw_layer(move |projector, frame| radar.draw(projector, frame))
+            .build(self.viewpoint);
+
+        column![
+            map,
+            container(self.timeline.controls().map(Message::Timeline))
+                .padding(10)
+                .width(Length::Fill)
+        ]
+        .into()
+    }
+}
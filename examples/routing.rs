@@ -0,0 +1,131 @@
+use iced::widget::{container, text};
+use iced::{
+    Element, Length, Padding, Subscription, Task, alignment, mouse, widget::canvas, widget::stack,
+};
+use slippery::{
+    Action, CacheMessage, MapProgram, Projector, TileCache, Viewpoint, Zoom, location,
+    routing::{Router, RoutingMessage, RoutingService},
+    sources::OpenStreetMap,
+};
+
+fn main() {
+    env_logger::builder()
+        .filter_level(log::LevelFilter::Error)
+        .filter_module("slippery", log::LevelFilter::Debug)
+        .init();
+
+    iced::application(Application::boot, Application::update, Application::view)
+        .title("Slippery - Routing Example")
+        .subscription(Application::subscription)
+        .run()
+        .unwrap();
+}
+
+#[derive(Debug, Clone)]
+enum Message {
+    Cache(CacheMessage),
+    Projector(Projector),
+    Routing(RoutingMessage),
+}
+
+struct Application {
+    cache: TileCache,
+    router: Router,
+    viewpoint: Viewpoint,
+}
+
+impl Application {
+    pub fn boot() -> (Self, Task<Message>) {
+        let mut router =
+            Router::new(RoutingService::osrm_demo()).animate(std::time::Duration::from_secs(2));
+
+        // Start out with a route between two points in Paris
+        let task = Task::batch([
+            router.update(RoutingMessage::AddWaypoint(location::paris())),
+            router.update(RoutingMessage::AddWaypoint(slippery::Geodetic::new(
+                2.295, 48.874,
+            ))),
+        ]);
+
+        (
+            Application {
+                cache: TileCache::new(OpenStreetMap),
+                router,
+                viewpoint: Viewpoint {
+                    position: location::paris().as_mercator(),
+                    zoom: Zoom::try_from(13.0).unwrap(),
+                },
+            },
+            task.map(Message::Routing),
+        )
+    }
+
+    pub fn update(&mut self, message: Message) -> Task<Message> {
+        match message {
+            Message::Projector(projector) => {
+                self.viewpoint = projector.viewpoint;
+            }
+            Message::Cache(message) => {
+                return self.cache.update(message).map(Message::Cache);
+            }
+            Message::Routing(message) => {
+                return self.router.update(message).map(Message::Routing);
+            }
+        }
+
+        Task::none()
+    }
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        self.router.subscription().map(Message::Routing)
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let draw_layer = self.router.layer();
+        let interact_layer = self.router.layer();
+
+        let map = MapProgram::new(&self.cache)
+            .on_cache(Message::Cache)
+            .on_update(Message::Projector)
+            .with_draw_layer(move |projector, frame| draw_layer.draw(projector, frame))
+            .with_interaction(move |projector, cursor, event| {
+                // Right click adds a new waypoint at the cursor
+                if let canvas::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Right)) =
+                    event
+                    && let Some(position) = cursor.position()
+                {
+                    let position = projector.screen_space_into_geodetic(position);
+                    return Action::Capture(Message::Routing(RoutingMessage::AddWaypoint(
+                        position,
+                    )));
+                }
+
+                interact_layer
+                    .interact(projector, cursor, event)
+                    .map(Message::Routing)
+            })
+            .build(self.viewpoint);
+
+        let summary = match self.router.route() {
+            Some(route) => format!(
+                "{:.1} km, {} min",
+                route.distance / 1000.0,
+                route.duration.as_secs() / 60
+            ),
+            None => "Right click to add waypoints".to_string(),
+        };
+
+        stack![
+            map,
+            container(
+                container(text(summary))
+                    .padding(8)
+                    .style(container::rounded_box)
+            )
+            .padding(Padding::new(10.0))
+            .width(Length::Fill)
+            .align_x(alignment::Horizontal::Right)
+        ]
+        .into()
+    }
+}
@@ -22,7 +22,6 @@ fn main() {
 enum Message {
     Cache(CacheMessage),
     MapProjector(Projector),
-    HoverVertex(Option<usize>),
     StartDrag(usize),
     EndDrag,
     MoveVertex(Geodetic),
@@ -32,7 +31,6 @@ struct Application {
     cache: TileCache,
     viewpoint: Viewpoint,
     vertices: Vec<Geodetic>,
-    hovered_vertex: Option<usize>,
     dragging_vertex: Option<usize>,
 }
 
@@ -51,7 +49,6 @@ impl Application {
                 },
                 // Initial triangle vertices
                 vertices: vec![PARIS, LONDON, BRUSSELS],
-                hovered_vertex: None,
                 dragging_vertex: None,
             },
             Task::done(Message::Cache(CacheMessage::Load {
@@ -68,12 +65,8 @@ impl Application {
             Message::Cache(message) => {
                 return self.cache.update(message).map(Message::Cache);
             }
-            Message::HoverVertex(index) => {
-                self.hovered_vertex = index;
-            }
             Message::StartDrag(index) => {
                 self.dragging_vertex = Some(index);
-                self.hovered_vertex = None;
             }
             Message::EndDrag => {
                 self.dragging_vertex = None;
@@ -90,10 +83,10 @@ impl Application {
 
     pub fn view(&self) -> impl Into<Element<'_, Message>> {
         let vertices = self.vertices.clone();
-        let hovered_vertex = self.hovered_vertex;
         let dragging_vertex = self.dragging_vertex;
 
         let vertices_interact = vertices.clone();
+        let vertices_handles = vertices.clone();
 
         MapProgram::new(&self.cache)
             .on_cache(Message::Cache)
@@ -120,85 +113,88 @@ impl Application {
                             .with_color(Color::from_rgb(0.0, 0.8, 0.0)),
                     );
                 }
-
-                // 2. Draw Points (Interactive Handles)
-                for (i, vertex) in vertices.iter().enumerate() {
-                    let pos = projector.geodetic_into_screen_space(*vertex);
-
-                    let (radius, color) = if dragging_vertex == Some(i) {
-                        (10.0, Color::from_rgb(1.0, 0.0, 0.0)) // Red when dragging
-                    } else if hovered_vertex == Some(i) {
-                        (8.0, Color::from_rgb(1.0, 0.5, 0.0)) // Orange when hovered
-                    } else {
-                        (5.0, Color::from_rgb(0.0, 0.0, 1.0)) // Blue normally
-                    };
-
-                    let circle = Path::circle(pos, radius);
-                    frame.fill(&circle, color);
-                    frame.stroke(
-                        &circle,
-                        Stroke::default().with_color(Color::WHITE).with_width(1.5),
-                    );
-                }
             })
-            .with_interaction(move |projector, cursor, event| {
-                use slippery::Action;
-                match event {
-                    canvas::Event::Mouse(mouse_event) => {
-                        match mouse_event {
-                            mouse::Event::CursorMoved { .. } => {
-                                let cursor_pos = if let Cursor::Available(p) = cursor {
-                                    *p
-                                } else {
-                                    return Action::None;
-                                };
-
-                                // Handling Dragging
-                                if let Some(_) = dragging_vertex {
-                                    // Project cursor back to Geodetic to move vertex
-                                    let mercator = projector.screen_space_into_mercator(cursor_pos);
-                                    let geo = mercator.as_geodetic();
-                                    return Action::Capture(Message::MoveVertex(geo));
-                                }
-
-                                // Handling Hover
-                                // Check if cursor is over any vertex
-                                for (i, vertex) in vertices_interact.iter().enumerate() {
-                                    let screen_pos = projector.geodetic_into_screen_space(*vertex);
-                                    if screen_pos.distance(cursor_pos) < 10.0 {
-                                        if hovered_vertex != Some(i) {
-                                            return Action::Publish(Message::HoverVertex(Some(i)));
-                                        }
-                                        return Action::None; // Already hovered, don't spam messages
+            // The hovered vertex is kept by the map, so hovering needs no messages
+            .with_interaction_state(
+                move |hovered: &mut Option<usize>, projector, cursor, event| {
+                    use slippery::Action;
+                    match event {
+                        canvas::Event::Mouse(mouse_event) => {
+                            match mouse_event {
+                                mouse::Event::CursorMoved { .. } => {
+                                    let cursor_pos = if let Cursor::Available(p) = cursor {
+                                        *p
+                                    } else {
+                                        *hovered = None;
+                                        return Action::None;
+                                    };
+
+                                    // Handling Dragging
+                                    if dragging_vertex.is_some() {
+                                        // Project cursor back to Geodetic to move vertex
+                                        let mercator =
+                                            projector.screen_space_into_mercator(cursor_pos);
+                                        let geo = mercator.as_geodetic();
+                                        return Action::Capture(Message::MoveVertex(geo));
                                     }
-                                }
 
-                                // If we were hovering, but now aren't
-                                if hovered_vertex.is_some() {
-                                    return Action::Publish(Message::HoverVertex(None));
+                                    // Handling Hover
+                                    // Check if cursor is over any vertex
+                                    *hovered = vertices_interact.iter().position(|vertex| {
+                                        projector.is_within_radius(
+                                            vertex.as_mercator(),
+                                            cursor_pos,
+                                            10.0,
+                                        )
+                                    });
+
+                                    // Hint that the vertex can be dragged
+                                    Action::SetCursor(match hovered {
+                                        Some(_) => mouse::Interaction::Grab,
+                                        None => mouse::Interaction::None,
+                                    })
                                 }
-
-                                Action::None
-                            }
-                            mouse::Event::ButtonPressed(mouse::Button::Left) => {
-                                // If hovering over a vertex, start dragging
-                                if let Some(idx) = hovered_vertex {
-                                    return Action::Capture(Message::StartDrag(idx));
+                                mouse::Event::ButtonPressed(mouse::Button::Left) => {
+                                    // If hovering over a vertex, start dragging
+                                    if let Some(idx) = hovered.take() {
+                                        return Action::Capture(Message::StartDrag(idx));
+                                    }
+                                    Action::None
                                 }
-                                Action::None
-                            }
-                            mouse::Event::ButtonReleased(mouse::Button::Left) => {
-                                if dragging_vertex.is_some() {
-                                    return Action::Capture(Message::EndDrag);
+                                mouse::Event::ButtonReleased(mouse::Button::Left) => {
+                                    if dragging_vertex.is_some() {
+                                        return Action::Capture(Message::EndDrag);
+                                    }
+                                    Action::None
                                 }
-                                Action::None
+                                _ => Action::None,
                             }
-                            _ => Action::None,
                         }
+                        _ => Action::None,
                     }
-                    _ => Action::None,
-                }
-            })
+                },
+                // Draw Points (Interactive Handles)
+                move |hovered, projector, frame| {
+                    for (i, vertex) in vertices_handles.iter().enumerate() {
+                        let pos = projector.geodetic_into_screen_space(*vertex);
+
+                        let (radius, color) = if dragging_vertex == Some(i) {
+                            (10.0, Color::from_rgb(1.0, 0.0, 0.0)) // Red when dragging
+                        } else if *hovered == Some(i) {
+                            (8.0, Color::from_rgb(1.0, 0.5, 0.0)) // Orange when hovered
+                        } else {
+                            (5.0, Color::from_rgb(0.0, 0.0, 1.0)) // Blue normally
+                        };
+
+                        let circle = Path::circle(pos, radius);
+                        frame.fill(&circle, color);
+                        frame.stroke(
+                            &circle,
+                            Stroke::default().with_color(Color::WHITE).with_width(1.5),
+                        );
+                    }
+                },
+            )
             .build(self.viewpoint)
     }
 }
@@ -0,0 +1,107 @@
+use std::time::{Duration, Instant};
+
+use iced::{Element, Subscription, Task};
+use slippery::{
+    CacheMessage, MapProgram, Projector, TileCache, Viewpoint, Zoom, location,
+    sources::OpenStreetMap,
+    vehicles::{VehicleFeed, VehicleReport},
+};
+
+fn main() {
+    env_logger::builder()
+        .filter_level(log::LevelFilter::Error)
+        .filter_module("slippery", log::LevelFilter::Debug)
+        .init();
+
+    iced::application(Application::boot, Application::update, Application::view)
+        .subscription(Application::subscription)
+        .title("Slippery - Vehicles Example")
+        .run()
+        .unwrap();
+}
+
+#[derive(Debug, Clone)]
+enum Message {
+    Cache(CacheMessage),
+    Projector(Projector),
+    /// Simulates receiving a batch of position reports from a live feed
+    Reports(Instant),
+    /// Redraws the map, such that the vehicles move smoothly between reports
+    Animate,
+}
+
+struct Application {
+    cache: TileCache,
+    feed: VehicleFeed<usize>,
+    viewpoint: Viewpoint,
+    started: Instant,
+}
+
+impl Application {
+    pub fn boot() -> (Self, Task<Message>) {
+        (
+            Application {
+                cache: TileCache::new(OpenStreetMap),
+                feed: VehicleFeed::new(),
+                viewpoint: Viewpoint {
+                    position: location::paris().as_mercator(),
+                    zoom: Zoom::try_from(13.0).unwrap(),
+                },
+                started: Instant::now(),
+            },
+            Task::done(Message::Reports(Instant::now())),
+        )
+    }
+
+    pub fn update(&mut self, message: Message) -> Task<Message> {
+        match message {
+            Message::Projector(projector) => {
+                self.viewpoint = projector.viewpoint;
+            }
+            Message::Cache(message) => {
+                return self.cache.update(message).map(Message::Cache);
+            }
+            Message::Reports(now) => {
+                // Buses driving in circles of different sizes around the center of Paris
+                let time = now.duration_since(self.started).as_secs_f64();
+                for id in 0..8 {
+                    let radius = 500.0 + id as f64 * 250.0;
+                    let speed = 10.0;
+                    let angle = (time * speed / radius + id as f64).to_degrees();
+
+                    self.feed.report(
+                        id,
+                        VehicleReport {
+                            position: location::paris().destination(angle, radius),
+                            heading: angle + 90.0,
+                            speed,
+                            label: Some(format!("Bus {}", id + 1)),
+                        },
+                        now,
+                    );
+                }
+                self.feed.prune(now);
+            }
+            Message::Animate => {}
+        }
+
+        Task::none()
+    }
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        Subscription::batch([
+            iced::time::every(Duration::from_secs(3)).map(Message::Reports),
+            iced::time::every(Duration::from_millis(30)).map(|_| Message::Animate),
+        ])
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let vehicles = self.feed.layer();
+
+        MapProgram::new(&self.cache)
+            .on_cache(Message::Cache)
+            .on_update(Message::Projector)
+            .with_draw_layer(move |projector, frame| vehicles.draw(projector, frame))
+            .build(self.viewpoint)
+    }
+}
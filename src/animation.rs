@@ -0,0 +1,312 @@
+//! Animations of map layers and the camera, driven by the redraw loop of the window.
+//!
+//! An animation is started at some point in time, and its progress is sampled whenever a
+//! frame is drawn, typically from the [`iced::window::frames`] subscription while it runs.
+
+use std::time::Duration;
+
+use iced::Point;
+use iced::time::Instant;
+
+use crate::{Mercator, Viewpoint, Zoom};
+
+/// How the progress of an animation accelerates and decelerates over its duration.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    #[default]
+    EaseInOut,
+}
+
+impl Easing {
+    /// Map the linear progress `t` in `0.0..=1.0` onto the eased progress.
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Easing::EaseInOut if t < 0.5 => 4.0 * t * t * t,
+            Easing::EaseInOut => 1.0 - (-2.0 * t + 2.0).powi(3) / 2.0,
+        }
+    }
+}
+
+/// Progressively reveals a path from its start to its end, such as a route being "drawn"
+/// onto the map.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Reveal {
+    started: Instant,
+    duration: Duration,
+    easing: Easing,
+}
+
+impl Reveal {
+    /// Start revealing now, over the given duration.
+    pub fn new(duration: Duration) -> Self {
+        Self::starting_at(Instant::now(), duration)
+    }
+
+    pub fn starting_at(started: Instant, duration: Duration) -> Self {
+        Self {
+            started,
+            duration,
+            easing: Easing::default(),
+        }
+    }
+
+    pub fn easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// The fraction of the path which is revealed at some point in time.
+    pub fn progress(&self, now: Instant) -> f32 {
+        if self.duration.is_zero() {
+            return 1.0;
+        }
+
+        let elapsed = now.saturating_duration_since(self.started);
+        self.easing
+            .apply(elapsed.as_secs_f32() / self.duration.as_secs_f32())
+    }
+
+    pub fn is_finished(&self, now: Instant) -> bool {
+        now.saturating_duration_since(self.started) >= self.duration
+    }
+}
+
+/// How much a [`Flight`] zooms out in relation to how far it travels, where larger values
+/// zoom out further. This is the value suggested by van Wijk and Nuij.
+const CURVATURE: f64 = 1.42;
+
+/// The width in pixels of the viewport a [`Flight`] is planned for, which only decides how
+/// far it zooms out on its way.
+const FLIGHT_VIEWPORT: f64 = 1024.0;
+
+/// Moves the camera between two viewpoints by zooming out, panning and zooming back in,
+/// following the [smooth and efficient zooming and panning](https://www.win.tue.nl/~vanwijk/zoompan.pdf)
+/// of van Wijk and Nuij. The viewpoint is sampled for each frame, and assigned to the map.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Flight {
+    from: Viewpoint,
+    to: Viewpoint,
+    started: Instant,
+    duration: Duration,
+    easing: Easing,
+}
+
+impl Flight {
+    /// Start flying now, over the given duration.
+    pub fn new(from: Viewpoint, to: Viewpoint, duration: Duration) -> Self {
+        Self::starting_at(Instant::now(), from, to, duration)
+    }
+
+    pub fn starting_at(
+        started: Instant,
+        from: Viewpoint,
+        to: Viewpoint,
+        duration: Duration,
+    ) -> Self {
+        Self {
+            from,
+            to,
+            started,
+            duration,
+            easing: Easing::default(),
+        }
+    }
+
+    pub fn easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// The viewpoint where the flight ends.
+    pub fn destination(&self) -> Viewpoint {
+        self.to
+    }
+
+    pub fn is_finished(&self, now: Instant) -> bool {
+        now.saturating_duration_since(self.started) >= self.duration
+    }
+
+    /// The viewpoint of the camera at some point in time.
+    pub fn viewpoint(&self, now: Instant) -> Viewpoint {
+        if self.is_finished(now) {
+            return self.to;
+        }
+
+        let elapsed = now.saturating_duration_since(self.started);
+        let t = self
+            .easing
+            .apply(elapsed.as_secs_f32() / self.duration.as_secs_f32()) as f64;
+
+        // Work in the pixel space of the starting zoom level, where the viewport is `w0` wide
+        let zoom = self.from.zoom.f64();
+        let start = self.from.position.into_pixel_space(zoom);
+        let end = self.to.position.into_pixel_space(zoom);
+        let distance = (end.x - start.x).hypot(end.y - start.y);
+
+        let w0 = FLIGHT_VIEWPORT;
+        let w1 = w0 * 2f64.powf(zoom - self.to.zoom.f64());
+        let rho2 = CURVATURE * CURVATURE;
+
+        // Without any distance to travel, only zoom
+        if distance < 1e-6 {
+            let zoom = zoom + (self.to.zoom.f64() - zoom) * t;
+            return Viewpoint {
+                position: self.to.position,
+                zoom: Zoom::try_from(zoom).unwrap_or(self.to.zoom),
+            };
+        }
+
+        let r = |end: bool| {
+            let (sign, w) = if end { (-1.0, w1) } else { (1.0, w0) };
+            let b = (w1 * w1 - w0 * w0 + sign * rho2 * rho2 * distance * distance)
+                / (2.0 * w * rho2 * distance);
+            (b * b + 1.0).sqrt() - b
+        };
+        let r0 = r(false).max(f64::MIN_POSITIVE).ln();
+        let r1 = r(true).max(f64::MIN_POSITIVE).ln();
+
+        let s = t * (r1 - r0) / CURVATURE;
+        let width = w0 * r0.cosh() / (r0 + CURVATURE * s).cosh();
+        let traveled = w0 * (r0.cosh() * (r0 + CURVATURE * s).tanh() - r0.sinh()) / rho2;
+
+        let fraction = traveled / distance;
+        let position = Mercator::from_pixel_space(
+            Point::new(
+                start.x + (end.x - start.x) * fraction,
+                start.y + (end.y - start.y) * fraction,
+            ),
+            zoom,
+        );
+        let zoom = (zoom + (w0 / width).log2()).clamp(Zoom::MIN.f64(), Zoom::MAX.f64());
+
+        Viewpoint {
+            position,
+            zoom: Zoom::try_from(zoom).unwrap_or(self.to.zoom),
+        }
+    }
+}
+
+/// The leading part of a polyline, covering a fraction of its length. The last point is
+/// interpolated along the segment where the fraction ends.
+pub fn partial_polyline(points: &[Point], fraction: f32) -> Vec<Point> {
+    if fraction >= 1.0 {
+        return points.to_vec();
+    }
+
+    let length: f32 = points.windows(2).map(|w| w[0].distance(w[1])).sum();
+    let mut remaining = length * fraction.max(0.0);
+
+    let mut partial = Vec::with_capacity(points.len());
+    partial.extend(points.first());
+
+    for segment in points.windows(2) {
+        let (a, b) = (segment[0], segment[1]);
+        let distance = a.distance(b);
+
+        if distance >= remaining {
+            let t = if distance > 0.0 {
+                remaining / distance
+            } else {
+                0.0
+            };
+            partial.push(a + (b - a) * t);
+            break;
+        }
+
+        remaining -= distance;
+        partial.push(b);
+    }
+
+    partial
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn easing_endpoints() {
+        for easing in [
+            Easing::Linear,
+            Easing::EaseIn,
+            Easing::EaseOut,
+            Easing::EaseInOut,
+        ] {
+            assert_eq!(easing.apply(0.0), 0.0);
+            assert_eq!(easing.apply(1.0), 1.0);
+            assert_eq!(easing.apply(2.0), 1.0);
+        }
+        assert_eq!(Easing::EaseInOut.apply(0.5), 0.5);
+    }
+
+    #[test]
+    fn partial_polyline_interpolates() {
+        let points = [
+            Point::new(0.0, 0.0),
+            Point::new(10.0, 0.0),
+            Point::new(10.0, 10.0),
+        ];
+
+        assert_eq!(partial_polyline(&points, 1.0), points);
+        assert_eq!(
+            partial_polyline(&points, 0.75),
+            [
+                Point::new(0.0, 0.0),
+                Point::new(10.0, 0.0),
+                Point::new(10.0, 5.0)
+            ]
+        );
+        assert_eq!(
+            partial_polyline(&points, 0.25),
+            [Point::new(0.0, 0.0), Point::new(5.0, 0.0)]
+        );
+    }
+
+    #[test]
+    fn reveal_progress() {
+        let start = Instant::now();
+        let reveal = Reveal::starting_at(start, Duration::from_secs(2)).easing(Easing::Linear);
+
+        assert_eq!(reveal.progress(start), 0.0);
+        assert_eq!(reveal.progress(start + Duration::from_secs(1)), 0.5);
+        assert!(reveal.is_finished(start + Duration::from_secs(3)));
+    }
+
+    #[test]
+    fn flight_zooms_out_on_the_way() {
+        let viewpoint = |position: crate::Geodetic, zoom: f64| Viewpoint {
+            position: position.as_mercator(),
+            zoom: Zoom::try_from(zoom).unwrap(),
+        };
+        let from = viewpoint(crate::location::paris(), 12.0);
+        let to = viewpoint(crate::location::rome(), 14.0);
+
+        let start = Instant::now();
+        let flight = Flight::starting_at(start, from, to, Duration::from_secs(2));
+
+        let departed = flight.viewpoint(start);
+        approx::assert_relative_eq!(departed.zoom.f64(), 12.0, epsilon = 1e-6);
+        approx::assert_relative_eq!(
+            departed
+                .position
+                .as_geodetic()
+                .distance_to(crate::location::paris()),
+            0.0,
+            epsilon = 1e-3
+        );
+
+        let halfway = flight.viewpoint(start + Duration::from_secs(1));
+        assert!(halfway.zoom.f64() < 10.0);
+
+        let arrived = flight.viewpoint(start + Duration::from_secs(2));
+        assert_eq!(arrived, to);
+        assert!(flight.is_finished(start + Duration::from_secs(2)));
+    }
+}
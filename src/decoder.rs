@@ -0,0 +1,288 @@
+//! Decoding of tile images on a bounded pool of worker threads.
+//!
+//! Without this, tiles are decoded by the renderer when they are allocated, which happens
+//! on the main thread. When dozens of tiles arrive at once, the pending tiles are decoded
+//! in order of their distance to the focus of the map, such that the tiles the user is
+//! looking at appear first.
+
+use std::{
+    sync::{Arc, Condvar, Mutex, OnceLock},
+    thread,
+};
+
+use iced::futures::channel::oneshot;
+use iced_core::{Bytes, image::Handle};
+
+use crate::{Mercator, tile_coord::TileCoord};
+
+/// Changes the pixels of each tile once it is decoded, set with
+/// [`crate::TileCacheBuilder::processor`].
+#[derive(Clone)]
+pub(crate) struct Processor(pub Arc<ProcessFn>);
+
+type ProcessFn = dyn Fn(TileCoord, &mut image::RgbaImage) + Send + Sync;
+
+impl core::fmt::Debug for Processor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Processor..")
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub(crate) enum DecodeError {
+    #[error(transparent)]
+    Image(#[from] image::ImageError),
+    #[error("The decoder was shut down")]
+    Closed,
+}
+
+struct Job {
+    id: TileCoord,
+    bytes: Bytes,
+    /// The pixels around the edges to crop.
+    border: u32,
+    processor: Option<Processor>,
+    sender: oneshot::Sender<Result<Handle, DecodeError>>,
+}
+
+struct Queue {
+    jobs: Vec<Job>,
+    focus: Mercator,
+    closed: bool,
+}
+
+impl Default for Queue {
+    fn default() -> Self {
+        Self {
+            jobs: Vec::new(),
+            focus: Mercator::new(0.0, 0.0),
+            closed: false,
+        }
+    }
+}
+
+impl Queue {
+    /// Take the job of the tile closest to the focus.
+    fn pop(&mut self) -> Option<Job> {
+        let distance = |id: &TileCoord| {
+            let corner = id.to_mercator();
+            let half = 1.0 / 2u32.pow(id.zoom() as u32) as f64;
+            let x = corner.east_x() + half - self.focus.east_x();
+            let y = corner.south_y() + half - self.focus.south_y();
+            x * x + y * y
+        };
+
+        let index = (0..self.jobs.len())
+            .min_by(|&a, &b| distance(&self.jobs[a].id).total_cmp(&distance(&self.jobs[b].id)))?;
+
+        Some(self.jobs.swap_remove(index))
+    }
+}
+
+#[derive(Default)]
+struct Shared {
+    queue: Mutex<Queue>,
+    available: Condvar,
+}
+
+pub(crate) struct Decoder {
+    shared: Arc<Shared>,
+}
+
+impl core::fmt::Debug for Decoder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Decoder..")
+    }
+}
+
+impl Decoder {
+    /// The decoder of the process, which is shared by all caches such that the number of
+    /// workers stays bounded however many caches there are, e.g. one per frame of a timeline.
+    /// The tiles of all caches are prioritized by the focus that was set last.
+    pub fn shared() -> Arc<Self> {
+        static DECODER: OnceLock<Arc<Decoder>> = OnceLock::new();
+        DECODER.get_or_init(|| Arc::new(Self::new())).clone()
+    }
+
+    /// Start a decoder with one worker per available core, leaving one for the renderer.
+    fn new() -> Self {
+        let workers = thread::available_parallelism()
+            .map(|cores| cores.get().saturating_sub(1))
+            .unwrap_or(1)
+            .clamp(1, 8);
+
+        let shared = Arc::new(Shared::default());
+        for index in 0..workers {
+            let shared = shared.clone();
+            thread::Builder::new()
+                .name(format!("slippery-decoder-{index}"))
+                .spawn(move || work(&shared))
+                .expect("Unable to spawn decoder thread");
+        }
+
+        Self { shared }
+    }
+
+    /// Set the position which tiles are prioritized by.
+    pub fn focus(&self, position: Mercator) {
+        self.shared.queue.lock().unwrap().focus = position;
+    }
+
+    /// Decode the encoded image of a tile into its pixels, without the given border, and
+    /// pass them through the processor.
+    pub async fn decode(
+        &self,
+        id: TileCoord,
+        bytes: Bytes,
+        border: u32,
+        processor: Option<Processor>,
+    ) -> Result<Handle, DecodeError> {
+        let (sender, receiver) = oneshot::channel();
+
+        self.shared.queue.lock().unwrap().jobs.push(Job {
+            id,
+            bytes,
+            border,
+            processor,
+            sender,
+        });
+        self.shared.available.notify_one();
+
+        receiver.await.map_err(|_| DecodeError::Closed)?
+    }
+}
+
+impl Drop for Decoder {
+    fn drop(&mut self) {
+        self.shared.queue.lock().unwrap().closed = true;
+        self.shared.available.notify_all();
+    }
+}
+
+/// Remove the pixels around the edges of a tile which overlap with its neighbours.
+pub(crate) fn crop_border(image: image::RgbaImage, border: u32) -> image::RgbaImage {
+    let (width, height) = image.dimensions();
+    if border == 0 || width <= 2 * border || height <= 2 * border {
+        return image;
+    }
+
+    image::imageops::crop_imm(
+        &image,
+        border,
+        border,
+        width - 2 * border,
+        height - 2 * border,
+    )
+    .to_image()
+}
+
+fn work(shared: &Shared) {
+    loop {
+        let job = {
+            let mut queue = shared.queue.lock().unwrap();
+            loop {
+                if queue.closed {
+                    return;
+                }
+
+                if let Some(job) = queue.pop() {
+                    break job;
+                }
+
+                queue = shared.available.wait(queue).unwrap();
+            }
+        };
+
+        // The tile is no longer needed
+        if job.sender.is_canceled() {
+            continue;
+        }
+
+        let result = decode_tile(&job);
+        let _ = job.sender.send(result);
+    }
+}
+
+fn decode_tile(job: &Job) -> Result<Handle, DecodeError> {
+    let mut image = crop_border(
+        image::load_from_memory(&job.bytes)?.into_rgba8(),
+        job.border,
+    );
+    if let Some(processor) = &job.processor {
+        (processor.0)(job.id, &mut image);
+    }
+    Ok(Handle::from_rgba(
+        image.width(),
+        image.height(),
+        image.into_raw(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::location;
+
+    #[test]
+    fn closest_tile_first() {
+        let focus = location::paris().as_mercator();
+        let mut queue = Queue {
+            focus,
+            ..Default::default()
+        };
+
+        let near = focus.tile_id(12);
+        let far = near.east().and_then(|id| id.east()).unwrap();
+        for id in [far, near, near.south().unwrap()] {
+            queue.jobs.push(Job {
+                id,
+                bytes: Bytes::new(),
+                border: 0,
+                processor: None,
+                sender: oneshot::channel().0,
+            });
+        }
+
+        assert_eq!(queue.pop().map(|job| job.id), Some(near));
+        queue.pop();
+        assert_eq!(queue.pop().map(|job| job.id), Some(far));
+        assert!(queue.pop().is_none());
+    }
+
+    #[test]
+    fn borders_are_cropped() {
+        let image = image::RgbaImage::from_fn(516, 516, |x, y| {
+            let inside = (2..514).contains(&x) && (2..514).contains(&y);
+            image::Rgba(if inside { [255; 4] } else { [0; 4] })
+        });
+
+        let cropped = crop_border(image, 2);
+        assert_eq!(cropped.dimensions(), (512, 512));
+        assert!(cropped.pixels().all(|pixel| pixel.0 == [255; 4]));
+    }
+
+    #[test]
+    fn tiles_are_processed() {
+        let mut png = Vec::new();
+        image::RgbaImage::from_pixel(4, 4, image::Rgba([255, 0, 0, 255]))
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+
+        let processor = Processor(Arc::new(|_, image| {
+            image.pixels_mut().for_each(|pixel| pixel.0.swap(0, 2));
+        }));
+        let job = Job {
+            id: TileCoord::ZERO,
+            bytes: Bytes::from(png),
+            border: 1,
+            processor: Some(processor),
+            sender: oneshot::channel().0,
+        };
+
+        let Handle::Rgba { width, pixels, .. } = decode_tile(&job).unwrap() else {
+            panic!("The tile should be decoded");
+        };
+        assert_eq!(width, 2);
+        assert_eq!(&pixels[..4], [0, 0, 255, 255]);
+    }
+}
@@ -1,21 +1,51 @@
 //! The draw cache is used to store which tiles should be drawn and where.
 //! It also holds on to the GPU-allocated image handle between draw calls.
 
-use std::collections::HashMap;
+use std::{collections::HashMap, time::Duration};
 
 use iced::Rectangle;
+use iced::time::Instant;
 use iced_core::image::{Allocation, Handle};
 
 use crate::tile_coord::TileCoord;
 
+/// How long it takes for a newly drawn tile to fade in.
+pub(crate) const FADE_IN: Duration = Duration::from_millis(200);
+
 pub(crate) struct DrawCache {
     pub(crate) maps: HashMap<u8, HashMap<(u32, u32), DrawData>>,
+    /// Blurred placeholders, which are kept apart such that they can be drawn underneath
+    /// the actual tile while it fades in.
+    pub(crate) placeholders: HashMap<u8, HashMap<(u32, u32), DrawData>>,
+    /// The outdated images of refreshed tiles, drawn underneath them while they fade in.
+    pub(crate) replaced: HashMap<TileCoord, DrawData>,
 }
 
 pub struct DrawData {
     pub handle: Handle,
     pub rectangle: Rectangle,
-    pub allocation: Allocation,
+    /// Only held to keep the tile allocated with the renderer while it is drawn. This is
+    /// `None` for placeholders, which are small enough to be uploaded when drawn.
+    #[allow(dead_code)]
+    pub allocation: Option<Allocation>,
+    /// When the tile was first drawn, for fading it in.
+    pub shown: Instant,
+}
+
+impl DrawData {
+    /// The opacity of the tile while it is fading in.
+    pub fn opacity(&self, now: Instant) -> f32 {
+        let elapsed = now.saturating_duration_since(self.shown);
+        (elapsed.as_secs_f32() / FADE_IN.as_secs_f32()).min(1.0)
+    }
+
+    pub fn is_fading(&self, now: Instant) -> bool {
+        now.saturating_duration_since(self.shown) < FADE_IN
+    }
+
+    pub fn is_placeholder(&self) -> bool {
+        self.allocation.is_none()
+    }
 }
 
 impl Default for DrawCache {
@@ -28,19 +58,24 @@ impl DrawCache {
     pub fn new() -> Self {
         Self {
             maps: HashMap::with_capacity(2),
+            placeholders: HashMap::new(),
+            replaced: HashMap::new(),
         }
     }
 
     /// Remove a tiles handle and allocation for reuse
-    pub fn remove(&mut self, tile_id: &TileCoord) -> Option<(Handle, Allocation)> {
+    pub fn remove(&mut self, tile_id: &TileCoord) -> Option<DrawData> {
         self.maps
             .get_mut(&tile_id.zoom())
-            .map(|inner| {
-                inner
-                    .remove(&tile_id.x_y())
-                    .map(|data| (data.handle, data.allocation))
-            })
-            .flatten()
+            .and_then(|inner| inner.remove(&tile_id.x_y()))
+    }
+
+    /// Remove the placeholder of a tile for reuse
+    #[cfg(feature = "decode")]
+    pub fn remove_placeholder(&mut self, tile_id: &TileCoord) -> Option<DrawData> {
+        self.placeholders
+            .get_mut(&tile_id.zoom())
+            .and_then(|inner| inner.remove(&tile_id.x_y()))
     }
 
     /// Check whether the cache contains some tile
@@ -51,36 +86,91 @@ impl DrawCache {
     }
 
     /// Insert a tile using its Id, image handle and its screen-space rectangle
-    pub fn insert(
-        &mut self,
-        tile_id: TileCoord,
-        handle: Handle,
-        rectangle: Rectangle,
-        allocation: Allocation,
-    ) {
-        self.maps
-            .entry(tile_id.zoom())
+    pub fn insert(&mut self, tile_id: TileCoord, data: DrawData) {
+        let maps = match data.is_placeholder() {
+            true => &mut self.placeholders,
+            false => &mut self.maps,
+        };
+
+        maps.entry(tile_id.zoom())
             .or_insert_with(HashMap::default)
-            .insert(
-                tile_id.x_y(),
-                DrawData {
-                    handle,
-                    rectangle,
-                    allocation,
-                },
-            );
+            .insert(tile_id.x_y(), data);
+    }
+
+    /// Check whether a tile is fading in over its own outdated image.
+    pub fn is_replacing(&self, tile_id: &TileCoord, now: Instant) -> bool {
+        self.maps
+            .get(&tile_id.zoom())
+            .and_then(|inner| inner.get(&tile_id.x_y()))
+            .is_some_and(|data| data.is_fading(now))
+    }
+
+    /// The ids of the tiles in the cache, not counting placeholders.
+    pub fn tile_ids(&self) -> impl Iterator<Item = TileCoord> {
+        self.maps
+            .iter()
+            .flat_map(|(zoom, inner)| inner.keys().map(|&(x, y)| TileCoord::new(x, y, *zoom)))
+    }
+
+    /// Remove all tiles from the cache, such that they can be moved into another. The
+    /// outdated images of refreshed tiles are kept apart, in `replaced`.
+    pub fn drain(&mut self) -> impl Iterator<Item = (TileCoord, DrawData)> {
+        let placeholders = self.placeholders.drain();
+        self.maps
+            .drain()
+            .chain(placeholders)
+            .flat_map(|(zoom, inner)| {
+                inner
+                    .into_iter()
+                    .map(move |((x, y), data)| (TileCoord::new(x, y, zoom), data))
+            })
+    }
+
+    /// Only keep the tiles which intersect the given rectangle, dropping the allocations
+    /// of all others.
+    pub fn retain_intersections(&mut self, rectangle: &Rectangle) {
+        for maps in [&mut self.maps, &mut self.placeholders] {
+            maps.retain(|_, inner| {
+                inner.retain(|_, data| data.rectangle.intersects(rectangle));
+                !inner.is_empty()
+            });
+        }
+        self.replaced
+            .retain(|_, data| data.rectangle.intersects(rectangle));
+    }
+
+    /// Check whether any tile which is still fading in overlaps the given rectangle.
+    pub fn is_fading_over(&self, rectangle: &Rectangle, now: Instant) -> bool {
+        self.iter_tiles()
+            .any(|data| data.is_fading(now) && data.rectangle.intersects(rectangle))
     }
 
-    /// Iterate through all tiles in ascending zoom order
+    /// Iterate through all tiles in ascending zoom order, with placeholders and outdated
+    /// images before the tiles of the same zoom level
     pub fn iter_tiles(&self) -> impl Iterator<Item = &DrawData> {
         // Get a sorted vector of the zoom levels
-        let mut zooms: Vec<&u8> = self.maps.keys().collect();
+        let mut zooms: Vec<u8> = self
+            .maps
+            .keys()
+            .chain(self.placeholders.keys())
+            .copied()
+            .chain(self.replaced.keys().map(TileCoord::zoom))
+            .collect();
         zooms.sort();
+        zooms.dedup();
 
         // Iterate over the maps in order of zoom level and yield the draw data.
         zooms.into_iter().flat_map(|zoom| {
-            let map = &self.maps[zoom];
-            map.iter().map(move |(_, data)| data)
+            let placeholders = self.placeholders.get(&zoom).into_iter().flatten();
+            let replaced = self
+                .replaced
+                .iter()
+                .filter(move |(id, _)| id.zoom() == zoom);
+            let tiles = self.maps.get(&zoom).into_iter().flatten();
+            placeholders
+                .map(|(_, data)| data)
+                .chain(replaced.map(|(_, data)| data))
+                .chain(tiles.map(|(_, data)| data))
         })
     }
 }
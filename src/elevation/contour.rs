@@ -0,0 +1,292 @@
+//! Contour lines traced from decoded elevation tiles using
+//! [marching squares](https://en.wikipedia.org/wiki/Marching_squares).
+
+use iced::widget::canvas::{self, Frame, Path, Stroke};
+use iced::{Color, Point, Task, alignment};
+
+use super::{DemTile, Terrain};
+use crate::legend::{LegendEntry, Symbol};
+use crate::{CacheMessage, Projector};
+
+/// All contour line segments of a single elevation level within a tile.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contour {
+    /// Elevation in meters.
+    pub elevation: f32,
+    /// Line segments in coordinates relative to the top-left corner of the tile,
+    /// ranging from `[0 .. 1]`.
+    pub segments: Vec<[(f32, f32); 2]>,
+}
+
+/// Trace the contour lines of an elevation tile, for every multiple of `interval` meters.
+///
+/// The contours are traced between pixel centers, with the edge pixels extended to the
+/// boundary of the tile, such that the lines of neighboring tiles roughly meet.
+pub fn contours(tile: &DemTile, interval: f32) -> Vec<Contour> {
+    let mut contours: Vec<Contour> = Vec::new();
+    if interval <= 0.0 {
+        return contours;
+    }
+
+    let (width, height) = (tile.width() as f32, tile.height() as f32);
+    let relative = |x: f32, y: f32| {
+        (
+            ((x + 0.5) / width).clamp(0.0, 1.0),
+            ((y + 0.5) / height).clamp(0.0, 1.0),
+        )
+    };
+
+    for y in -1..tile.height() as i64 {
+        for x in -1..tile.width() as i64 {
+            // Corners in clockwise order, starting from the top-left
+            let corners = [
+                tile.get(x, y),
+                tile.get(x + 1, y),
+                tile.get(x + 1, y + 1),
+                tile.get(x, y + 1),
+            ];
+
+            let min = corners.iter().copied().fold(f32::INFINITY, f32::min);
+            let max = corners.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+
+            let (first, last) = (
+                (min / interval).ceil() as i64,
+                (max / interval).floor() as i64,
+            );
+            for level in (first..=last).map(|i| i as f32 * interval) {
+                let segments = cell_segments(corners, level);
+                if segments.is_empty() {
+                    continue;
+                }
+
+                let contour = match contours.iter().position(|c| c.elevation == level) {
+                    Some(index) => &mut contours[index],
+                    None => {
+                        contours.push(Contour {
+                            elevation: level,
+                            segments: Vec::new(),
+                        });
+                        contours.last_mut().unwrap()
+                    }
+                };
+
+                let (x, y) = (x as f32, y as f32);
+                contour.segments.extend(
+                    segments
+                        .into_iter()
+                        .map(|[a, b]| [relative(x + a.0, y + a.1), relative(x + b.0, y + b.1)]),
+                );
+            }
+        }
+    }
+
+    contours.sort_by(|a, b| a.elevation.total_cmp(&b.elevation));
+    contours
+}
+
+/// The segments crossing a single cell, relative to its top-left corner.
+fn cell_segments(corners: [f32; 4], level: f32) -> Vec<[(f32, f32); 2]> {
+    // The offsets of the cell corners, matching their order
+    const OFFSETS: [(f32, f32); 4] = [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)];
+
+    // Find where the level crosses each edge: top, right, bottom, left
+    let crossings: Vec<(f32, f32)> = (0..4)
+        .filter_map(|edge| {
+            let (a, b) = (corners[edge], corners[(edge + 1) % 4]);
+            if (a >= level) == (b >= level) {
+                return None;
+            }
+
+            let t = (level - a) / (b - a);
+            let (start, end) = (OFFSETS[edge], OFFSETS[(edge + 1) % 4]);
+            Some((
+                start.0 + (end.0 - start.0) * t,
+                start.1 + (end.1 - start.1) * t,
+            ))
+        })
+        .collect();
+
+    match crossings[..] {
+        [a, b] => vec![[a, b]],
+        [top, right, bottom, left] => {
+            // A saddle point, resolved by the average of the cell
+            let center = corners.iter().sum::<f32>() / 4.0;
+            if (center >= level) == (corners[0] >= level) {
+                vec![[top, right], [bottom, left]]
+            } else {
+                vec![[top, left], [right, bottom]]
+            }
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// The visual appearance of a [`ContourLayer`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ContourStyle {
+    pub color: Color,
+    pub width: f32,
+    /// Width of the index contours, which are drawn thicker and labeled.
+    pub index_width: f32,
+    /// Size of the index contour labels, or `None` to not draw labels.
+    pub label_size: Option<f32>,
+}
+
+impl Default for ContourStyle {
+    fn default() -> Self {
+        Self {
+            color: Color::from_rgba(0.45, 0.3, 0.15, 0.8),
+            width: 1.0,
+            index_width: 2.0,
+            label_size: Some(11.0),
+        }
+    }
+}
+
+impl ContourStyle {
+    /// An entry for a [`Legend`](crate::legend::Legend) explaining the contour lines.
+    pub fn legend(&self, label: impl Into<String>) -> LegendEntry {
+        let symbol = Symbol::Line {
+            color: self.color,
+            width: self.width,
+        };
+        LegendEntry::new(symbol, label)
+    }
+}
+
+/// Draws contour lines of a [`Terrain`] at a fixed elevation interval.
+///
+/// Every `index_every`-th contour is an index contour, which is drawn thicker and labeled
+/// with its elevation. The elevation tiles must be requested separately with
+/// [`ContourLayer::request`], typically whenever the viewpoint changes.
+#[derive(Debug, Clone, Copy)]
+pub struct ContourLayer<'a> {
+    terrain: &'a Terrain,
+    interval: f32,
+    index_every: u32,
+    style: ContourStyle,
+}
+
+impl<'a> ContourLayer<'a> {
+    pub fn new(terrain: &'a Terrain, interval: f32) -> Self {
+        Self {
+            terrain,
+            interval,
+            index_every: 5,
+            style: ContourStyle::default(),
+        }
+    }
+
+    pub fn index_every(mut self, index_every: u32) -> Self {
+        self.index_every = index_every;
+        self
+    }
+
+    pub fn style(mut self, style: ContourStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Request the elevation tiles needed to trace the current view.
+    pub fn request(&self, projector: &Projector) -> Task<CacheMessage> {
+        let zoom = self.terrain.zoom_for_view(projector);
+        self.terrain.request_tiles(projector.tiles_in_view(zoom))
+    }
+
+    pub fn draw(&self, projector: &Projector, frame: &mut Frame<iced::Renderer>) {
+        let zoom = self.terrain.zoom_for_view(projector);
+        let index_every = self.index_every.max(1) as i64;
+
+        for tile_id in projector.tiles_in_view(zoom) {
+            let Some(contours) = self.terrain.contours(&tile_id, self.interval) else {
+                continue;
+            };
+
+            let bounds = projector.tile_bounds(&tile_id);
+            let to_screen = |(u, v): (f32, f32)| {
+                Point::new(bounds.x + u * bounds.width, bounds.y + v * bounds.height)
+            };
+
+            let is_index = |contour: &&Contour| {
+                ((contour.elevation / self.interval).round() as i64).rem_euclid(index_every) == 0
+            };
+
+            for (index, width) in [(false, self.style.width), (true, self.style.index_width)] {
+                let path = Path::new(|builder| {
+                    for contour in contours.iter().filter(|c| is_index(c) == index) {
+                        for [a, b] in &contour.segments {
+                            builder.move_to(to_screen(*a));
+                            builder.line_to(to_screen(*b));
+                        }
+                    }
+                });
+
+                frame.stroke(
+                    &path,
+                    Stroke::default()
+                        .with_color(self.style.color)
+                        .with_width(width),
+                );
+            }
+
+            let Some(label_size) = self.style.label_size else {
+                continue;
+            };
+
+            // Label each index contour once per tile, at its middle segment
+            for contour in contours.iter().filter(is_index) {
+                let [a, b] = contour.segments[contour.segments.len() / 2];
+                let (a, b) = (to_screen(a), to_screen(b));
+
+                frame.fill_text(canvas::Text {
+                    content: format!("{:.0}", contour.elevation),
+                    position: Point::new((a.x + b.x) / 2.0, (a.y + b.y) / 2.0),
+                    color: self.style.color,
+                    size: label_size.into(),
+                    align_x: alignment::Horizontal::Center.into(),
+                    align_y: alignment::Vertical::Center,
+                    ..Default::default()
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn traces_ramp() {
+        // Elevation rising from 0 to 30 meters towards the east
+        let tile = DemTile {
+            width: 4,
+            height: 4,
+            heights: (0..16).map(|i| (i % 4) as f32 * 10.0).collect(),
+        };
+
+        let contours = contours(&tile, 10.0);
+        let levels: Vec<f32> = contours.iter().map(|c| c.elevation).collect();
+        assert_eq!(levels, [10.0, 20.0, 30.0]);
+
+        // The 10 meter contour is a vertical line through the center of the second pixel
+        for [a, b] in &contours[0].segments {
+            assert_eq!(a.0, 0.375);
+            assert_eq!(b.0, 0.375);
+        }
+
+        // And spans the full height of the tile
+        let min = contours[0].segments.iter().map(|[a, b]| a.1.min(b.1));
+        let max = contours[0].segments.iter().map(|[a, b]| a.1.max(b.1));
+        assert_eq!(min.fold(f32::INFINITY, f32::min), 0.0);
+        assert_eq!(max.fold(f32::NEG_INFINITY, f32::max), 1.0);
+    }
+
+    #[test]
+    fn resolves_saddle() {
+        // Diagonal corners above the level, with a high center connecting them
+        let segments = cell_segments([10.0, 0.0, 10.0, 0.0], 4.0);
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[0], [(0.6, 0.0), (1.0, 0.4)]);
+    }
+}
@@ -0,0 +1,195 @@
+//! Hillshading computed on the fly from decoded elevation tiles, using
+//! [Horn's method](https://pro.arcgis.com/en/pro-app/latest/tool-reference/3d-analyst/how-hillshade-works.htm)
+//! for estimating the slope and aspect of each pixel.
+
+use std::f64::consts::PI;
+
+use iced::Task;
+use iced::widget::canvas::{Frame, Image};
+use iced_core::image::{FilterMethod, Handle};
+
+use super::{DemTile, Terrain};
+use crate::{CacheMessage, Projector, TileCoord, position::EARTH_RADIUS};
+
+/// The position of the light source illuminating the terrain.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sun {
+    /// Direction of the light source in degrees, clockwise from north.
+    pub azimuth: f64,
+    /// Angle of the light source in degrees above the horizon.
+    pub altitude: f64,
+}
+
+impl Default for Sun {
+    /// Light from the north-west, which is the cartographic convention.
+    fn default() -> Self {
+        Self {
+            azimuth: 315.0,
+            altitude: 45.0,
+        }
+    }
+}
+
+/// Compute a grayscale hillshade image for an elevation tile.
+///
+/// The `exaggeration` scales the elevation differences, which is useful for bringing out
+/// the relief in flat terrain.
+pub fn hillshade(tile: &DemTile, tile_id: TileCoord, sun: Sun, exaggeration: f64) -> Handle {
+    let (width, height) = (tile.width(), tile.height());
+
+    // The ground distance covered by each pixel, at the latitude of the tile center
+    let total_tiles = 2f64.powi(tile_id.zoom() as i32);
+    let center_y = (tile_id.y() as f64 + 0.5) / total_tiles * 2.0 - 1.0;
+    let latitude = (center_y * PI).sinh().atan();
+    let cell_size = 2.0 * PI * EARTH_RADIUS * latitude.cos() / (total_tiles * width as f64);
+
+    let zenith = (90.0 - sun.altitude).to_radians();
+    let azimuth = (360.0 - sun.azimuth + 90.0).to_radians();
+
+    let mut pixels = Vec::with_capacity((width * height * 4) as usize);
+    for y in 0..height as i64 {
+        for x in 0..width as i64 {
+            let z = |dx: i64, dy: i64| tile.get(x + dx, y + dy) as f64;
+
+            let dz_dx = ((z(1, -1) + 2.0 * z(1, 0) + z(1, 1))
+                - (z(-1, -1) + 2.0 * z(-1, 0) + z(-1, 1)))
+                / (8.0 * cell_size);
+            let dz_dy = ((z(-1, 1) + 2.0 * z(0, 1) + z(1, 1))
+                - (z(-1, -1) + 2.0 * z(0, -1) + z(1, -1)))
+                / (8.0 * cell_size);
+
+            let slope = (exaggeration * dz_dx.hypot(dz_dy)).atan();
+            let aspect = dz_dy.atan2(-dz_dx);
+
+            let shade =
+                zenith.cos() * slope.cos() + zenith.sin() * slope.sin() * (azimuth - aspect).cos();
+
+            let value = (shade.clamp(0.0, 1.0) * 255.0) as u8;
+            pixels.extend_from_slice(&[value, value, value, 255]);
+        }
+    }
+
+    Handle::from_rgba(width, height, pixels)
+}
+
+/// Draws the hillshade of a [`Terrain`] as a translucent overlay.
+///
+/// The elevation tiles must be requested separately with [`HillshadeLayer::request`],
+/// typically whenever the viewpoint changes.
+#[derive(Debug, Clone, Copy)]
+pub struct HillshadeLayer<'a> {
+    terrain: &'a Terrain,
+    sun: Sun,
+    exaggeration: f64,
+    opacity: f32,
+}
+
+impl<'a> HillshadeLayer<'a> {
+    pub fn new(terrain: &'a Terrain) -> Self {
+        Self {
+            terrain,
+            sun: Sun::default(),
+            exaggeration: 1.0,
+            opacity: 0.4,
+        }
+    }
+
+    pub fn sun(mut self, sun: Sun) -> Self {
+        self.sun = sun;
+        self
+    }
+
+    pub fn exaggeration(mut self, exaggeration: f64) -> Self {
+        self.exaggeration = exaggeration;
+        self
+    }
+
+    pub fn opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity;
+        self
+    }
+
+    /// Request the elevation tiles needed to shade the current view.
+    pub fn request(&self, projector: &Projector) -> Task<CacheMessage> {
+        let zoom = self.terrain.zoom_for_view(projector);
+        self.terrain.request_tiles(projector.tiles_in_view(zoom))
+    }
+
+    pub fn draw(&self, projector: &Projector, frame: &mut Frame<iced::Renderer>) {
+        let zoom = self.terrain.zoom_for_view(projector);
+
+        for tile_id in projector.tiles_in_view(zoom) {
+            let Some(handle) = self
+                .terrain
+                .hillshade(&tile_id, self.sun, self.exaggeration)
+            else {
+                continue;
+            };
+
+            frame.draw_image(
+                projector.tile_bounds(&tile_id),
+                Image::new(handle)
+                    .filter_method(FilterMethod::Linear)
+                    .opacity(self.opacity),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shades(handle: &Handle) -> Vec<u8> {
+        let Handle::Rgba { pixels, .. } = handle else {
+            panic!("hillshade should produce raw pixels");
+        };
+        pixels.chunks_exact(4).map(|pixel| pixel[0]).collect()
+    }
+
+    #[test]
+    fn flat_terrain_is_uniform() {
+        let tile = DemTile {
+            width: 4,
+            height: 4,
+            heights: vec![100.0; 16],
+        };
+
+        let handle = hillshade(&tile, TileCoord::new(0, 0, 1), Sun::default(), 1.0);
+
+        // Flat ground is lit by the sine of the sun altitude
+        let expected = (45f64.to_radians().sin() * 255.0) as u8;
+        assert!(shades(&handle).iter().all(|&shade| shade == expected));
+    }
+
+    #[test]
+    fn slopes_facing_the_sun_are_brighter() {
+        // Terrain rising towards the east, so the slope faces west
+        let tile = DemTile {
+            width: 4,
+            height: 4,
+            heights: (0..16).map(|i| (i % 4) as f32 * 500_000.0).collect(),
+        };
+
+        let west = hillshade(
+            &tile,
+            TileCoord::new(0, 0, 1),
+            Sun {
+                azimuth: 270.0,
+                altitude: 45.0,
+            },
+            1.0,
+        );
+        let east = hillshade(
+            &tile,
+            TileCoord::new(0, 0, 1),
+            Sun {
+                azimuth: 90.0,
+                altitude: 45.0,
+            },
+            1.0,
+        );
+
+        assert!(shades(&west)[5] > shades(&east)[5]);
+    }
+}
@@ -0,0 +1,364 @@
+//! Elevation lookups from terrain-RGB tiles, such as [`crate::sources::MapboxTerrain`]
+//! or [`crate::sources::Terrarium`].
+//!
+//! The elevation tiles are fetched through a regular [`TileCache`], but are never allocated
+//! with the renderer. Instead they are decoded on the CPU the first time they are sampled.
+
+mod contour;
+mod hillshade;
+
+pub use contour::{Contour, ContourLayer, ContourStyle, contours};
+pub use hillshade::{HillshadeLayer, Sun, hillshade};
+
+use std::{cell::RefCell, collections::HashMap, sync::Arc};
+
+use iced::Task;
+use iced_core::image::Handle;
+
+use crate::{CacheMessage, Geodetic, Mercator, Projector, TileCache, TileCoord, sources::Source};
+
+/// How the elevation is encoded into the color channels of a terrain tile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TerrainEncoding {
+    /// `-10000 + (R * 256² + G * 256 + B) * 0.1` meters
+    Mapbox,
+    /// `(R * 256 + G + B / 256) - 32768` meters
+    Terrarium,
+}
+
+impl TerrainEncoding {
+    /// Decode the elevation in meters from the color of a single pixel.
+    pub fn decode(&self, [r, g, b]: [u8; 3]) -> f32 {
+        let (r, g, b) = (r as f32, g as f32, b as f32);
+        match self {
+            Self::Mapbox => -10000.0 + (r * 65536.0 + g * 256.0 + b) * 0.1,
+            Self::Terrarium => (r * 256.0 + g + b / 256.0) - 32768.0,
+        }
+    }
+}
+
+/// A decoded elevation tile, holding the elevation in meters of each pixel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DemTile {
+    width: u32,
+    height: u32,
+    heights: Vec<f32>,
+}
+
+impl DemTile {
+    /// Decode a tile from RGBA pixels. The alpha channel is ignored.
+    pub fn from_rgba(width: u32, height: u32, pixels: &[u8], encoding: TerrainEncoding) -> Self {
+        let heights = pixels
+            .chunks_exact(4)
+            .map(|pixel| encoding.decode([pixel[0], pixel[1], pixel[2]]))
+            .collect();
+
+        Self {
+            width,
+            height,
+            heights,
+        }
+    }
+
+    /// Decode a tile from an image [`Handle`], as stored in the [`TileCache`].
+    pub fn from_handle(handle: &Handle, encoding: TerrainEncoding) -> Option<Self> {
+        let image = match handle {
+            Handle::Bytes(_, bytes) => image::load_from_memory(bytes).ok()?.to_rgba8(),
+            Handle::Path(_, path) => image::open(path).ok()?.to_rgba8(),
+            Handle::Rgba {
+                width,
+                height,
+                pixels,
+                ..
+            } => return Some(Self::from_rgba(*width, *height, pixels, encoding)),
+        };
+
+        Some(Self::from_rgba(
+            image.width(),
+            image.height(),
+            image.as_raw(),
+            encoding,
+        ))
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The elevation of a single pixel. Coordinates outside the tile are clamped to its edge.
+    pub fn get(&self, x: i64, y: i64) -> f32 {
+        let x = x.clamp(0, self.width as i64 - 1) as usize;
+        let y = y.clamp(0, self.height as i64 - 1) as usize;
+        self.heights
+            .get(y * self.width as usize + x)
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    /// Sample the elevation with bilinear interpolation, where `u` and `v` are the relative
+    /// position within the tile, ranging from `[0 .. 1]` from the top-left corner.
+    pub fn sample(&self, u: f64, v: f64) -> f32 {
+        // Pixel centers are at half-pixel offsets
+        let x = u * self.width as f64 - 0.5;
+        let y = v * self.height as f64 - 0.5;
+
+        let (x0, y0) = (x.floor(), y.floor());
+        let (tx, ty) = ((x - x0) as f32, (y - y0) as f32);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        let top = self.get(x0, y0) * (1.0 - tx) + self.get(x0 + 1, y0) * tx;
+        let bottom = self.get(x0, y0 + 1) * (1.0 - tx) + self.get(x0 + 1, y0 + 1) * tx;
+        top * (1.0 - ty) + bottom * ty
+    }
+}
+
+/// A single sample of an elevation profile.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProfileSample {
+    /// Distance in meters along the track.
+    pub distance: f64,
+    pub position: Geodetic,
+    /// Elevation in meters, if the tile has been loaded.
+    pub elevation: Option<f32>,
+}
+
+/// Products derived from decoded tiles, along with the parameters they were derived with.
+type Derived<P, T> = RefCell<HashMap<TileCoord, (P, T)>>;
+
+/// Looks up elevations from terrain-RGB tiles at a fixed zoom level.
+#[derive(Debug)]
+pub struct Terrain {
+    cache: TileCache,
+    encoding: TerrainEncoding,
+    zoom: u8,
+    decoded: RefCell<HashMap<TileCoord, Arc<DemTile>>>,
+    shaded: Derived<(Sun, f64), Handle>,
+    contoured: Derived<f32, Arc<[Contour]>>,
+}
+
+impl Terrain {
+    /// Create a new terrain lookup. Elevations are sampled from tiles at the given zoom
+    /// level, which is limited by the maximum zoom of the source.
+    pub fn new(source: impl Source + 'static, encoding: TerrainEncoding, zoom: u8) -> Self {
+        let zoom = zoom.min(source.max_zoom());
+        Self {
+            cache: TileCache::new(source),
+            encoding,
+            zoom,
+            decoded: RefCell::new(HashMap::new()),
+            shaded: RefCell::new(HashMap::new()),
+            contoured: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn zoom(&self) -> u8 {
+        self.zoom
+    }
+
+    pub fn encoding(&self) -> TerrainEncoding {
+        self.encoding
+    }
+
+    /// Glue this into the application update function, like [`TileCache::update`].
+    pub fn update(&mut self, message: CacheMessage) -> Task<CacheMessage> {
+        let prune = matches!(message, CacheMessage::Prune);
+
+        let task = match message {
+            // Elevation tiles are never drawn, so they need not be allocated
+            CacheMessage::Allocate { .. } => Task::none(),
+            message => self.cache.update(message),
+        };
+
+        if prune {
+            // Forget decoded tiles which were pruned from the underlying cache
+            let cache = &self.cache;
+            self.decoded.get_mut().retain(|id, _| cache.is_loaded(id));
+            self.shaded.get_mut().retain(|id, _| cache.is_loaded(id));
+            self.contoured.get_mut().retain(|id, _| cache.is_loaded(id));
+        }
+
+        task
+    }
+
+    /// Request loading of the tiles needed to look up the elevation at the given points.
+    pub fn request(&self, points: impl IntoIterator<Item = Geodetic>) -> Task<CacheMessage> {
+        let mut tiles: Vec<TileCoord> = points
+            .into_iter()
+            .map(|point| point.as_mercator().tile_id(self.zoom))
+            .filter(|id| self.cache.should_load(id))
+            .collect();
+
+        tiles.sort_by_key(|id| id.x_y());
+        tiles.dedup();
+
+        Task::batch(
+            tiles
+                .into_iter()
+                .map(|id| Task::done(CacheMessage::Load { id })),
+        )
+    }
+
+    /// Request loading of specific tiles, such as those returned by [`Projector::tiles_in_view`].
+    pub fn request_tiles(&self, tiles: impl IntoIterator<Item = TileCoord>) -> Task<CacheMessage> {
+        Task::batch(
+            tiles
+                .into_iter()
+                .filter(|id| self.cache.should_load(id))
+                .map(|id| Task::done(CacheMessage::Load { id })),
+        )
+    }
+
+    /// The zoom level of the tiles matching the resolution of the current view.
+    pub fn zoom_for_view(&self, projector: &Projector) -> u8 {
+        projector.tile_zoom(self.cache.tile_size(), self.cache.max_zoom())
+    }
+
+    /// Get the hillshade of a tile, if it has been loaded. The shading is computed once
+    /// and reused until the lighting or exaggeration changes.
+    pub fn hillshade(&self, tile_id: &TileCoord, sun: Sun, exaggeration: f64) -> Option<Handle> {
+        if let Some((parameters, handle)) = self.shaded.borrow().get(tile_id)
+            && *parameters == (sun, exaggeration)
+        {
+            return Some(handle.clone());
+        }
+
+        let tile = self.tile(tile_id)?;
+        let handle = hillshade(&tile, *tile_id, sun, exaggeration);
+        self.shaded
+            .borrow_mut()
+            .insert(*tile_id, ((sun, exaggeration), handle.clone()));
+        Some(handle)
+    }
+
+    /// Get the contour lines of a tile, if it has been loaded. The contours are traced once
+    /// and reused until the interval changes.
+    pub fn contours(&self, tile_id: &TileCoord, interval: f32) -> Option<Arc<[Contour]>> {
+        if let Some((cached_interval, contours)) = self.contoured.borrow().get(tile_id)
+            && *cached_interval == interval
+        {
+            return Some(contours.clone());
+        }
+
+        let tile = self.tile(tile_id)?;
+        let traced: Arc<[Contour]> = contours(&tile, interval).into();
+        self.contoured
+            .borrow_mut()
+            .insert(*tile_id, (interval, traced.clone()));
+        Some(traced)
+    }
+
+    /// Get the decoded elevation tile, if it has been loaded.
+    pub fn tile(&self, tile_id: &TileCoord) -> Option<Arc<DemTile>> {
+        if let Some(tile) = self.decoded.borrow().get(tile_id) {
+            return Some(tile.clone());
+        }
+
+        let handle = self.cache.get_loaded(tile_id)?;
+        let Some(tile) = DemTile::from_handle(&handle, self.encoding) else {
+            log::error!("Unable to decode elevation tile {tile_id:?}");
+            return None;
+        };
+
+        let tile = Arc::new(tile);
+        self.decoded.borrow_mut().insert(*tile_id, tile.clone());
+        Some(tile)
+    }
+
+    /// Look up the elevation in meters at some point. Returns `None` if the tile
+    /// covering the point has not been loaded, see [`Terrain::request`].
+    pub fn elevation_at(&self, point: Geodetic) -> Option<f32> {
+        let mercator = point.as_mercator();
+        let tile_id = mercator.tile_id(self.zoom);
+        let tile = self.tile(&tile_id)?;
+
+        let (u, v) = relative_position(mercator, tile_id);
+        Some(tile.sample(u, v))
+    }
+
+    /// Sample the elevation along a track, with roughly `spacing` meters between samples.
+    /// Each vertex of the track is always included.
+    pub fn profile(&self, track: &[Geodetic], spacing: f64) -> Vec<ProfileSample> {
+        let mut samples = Vec::new();
+        let mut distance = 0.0;
+
+        for (i, &start) in track.iter().enumerate() {
+            let Some(&end) = track.get(i + 1) else {
+                samples.push(ProfileSample {
+                    distance,
+                    position: start,
+                    elevation: self.elevation_at(start),
+                });
+                break;
+            };
+
+            let length = start.distance_to(end);
+            let steps = (length / spacing.max(1.0)).ceil().max(1.0) as usize;
+
+            // Interpolate in mercator space, which matches the straight lines drawn on the map
+            let (a, b) = (start.as_mercator(), end.as_mercator());
+            for step in 0..steps {
+                let t = step as f64 / steps as f64;
+                let position = Mercator::new(
+                    a.east_x() + (b.east_x() - a.east_x()) * t,
+                    a.south_y() + (b.south_y() - a.south_y()) * t,
+                )
+                .as_geodetic();
+
+                samples.push(ProfileSample {
+                    distance: distance + length * t,
+                    position,
+                    elevation: self.elevation_at(position),
+                });
+            }
+
+            distance += length;
+        }
+
+        samples
+    }
+}
+
+/// The position of a point relative to the top-left corner of a tile, ranging from `[0 .. 1]`.
+fn relative_position(mercator: Mercator, tile_id: TileCoord) -> (f64, f64) {
+    let total_tiles = 2f64.powi(tile_id.zoom() as i32);
+    let x = (mercator.east_x() + 1.0) / 2.0 * total_tiles - tile_id.x() as f64;
+    let y = (mercator.south_y() + 1.0) / 2.0 * total_tiles - tile_id.y() as f64;
+    (x.clamp(0.0, 1.0), y.clamp(0.0, 1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_encodings() {
+        assert_eq!(TerrainEncoding::Mapbox.decode([1, 134, 160]), 0.0);
+        assert_eq!(TerrainEncoding::Terrarium.decode([128, 0, 0]), 0.0);
+        assert_eq!(TerrainEncoding::Terrarium.decode([128, 100, 128]), 100.5);
+    }
+
+    #[test]
+    fn bilinear_sampling() {
+        // A 2x2 tile with elevations 0, 10 on the top row and 20, 30 on the bottom row
+        let tile = DemTile {
+            width: 2,
+            height: 2,
+            heights: vec![0.0, 10.0, 20.0, 30.0],
+        };
+
+        // Pixel centers are sampled exactly
+        assert_eq!(tile.sample(0.25, 0.25), 0.0);
+        assert_eq!(tile.sample(0.75, 0.75), 30.0);
+
+        // The tile center is the average of all four
+        assert_eq!(tile.sample(0.5, 0.5), 15.0);
+
+        // Edges are clamped
+        assert_eq!(tile.sample(0.0, 0.0), 0.0);
+        assert_eq!(tile.sample(1.0, 0.25), 10.0);
+    }
+}
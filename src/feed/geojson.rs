@@ -0,0 +1,194 @@
+//! Just enough [GeoJSON](https://datatracker.ietf.org/doc/html/rfc7946) parsing to draw
+//! the features of a feed.
+
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::{Geodetic, geometry::Geometry};
+
+/// A single GeoJSON feature.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Feature {
+    /// The feature ID, where numeric IDs are converted to strings.
+    pub id: Option<String>,
+    pub geometry: Option<Geometry>,
+    pub properties: Map<String, Value>,
+}
+
+/// Parse a GeoJSON document, which may either be a `FeatureCollection` or a single `Feature`.
+pub fn parse_features(bytes: &[u8]) -> Result<Vec<Feature>, serde_json::Error> {
+    let features = match serde_json::from_slice(bytes)? {
+        raw::Document::FeatureCollection { features } => features,
+        raw::Document::Feature(feature) => vec![feature],
+    };
+
+    Ok(features.into_iter().map(Feature::from).collect())
+}
+
+impl From<raw::Feature> for Feature {
+    fn from(feature: raw::Feature) -> Self {
+        let id = feature.id.and_then(|id| match id {
+            Value::String(id) => Some(id),
+            Value::Number(id) => Some(id.to_string()),
+            _ => None,
+        });
+
+        Self {
+            id,
+            geometry: feature.geometry.map(Geometry::from),
+            properties: feature.properties.unwrap_or_default(),
+        }
+    }
+}
+
+impl From<raw::Geometry> for Geometry {
+    fn from(geometry: raw::Geometry) -> Self {
+        // Positions without both a longitude and latitude are skipped
+        fn point(position: Vec<f64>) -> Option<Geodetic> {
+            match position[..] {
+                [lon, lat, ..] => Some(Geodetic::new(lon, lat)),
+                _ => None,
+            }
+        }
+
+        fn line(positions: Vec<Vec<f64>>) -> Vec<Geodetic> {
+            positions.into_iter().filter_map(point).collect()
+        }
+
+        fn lines(lines: Vec<Vec<Vec<f64>>>) -> Vec<Vec<Geodetic>> {
+            lines.into_iter().map(line).collect()
+        }
+
+        match geometry {
+            raw::Geometry::Point { coordinates } => match point(coordinates) {
+                Some(point) => Self::Point(point),
+                None => Self::MultiPoint(Vec::new()),
+            },
+            raw::Geometry::MultiPoint { coordinates } => Self::MultiPoint(line(coordinates)),
+            raw::Geometry::LineString { coordinates } => Self::LineString(line(coordinates)),
+            raw::Geometry::MultiLineString { coordinates } => {
+                Self::MultiLineString(lines(coordinates))
+            }
+            raw::Geometry::Polygon { coordinates } => Self::Polygon(lines(coordinates)),
+            raw::Geometry::MultiPolygon { coordinates } => {
+                Self::MultiPolygon(coordinates.into_iter().map(lines).collect())
+            }
+            raw::Geometry::Collection { geometries } => {
+                Self::Collection(geometries.into_iter().map(Self::from).collect())
+            }
+        }
+    }
+}
+
+mod raw {
+    use super::*;
+
+    #[derive(Deserialize)]
+    #[serde(tag = "type")]
+    pub enum Document {
+        FeatureCollection { features: Vec<Feature> },
+        Feature(Feature),
+    }
+
+    #[derive(Deserialize)]
+    pub struct Feature {
+        #[serde(default)]
+        pub id: Option<Value>,
+        pub geometry: Option<Geometry>,
+        #[serde(default)]
+        pub properties: Option<Map<String, Value>>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(tag = "type")]
+    pub enum Geometry {
+        Point {
+            coordinates: Vec<f64>,
+        },
+        MultiPoint {
+            coordinates: Vec<Vec<f64>>,
+        },
+        LineString {
+            coordinates: Vec<Vec<f64>>,
+        },
+        MultiLineString {
+            coordinates: Vec<Vec<Vec<f64>>>,
+        },
+        Polygon {
+            coordinates: Vec<Vec<Vec<f64>>>,
+        },
+        MultiPolygon {
+            coordinates: Vec<Vec<Vec<Vec<f64>>>>,
+        },
+        #[serde(rename = "GeometryCollection")]
+        Collection {
+            geometries: Vec<Geometry>,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_feature_collection() {
+        let document = br#"{
+            "type": "FeatureCollection",
+            "features": [
+                {
+                    "type": "Feature",
+                    "id": "us7000abcd",
+                    "geometry": { "type": "Point", "coordinates": [-122.4, 37.8, 10.0] },
+                    "properties": { "mag": 4.5 }
+                },
+                {
+                    "type": "Feature",
+                    "id": 42,
+                    "geometry": {
+                        "type": "Polygon",
+                        "coordinates": [[[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 0.0]]]
+                    },
+                    "properties": null
+                },
+                { "type": "Feature", "geometry": null, "properties": {} }
+            ]
+        }"#;
+
+        let features = parse_features(document).unwrap();
+        assert_eq!(features.len(), 3);
+
+        assert_eq!(features[0].id.as_deref(), Some("us7000abcd"));
+        assert_eq!(
+            features[0].geometry,
+            Some(Geometry::Point(Geodetic::new(-122.4, 37.8)))
+        );
+        assert_eq!(features[0].properties["mag"], 4.5);
+
+        assert_eq!(features[1].id.as_deref(), Some("42"));
+        let Some(Geometry::Polygon(rings)) = &features[1].geometry else {
+            panic!("expected a polygon");
+        };
+        assert_eq!(rings[0].len(), 4);
+
+        assert_eq!(features[2].id, None);
+        assert_eq!(features[2].geometry, None);
+    }
+
+    #[test]
+    fn parse_single_feature() {
+        let document = br#"{
+            "type": "Feature",
+            "geometry": { "type": "LineString", "coordinates": [[0.0, 0.0], [1.0], [2.0, 2.0]] }
+        }"#;
+
+        let features = parse_features(document).unwrap();
+        assert_eq!(
+            features[0].geometry,
+            Some(Geometry::LineString(vec![
+                Geodetic::new(0.0, 0.0),
+                Geodetic::new(2.0, 2.0)
+            ]))
+        );
+    }
+}
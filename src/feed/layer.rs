@@ -0,0 +1,221 @@
+use iced::Color;
+use iced::time::Instant;
+use iced::widget::canvas::{Frame, Path, Stroke, path::Builder};
+
+use super::{FeedFeature, GeoJsonFeed, Geometry};
+use crate::legend::{LegendEntry, Symbol};
+use crate::{Geodetic, Mercator, Projector};
+
+/// The visual appearance of a [`FeedLayer`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FeedStyle {
+    pub color: Color,
+    /// Radius of points in pixels.
+    pub radius: f32,
+    /// Width of lines and polygon outlines in pixels.
+    pub width: f32,
+    pub fill: Color,
+}
+
+impl Default for FeedStyle {
+    fn default() -> Self {
+        Self {
+            color: Color::from_rgb(0.85, 0.15, 0.15),
+            radius: 5.0,
+            width: 2.0,
+            fill: Color::from_rgba(0.85, 0.15, 0.15, 0.25),
+        }
+    }
+}
+
+impl FeedStyle {
+    /// An entry for a [`Legend`](crate::legend::Legend) explaining the features.
+    pub fn legend(&self, label: impl Into<String>) -> LegendEntry {
+        let symbol = Symbol::Area {
+            fill: self.fill,
+            stroke: self.color,
+        };
+        LegendEntry::new(symbol, label)
+    }
+
+    /// Draw a geometry in this style, such as one parsed from WKT, outside of a feed.
+    pub fn draw(
+        &self,
+        projector: &Projector,
+        frame: &mut Frame<iced::Renderer>,
+        geometry: &Geometry,
+    ) {
+        self.draw_geometry(projector, frame, geometry, 1.0);
+    }
+
+    fn draw_geometry(
+        &self,
+        projector: &Projector,
+        frame: &mut Frame<iced::Renderer>,
+        geometry: &Geometry,
+        opacity: f32,
+    ) {
+        let stroke = Stroke::default()
+            .with_color(self.color.scale_alpha(opacity))
+            .with_width(self.width);
+
+        let line = |builder: &mut Builder, points: &[Geodetic], close: bool| {
+            let mut points = points
+                .iter()
+                .map(|point| projector.geodetic_into_screen_space(*point));
+
+            if let Some(first) = points.next() {
+                builder.move_to(first);
+                points.for_each(|point| builder.line_to(point));
+                if close {
+                    builder.close();
+                }
+            }
+        };
+
+        match geometry {
+            Geometry::Point(point) => self.draw_points(projector, frame, &[*point], opacity),
+            Geometry::MultiPoint(points) => self.draw_points(projector, frame, points, opacity),
+            Geometry::LineString(points) => {
+                frame.stroke(&Path::new(|builder| line(builder, points, false)), stroke);
+            }
+            Geometry::MultiLineString(lines) => {
+                let path = Path::new(|builder| {
+                    lines.iter().for_each(|points| line(builder, points, false))
+                });
+                frame.stroke(&path, stroke);
+            }
+            Geometry::Polygon(rings) => {
+                let path =
+                    Path::new(|builder| rings.iter().for_each(|ring| line(builder, ring, true)));
+                frame.fill(&path, self.fill.scale_alpha(opacity));
+                frame.stroke(&path, stroke);
+            }
+            Geometry::MultiPolygon(polygons) => {
+                let path = Path::new(|builder| {
+                    polygons
+                        .iter()
+                        .flatten()
+                        .for_each(|ring| line(builder, ring, true))
+                });
+                frame.fill(&path, self.fill.scale_alpha(opacity));
+                frame.stroke(&path, stroke);
+            }
+            Geometry::Collection(geometries) => {
+                for geometry in geometries {
+                    self.draw_geometry(projector, frame, geometry, opacity);
+                }
+            }
+        }
+    }
+
+    fn draw_points(
+        &self,
+        projector: &Projector,
+        frame: &mut Frame<iced::Renderer>,
+        points: &[Geodetic],
+        opacity: f32,
+    ) {
+        let viewport = projector.bounds.expand(self.radius);
+
+        let path = Path::new(|builder| {
+            for point in points {
+                let center = projector.geodetic_into_screen_space(*point);
+                if viewport.contains(center) {
+                    builder.circle(center, self.radius);
+                }
+            }
+        });
+
+        frame.fill(&path, self.color.scale_alpha(opacity));
+        frame.stroke(
+            &path,
+            Stroke::default()
+                .with_color(Color::WHITE.scale_alpha(opacity))
+                .with_width(1.0),
+        );
+    }
+}
+
+/// Draws the features of a [`GeoJsonFeed`]. New features fade in, removed features fade
+/// out, and points which moved glide to their new position.
+///
+/// The animations are evaluated at the time of drawing, so the application should request
+/// redraws while [`GeoJsonFeed::is_animating`].
+#[derive(Debug, Clone, Copy)]
+pub struct FeedLayer<'a> {
+    feed: &'a GeoJsonFeed,
+    style: FeedStyle,
+}
+
+impl<'a> FeedLayer<'a> {
+    pub fn new(feed: &'a GeoJsonFeed) -> Self {
+        Self {
+            feed,
+            style: FeedStyle::default(),
+        }
+    }
+
+    pub fn style(mut self, style: FeedStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    pub fn draw(&self, projector: &Projector, frame: &mut Frame<iced::Renderer>) {
+        let now = Instant::now();
+
+        for feature in self.feed.features.values() {
+            let Some(geometry) = &feature.feature.geometry else {
+                continue;
+            };
+
+            let progress = self.progress(feature, now);
+            let opacity = match (feature.added, feature.removed) {
+                (_, true) => 1.0 - progress,
+                (true, _) => progress,
+                _ => 1.0,
+            };
+
+            if opacity <= 0.0 {
+                continue;
+            }
+
+            // Glide moved points from their previous position
+            if let (Geometry::Point(to), Some(Geometry::Point(from))) =
+                (geometry, &feature.previous)
+                && progress < 1.0
+            {
+                let position = interpolate(*from, *to, progress);
+                self.style
+                    .draw_geometry(projector, frame, &Geometry::Point(position), opacity);
+            } else {
+                self.style
+                    .draw_geometry(projector, frame, geometry, opacity);
+            }
+        }
+    }
+
+    /// The eased progress of the most recent change, from `0.0` to `1.0`.
+    fn progress(&self, feature: &FeedFeature, now: Instant) -> f32 {
+        let transition = self.feed.transition.as_secs_f32();
+        if transition <= 0.0 {
+            return 1.0;
+        }
+
+        let t =
+            (now.saturating_duration_since(feature.changed).as_secs_f32() / transition).min(1.0);
+
+        // Ease out
+        1.0 - (1.0 - t).powi(3)
+    }
+}
+
+/// Interpolate between two coordinates in mercator space, matching straight lines on the map.
+fn interpolate(from: Geodetic, to: Geodetic, t: f32) -> Geodetic {
+    let (a, b, t) = (from.as_mercator(), to.as_mercator(), t as f64);
+    Mercator::new(
+        a.east_x() + (b.east_x() - a.east_x()) * t,
+        a.south_y() + (b.south_y() - a.south_y()) * t,
+    )
+    .as_geodetic()
+}
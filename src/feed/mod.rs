@@ -0,0 +1,269 @@
+//! Periodically re-fetched [GeoJSON](https://geojson.org/) feeds, such as earthquake
+//! catalogues, vehicle positions or incident reports.
+//!
+//! The [`GeoJsonFeed`] works like the [`crate::TileCache`]: it is held in the application
+//! state, and its [`GeoJsonFeed::update`] function must be glued into the application update
+//! loop. Send it a [`FeedMessage::Refresh`] to start fetching, after which it keeps itself
+//! up to date. Features are matched by their ID between fetches, such that changes can be
+//! animated by the [`FeedLayer`].
+
+use std::{collections::HashMap, time::Duration};
+
+use iced::{Task, time::Instant};
+
+mod geojson;
+mod layer;
+
+pub use crate::geometry::Geometry;
+pub use geojson::{Feature, parse_features};
+pub use layer::{FeedLayer, FeedStyle};
+
+/// The message that the [`GeoJsonFeed`] uses to update.
+#[derive(Debug, Clone)]
+pub enum FeedMessage {
+    /// Fetch the feed right away, and restart the refresh interval.
+    Refresh,
+    /// A refresh scheduled by a previous fetch.
+    Scheduled {
+        generation: u64,
+    },
+    Fetched(Vec<Feature>),
+    FetchFailed,
+}
+
+#[derive(thiserror::Error, Debug)]
+enum FeedError {
+    #[error(transparent)]
+    Reqwest(#[from] reqwest::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+/// A feature of the feed, along with its most recent change.
+#[derive(Debug, Clone)]
+struct FeedFeature {
+    feature: Feature,
+    /// The geometry before the most recent update, for animating the change.
+    previous: Option<Geometry>,
+    changed: Instant,
+    added: bool,
+    removed: bool,
+}
+
+/// Keeps the features of a remote GeoJSON document up to date.
+#[derive(Debug)]
+pub struct GeoJsonFeed {
+    url: String,
+    interval: Duration,
+    transition: Duration,
+    id_property: Option<String>,
+    client: reqwest::Client,
+    features: HashMap<String, FeedFeature>,
+    last_updated: Option<Instant>,
+    // Incremented for each refresh, such that superseded timers can be discarded
+    generation: u64,
+}
+
+impl GeoJsonFeed {
+    pub fn new(url: impl Into<String>, interval: Duration) -> Self {
+        Self {
+            url: url.into(),
+            interval,
+            transition: Duration::from_millis(750),
+            id_property: None,
+            client: reqwest::ClientBuilder::new()
+                .user_agent("lib-slippery")
+                .build()
+                .unwrap(),
+            features: HashMap::new(),
+            last_updated: None,
+            generation: 0,
+        }
+    }
+
+    /// The duration of the animations when features are added, moved or removed.
+    pub fn transition(mut self, transition: Duration) -> Self {
+        self.transition = transition;
+        self
+    }
+
+    /// Identify features by one of their properties, rather than the feature ID.
+    pub fn id_property(mut self, property: impl Into<String>) -> Self {
+        self.id_property = Some(property.into());
+        self
+    }
+
+    /// The current features of the feed, excluding those which are fading out.
+    pub fn features(&self) -> impl Iterator<Item = &Feature> {
+        self.features
+            .values()
+            .filter(|feature| !feature.removed)
+            .map(|feature| &feature.feature)
+    }
+
+    /// When the feed was last fetched successfully.
+    pub fn last_updated(&self) -> Option<Instant> {
+        self.last_updated
+    }
+
+    /// Whether any changes are still being animated, which requires regular redraws.
+    pub fn is_animating(&self, now: Instant) -> bool {
+        self.features
+            .values()
+            .any(|feature| now.saturating_duration_since(feature.changed) < self.transition)
+    }
+
+    pub fn layer(&self) -> FeedLayer<'_> {
+        FeedLayer::new(self)
+    }
+
+    pub fn update(&mut self, message: FeedMessage) -> Task<FeedMessage> {
+        match message {
+            FeedMessage::Refresh => {
+                self.generation += 1;
+                self.fetch()
+            }
+            FeedMessage::Scheduled { generation } => {
+                if generation == self.generation {
+                    self.fetch()
+                } else {
+                    Task::none()
+                }
+            }
+            FeedMessage::Fetched(features) => {
+                let now = Instant::now();
+                self.apply(features, now);
+                self.last_updated = Some(now);
+                self.schedule()
+            }
+            FeedMessage::FetchFailed => self.schedule(),
+        }
+    }
+
+    fn fetch(&self) -> Task<FeedMessage> {
+        let request = self.client.get(&self.url);
+
+        Task::future(async move {
+            let bytes = request.send().await?.error_for_status()?.bytes().await?;
+            Ok::<_, FeedError>(parse_features(&bytes)?)
+        })
+        .map(|result| match result {
+            Ok(features) => FeedMessage::Fetched(features),
+            Err(err) => {
+                log::warn!("Unable to fetch feed: {err}");
+                FeedMessage::FetchFailed
+            }
+        })
+    }
+
+    fn schedule(&self) -> Task<FeedMessage> {
+        let (generation, interval) = (self.generation, self.interval);
+        Task::future(async move {
+            crate::platform::sleep(interval).await;
+            FeedMessage::Scheduled { generation }
+        })
+    }
+
+    fn key(&self, index: usize, feature: &Feature) -> String {
+        let id = match &self.id_property {
+            Some(property) => feature.properties.get(property).map(|id| match id {
+                serde_json::Value::String(id) => id.clone(),
+                id => id.to_string(),
+            }),
+            None => feature.id.clone(),
+        };
+
+        // Features without an ID can not be matched, so they are matched by position instead
+        id.unwrap_or_else(|| format!("#{index}"))
+    }
+
+    /// Match the fetched features against the current ones, and record what changed.
+    fn apply(&mut self, features: Vec<Feature>, now: Instant) {
+        // Forget features which have finished fading out
+        let transition = self.transition;
+        self.features.retain(|_, feature| {
+            !feature.removed || now.saturating_duration_since(feature.changed) < transition
+        });
+
+        let mut seen = Vec::with_capacity(features.len());
+        for (index, feature) in features.into_iter().enumerate() {
+            let key = self.key(index, &feature);
+
+            match self.features.get_mut(&key) {
+                Some(existing) if existing.removed || existing.feature != feature => {
+                    let previous = std::mem::replace(&mut existing.feature, feature);
+                    existing.previous = (!existing.removed).then_some(previous.geometry).flatten();
+                    existing.added = existing.removed;
+                    existing.removed = false;
+                    existing.changed = now;
+                }
+                Some(_) => {}
+                None => {
+                    self.features.insert(
+                        key.clone(),
+                        FeedFeature {
+                            feature,
+                            previous: None,
+                            changed: now,
+                            added: true,
+                            removed: false,
+                        },
+                    );
+                }
+            }
+
+            seen.push(key);
+        }
+
+        seen.sort_unstable();
+        for (key, feature) in &mut self.features {
+            if !feature.removed && seen.binary_search(key).is_err() {
+                feature.removed = true;
+                feature.changed = now;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Geodetic;
+
+    fn point(id: &str, lon: f64) -> Feature {
+        Feature {
+            id: Some(id.to_string()),
+            geometry: Some(Geometry::Point(Geodetic::new(lon, 0.0))),
+            properties: Default::default(),
+        }
+    }
+
+    #[test]
+    fn diff_features_by_id() {
+        let mut feed = GeoJsonFeed::new("http://localhost", Duration::from_secs(60));
+        let start = Instant::now();
+
+        feed.apply(vec![point("a", 0.0), point("b", 0.0)], start);
+        assert!(feed.features.values().all(|f| f.added && !f.removed));
+
+        // `a` moves, `b` disappears and `c` appears
+        let later = start + Duration::from_secs(60);
+        feed.apply(vec![point("a", 1.0), point("c", 0.0)], later);
+
+        let a = &feed.features["a"];
+        assert!(!a.added && !a.removed);
+        assert_eq!(a.changed, later);
+        assert_eq!(a.previous, Some(Geometry::Point(Geodetic::new(0.0, 0.0))));
+
+        assert!(feed.features["b"].removed);
+        assert!(feed.features["c"].added);
+        assert_eq!(feed.features().count(), 2);
+
+        // Unchanged features are left alone, and faded out features are forgotten
+        let even_later = later + Duration::from_secs(60);
+        feed.apply(vec![point("a", 1.0), point("c", 0.0)], even_later);
+        assert_eq!(feed.features["a"].changed, later);
+        assert!(!feed.features.contains_key("b"));
+        assert!(!feed.is_animating(even_later));
+    }
+}
@@ -0,0 +1,24 @@
+//! Geometries of points, lines and polygons in geodetic coordinates, such as the features of a
+//! [GeoJSON](https://geojson.org/) feed. With the `wkt` feature, they can also be parsed from
+//! the well-known text and binary representations of spatial databases.
+
+use crate::Geodetic;
+
+#[cfg(feature = "wkt")]
+mod wkt;
+
+#[cfg(feature = "wkt")]
+pub use wkt::{GeometryError, parse_wkb, parse_wkb_hex, parse_wkt};
+
+/// A geometry, such as that of a GeoJSON feature. Rings and lines are lists of coordinates.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Geometry {
+    Point(Geodetic),
+    MultiPoint(Vec<Geodetic>),
+    LineString(Vec<Geodetic>),
+    MultiLineString(Vec<Vec<Geodetic>>),
+    /// The exterior ring, followed by any holes.
+    Polygon(Vec<Vec<Geodetic>>),
+    MultiPolygon(Vec<Vec<Vec<Geodetic>>>),
+    Collection(Vec<Geometry>),
+}
@@ -0,0 +1,503 @@
+//! Parsing of the [well-known text and binary](https://libgeos.org/specifications/wkb/)
+//! representations of geometries, as returned by spatial databases such as PostGIS and
+//! GeoPackage, into a [`Geometry`]. With the `geojson` feature, it can be drawn with
+//! `FeedStyle::draw` of the [`crate::feed`] module.
+//!
+//! Coordinates are taken as longitude and latitude, and any Z and M values are dropped. The
+//! extended variants of PostGIS with an embedded SRID are accepted, but the SRID is ignored.
+
+use super::Geometry;
+use crate::Geodetic;
+
+/// The deepest nesting of geometries which is parsed, such that a corrupt or hostile geometry
+/// returns an error rather than overflowing the stack.
+const MAX_DEPTH: usize = 32;
+
+/// The reason a geometry could not be parsed.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum GeometryError {
+    #[error("Unexpected end of the geometry")]
+    UnexpectedEnd,
+    #[error("Unexpected `{found}` at offset {offset}")]
+    Unexpected { found: String, offset: usize },
+    #[error("Unknown geometry type {0}")]
+    UnknownType(String),
+    #[error("Invalid hexadecimal encoding")]
+    InvalidHex,
+    #[error("The geometry is nested too deeply")]
+    TooDeep,
+}
+
+/// Parse a geometry from its well-known text, e.g. `POINT (12.5 41.9)`.
+pub fn parse_wkt(text: &str) -> Result<Geometry, GeometryError> {
+    let mut parser = WktParser { text, offset: 0 };
+
+    // Extended WKT starts with the SRID, e.g. `SRID=4326;POINT (12.5 41.9)`
+    if parser
+        .rest()
+        .get(..5)
+        .is_some_and(|srid| srid.eq_ignore_ascii_case("SRID="))
+    {
+        match parser.rest().find(';') {
+            Some(end) => parser.offset += end + 1,
+            None => return Err(GeometryError::UnexpectedEnd),
+        }
+    }
+
+    let geometry = parser.geometry(0)?;
+    parser.end()?;
+    Ok(geometry)
+}
+
+/// Parse a geometry from its well-known binary representation, which may also be the
+/// extended representation of PostGIS, or the geometry blob of a GeoPackage.
+pub fn parse_wkb(bytes: &[u8]) -> Result<Geometry, GeometryError> {
+    let mut reader = WkbReader { bytes, offset: 0 };
+
+    if bytes.starts_with(b"GP") {
+        reader.geopackage_header()?;
+    }
+
+    reader.geometry(0)
+}
+
+/// Parse a geometry from hex encoded well-known binary, as PostGIS returns it as text.
+pub fn parse_wkb_hex(hex: &str) -> Result<Geometry, GeometryError> {
+    let hex = hex.trim().trim_start_matches("\\x");
+    if !hex.len().is_multiple_of(2) {
+        return Err(GeometryError::InvalidHex);
+    }
+
+    let bytes = (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            hex.get(i..i + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+        })
+        .collect::<Option<Vec<u8>>>()
+        .ok_or(GeometryError::InvalidHex)?;
+
+    parse_wkb(&bytes)
+}
+
+/// An empty point, which has no coordinates to draw.
+fn empty_point() -> Geometry {
+    Geometry::MultiPoint(Vec::new())
+}
+
+struct WktParser<'a> {
+    text: &'a str,
+    offset: usize,
+}
+
+impl WktParser<'_> {
+    fn rest(&self) -> &str {
+        &self.text[self.offset..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.offset += rest.len() - rest.trim_start().len();
+    }
+
+    fn unexpected(&self) -> GeometryError {
+        match self.rest().split_whitespace().next() {
+            Some(found) => GeometryError::Unexpected {
+                found: found.chars().take(16).collect(),
+                offset: self.offset,
+            },
+            None => GeometryError::UnexpectedEnd,
+        }
+    }
+
+    /// Consume the next word, such as a geometry type or a dimension.
+    fn word(&mut self) -> Option<String> {
+        self.skip_whitespace();
+        let len = self
+            .rest()
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(self.rest().len());
+
+        let word = self.rest()[..len].to_ascii_uppercase();
+        self.offset += len;
+        (!word.is_empty()).then_some(word)
+    }
+
+    /// Consume a single character if it comes next.
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        let eaten = self.rest().starts_with(c);
+        if eaten {
+            self.offset += c.len_utf8();
+        }
+        eaten
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), GeometryError> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    fn end(&mut self) -> Result<(), GeometryError> {
+        self.skip_whitespace();
+        match self.rest() {
+            "" => Ok(()),
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    /// A geometry, nested in `depth` collections.
+    fn geometry(&mut self, depth: usize) -> Result<Geometry, GeometryError> {
+        if depth > MAX_DEPTH {
+            return Err(GeometryError::TooDeep);
+        }
+
+        let Some(kind) = self.word() else {
+            return Err(self.unexpected());
+        };
+
+        // The dimension, e.g. `POINT Z`, does not matter since only two values are used
+        let mut empty = false;
+        let checkpoint = self.offset;
+        match self.word().as_deref() {
+            Some("Z" | "M" | "ZM") => empty = self.word().as_deref() == Some("EMPTY"),
+            Some("EMPTY") => empty = true,
+            Some(_) => return Err(self.unexpected()),
+            None => self.offset = checkpoint,
+        }
+
+        if empty {
+            return Ok(match kind.as_str() {
+                "POINT" => empty_point(),
+                "MULTIPOINT" => Geometry::MultiPoint(Vec::new()),
+                "LINESTRING" => Geometry::LineString(Vec::new()),
+                "MULTILINESTRING" => Geometry::MultiLineString(Vec::new()),
+                "POLYGON" => Geometry::Polygon(Vec::new()),
+                "MULTIPOLYGON" => Geometry::MultiPolygon(Vec::new()),
+                "GEOMETRYCOLLECTION" => Geometry::Collection(Vec::new()),
+                _ => return Err(GeometryError::UnknownType(kind)),
+            });
+        }
+
+        Ok(match kind.as_str() {
+            "POINT" => {
+                self.expect('(')?;
+                let point = self.point()?;
+                self.expect(')')?;
+                Geometry::Point(point)
+            }
+            "MULTIPOINT" => Geometry::MultiPoint(self.list(|parser| {
+                // Points may or may not be enclosed by parentheses of their own
+                if parser.eat('(') {
+                    let point = parser.point()?;
+                    parser.expect(')')?;
+                    Ok(point)
+                } else {
+                    parser.point()
+                }
+            })?),
+            "LINESTRING" => Geometry::LineString(self.points()?),
+            "MULTILINESTRING" => Geometry::MultiLineString(self.list(Self::points)?),
+            "POLYGON" => Geometry::Polygon(self.list(Self::points)?),
+            "MULTIPOLYGON" => {
+                Geometry::MultiPolygon(self.list(|parser| parser.list(Self::points))?)
+            }
+            "GEOMETRYCOLLECTION" => {
+                Geometry::Collection(self.list(|parser| parser.geometry(depth + 1))?)
+            }
+            _ => return Err(GeometryError::UnknownType(kind)),
+        })
+    }
+
+    /// A parenthesized, comma separated list.
+    fn list<T>(
+        &mut self,
+        mut item: impl FnMut(&mut Self) -> Result<T, GeometryError>,
+    ) -> Result<Vec<T>, GeometryError> {
+        self.expect('(')?;
+        let mut items = vec![item(self)?];
+        while self.eat(',') {
+            items.push(item(self)?);
+        }
+        self.expect(')')?;
+        Ok(items)
+    }
+
+    fn points(&mut self) -> Result<Vec<Geodetic>, GeometryError> {
+        self.list(Self::point)
+    }
+
+    /// A coordinate of two to four numbers, of which the first two are used.
+    fn point(&mut self) -> Result<Geodetic, GeometryError> {
+        let longitude = self.number()?;
+        let latitude = self.number()?;
+        while self.number().is_ok() {}
+        Ok(Geodetic::new(longitude, latitude))
+    }
+
+    fn number(&mut self) -> Result<f64, GeometryError> {
+        self.skip_whitespace();
+        let len = self
+            .rest()
+            .find(|c: char| !(c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E')))
+            .unwrap_or(self.rest().len());
+
+        let number = self.rest()[..len].parse().map_err(|_| self.unexpected())?;
+        self.offset += len;
+        Ok(number)
+    }
+}
+
+struct WkbReader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl WkbReader<'_> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], GeometryError> {
+        let bytes = self
+            .bytes
+            .get(self.offset..self.offset + N)
+            .ok_or(GeometryError::UnexpectedEnd)?;
+        self.offset += N;
+        Ok(bytes.try_into().expect("slice has N bytes"))
+    }
+
+    fn u32(&mut self, little_endian: bool) -> Result<u32, GeometryError> {
+        let bytes = self.take()?;
+        Ok(match little_endian {
+            true => u32::from_le_bytes(bytes),
+            false => u32::from_be_bytes(bytes),
+        })
+    }
+
+    fn f64(&mut self, little_endian: bool) -> Result<f64, GeometryError> {
+        let bytes = self.take()?;
+        Ok(match little_endian {
+            true => f64::from_le_bytes(bytes),
+            false => f64::from_be_bytes(bytes),
+        })
+    }
+
+    /// Skip the header of a GeoPackage geometry blob, which precedes the WKB.
+    fn geopackage_header(&mut self) -> Result<(), GeometryError> {
+        let [_, _, _version, flags] = self.take()?;
+
+        // The SRID, followed by an envelope of a size given by the flags
+        let envelope = match (flags >> 1) & 0b111 {
+            0 => 0,
+            1 => 32,
+            2 | 3 => 48,
+            4 => 64,
+            _ => {
+                return Err(GeometryError::Unexpected {
+                    found: format!("{flags:#04x}"),
+                    offset: 3,
+                });
+            }
+        };
+
+        self.offset += 4 + envelope;
+        Ok(())
+    }
+
+    /// A geometry, nested in `depth` collections.
+    fn geometry(&mut self, depth: usize) -> Result<Geometry, GeometryError> {
+        if depth > MAX_DEPTH {
+            return Err(GeometryError::TooDeep);
+        }
+
+        let [byte_order] = self.take()?;
+        let nested = |wkb: &mut Self| wkb.geometry(depth + 1);
+        let little_endian = byte_order == 1;
+        let code = self.u32(little_endian)?;
+
+        // The extended WKB of PostGIS flags the dimensions and SRID in the high bits, while
+        // ISO WKB adds multiples of 1000 to the type
+        let dimensions =
+            2 + (code & 0x8000_0000 != 0) as usize + (code & 0x4000_0000 != 0) as usize;
+        if code & 0x2000_0000 != 0 {
+            self.u32(little_endian)?;
+        }
+        let code = code & 0x0fff_ffff;
+        let dimensions = match code / 1000 {
+            0 => dimensions,
+            1 | 2 => 3,
+            3 => 4,
+            _ => return Err(GeometryError::UnknownType(code.to_string())),
+        };
+
+        let mut reader = PointReader {
+            little_endian,
+            dimensions,
+        };
+
+        Ok(match code % 1000 {
+            1 => {
+                let point = reader.point(self)?;
+                // Empty points have NaN coordinates
+                match point.longitude().is_nan() {
+                    true => empty_point(),
+                    false => Geometry::Point(point),
+                }
+            }
+            2 => Geometry::LineString(reader.points(self)?),
+            3 => Geometry::Polygon(self.list(little_endian, |wkb| reader.points(wkb))?),
+            4 => Geometry::MultiPoint(
+                self.list(little_endian, nested)?
+                    .into_iter()
+                    .filter_map(|geometry| match geometry {
+                        Geometry::Point(point) => Some(point),
+                        _ => None,
+                    })
+                    .collect(),
+            ),
+            5 => Geometry::MultiLineString(
+                self.list(little_endian, nested)?
+                    .into_iter()
+                    .filter_map(|geometry| match geometry {
+                        Geometry::LineString(line) => Some(line),
+                        _ => None,
+                    })
+                    .collect(),
+            ),
+            6 => Geometry::MultiPolygon(
+                self.list(little_endian, nested)?
+                    .into_iter()
+                    .filter_map(|geometry| match geometry {
+                        Geometry::Polygon(rings) => Some(rings),
+                        _ => None,
+                    })
+                    .collect(),
+            ),
+            7 => Geometry::Collection(self.list(little_endian, nested)?),
+            _ => return Err(GeometryError::UnknownType(code.to_string())),
+        })
+    }
+
+    /// A list preceded by its number of items.
+    fn list<T>(
+        &mut self,
+        little_endian: bool,
+        mut item: impl FnMut(&mut Self) -> Result<T, GeometryError>,
+    ) -> Result<Vec<T>, GeometryError> {
+        let count = self.u32(little_endian)? as usize;
+
+        // Do not trust the count to allocate, in case the geometry is corrupt
+        let mut items = Vec::with_capacity(count.min(1024));
+        for _ in 0..count {
+            items.push(item(self)?);
+        }
+        Ok(items)
+    }
+}
+
+/// Reads the coordinates of a geometry, with the byte order and dimensions of its header.
+#[derive(Clone, Copy)]
+struct PointReader {
+    little_endian: bool,
+    dimensions: usize,
+}
+
+impl PointReader {
+    fn point(&mut self, wkb: &mut WkbReader) -> Result<Geodetic, GeometryError> {
+        let longitude = wkb.f64(self.little_endian)?;
+        let latitude = wkb.f64(self.little_endian)?;
+        for _ in 2..self.dimensions {
+            wkb.f64(self.little_endian)?;
+        }
+        Ok(Geodetic::new(longitude, latitude))
+    }
+
+    fn points(&mut self, wkb: &mut WkbReader) -> Result<Vec<Geodetic>, GeometryError> {
+        let mut reader = *self;
+        wkb.list(self.little_endian, |wkb| reader.point(wkb))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wkt_geometries() {
+        let rome = Geodetic::new(12.5, 41.9);
+        assert_eq!(parse_wkt("POINT (12.5 41.9)"), Ok(Geometry::Point(rome)));
+        assert_eq!(
+            parse_wkt("SRID=4326;point z(12.5 41.9 20)"),
+            Ok(Geometry::Point(rome))
+        );
+        assert_eq!(parse_wkt("POINT EMPTY"), Ok(empty_point()));
+        assert_eq!(
+            parse_wkt("MULTIPOINT ((12.5 41.9), 12.5 41.9)"),
+            Ok(Geometry::MultiPoint(vec![rome, rome]))
+        );
+        assert_eq!(
+            parse_wkt("MULTIPOLYGON (((0 0, 1 0, 1 1, 0 0)), ((2 2, 3 2, 3 3, 2 2)))").map(
+                |geometry| match geometry {
+                    Geometry::MultiPolygon(polygons) => polygons.len(),
+                    _ => 0,
+                }
+            ),
+            Ok(2)
+        );
+        assert_eq!(
+            parse_wkt("GEOMETRYCOLLECTION (POINT (12.5 41.9), LINESTRING EMPTY)"),
+            Ok(Geometry::Collection(vec![
+                Geometry::Point(rome),
+                Geometry::LineString(Vec::new())
+            ]))
+        );
+        assert!(matches!(
+            parse_wkt("POINT (12.5 41.9) trailing"),
+            Err(GeometryError::Unexpected { offset: 18, .. })
+        ));
+        assert_eq!(
+            parse_wkt("LINESTRING (0 0"),
+            Err(GeometryError::UnexpectedEnd)
+        );
+
+        // Collections nested without end are rejected, rather than overflowing the stack
+        let nested = "GEOMETRYCOLLECTION (".repeat(100_000);
+        assert_eq!(parse_wkt(&nested), Err(GeometryError::TooDeep));
+    }
+
+    #[test]
+    fn wkb_geometries() {
+        // POINT (1 2) in little endian WKB, as PostGIS returns it as text
+        let point = "0101000000000000000000F03F0000000000000040";
+        let expected = Ok(Geometry::Point(Geodetic::new(1.0, 2.0)));
+        assert_eq!(parse_wkb_hex(point), expected);
+
+        // The same point with an SRID in extended WKB, and in big endian
+        let srid = "0101000020E6100000000000000000F03F0000000000000040";
+        assert_eq!(parse_wkb_hex(srid), expected);
+        let big_endian = "00000000013FF00000000000004000000000000000";
+        assert_eq!(parse_wkb_hex(big_endian), expected);
+
+        // A GeoPackage blob without an envelope
+        let geopackage = format!("4750000100000000{point}");
+        assert_eq!(parse_wkb_hex(&geopackage), expected);
+
+        // LINESTRING Z (1 2 3, 4 5 6) in ISO WKB
+        let mut line = vec![1, 0xea, 0x03, 0, 0, 2, 0, 0, 0];
+        for value in [1.0f64, 2.0, 3.0, 4.0, 5.0, 6.0] {
+            line.extend(value.to_le_bytes());
+        }
+        assert_eq!(
+            parse_wkb(&line),
+            Ok(Geometry::LineString(vec![
+                Geodetic::new(1.0, 2.0),
+                Geodetic::new(4.0, 5.0)
+            ]))
+        );
+        assert_eq!(parse_wkb(&line[..20]), Err(GeometryError::UnexpectedEnd));
+
+        // Collections of a single collection each, nested without end
+        let nested = [1, 7, 0, 0, 0, 1, 0, 0, 0].repeat(100_000);
+        assert_eq!(parse_wkb(&nested), Err(GeometryError::TooDeep));
+    }
+}
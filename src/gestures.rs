@@ -0,0 +1,153 @@
+use std::time::Duration;
+
+use crate::Zoom;
+
+/// The smallest zoom step of a mouse wheel, as zoom targets are snapped to multiples of it.
+const MIN_ZOOM_STEP: f64 = 1e-3;
+
+/// How the map responds to the gestures of one kind of input device.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GestureProfile {
+    /// A factor on how far the map zooms. For a mouse wheel this is the number of zoom levels
+    /// per notch, for a trackpad it scales the scrolled pixels, and for touch it scales the
+    /// zoom of pinching. Mouse wheels zoom by at least a thousandth of a level per notch.
+    pub zoom_sensitivity: f64,
+    /// How long zooming continues after the input stops. For a mouse wheel this is the
+    /// duration of the animation of each notch, and otherwise the time constant of the
+    /// decaying zoom velocity.
+    pub zoom_inertia: Duration,
+    /// The time constant of the decaying velocity when panning continues after a release.
+    pub pan_inertia: Duration,
+    /// The speed in pixels per second above which panning continues after a release.
+    pub fling_threshold: f32,
+}
+
+impl GestureProfile {
+    /// The zoom levels per notch of a mouse wheel, kept away from zero and within the range
+    /// of zoom levels, such that zoom targets can be snapped to multiples of it.
+    pub(crate) fn zoom_step(&self) -> f64 {
+        if self.zoom_sensitivity.is_nan() {
+            return 1.0;
+        }
+        let step = self
+            .zoom_sensitivity
+            .abs()
+            .clamp(MIN_ZOOM_STEP, Zoom::MAX.f64());
+        step.copysign(self.zoom_sensitivity)
+    }
+
+    /// The time constant of the zoom velocity in seconds, kept above zero.
+    pub(crate) fn zoom_tau(&self) -> f64 {
+        self.zoom_inertia.as_secs_f64().max(1e-3)
+    }
+
+    /// The time constant of the pan velocity in seconds, kept above zero.
+    pub(crate) fn pan_tau(&self) -> f32 {
+        self.pan_inertia.as_secs_f32().max(1e-3)
+    }
+
+    /// Stop panning as soon as the input is released.
+    pub fn without_inertia(self) -> Self {
+        Self {
+            pan_inertia: Duration::ZERO,
+            fling_threshold: f32::INFINITY,
+            ..self
+        }
+    }
+}
+
+/// Separate [`GestureProfile`]s for each kind of input device, as one set of constants can
+/// not feel right on all of them. The device is detected from the input: scrolling by lines
+/// is a mouse wheel, scrolling by pixels is a precision trackpad, and touch events come
+/// from a touch screen. Dragging with the pointer uses the profile of the last device
+/// which scrolled, as mice and trackpads can not be told apart otherwise.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Gestures {
+    pub mouse: GestureProfile,
+    pub trackpad: GestureProfile,
+    pub touch: GestureProfile,
+}
+
+impl Default for Gestures {
+    fn default() -> Self {
+        Self {
+            mouse: GestureProfile {
+                zoom_sensitivity: 1.0,
+                zoom_inertia: Duration::from_millis(250),
+                pan_inertia: Duration::from_millis(200),
+                fling_threshold: 10.0,
+            },
+            trackpad: GestureProfile {
+                zoom_sensitivity: 1.0,
+                zoom_inertia: Duration::from_millis(50),
+                pan_inertia: Duration::from_millis(200),
+                fling_threshold: 10.0,
+            },
+            touch: GestureProfile {
+                zoom_sensitivity: 1.0,
+                zoom_inertia: Duration::from_millis(200),
+                pan_inertia: Duration::from_millis(200),
+                fling_threshold: 10.0,
+            },
+        }
+    }
+}
+
+impl Gestures {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn mouse(mut self, profile: GestureProfile) -> Self {
+        self.mouse = profile;
+        self
+    }
+
+    pub fn trackpad(mut self, profile: GestureProfile) -> Self {
+        self.trackpad = profile;
+        self
+    }
+
+    pub fn touch(mut self, profile: GestureProfile) -> Self {
+        self.touch = profile;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zoom_steps_stay_usable() {
+        let step = |zoom_sensitivity| {
+            GestureProfile {
+                zoom_sensitivity,
+                ..Gestures::default().mouse
+            }
+            .zoom_step()
+        };
+
+        assert_eq!(step(0.5), 0.5);
+        assert_eq!(step(-1.0), -1.0);
+        assert_eq!(step(0.0), MIN_ZOOM_STEP);
+        assert_eq!(step(f64::NAN), 1.0);
+        assert_eq!(step(f64::INFINITY), Zoom::MAX.f64());
+
+        // Zoom targets are snapped to multiples of the step
+        let target = (7.3 / step(0.0)).round() * step(0.0);
+        assert!(target.is_finite());
+    }
+
+    #[test]
+    fn profiles_without_inertia() {
+        let gestures = Gestures::new().touch(Gestures::default().touch.without_inertia());
+        assert_eq!(gestures.mouse, Gestures::default().mouse);
+
+        let touch = gestures.touch;
+        assert_eq!(touch.pan_inertia, Duration::ZERO);
+        assert!(touch.pan_tau() > 0.0);
+        assert!(touch.zoom_tau() > 0.0);
+        assert_eq!(touch.zoom_inertia, Gestures::default().touch.zoom_inertia);
+    }
+}
@@ -1,12 +1,277 @@
-use crate::Mercator;
-use iced::{Element, alignment};
+use std::time::Duration;
 
-/// Like a regular [`Element`] but tied to a specific [`Geodetic`] coordinate
+use crate::{Mercator, Projector};
+use iced::widget::{container, text, tooltip};
+use iced::{Color, Element, Point, Rectangle, Shadow, Size, Theme, Vector, alignment, border};
+
+/// How long the pointer has to rest on an element before its tooltip is shown.
+const TOOLTIP_DELAY: Duration = Duration::from_millis(400);
+
+/// Like a regular [`Element`] but tied to a specific [`Geodetic`] coordinate, or to a fixed
+/// place on the map, like a legend in one of its corners.
 pub struct GlobalElement<'a, Message, Theme, Renderer> {
     pub element: Element<'a, Message, Theme, Renderer>,
-    pub position: Mercator,
+    pub anchor: Anchor,
+    /// Moves the anchor by some pixels on the screen.
+    pub offset: Vector,
     pub horizontal_alignment: alignment::Horizontal,
     pub vertical_alignment: alignment::Vertical,
+    /// Placed as a popup instead, which ignores the alignment.
+    pub popup: Option<Popup>,
+    /// Which of two overlapping elements is kept when the map is decluttered, see
+    /// [`crate::MapProgram::declutter`].
+    pub priority: i32,
+}
+
+/// The point a [`GlobalElement`] is aligned to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Anchor {
+    /// A position on the map, which the element follows when panning and zooming.
+    Map(Mercator),
+    /// The side or corner of the map given by the alignment of the element, such that the
+    /// element stays in place, e.g. in the bottom right corner.
+    Screen,
+}
+
+impl Anchor {
+    /// The anchor in screen space, given the alignment of the element.
+    pub(crate) fn point(
+        &self,
+        projector: &Projector,
+        horizontal: alignment::Horizontal,
+        vertical: alignment::Vertical,
+    ) -> Point {
+        match self {
+            Anchor::Map(position) => projector.mercator_into_screen_space(*position),
+            Anchor::Screen => {
+                let bounds = projector.bounds;
+                let x = match horizontal {
+                    alignment::Horizontal::Left => bounds.x,
+                    alignment::Horizontal::Center => bounds.center_x(),
+                    alignment::Horizontal::Right => bounds.x + bounds.width,
+                };
+                let y = match vertical {
+                    alignment::Vertical::Top => bounds.y,
+                    alignment::Vertical::Center => bounds.center_y(),
+                    alignment::Vertical::Bottom => bounds.y + bounds.height,
+                };
+                Point::new(x, y)
+            }
+        }
+    }
+}
+
+/// A popup is placed next to its position, on whichever side it fits within the map, with a
+/// leader line pointing from the popup to the position.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Popup {
+    /// The distance between the popup and its position.
+    pub distance: f32,
+    pub color: Color,
+    /// The width of the leader line.
+    pub width: f32,
+}
+
+impl Default for Popup {
+    fn default() -> Self {
+        Self {
+            distance: 16.0,
+            color: Color::from_rgba(0.0, 0.0, 0.0, 0.6),
+            width: 2.0,
+        }
+    }
+}
+
+impl Popup {
+    /// The top left corner of a popup of some size, which is placed above, below, right or
+    /// left of the anchor, in that order of preference.
+    pub(crate) fn place(&self, anchor: Point, size: Size, bounds: Rectangle) -> Point {
+        let distance = self.distance;
+        let above = anchor.y - distance - size.height;
+        let below = anchor.y + distance;
+        let right = anchor.x + distance;
+        let left = anchor.x - distance - size.width;
+
+        let fits_x = |x: f32| x >= bounds.x && x + size.width <= bounds.x + bounds.width;
+        let fits_y = |y: f32| y >= bounds.y && y + size.height <= bounds.y + bounds.height;
+
+        // Slide along the side, while keeping the anchor within reach of the leader line
+        let slide = |start: f32, length: f32, anchor: f32, min: f32, max: f32| {
+            start
+                .min(max - length)
+                .max(min)
+                .clamp(anchor - length, anchor)
+        };
+        let slide_x = || {
+            let centered = anchor.x - size.width / 2.0;
+            slide(
+                centered,
+                size.width,
+                anchor.x,
+                bounds.x,
+                bounds.x + bounds.width,
+            )
+        };
+        let slide_y = || {
+            let centered = anchor.y - size.height / 2.0;
+            slide(
+                centered,
+                size.height,
+                anchor.y,
+                bounds.y,
+                bounds.y + bounds.height,
+            )
+        };
+
+        if fits_y(above) {
+            Point::new(slide_x(), above)
+        } else if fits_y(below) {
+            Point::new(slide_x(), below)
+        } else if fits_x(right) {
+            Point::new(right, slide_y())
+        } else if fits_x(left) {
+            Point::new(left, slide_y())
+        } else {
+            Point::new(slide_x(), above)
+        }
+    }
+
+    /// The leader line from the edge of the popup to its anchor, if the anchor is outside.
+    pub(crate) fn leader(&self, anchor: Point, popup: Rectangle) -> Option<Rectangle> {
+        let half = self.width / 2.0;
+        let (x, y) = (anchor.x - half, anchor.y - half);
+
+        if anchor.y >= popup.y + popup.height {
+            let top = popup.y + popup.height;
+            Some(Rectangle::new(
+                Point::new(x, top),
+                Size::new(self.width, anchor.y - top),
+            ))
+        } else if anchor.y <= popup.y {
+            Some(Rectangle::new(
+                Point::new(x, anchor.y),
+                Size::new(self.width, popup.y - anchor.y),
+            ))
+        } else if anchor.x >= popup.x + popup.width {
+            let left = popup.x + popup.width;
+            Some(Rectangle::new(
+                Point::new(left, y),
+                Size::new(anchor.x - left, self.width),
+            ))
+        } else if anchor.x <= popup.x {
+            Some(Rectangle::new(
+                Point::new(anchor.x, y),
+                Size::new(popup.x - anchor.x, self.width),
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+/// How [`GlobalElement`]s on the map which overlap each other are dealt with, keeping dense
+/// sets of markers readable without clustering them. Elements anchored to the screen take no
+/// part, and popups are always kept, while the others make way for them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Declutter {
+    /// Hide the elements which overlap one of a higher priority.
+    Hide,
+    /// Move the elements which overlap one of a higher priority up, down, right or left, by
+    /// at most some pixels, with a leader line back to their position. Elements which can
+    /// not be moved clear of the others are hidden.
+    Displace { max_distance: f32, leader: Popup },
+}
+
+/// Where a [`GlobalElement`] ends up after decluttering.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Placement {
+    Shown,
+    Moved(Vector),
+    Hidden,
+}
+
+/// An element as seen by the declutter pass.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Clutter {
+    /// Takes no part, like elements anchored to the screen.
+    Ignored,
+    /// Kept in place, while the others make way.
+    Fixed(Rectangle),
+    Movable {
+        bounds: Rectangle,
+        priority: i32,
+    },
+}
+
+impl Declutter {
+    /// The number of steps in which elements are moved away, up to the maximum distance.
+    const STEPS: u32 = 8;
+
+    /// Place the elements in order of their priority, or else their order on the map.
+    pub(crate) fn place(&self, elements: &[Clutter]) -> Vec<Placement> {
+        let mut placements = vec![Placement::Shown; elements.len()];
+        let mut taken: Vec<Rectangle> = elements
+            .iter()
+            .filter_map(|element| match element {
+                Clutter::Fixed(bounds) => Some(*bounds),
+                _ => None,
+            })
+            .collect();
+
+        let mut movable: Vec<(usize, Rectangle, i32)> = elements
+            .iter()
+            .enumerate()
+            .filter_map(|(i, element)| match element {
+                Clutter::Movable { bounds, priority } => Some((i, *bounds, *priority)),
+                _ => None,
+            })
+            .collect();
+        movable.sort_by_key(|(_, _, priority)| std::cmp::Reverse(*priority));
+
+        let is_free = |taken: &[Rectangle], bounds: Rectangle| {
+            !taken.iter().any(|other| other.intersects(&bounds))
+        };
+
+        for (i, bounds, _) in movable {
+            if is_free(&taken, bounds) {
+                taken.push(bounds);
+                continue;
+            }
+
+            let moved = match self {
+                Declutter::Hide => None,
+                Declutter::Displace { max_distance, .. } => (1..=Self::STEPS)
+                    .map(|step| max_distance * step as f32 / Self::STEPS as f32)
+                    .flat_map(|distance| {
+                        [
+                            Vector::new(0.0, -distance),
+                            Vector::new(0.0, distance),
+                            Vector::new(distance, 0.0),
+                            Vector::new(-distance, 0.0),
+                        ]
+                    })
+                    .find(|offset| is_free(&taken, bounds + *offset)),
+            };
+
+            placements[i] = match moved {
+                Some(offset) => {
+                    taken.push(bounds + offset);
+                    Placement::Moved(offset)
+                }
+                None => Placement::Hidden,
+            };
+        }
+
+        placements
+    }
+
+    /// The style of the leader lines of moved elements.
+    pub(crate) fn leader(&self) -> Option<&Popup> {
+        match self {
+            Declutter::Hide => None,
+            Declutter::Displace { leader, .. } => Some(leader),
+        }
+    }
 }
 
 impl<'a, Message, Theme, Renderer> GlobalElement<'a, Message, Theme, Renderer> {
@@ -16,12 +281,93 @@ impl<'a, Message, Theme, Renderer> GlobalElement<'a, Message, Theme, Renderer> {
     ) -> Self {
         Self {
             element: element.into(),
-            position,
+            anchor: Anchor::Map(position),
+            offset: Vector::ZERO,
             horizontal_alignment: alignment::Horizontal::Center,
             vertical_alignment: alignment::Vertical::Center,
+            popup: None,
+            priority: 0,
+        }
+    }
+
+    /// An element which is placed as a [`Popup`] next to its position.
+    pub fn popup(
+        element: impl Into<Element<'a, Message, Theme, Renderer>>,
+        position: Mercator,
+    ) -> Self {
+        Self::new(element, position).leader(Popup::default())
+    }
+
+    /// An element which stays in place on the screen, at the side or corner of the map given
+    /// by the alignment. Use [`GlobalElement::offset`] to keep some distance to the edges.
+    ///
+    /// ```ignore
+    /// // A legend 20 pixels from the bottom right corner
+    /// GlobalElement::screen(legend, Horizontal::Right, Vertical::Bottom)
+    ///     .offset(Vector::new(-20.0, -20.0))
+    /// ```
+    pub fn screen(
+        element: impl Into<Element<'a, Message, Theme, Renderer>>,
+        horizontal: alignment::Horizontal,
+        vertical: alignment::Vertical,
+    ) -> Self {
+        Self {
+            element: element.into(),
+            anchor: Anchor::Screen,
+            offset: Vector::ZERO,
+            horizontal_alignment: horizontal,
+            vertical_alignment: vertical,
+            popup: None,
+            priority: 0,
+        }
+    }
+
+    /// Move the anchor by some pixels, e.g. to place a callout above a position on the map.
+    pub fn offset(mut self, offset: Vector) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// The anchor of the element in screen space.
+    pub(crate) fn anchor_point(&self, projector: &Projector) -> Point {
+        self.anchor.point(
+            projector,
+            self.horizontal_alignment,
+            self.vertical_alignment,
+        ) + self.offset
+    }
+
+    /// Convert the messages of the element, keeping its placement.
+    pub fn map<B: 'a>(self, f: impl Fn(Message) -> B + 'a) -> GlobalElement<'a, B, Theme, Renderer>
+    where
+        Message: 'a,
+        Theme: 'a,
+        Renderer: iced_core::Renderer + 'a,
+    {
+        GlobalElement {
+            element: self.element.map(f),
+            anchor: self.anchor,
+            offset: self.offset,
+            horizontal_alignment: self.horizontal_alignment,
+            vertical_alignment: self.vertical_alignment,
+            popup: self.popup,
+            priority: self.priority,
         }
     }
 
+    /// Place the element as a popup, with the given style of leader line.
+    pub fn leader(mut self, popup: Popup) -> Self {
+        self.popup = Some(popup);
+        self
+    }
+
+    /// Elements with a higher priority are kept in place when the map is decluttered, while
+    /// those with a lower one make way.
+    pub fn priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
     pub fn align(
         mut self,
         horizontal: alignment::Horizontal,
@@ -32,3 +378,142 @@ impl<'a, Message, Theme, Renderer> GlobalElement<'a, Message, Theme, Renderer> {
         self
     }
 }
+
+impl<'a, Message: 'a> GlobalElement<'a, Message, Theme, iced::Renderer> {
+    /// Show a small bubble with some text when hovering the element for a moment. The bubble
+    /// is placed above the element, but moved to stay within the window.
+    pub fn tooltip(self, content: impl text::IntoFragment<'a>) -> Self {
+        let element = tooltip(self.element, text(content).size(12), tooltip::Position::Top)
+            .gap(4)
+            .padding(6)
+            .delay(TOOLTIP_DELAY)
+            .style(tooltip_bubble);
+
+        Self {
+            element: element.into(),
+            ..self
+        }
+    }
+}
+
+fn tooltip_bubble(theme: &Theme) -> container::Style {
+    let palette = theme.extended_palette();
+    container::Style {
+        text_color: Some(palette.background.base.text),
+        background: Some(palette.background.base.color.into()),
+        border: border::rounded(4)
+            .color(palette.background.strong.color)
+            .width(1),
+        shadow: Shadow {
+            color: Color::from_rgba(0.0, 0.0, 0.0, 0.25),
+            offset: Vector::new(0.0, 1.0),
+            blur_radius: 4.0,
+        },
+        ..container::Style::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOUNDS: Rectangle = Rectangle {
+        x: 0.0,
+        y: 0.0,
+        width: 400.0,
+        height: 300.0,
+    };
+    const SIZE: Size = Size::new(100.0, 50.0);
+
+    #[test]
+    fn popup_prefers_above() {
+        let popup = Popup::default();
+        let position = popup.place(Point::new(200.0, 150.0), SIZE, BOUNDS);
+        assert_eq!(position, Point::new(150.0, 84.0));
+
+        let leader = popup.leader(Point::new(200.0, 150.0), Rectangle::new(position, SIZE));
+        assert_eq!(leader.unwrap().height, 16.0);
+    }
+
+    #[test]
+    fn screen_anchor_follows_alignment() {
+        let projector = Projector {
+            viewpoint: crate::Viewpoint {
+                position: crate::location::paris().as_mercator(),
+                zoom: crate::Zoom::try_from(4.0).unwrap(),
+            },
+            bounds: BOUNDS,
+        };
+
+        let element = GlobalElement::<(), Theme, iced::Renderer>::screen(
+            text("Legend"),
+            alignment::Horizontal::Right,
+            alignment::Vertical::Bottom,
+        )
+        .offset(Vector::new(-20.0, -20.0));
+        assert_eq!(element.anchor_point(&projector), Point::new(380.0, 280.0));
+
+        // A map anchor is moved by the offset in screen space
+        let element = GlobalElement::<(), Theme, iced::Renderer>::new(
+            text("Paris"),
+            crate::location::paris().as_mercator(),
+        )
+        .offset(Vector::new(0.0, -10.0));
+        assert_eq!(element.anchor_point(&projector), Point::new(200.0, 140.0));
+    }
+
+    #[test]
+    fn popup_flips_to_stay_within_bounds() {
+        let popup = Popup::default();
+
+        // Too close to the top, so placed below
+        let position = popup.place(Point::new(200.0, 20.0), SIZE, BOUNDS);
+        assert_eq!(position.y, 36.0);
+
+        // Too close to the left edge, so slid to the right
+        let position = popup.place(Point::new(10.0, 150.0), SIZE, BOUNDS);
+        assert_eq!(position, Point::new(0.0, 84.0));
+    }
+
+    #[test]
+    fn declutter_keeps_higher_priority() {
+        let at = |x: f32, y: f32| Rectangle::new(Point::new(x, y), Size::new(20.0, 10.0));
+        let elements = [
+            Clutter::Movable {
+                bounds: at(0.0, 0.0),
+                priority: 0,
+            },
+            Clutter::Movable {
+                bounds: at(10.0, 0.0),
+                priority: 1,
+            },
+            Clutter::Fixed(at(100.0, 100.0)),
+            Clutter::Movable {
+                bounds: at(105.0, 100.0),
+                priority: 5,
+            },
+            Clutter::Ignored,
+        ];
+
+        let placements = Declutter::Hide.place(&elements);
+        assert_eq!(
+            placements,
+            [
+                Placement::Hidden,
+                Placement::Shown,
+                Placement::Shown,
+                Placement::Hidden,
+                Placement::Shown,
+            ]
+        );
+
+        // Moved up by the first step that clears the others, which may touch them
+        let displace = Declutter::Displace {
+            max_distance: 40.0,
+            leader: Popup::default(),
+        };
+        let placements = displace.place(&elements);
+        assert_eq!(placements[0], Placement::Moved(Vector::new(0.0, -10.0)));
+        assert_eq!(placements[3], Placement::Moved(Vector::new(0.0, -10.0)));
+    }
+}
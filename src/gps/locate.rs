@@ -0,0 +1,197 @@
+use std::time::Duration;
+
+use iced::futures::StreamExt;
+use iced::time::Instant;
+use iced::widget::{button, text};
+use iced::{Element, Subscription, Task};
+
+use super::{Fix, GpsError, PositionLayer, PositionProvider};
+use crate::animation::Flight;
+use crate::{Viewpoint, Zoom};
+
+#[derive(Debug, Clone)]
+pub enum LocateMessage {
+    /// Ask the provider for the current position.
+    Request,
+    Located(Result<Fix, GpsError>),
+    /// Advance the flight to the own position, produced by [`Locate::subscription`].
+    Frame(Instant),
+}
+
+/// A "locate me" control, which asks a [`PositionProvider`] for a single fix when pressed,
+/// and flies the camera to it. The own position is drawn by [`Locate::layer`].
+#[derive(Debug)]
+pub struct Locate<P> {
+    provider: P,
+    fix: Option<Fix>,
+    error: Option<GpsError>,
+    locating: bool,
+    zoom: Zoom,
+    duration: Duration,
+    flight: Option<Flight>,
+}
+
+impl<P: PositionProvider> Locate<P> {
+    pub fn new(provider: P) -> Self {
+        Self {
+            provider,
+            fix: None,
+            error: None,
+            locating: false,
+            zoom: Zoom::try_from(16.0).unwrap(),
+            duration: Duration::from_millis(1500),
+            flight: None,
+        }
+    }
+
+    /// The zoom level the camera flies to.
+    pub fn zoom(mut self, zoom: Zoom) -> Self {
+        self.zoom = zoom;
+        self
+    }
+
+    /// How long the flight to the own position takes.
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// The most recently located fix, if any.
+    pub fn fix(&self) -> Option<&Fix> {
+        self.fix.as_ref()
+    }
+
+    /// The error of the most recent request, if it failed.
+    pub fn error(&self) -> Option<&GpsError> {
+        self.error.as_ref()
+    }
+
+    /// Whether a request is waiting for the provider.
+    pub fn is_locating(&self) -> bool {
+        self.locating
+    }
+
+    /// Whether the camera is flying to the own position. The inertia of the map should be
+    /// disabled meanwhile, see [`crate::MapProgram::inertia`].
+    pub fn is_flying(&self) -> bool {
+        self.flight.is_some()
+    }
+
+    /// Produces a message for each frame while flying to the own position.
+    pub fn subscription(&self) -> Subscription<LocateMessage> {
+        if self.flight.is_some() {
+            iced::window::frames().map(LocateMessage::Frame)
+        } else {
+            Subscription::none()
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        viewpoint: &mut Viewpoint,
+        message: LocateMessage,
+    ) -> Task<LocateMessage> {
+        match message {
+            LocateMessage::Request => {
+                if self.locating {
+                    return Task::none();
+                }
+
+                self.locating = true;
+                let mut fixes = self.provider.fixes();
+                Task::future(async move {
+                    LocateMessage::Located(fixes.next().await.unwrap_or(Err(GpsError::Closed)))
+                })
+            }
+            LocateMessage::Located(result) => {
+                self.locating = false;
+                match result {
+                    Ok(fix) => {
+                        let target = Viewpoint {
+                            position: fix.position.as_mercator(),
+                            zoom: self.zoom,
+                        };
+                        self.flight = Some(Flight::new(*viewpoint, target, self.duration));
+                        self.fix = Some(fix);
+                        self.error = None;
+                    }
+                    Err(error) => self.error = Some(error),
+                }
+                Task::none()
+            }
+            LocateMessage::Frame(now) => {
+                if let Some(flight) = self.flight {
+                    *viewpoint = flight.viewpoint(now);
+                    if flight.is_finished(now) {
+                        self.flight = None;
+                    }
+                }
+                Task::none()
+            }
+        }
+    }
+
+    /// A button requesting the current position, which is disabled while waiting for it.
+    pub fn button(&self) -> Element<'_, LocateMessage> {
+        let label = if self.locating {
+            "Locating.."
+        } else {
+            "Locate me"
+        };
+
+        button(text(label))
+            .on_press_maybe((!self.locating).then_some(LocateMessage::Request))
+            .into()
+    }
+
+    /// Create a [`PositionLayer`] for drawing the located position and its accuracy.
+    pub fn layer(&self) -> PositionLayer<'_> {
+        PositionLayer::new(self.fix.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gps::ManualProvider;
+    use crate::location;
+
+    #[test]
+    fn fly_to_located_position() {
+        let fix = Fix {
+            position: location::berlin(),
+            accuracy: Some(25.0),
+            course: None,
+            speed: None,
+            altitude: None,
+        };
+        let mut locate = Locate::new(ManualProvider::new(fix));
+        let mut viewpoint = Viewpoint {
+            position: location::paris().as_mercator(),
+            zoom: Zoom::try_from(10.0).unwrap(),
+        };
+
+        let _ = locate.update(&mut viewpoint, LocateMessage::Request);
+        assert!(locate.is_locating());
+
+        let _ = locate.update(&mut viewpoint, LocateMessage::Located(Ok(fix)));
+        assert!(!locate.is_locating());
+        assert_eq!(locate.fix(), Some(&fix));
+
+        // The camera only moves as the flight progresses
+        assert!(locate.is_flying());
+        assert_eq!(viewpoint.position, location::paris().as_mercator());
+        let landed = Instant::now() + Duration::from_secs(2);
+        let _ = locate.update(&mut viewpoint, LocateMessage::Frame(landed));
+        assert_eq!(viewpoint.position, location::berlin().as_mercator());
+        assert_eq!(viewpoint.zoom, Zoom::try_from(16.0).unwrap());
+        assert!(!locate.is_flying());
+
+        let _ = locate.update(
+            &mut viewpoint,
+            LocateMessage::Located(Err(GpsError::Closed)),
+        );
+        assert!(locate.error().is_some());
+        assert_eq!(locate.fix(), Some(&fix));
+    }
+}
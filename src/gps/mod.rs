@@ -0,0 +1,312 @@
+//! Display of the own position from a GPS receiver, along with a camera that follows it.
+//!
+//! Positions are delivered by a [`PositionProvider`], such as the [`NmeaProvider`] which
+//! reads NMEA-0183 sentences from a serial device or TCP connection, or the
+//! [`GpsdProvider`]. Other sources, like the location services of the operating system,
+//! are supported by implementing the trait.
+//!
+//! Glue the [`subscription`] into the application, and feed the fixes to a [`Follow`]
+//! camera and a [`PositionLayer`]. Alternatively, the [`Locate`] control only asks for the
+//! position when pressed, and flies the camera there.
+
+mod locate;
+mod nmea;
+
+pub use locate::{Locate, LocateMessage};
+pub use nmea::{GpsdProvider, NmeaParser, NmeaProvider, read_fixes};
+
+use std::{
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+
+use iced::futures::{StreamExt, stream::BoxStream};
+use iced::widget::canvas::{Frame, Path, Stroke};
+use iced::{Color, Subscription, Vector};
+
+use crate::{Geodetic, Mercator, Projector, Viewpoint};
+
+/// A single position fix from a GPS receiver.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fix {
+    pub position: Geodetic,
+    /// Estimated horizontal accuracy in meters, if reported by the receiver.
+    pub accuracy: Option<f64>,
+    /// Course over ground in degrees, clockwise from north.
+    pub course: Option<f64>,
+    /// Speed over ground in meters per second.
+    pub speed: Option<f64>,
+    /// Altitude above mean sea level in meters.
+    pub altitude: Option<f64>,
+}
+
+#[derive(thiserror::Error, Debug, Clone)]
+pub enum GpsError {
+    #[error("Unable to read from the receiver: {0}")]
+    Io(Arc<std::io::Error>),
+    #[error("The receiver closed the connection")]
+    Closed,
+}
+
+impl From<std::io::Error> for GpsError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(Arc::new(error))
+    }
+}
+
+/// A source of position fixes. The provider is hashed to identify its [`subscription`],
+/// so providers which compare equal share the same underlying connection.
+pub trait PositionProvider: Hash + 'static {
+    /// Connect to the receiver and stream its fixes. The stream ends after an error.
+    fn fixes(&self) -> BoxStream<'static, Result<Fix, GpsError>>;
+}
+
+/// Subscribe to the fixes of a [`PositionProvider`].
+pub fn subscription<P: PositionProvider>(provider: P) -> Subscription<Result<Fix, GpsError>> {
+    Subscription::run_with(provider, |provider| provider.fixes())
+}
+
+/// Provides a fixed position, such as one entered by the user or injected for testing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ManualProvider {
+    fix: Fix,
+}
+
+impl ManualProvider {
+    pub fn new(fix: Fix) -> Self {
+        Self { fix }
+    }
+}
+
+impl Hash for ManualProvider {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let Fix {
+            position,
+            accuracy,
+            course,
+            speed,
+            altitude,
+        } = self.fix;
+
+        position.longitude().to_bits().hash(state);
+        position.latitude().to_bits().hash(state);
+        for value in [accuracy, course, speed, altitude] {
+            value.map(f64::to_bits).hash(state);
+        }
+    }
+}
+
+impl PositionProvider for ManualProvider {
+    fn fixes(&self) -> BoxStream<'static, Result<Fix, GpsError>> {
+        iced::futures::stream::once(std::future::ready(Ok(self.fix))).boxed()
+    }
+}
+
+/// Keeps the camera centered on the own position, until the map is panned away.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Follow {
+    active: bool,
+    target: Option<Mercator>,
+}
+
+impl Default for Follow {
+    fn default() -> Self {
+        Self {
+            active: true,
+            target: None,
+        }
+    }
+}
+
+impl Follow {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Start or stop following. When started, the camera jumps to the last known position.
+    pub fn set_active(&mut self, viewpoint: &mut Viewpoint, active: bool) {
+        self.active = active;
+        if active && let Some(target) = self.target {
+            viewpoint.position = target;
+        }
+    }
+
+    /// Center the viewpoint on a new fix, if following.
+    pub fn fix(&mut self, viewpoint: &mut Viewpoint, fix: &Fix) {
+        let target = fix.position.as_mercator();
+        self.target = Some(target);
+
+        if self.active {
+            viewpoint.position = target;
+        }
+    }
+
+    /// Apply a viewpoint update from the map, as received through
+    /// [`crate::MapProgram::on_update`]. Zooming keeps following the own position,
+    /// while panning the map stops following.
+    pub fn update(&mut self, viewpoint: &mut Viewpoint, updated: Viewpoint) {
+        if self.active
+            && let Some(target) = self.target
+        {
+            if updated.zoom != viewpoint.zoom {
+                *viewpoint = Viewpoint {
+                    position: target,
+                    zoom: updated.zoom,
+                };
+                return;
+            }
+
+            if updated.position != viewpoint.position {
+                self.active = false;
+            }
+        }
+
+        *viewpoint = updated;
+    }
+}
+
+/// The visual appearance of a [`PositionLayer`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PositionStyle {
+    pub color: Color,
+    pub radius: f32,
+    pub accuracy_color: Color,
+}
+
+impl Default for PositionStyle {
+    fn default() -> Self {
+        Self {
+            color: Color::from_rgb(0.1, 0.45, 0.95),
+            radius: 7.0,
+            accuracy_color: Color::from_rgba(0.1, 0.45, 0.95, 0.15),
+        }
+    }
+}
+
+/// Draws the own position as a dot, surrounded by a circle showing its accuracy.
+/// While moving, an arrow shows the course over ground.
+#[derive(Debug, Clone, Copy)]
+pub struct PositionLayer<'a> {
+    fix: Option<&'a Fix>,
+    style: PositionStyle,
+}
+
+impl<'a> PositionLayer<'a> {
+    pub fn new(fix: Option<&'a Fix>) -> Self {
+        Self {
+            fix,
+            style: PositionStyle::default(),
+        }
+    }
+
+    pub fn style(mut self, style: PositionStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    pub fn draw(&self, projector: &Projector, frame: &mut Frame<iced::Renderer>) {
+        let Some(fix) = self.fix else {
+            return;
+        };
+
+        let center = projector.geodetic_into_screen_space(fix.position);
+
+        if let Some(accuracy) = fix.accuracy {
+            let edge =
+                projector.geodetic_into_screen_space(fix.position.destination(90.0, accuracy));
+            let circle = Path::circle(center, center.distance(edge));
+            frame.fill(&circle, self.style.accuracy_color);
+            frame.stroke(
+                &circle,
+                Stroke::default()
+                    .with_color(self.style.accuracy_color.scale_alpha(3.0))
+                    .with_width(1.0),
+            );
+        }
+
+        // Only show the course while moving, as it is unreliable when standing still
+        if let (Some(course), Some(speed)) = (fix.course, fix.speed)
+            && speed > 0.5
+        {
+            let radius = self.style.radius;
+            let direction = Vector::new(
+                course.to_radians().sin() as f32,
+                -course.to_radians().cos() as f32,
+            );
+            let normal = Vector::new(-direction.y, direction.x);
+
+            let arrow = Path::new(|builder| {
+                builder.move_to(center + direction * radius * 2.5);
+                builder.line_to(center + normal * radius);
+                builder.line_to(center - normal * radius);
+                builder.close();
+            });
+            frame.fill(&arrow, self.style.color);
+        }
+
+        let dot = Path::circle(center, self.style.radius);
+        frame.fill(&dot, self.style.color);
+        frame.stroke(
+            &dot,
+            Stroke::default().with_color(Color::WHITE).with_width(2.0),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Zoom, location};
+
+    fn fix(position: Geodetic) -> Fix {
+        Fix {
+            position,
+            accuracy: None,
+            course: None,
+            speed: None,
+            altitude: None,
+        }
+    }
+
+    #[test]
+    fn follow_until_panned() {
+        let mut follow = Follow::new();
+        let mut viewpoint = Viewpoint {
+            position: location::paris().as_mercator(),
+            zoom: Zoom::try_from(10.0).unwrap(),
+        };
+
+        follow.fix(&mut viewpoint, &fix(location::berlin()));
+        assert_eq!(viewpoint.position, location::berlin().as_mercator());
+
+        // Zooming around the cursor stays centered on the own position
+        follow.update(
+            &mut viewpoint,
+            Viewpoint {
+                position: location::rome().as_mercator(),
+                zoom: Zoom::try_from(12.0).unwrap(),
+            },
+        );
+        assert!(follow.is_active());
+        assert_eq!(viewpoint.position, location::berlin().as_mercator());
+
+        // Panning stops following
+        let panned = Viewpoint {
+            position: location::rome().as_mercator(),
+            zoom: viewpoint.zoom,
+        };
+        follow.update(&mut viewpoint, panned);
+        assert!(!follow.is_active());
+
+        follow.fix(&mut viewpoint, &fix(location::vienna()));
+        assert_eq!(viewpoint, panned);
+
+        // Re-enabling jumps back to the last fix
+        follow.set_active(&mut viewpoint, true);
+        assert_eq!(viewpoint.position, location::vienna().as_mercator());
+    }
+}
@@ -0,0 +1,272 @@
+//! A minimal [NMEA-0183](https://en.wikipedia.org/wiki/NMEA_0183) parser, which only
+//! understands the sentences needed for a position fix: `GGA`, `RMC` and `GST`.
+
+use std::path::PathBuf;
+
+use iced::futures::{SinkExt, StreamExt, stream::BoxStream};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWriteExt, BufReader};
+
+use super::{Fix, GpsError, PositionProvider};
+use crate::Geodetic;
+
+/// Meters per second in a knot.
+const KNOT: f64 = 1852.0 / 3600.0;
+
+/// A typical user equivalent range error in meters, used to estimate the accuracy from
+/// the horizontal dilution of precision, when the receiver does not report it directly.
+const UERE: f64 = 5.0;
+
+/// Combines the information of consecutive NMEA sentences into position fixes.
+#[derive(Debug, Clone, Default)]
+pub struct NmeaParser {
+    /// Accuracy from the most recent `GST` sentence.
+    accuracy: Option<f64>,
+    /// Horizontal dilution of precision from the most recent `GGA` sentence.
+    hdop: Option<f64>,
+    altitude: Option<f64>,
+    course: Option<f64>,
+    speed: Option<f64>,
+}
+
+impl NmeaParser {
+    /// Parse a single sentence, returning a fix if the sentence contained a valid position.
+    /// Sentences with an invalid checksum are ignored.
+    pub fn parse_line(&mut self, line: &str) -> Option<Fix> {
+        let fields = checked_fields(line.trim())?;
+        let (kind, fields) = fields.split_first()?;
+
+        // Ignore the talker ID, such that GPS, GLONASS and combined fixes are all accepted
+        match kind.get(2..)? {
+            "GGA" => {
+                // A fix quality of 0 means no fix
+                if fields.get(5)?.parse::<u8>().ok()? == 0 {
+                    return None;
+                }
+
+                self.hdop = fields.get(7).and_then(|hdop| hdop.parse().ok());
+                self.altitude = fields.get(8).and_then(|altitude| altitude.parse().ok());
+
+                let position = position(fields.get(1..5)?)?;
+                Some(self.fix(position))
+            }
+            "RMC" => {
+                // Status `A` means the data is valid, `V` is a warning
+                if *fields.get(1)? != "A" {
+                    return None;
+                }
+
+                self.speed = fields
+                    .get(6)
+                    .and_then(|speed| speed.parse::<f64>().ok())
+                    .map(|knots| knots * KNOT);
+                self.course = fields.get(7).and_then(|course| course.parse().ok());
+
+                let position = position(fields.get(2..6)?)?;
+                Some(self.fix(position))
+            }
+            "GST" => {
+                let lat_deviation: f64 = fields.get(5)?.parse().ok()?;
+                let lon_deviation: f64 = fields.get(6)?.parse().ok()?;
+                self.accuracy = Some(lat_deviation.hypot(lon_deviation));
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn fix(&self, position: Geodetic) -> Fix {
+        Fix {
+            position,
+            accuracy: self.accuracy.or(self.hdop.map(|hdop| hdop * UERE)),
+            course: self.course,
+            speed: self.speed,
+            altitude: self.altitude,
+        }
+    }
+}
+
+/// Split a sentence into its fields, if it is well-formed and its checksum matches.
+fn checked_fields(line: &str) -> Option<Vec<&str>> {
+    let body = line.strip_prefix('$')?;
+    let (body, checksum) = body.split_once('*')?;
+
+    let expected = u8::from_str_radix(checksum, 16).ok()?;
+    let actual = body.bytes().fold(0, |sum, byte| sum ^ byte);
+    if expected != actual {
+        return None;
+    }
+
+    Some(body.split(',').collect())
+}
+
+/// Parse the latitude and longitude fields, formatted as `ddmm.mmm,N,dddmm.mmm,E`.
+fn position(fields: &[&str]) -> Option<Geodetic> {
+    let [lat, north_south, lon, east_west] = fields else {
+        return None;
+    };
+
+    let degrees_minutes = |value: &str, degree_digits: usize| -> Option<f64> {
+        let degrees: f64 = value.get(..degree_digits)?.parse().ok()?;
+        let minutes: f64 = value.get(degree_digits..)?.parse().ok()?;
+        Some(degrees + minutes / 60.0)
+    };
+
+    let lat = match *north_south {
+        "N" => degrees_minutes(lat, 2)?,
+        "S" => -degrees_minutes(lat, 2)?,
+        _ => return None,
+    };
+
+    let lon = match *east_west {
+        "E" => degrees_minutes(lon, 3)?,
+        "W" => -degrees_minutes(lon, 3)?,
+        _ => return None,
+    };
+
+    Some(Geodetic::new(lon, lat))
+}
+
+/// Read position fixes from a stream of NMEA sentences, one per line.
+pub fn read_fixes(
+    reader: impl AsyncRead + Send + Unpin + 'static,
+) -> BoxStream<'static, Result<Fix, GpsError>> {
+    iced::stream::try_channel(16, async move |mut output| {
+        let mut lines = BufReader::new(reader).lines();
+        let mut parser = NmeaParser::default();
+
+        while let Some(line) = lines.next_line().await? {
+            if let Some(fix) = parser.parse_line(&line)
+                && output.send(fix).await.is_err()
+            {
+                // The subscription was dropped
+                return Ok(());
+            }
+        }
+
+        Err(GpsError::Closed)
+    })
+    .boxed()
+}
+
+/// Reads NMEA-0183 sentences from a GPS receiver.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum NmeaProvider {
+    /// Connect to a TCP server streaming raw NMEA sentences, such as a marine
+    /// instrument gateway or `gpsd` in raw mode.
+    Tcp(String),
+    /// Read from a serial device, such as `/dev/ttyUSB0`. The device is read like a file,
+    /// so its baud rate must be configured beforehand, for example with `stty`.
+    Serial(PathBuf),
+}
+
+impl PositionProvider for NmeaProvider {
+    fn fixes(&self) -> BoxStream<'static, Result<Fix, GpsError>> {
+        let provider = self.clone();
+
+        iced::futures::stream::once(async move {
+            match provider {
+                NmeaProvider::Tcp(address) => tokio::net::TcpStream::connect(address)
+                    .await
+                    .map(read_fixes),
+                NmeaProvider::Serial(path) => tokio::fs::File::open(path).await.map(read_fixes),
+            }
+        })
+        .flat_map(|result| match result {
+            Ok(fixes) => fixes,
+            Err(error) => iced::futures::stream::once(async move { Err(error.into()) }).boxed(),
+        })
+        .boxed()
+    }
+}
+
+/// Reads the position from a [`gpsd`](https://gpsd.io) daemon, which shares the receivers
+/// of the system between applications.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GpsdProvider {
+    address: String,
+}
+
+impl Default for GpsdProvider {
+    fn default() -> Self {
+        Self::new("127.0.0.1:2947")
+    }
+}
+
+impl GpsdProvider {
+    pub fn new(address: impl Into<String>) -> Self {
+        Self {
+            address: address.into(),
+        }
+    }
+}
+
+impl PositionProvider for GpsdProvider {
+    fn fixes(&self) -> BoxStream<'static, Result<Fix, GpsError>> {
+        let address = self.address.clone();
+
+        iced::futures::stream::once(async move {
+            let mut stream = tokio::net::TcpStream::connect(address).await?;
+
+            // Ask for the raw NMEA sentences, as the JSON reports are not parsed. The
+            // status reports sent along with them are ignored by the parser.
+            stream
+                .write_all(b"?WATCH={\"enable\":true,\"nmea\":true};\n")
+                .await?;
+
+            Ok::<_, std::io::Error>(read_fixes(stream))
+        })
+        .flat_map(|result| match result {
+            Ok(fixes) => fixes,
+            Err(error) => iced::futures::stream::once(async move { Err(error.into()) }).boxed(),
+        })
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GGA: &str = "$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47";
+    const RMC: &str = "$GPRMC,123519,A,4807.038,N,01131.000,E,022.4,084.4,230394,003.1,W*6A";
+    const GST: &str = "$GPGST,172814.0,0.006,0.023,0.020,273.6,0.023,0.020,0.031*6A";
+
+    #[test]
+    fn parse_gga() {
+        let mut parser = NmeaParser::default();
+        let fix = parser.parse_line(GGA).unwrap();
+
+        approx::assert_relative_eq!(fix.position.latitude(), 48.1173, epsilon = 1e-6);
+        approx::assert_relative_eq!(fix.position.longitude(), 11.516_666, epsilon = 1e-6);
+        assert_eq!(fix.altitude, Some(545.4));
+        approx::assert_relative_eq!(fix.accuracy.unwrap(), 0.9 * UERE);
+    }
+
+    #[test]
+    fn parse_rmc_and_gst() {
+        let mut parser = NmeaParser::default();
+        assert_eq!(parser.parse_line(GST), None);
+
+        let fix = parser.parse_line(RMC).unwrap();
+        approx::assert_relative_eq!(fix.speed.unwrap(), 22.4 * KNOT);
+        assert_eq!(fix.course, Some(84.4));
+
+        // The accuracy reported by GST takes precedence
+        approx::assert_relative_eq!(fix.accuracy.unwrap(), 0.023f64.hypot(0.020));
+    }
+
+    #[test]
+    fn reject_invalid_sentences() {
+        let mut parser = NmeaParser::default();
+
+        // Bad checksum
+        assert_eq!(parser.parse_line(&GGA.replace("*47", "*48")), None);
+
+        // No fix
+        let no_fix = "$GPGGA,123519,,,,,0,00,,,M,,M,,*6B";
+        assert_eq!(parser.parse_line(no_fix), None);
+
+        // Garbage
+        assert_eq!(parser.parse_line("hello"), None);
+    }
+}
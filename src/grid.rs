@@ -0,0 +1,440 @@
+//! Coordinate grids drawn over the map: the zones and 100 km squares of the
+//! [Military Grid Reference System](https://en.wikipedia.org/wiki/Military_Grid_Reference_System),
+//! and the fields and squares of
+//! [Maidenhead locators](https://en.wikipedia.org/wiki/Maidenhead_Locator_System).
+//!
+//! The conversions between positions and the references of either grid are found on
+//! [`Geodetic`].
+
+use iced::widget::canvas::{self, Frame, Path, Stroke, path};
+use iced::{Color, Point, Vector, alignment};
+
+use crate::position::utm::{self, BANDS, Utm, UtmZone};
+use crate::position::{maidenhead, mgrs};
+use crate::{Geodetic, Projector};
+
+/// Grid cells narrower than this many pixels are not drawn.
+const MIN_CELL: f64 = 48.0;
+
+/// Cells narrower than this many pixels are not labelled.
+const MIN_LABEL: f64 = 80.0;
+
+/// Lines of the 100 km squares are curved on the map, so they are traced with this many
+/// segments across each grid zone.
+const SAMPLES: usize = 32;
+
+/// The visible part of the map in degrees, as `(west, south, east, north)`.
+fn extent(projector: &Projector) -> Option<(f64, f64, f64, f64)> {
+    let bounds = projector.bounds;
+    let top_left = projector.screen_space_into_geodetic(bounds.position());
+    let bottom_right = projector
+        .screen_space_into_geodetic(bounds.position() + Vector::new(bounds.width, bounds.height));
+
+    let west = top_left.longitude().max(-180.0);
+    let east = bottom_right.longitude().min(180.0);
+    let south = bottom_right.latitude().max(-Geodetic::MAX_LATITUDE);
+    let north = top_left.latitude().min(Geodetic::MAX_LATITUDE);
+
+    (west < east && south < north).then_some((west, south, east, north))
+}
+
+/// The number of pixels per degree of longitude.
+fn pixels_per_degree(projector: &Projector) -> f64 {
+    let a = projector.screen_space_into_geodetic(projector.bounds.center());
+    let b =
+        projector.screen_space_into_geodetic(projector.bounds.center() + Vector::new(100.0, 0.0));
+    100.0 / (b.longitude() - a.longitude())
+}
+
+fn label(frame: &mut Frame<iced::Renderer>, content: String, position: Point, color: Color) {
+    frame.fill_text(canvas::Text {
+        content,
+        position,
+        color,
+        size: 13.0.into(),
+        align_x: alignment::Horizontal::Center.into(),
+        align_y: alignment::Vertical::Center,
+        ..Default::default()
+    });
+}
+
+/// Trace a line through the points, leaving gaps where they are `None`.
+fn trace(builder: &mut path::Builder, points: impl Iterator<Item = Option<Point>>) {
+    let mut drawing = false;
+    for point in points {
+        match point {
+            Some(point) if drawing => builder.line_to(point),
+            Some(point) => builder.move_to(point),
+            None => {}
+        }
+        drawing = point.is_some();
+    }
+}
+
+/// Draws the fields, squares, subsquares or extended squares of Maidenhead locators,
+/// whichever is the finest level still legible at the current zoom level.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MaidenheadGrid {
+    color: Color,
+    labels: bool,
+}
+
+impl Default for MaidenheadGrid {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MaidenheadGrid {
+    pub fn new() -> Self {
+        Self {
+            color: Color::from_rgb(0.8, 0.1, 0.3),
+            labels: true,
+        }
+    }
+
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Whether to label each cell with its locator.
+    pub fn labels(mut self, labels: bool) -> Self {
+        self.labels = labels;
+        self
+    }
+
+    /// The number of pairs of characters of the locators of the cells drawn at the current
+    /// zoom level.
+    pub fn pairs(projector: &Projector) -> usize {
+        let scale = pixels_per_degree(projector);
+        (1..=maidenhead::MAX_PAIRS)
+            .rev()
+            .find(|&pairs| maidenhead::cell_size(pairs).0 * scale >= MIN_CELL)
+            .unwrap_or(1)
+    }
+
+    pub fn draw(&self, projector: &Projector, frame: &mut Frame<iced::Renderer>) {
+        let Some((west, south, east, north)) = extent(projector) else {
+            return;
+        };
+
+        let pairs = Self::pairs(projector);
+        let point =
+            |lon: f64, lat: f64| projector.geodetic_into_screen_space(Geodetic::new(lon, lat));
+
+        // The lines of the cells, where the lines of the enclosing cells are drawn bolder
+        for level in [pairs, pairs - 1].into_iter().filter(|&level| level > 0) {
+            let (width, height) = maidenhead::cell_size(level);
+            let path = Path::new(|builder| {
+                for lon in steps(west, east, -180.0, width) {
+                    builder.move_to(point(lon, south));
+                    builder.line_to(point(lon, north));
+                }
+                for lat in steps(south, north, -90.0, height) {
+                    builder.move_to(point(west, lat));
+                    builder.line_to(point(east, lat));
+                }
+            });
+
+            let stroke_width = if level == pairs { 1.0 } else { 2.0 };
+            frame.stroke(
+                &path,
+                Stroke::default()
+                    .with_color(self.color)
+                    .with_width(stroke_width),
+            );
+        }
+
+        let (width, height) = maidenhead::cell_size(pairs);
+        if !self.labels || width * pixels_per_degree(projector) < MIN_LABEL {
+            return;
+        }
+
+        for lon in steps(west - width, east, -180.0, width) {
+            for lat in steps(south - height, north, -90.0, height) {
+                let center = Geodetic::new(lon + width / 2.0, lat + height / 2.0);
+                label(
+                    frame,
+                    center.to_maidenhead(pairs),
+                    projector.geodetic_into_screen_space(center),
+                    self.color,
+                );
+            }
+        }
+    }
+}
+
+/// The multiples of `step` from `origin` within `[start .. end]`.
+fn steps(start: f64, end: f64, origin: f64, step: f64) -> impl Iterator<Item = f64> {
+    let first = ((start - origin) / step).ceil() as i64;
+    let last = ((end - origin) / step).floor() as i64;
+    (first..=last).map(move |i| origin + i as f64 * step)
+}
+
+/// The western edges of the UTM zones within a latitude band, which are 6 degrees wide
+/// except around Norway and Svalbard.
+fn zone_edges(band: usize) -> Vec<f64> {
+    let edges = (0..60).map(|zone| -180.0 + zone as f64 * 6.0);
+    match BANDS[band] {
+        b'V' => edges
+            .map(|lon| if lon == 6.0 { 3.0 } else { lon })
+            .collect(),
+        b'X' => {
+            let mut edges = edges
+                .filter(|lon| !(6.0..=36.0).contains(lon))
+                .chain([9.0, 21.0, 33.0])
+                .collect::<Vec<_>>();
+            edges.sort_by(f64::total_cmp);
+            edges
+        }
+        _ => edges.collect(),
+    }
+}
+
+/// The latitudes of the southern and northern edges of a latitude band.
+fn band_edges(band: usize) -> (f64, f64) {
+    let south = -80.0 + band as f64 * 8.0;
+    let north = if band == BANDS.len() - 1 {
+        84.0
+    } else {
+        south + 8.0
+    };
+    (south, north)
+}
+
+/// Draws the grid zones of MGRS, and their 100 km squares once they are large enough.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MgrsGrid {
+    color: Color,
+    labels: bool,
+}
+
+impl Default for MgrsGrid {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MgrsGrid {
+    pub fn new() -> Self {
+        Self {
+            color: Color::from_rgb(0.1, 0.3, 0.7),
+            labels: true,
+        }
+    }
+
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Whether to label the grid zones and squares.
+    pub fn labels(mut self, labels: bool) -> Self {
+        self.labels = labels;
+        self
+    }
+
+    pub fn draw(&self, projector: &Projector, frame: &mut Frame<iced::Renderer>) {
+        let Some(view) = extent(projector) else {
+            return;
+        };
+        let (west, south, east, north) = view;
+
+        let scale = pixels_per_degree(projector);
+        let point =
+            |lon: f64, lat: f64| projector.geodetic_into_screen_space(Geodetic::new(lon, lat));
+
+        // The size of a square in pixels, near the center of the map
+        let center = projector.screen_space_into_geodetic(projector.bounds.center());
+        let square = mgrs::SQUARE / (center.latitude().to_radians().cos() * 111_320.0) * scale;
+
+        let zones = Path::new(|builder| {
+            for band in 0..BANDS.len() {
+                let (band_south, band_north) = band_edges(band);
+                if band_north < south || band_south > north {
+                    continue;
+                }
+
+                let (low, high) = (band_south.max(south), band_north.min(north));
+                builder.move_to(point(west, low));
+                builder.line_to(point(east, low));
+
+                for lon in zone_edges(band)
+                    .into_iter()
+                    .filter(|lon| (west..=east).contains(lon))
+                {
+                    builder.move_to(point(lon, low));
+                    builder.line_to(point(lon, high));
+                }
+            }
+        });
+        frame.stroke(
+            &zones,
+            Stroke::default().with_color(self.color).with_width(2.0),
+        );
+
+        for (band, &letter) in BANDS.iter().enumerate() {
+            let (band_south, band_north) = band_edges(band);
+            let edges = zone_edges(band);
+
+            for (i, &zone_west) in edges.iter().enumerate() {
+                let zone_east = edges.get(i + 1).copied().unwrap_or(180.0);
+                let cell = (zone_west, band_south, zone_east, band_north);
+                let Some(visible) = intersect(cell, view) else {
+                    continue;
+                };
+
+                let zone = UtmZone {
+                    number: utm::zone(Geodetic::new((zone_west + zone_east) / 2.0, band_south)),
+                    north: band_south >= 0.0,
+                };
+                if square >= MIN_CELL {
+                    self.draw_squares(projector, frame, zone, visible, square);
+                }
+
+                let width = (zone_east - zone_west) * scale;
+                if self.labels && width >= MIN_LABEL && square < MIN_LABEL {
+                    let (w, s, e, n) = visible;
+                    label(
+                        frame,
+                        format!("{}{}", zone.number, letter as char),
+                        point((w + e) / 2.0, (s + n) / 2.0),
+                        self.color,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Draw the lines of constant easting and northing of the 100 km squares within the
+    /// visible part of a grid zone.
+    fn draw_squares(
+        &self,
+        projector: &Projector,
+        frame: &mut Frame<iced::Renderer>,
+        zone: UtmZone,
+        cell: (f64, f64, f64, f64),
+        square: f64,
+    ) {
+        let (west, south, east, north_edge) = cell;
+        let inside = |position: Geodetic| {
+            (west..=east).contains(&position.longitude())
+                && (south..=north_edge).contains(&position.latitude())
+        };
+        let project = |easting: f64, northing: f64| {
+            Utm {
+                zone,
+                easting,
+                northing,
+            }
+            .to_geodetic()
+        };
+
+        // The range of eastings and northings covering the cell, where the extremes are
+        // found at the corners, or where the cell crosses the central meridian
+        let central = utm::central_meridian(zone.number).clamp(west, east);
+        let corners = [west, central, east]
+            .into_iter()
+            .flat_map(|lon| [Geodetic::new(lon, south), Geodetic::new(lon, north_edge)])
+            .map(|position| Utm::from_geodetic_in_zone(position, zone.number))
+            .collect::<Vec<_>>();
+        let range = |value: fn(&Utm) -> f64| {
+            corners
+                .iter()
+                .map(value)
+                .fold((f64::MAX, f64::MIN), |(low, high), value| {
+                    (low.min(value), high.max(value))
+                })
+        };
+        let (min_easting, max_easting) = range(|utm| utm.easting);
+        let (min_northing, max_northing) = range(|utm| utm.northing);
+
+        let sample = |low: f64, high: f64| {
+            (0..=SAMPLES).map(move |i| low + (high - low) * i as f64 / SAMPLES as f64)
+        };
+        let path = Path::new(|builder| {
+            for easting in steps(min_easting, max_easting, 0.0, mgrs::SQUARE) {
+                let points = sample(min_northing, max_northing).map(|northing| {
+                    let position = project(easting, northing);
+                    inside(position).then(|| projector.geodetic_into_screen_space(position))
+                });
+                trace(builder, points);
+            }
+            for northing in steps(min_northing, max_northing, 0.0, mgrs::SQUARE) {
+                let points = sample(min_easting, max_easting).map(|easting| {
+                    let position = project(easting, northing);
+                    inside(position).then(|| projector.geodetic_into_screen_space(position))
+                });
+                trace(builder, points);
+            }
+        });
+        frame.stroke(
+            &path,
+            Stroke::default().with_color(self.color).with_width(1.0),
+        );
+
+        if !self.labels || square < MIN_LABEL {
+            return;
+        }
+
+        let half = mgrs::SQUARE / 2.0;
+        for easting in steps(min_easting - half, max_easting, half, mgrs::SQUARE) {
+            for northing in steps(min_northing - half, max_northing, half, mgrs::SQUARE) {
+                let position = project(easting, northing);
+                if !inside(position) {
+                    continue;
+                }
+
+                let [column, row] = mgrs::square_id(zone.number, easting, northing);
+                label(
+                    frame,
+                    format!("{column}{row}"),
+                    projector.geodetic_into_screen_space(position),
+                    self.color,
+                );
+            }
+        }
+    }
+}
+
+/// The overlap of two extents.
+fn intersect(a: (f64, f64, f64, f64), b: (f64, f64, f64, f64)) -> Option<(f64, f64, f64, f64)> {
+    let (west, south) = (a.0.max(b.0), a.1.max(b.1));
+    let (east, north) = (a.2.min(b.2), a.3.min(b.3));
+    (west < east && south < north).then_some((west, south, east, north))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zone_exceptions() {
+        let regular = zone_edges(0);
+        assert_eq!(regular.len(), 60);
+        assert_eq!(regular[30], 0.0);
+
+        let norway = zone_edges(BANDS.iter().position(|&b| b == b'V').unwrap());
+        assert!(norway.contains(&3.0) && !norway.contains(&6.0));
+
+        let svalbard = zone_edges(BANDS.len() - 1);
+        assert_eq!(
+            svalbard
+                .iter()
+                .filter(|lon| (0.0..=42.0).contains(*lon))
+                .collect::<Vec<_>>(),
+            [&0.0, &9.0, &21.0, &33.0, &42.0]
+        );
+        assert!(svalbard.is_sorted());
+    }
+
+    #[test]
+    fn steps_within_range() {
+        assert_eq!(
+            steps(-7.0, 13.0, -180.0, 6.0).collect::<Vec<_>>(),
+            [-6.0, 0.0, 6.0, 12.0]
+        );
+        assert_eq!(steps(1.0, 2.0, 0.0, 6.0).count(), 0);
+    }
+}
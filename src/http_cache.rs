@@ -0,0 +1,239 @@
+//! Revalidation of tiles in the disk cache, following the caching headers of tile servers.
+
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use iced::time::SystemTime;
+
+use crate::HttpResponse;
+
+/// How long a cached tile stays fresh, and the validators with which it is revalidated once
+/// it went stale. They are kept in a file next to the tile, with the `meta` extension.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Validators {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    /// When the tile goes stale, in seconds since the unix epoch. Tiles without it stay
+    /// fresh, unless the cache is set to refresh them.
+    pub expires: Option<u64>,
+    /// Whether the server forbids storing the tile, with `Cache-Control: no-store`.
+    pub no_store: bool,
+}
+
+impl Validators {
+    /// The validators of a response, received at `now`.
+    pub fn from_response(response: &HttpResponse, now: SystemTime) -> Self {
+        let text = |name| response.header(name).map(str::trim);
+
+        let mut validators = Self {
+            etag: text("ETag").map(str::to_string),
+            last_modified: text("Last-Modified").map(str::to_string),
+            ..Self::default()
+        };
+
+        let mut max_age = None;
+        for directive in text("Cache-Control").unwrap_or_default().split(',') {
+            let directive = directive.trim().to_ascii_lowercase();
+            match directive.split_once('=') {
+                Some(("max-age", seconds)) => max_age = seconds.trim_matches('"').parse().ok(),
+                _ if directive == "no-cache" => max_age = Some(0),
+                _ if directive == "no-store" => validators.no_store = true,
+                _ => {}
+            }
+        }
+
+        let now = seconds(now);
+        validators.expires = match max_age {
+            // The age is the time the response already spent in caches along the way
+            Some(max_age) => {
+                let age = text("Age").and_then(|age| age.parse().ok());
+                let max_age = max_age.min(MAX_AGE_LIMIT);
+                Some(now.saturating_add(max_age).saturating_sub(age.unwrap_or(0)))
+            }
+            // Invalid dates, such as `0`, mean that the response is already stale
+            None => text("Expires").map(|date| parse_http_date(date).unwrap_or(now)),
+        };
+        validators
+    }
+
+    /// Whether the tile may still be used, without revalidating it.
+    pub fn is_fresh(&self, now: SystemTime) -> bool {
+        self.expires.is_none_or(|expires| seconds(now) < expires)
+    }
+
+    /// The headers which ask the server to only respond with the tile if it has changed.
+    pub fn conditions(&self) -> Vec<(String, String)> {
+        let mut headers = Vec::new();
+        if let Some(etag) = &self.etag {
+            headers.push(("If-None-Match".to_string(), etag.clone()));
+        }
+        if let Some(last_modified) = &self.last_modified {
+            headers.push(("If-Modified-Since".to_string(), last_modified.clone()));
+        }
+        headers
+    }
+
+    /// The validators after a revalidation, which may leave out those which did not change.
+    /// Without an expiry of its own, the tile stays stale and is revalidated again next time.
+    pub fn revalidated(self, response: Self) -> Self {
+        Self {
+            etag: response.etag.or(self.etag),
+            last_modified: response.last_modified.or(self.last_modified),
+            expires: response.expires.or(self.expires),
+            no_store: response.no_store,
+        }
+    }
+
+    /// The validators of a tile in the disk cache, if it has any.
+    pub async fn read(tile: &Path) -> Option<Self> {
+        let text = crate::platform::fs::read_to_string(meta_path(tile))
+            .await
+            .ok()?;
+
+        let mut validators = Self::default();
+        for line in text.lines() {
+            match line.split_once(' ') {
+                Some(("etag", etag)) => validators.etag = Some(etag.to_string()),
+                Some(("last-modified", date)) => validators.last_modified = Some(date.to_string()),
+                Some(("expires", seconds)) => validators.expires = seconds.parse().ok(),
+                _ => {}
+            }
+        }
+        Some(validators)
+    }
+
+    /// Store the validators next to a tile in the disk cache, replacing any previous ones.
+    pub async fn write(&self, tile: &Path) -> std::io::Result<()> {
+        let mut text = String::new();
+        if let Some(etag) = &self.etag {
+            text.push_str(&format!("etag {etag}\n"));
+        }
+        if let Some(date) = &self.last_modified {
+            text.push_str(&format!("last-modified {date}\n"));
+        }
+        if let Some(expires) = self.expires {
+            text.push_str(&format!("expires {expires}\n"));
+        }
+
+        match text.is_empty() {
+            true => match crate::platform::fs::remove_file(meta_path(tile)).await {
+                Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err),
+                _ => Ok(()),
+            },
+            false => crate::platform::fs::write(meta_path(tile), text).await,
+        }
+    }
+}
+
+/// The largest `max-age` which is respected, as recommended by RFC 9111.
+const MAX_AGE_LIMIT: u64 = 2_147_483_648;
+
+fn meta_path(tile: &Path) -> PathBuf {
+    tile.with_extension("meta")
+}
+
+fn seconds(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO)
+        .as_secs()
+}
+
+/// Parse a date in the format of HTTP, e.g. `Sun, 06 Nov 1994 08:49:37 GMT`, into seconds
+/// since the unix epoch.
+fn parse_http_date(date: &str) -> Option<u64> {
+    let [_, day, month, year, time, "GMT"] = date.split_whitespace().collect::<Vec<_>>()[..] else {
+        return None;
+    };
+
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    let month = MONTHS.iter().position(|name| *name == month)? as i64 + 1;
+    let day: i64 = day.parse().ok()?;
+    let year: i64 = year.parse().ok()?;
+    let mut time = time.split(':').map(|part| part.parse::<i64>().ok());
+    let (hours, minutes, seconds) = (time.next()??, time.next()??, time.next()??);
+
+    // Days since the epoch in the proleptic Gregorian calendar, with years starting in March
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    u64::try_from(days * 86_400 + hours * 3_600 + minutes * 60 + seconds).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn caching_headers() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+        let mut response = HttpResponse {
+            status: 200,
+            headers: [
+                ("etag", "\"abc\""),
+                ("cache-control", "public, max-age=600"),
+                ("age", "100"),
+                ("expires", "0"),
+            ]
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .to_vec(),
+            ..HttpResponse::default()
+        };
+
+        // The maximum age takes precedence over the expiry date
+        let validators = Validators::from_response(&response, now);
+        assert_eq!(validators.expires, Some(1_500));
+        assert!(validators.is_fresh(now));
+        assert!(!validators.is_fresh(now + Duration::from_secs(500)));
+        assert_eq!(
+            validators.conditions(),
+            [("If-None-Match".to_string(), "\"abc\"".to_string())]
+        );
+
+        // Invalid expiry dates have already passed
+        response.headers.retain(|(name, _)| name != "cache-control");
+        let validators = Validators::from_response(&response, now);
+        assert!(!validators.is_fresh(now));
+
+        // Responses to revalidations may leave out the validators and the expiry
+        let revalidated = validators.revalidated(Validators::default());
+        assert_eq!(revalidated.etag.as_deref(), Some("\"abc\""));
+        assert!(!revalidated.is_fresh(now));
+    }
+
+    #[test]
+    fn oversized_max_age() {
+        let response = HttpResponse {
+            status: 200,
+            headers: vec![(
+                "Cache-Control".to_string(),
+                "max-age=18446744073709551615".to_string(),
+            )],
+            ..HttpResponse::default()
+        };
+
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+        let validators = Validators::from_response(&response, now);
+        assert_eq!(validators.expires, Some(1_000 + MAX_AGE_LIMIT));
+    }
+
+    #[test]
+    fn http_dates() {
+        assert_eq!(
+            parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT"),
+            Some(784_111_777)
+        );
+        assert_eq!(
+            parse_http_date("Thu, 29 Feb 2024 00:00:00 GMT"),
+            Some(1_709_164_800)
+        );
+        assert_eq!(parse_http_date("Sunday, 06-Nov-94 08:49:37"), None);
+    }
+}
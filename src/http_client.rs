@@ -0,0 +1,77 @@
+//! The client making the requests for tiles, which applications can replace with their own.
+
+use iced_core::Bytes;
+
+use crate::TileError;
+
+/// The future of a request. Requests made by the browser can not be sent between threads,
+/// so they only need to be `Send` in native builds.
+#[cfg(not(target_arch = "wasm32"))]
+pub type HttpFuture<'a> = iced::futures::future::BoxFuture<'a, Result<HttpResponse, TileError>>;
+#[cfg(target_arch = "wasm32")]
+pub type HttpFuture<'a> =
+    iced::futures::future::LocalBoxFuture<'a, Result<HttpResponse, TileError>>;
+
+/// The response of a tile server, whatever its status.
+#[derive(Debug, Clone, Default)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Bytes,
+}
+
+impl HttpResponse {
+    /// The value of a header, whose name is compared regardless of case.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Makes the requests for tiles. Implement it to send them through a client of your own, such
+/// as one with another TLS stack, a corporate proxy or instrumentation, and set it with
+/// [`crate::TileCacheBuilder::http_client`]. It is implemented for [`reqwest::Client`],
+/// which is used by default, and which makes its requests with `fetch` in web builds.
+///
+/// Retries, rate limits and the disk cache are handled by the [`crate::TileCache`], such
+/// that a client only makes single requests, following any redirects.
+pub trait HttpClient: Send + Sync {
+    /// Make a GET request with the given headers. Responses with an error status are still
+    /// responses, while failing to get one at all is reported as [`TileError::Timeout`] or
+    /// [`TileError::Network`].
+    fn get<'a>(&'a self, url: &'a str, headers: &'a [(String, String)]) -> HttpFuture<'a>;
+}
+
+impl core::fmt::Debug for dyn HttpClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("HttpClient..")
+    }
+}
+
+impl HttpClient for reqwest::Client {
+    fn get<'a>(&'a self, url: &'a str, headers: &'a [(String, String)]) -> HttpFuture<'a> {
+        Box::pin(async move {
+            let mut request = reqwest::Client::get(self, url);
+            for (name, value) in headers {
+                request = request.header(name, value);
+            }
+
+            let response = request.send().await?;
+            let headers = response
+                .headers()
+                .iter()
+                .filter_map(|(name, value)| {
+                    Some((name.to_string(), value.to_str().ok()?.to_string()))
+                })
+                .collect();
+
+            Ok(HttpResponse {
+                status: response.status().as_u16(),
+                headers,
+                body: response.bytes().await?,
+            })
+        })
+    }
+}
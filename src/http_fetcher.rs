@@ -0,0 +1,719 @@
+//! Fetching of tiles from the tile servers of a [`Source`], over HTTP.
+
+use std::{
+    path::PathBuf,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+#[cfg(feature = "decode")]
+use iced::futures::future::join_all;
+use iced::{
+    Task,
+    futures::StreamExt,
+    time::{Instant, SystemTime},
+};
+use iced_core::{Bytes, image::Handle};
+use tokio::sync::Semaphore;
+
+#[cfg(feature = "decode")]
+use crate::decoder::{Decoder, Processor};
+use crate::{
+    HttpClient, Mercator,
+    http_cache::Validators,
+    platform,
+    sources::{self, Source, TileGrid},
+    tile_cache::{CacheMessage, Fetcher, TileError},
+    tile_coord::TileCoord,
+};
+
+/// The number of tiles of a region which are downloaded at once. Few enough to leave room
+/// for the tiles in view, and to not burden the servers.
+const DOWNLOAD_CONCURRENCY: usize = 2;
+
+/// How failed requests for tiles are retried, such that a brief loss of the connection does
+/// not leave holes in the map. The delay doubles with each retry, to not pile onto a server
+/// which is struggling. Errors of the client, such as a missing tile, are not retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// The number of attempts made for each request, including the first one.
+    pub attempts: u32,
+    /// The delay before the first retry.
+    pub delay: Duration,
+    /// The longest delay between retries.
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: 3,
+            delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(4),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Never retry failed requests.
+    pub const NEVER: Self = Self {
+        attempts: 1,
+        delay: Duration::ZERO,
+        max_delay: Duration::ZERO,
+    };
+
+    /// The delay before a retry, counting from 1.
+    fn backoff(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        self.delay.saturating_mul(factor).min(self.max_delay)
+    }
+}
+
+/// The configuration of the [`HttpFetcher`], set through the [`crate::TileCacheBuilder`].
+#[derive(Debug)]
+pub(crate) struct HttpConfig {
+    pub user_agent: String,
+    /// Replaces the client built with the user agent and request timeout.
+    pub client: Option<Arc<dyn HttpClient>>,
+    pub concurrency: usize,
+    pub queue_timeout: Duration,
+    pub request_timeout: Option<Duration>,
+    pub disk_cache: Option<PathBuf>,
+    pub rate_limit: Option<f32>,
+    pub retry: RetryPolicy,
+    #[cfg(feature = "decode")]
+    pub processor: Option<Processor>,
+    /// Tiles in the disk cache older than this are fetched again.
+    pub refresh_after: Option<Duration>,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            user_agent: "lib-slippery".to_string(),
+            client: None,
+            concurrency: 6,
+            queue_timeout: Duration::from_millis(50),
+            request_timeout: None,
+            disk_cache: None,
+            rate_limit: None,
+            retry: RetryPolicy::default(),
+            #[cfg(feature = "decode")]
+            processor: None,
+            refresh_after: None,
+        }
+    }
+}
+
+/// The fetcher is cloned and moved into an async task to fetch a tile.
+#[derive(Debug)]
+pub(crate) struct HttpFetcher {
+    semaphore: Semaphore,
+    concurrency: usize,
+    queue_timeout: Duration,
+    source: Box<dyn Source>,
+    client: Arc<dyn HttpClient>,
+    hidpi: AtomicBool,
+    /// Whether only the tiles in the disk cache are used, without making any requests.
+    offline: AtomicBool,
+    /// The number of requests made, for rotating the subdomains of the source.
+    requests: AtomicUsize,
+    /// The number of times the credentials of the source were renewed.
+    credentials: AtomicUsize,
+    /// Held while renewing the credentials, such that rejected requests renew them once.
+    renewal: tokio::sync::Mutex<()>,
+    /// The directory of the disk cache, as configured, which holds a directory for each source.
+    cache_root: Option<PathBuf>,
+    /// The directory of the disk cache holding the tiles of this source.
+    disk_cache: Option<PathBuf>,
+    refresh_after: Option<Duration>,
+    rate_limit: Option<RateLimit>,
+    retry: RetryPolicy,
+    #[cfg(feature = "decode")]
+    /// Shared with the fetchers of all other caches.
+    decoder: Arc<Decoder>,
+    #[cfg(feature = "decode")]
+    processor: Option<Processor>,
+}
+
+/// Spaces requests evenly in time.
+#[derive(Debug)]
+struct RateLimit {
+    interval: Duration,
+    next: Mutex<Instant>,
+}
+
+impl RateLimit {
+    /// Wait for the next free slot.
+    async fn wait(&self) {
+        let slot = {
+            let mut next = self.next.lock().unwrap();
+            let slot = (*next).max(Instant::now());
+            *next = slot + self.interval;
+            slot
+        };
+
+        platform::sleep(slot.saturating_duration_since(Instant::now())).await;
+    }
+}
+
+/// The directory of the disk cache holding the tiles of a source, named after the host of its
+/// tiles and a hash of their url. The query of the url is left out, since it often holds keys
+/// or tokens which change, and so is the frame of time-dimension sources.
+pub(crate) fn source_dir(source: &dyn Source) -> String {
+    let mut url = source.tile_url(TileCoord::ZERO);
+    if let Some(time) = source.time().filter(|time| !time.is_empty()) {
+        url = url.replace(&time, "{time}");
+    }
+    let url = url.split('?').next().unwrap_or_default();
+
+    let host = url.split("://").last().unwrap_or_default();
+    let host = host.split('/').next().unwrap_or_default();
+    let host: String = host
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '-' => c,
+            _ => '-',
+        })
+        .collect();
+
+    // FNV-1a, which unlike the hasher of the standard library does not change between releases
+    let hash = url.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    });
+
+    format!("{host}-{hash:016x}")
+}
+
+impl From<reqwest::Error> for TileError {
+    fn from(err: reqwest::Error) -> Self {
+        match err.status() {
+            Some(reqwest::StatusCode::TOO_MANY_REQUESTS) => Self::RateLimited,
+            Some(status) => Self::Status(status.as_u16()),
+            None if err.is_timeout() => Self::Timeout,
+            None if err.is_decode() => Self::Decode(err.to_string()),
+            None => Self::Network(err.to_string()),
+        }
+    }
+}
+
+impl HttpFetcher {
+    pub(crate) fn new(source: Box<dyn Source>, config: HttpConfig) -> Self {
+        let client = config.client.unwrap_or_else(|| {
+            let mut client = reqwest::ClientBuilder::new().user_agent(config.user_agent);
+            // Browsers have no timeouts for their requests
+            #[cfg(not(target_arch = "wasm32"))]
+            if let Some(timeout) = config.request_timeout {
+                client = client.timeout(timeout);
+            }
+            // Fails only if the user agent is no valid header value, or TLS is unavailable
+            Arc::new(
+                client
+                    .build()
+                    .expect("Unable to build the HTTP client of the tile cache"),
+            )
+        });
+
+        let rate_limit = config
+            .rate_limit
+            .filter(|rate| *rate > 0.0)
+            .map(|rate| RateLimit {
+                interval: Duration::from_secs_f32(1.0 / rate),
+                next: Mutex::new(Instant::now()),
+            });
+
+        let tile_dir = |dir: &PathBuf| dir.join(source_dir(&*source));
+        let disk_cache = config.disk_cache.as_ref().map(tile_dir);
+
+        Self {
+            semaphore: Semaphore::new(config.concurrency),
+            concurrency: config.concurrency,
+            queue_timeout: config.queue_timeout,
+            source,
+            client,
+            hidpi: AtomicBool::new(false),
+            offline: AtomicBool::new(false),
+            requests: AtomicUsize::new(0),
+            credentials: AtomicUsize::new(0),
+            renewal: tokio::sync::Mutex::new(()),
+            disk_cache,
+            cache_root: config.disk_cache,
+            refresh_after: config.refresh_after,
+            rate_limit,
+            retry: config.retry,
+            #[cfg(feature = "decode")]
+            decoder: Decoder::shared(),
+            #[cfg(feature = "decode")]
+            processor: config.processor,
+        }
+    }
+
+    /// Fetch and decode a tile, counting the retries of its requests in `retries`.
+    async fn fetch(&self, tile_id: TileCoord, retries: &AtomicU32) -> Result<Handle, TileError> {
+        // Semaphore ensures we are not making too many requests.
+        // Assume that if we have been waiting for a while, that the
+        // viewpoint may have moved and the tile in no longer needed.
+        // If it was needed, another fetch request will just be made.
+        let _permit = platform::timeout(self.queue_timeout, self.semaphore.acquire())
+            .await
+            .ok_or(TileError::Busy)?
+            .map_err(|_| TileError::Closed)?;
+
+        // Go down the fallbacks of the source until one of them serves the tile
+        let mut error = None;
+        let mut primary = true;
+        let mut next = Some(&*self.source);
+        while let Some(source) = next {
+            if sources::covers(source, &tile_id) && tile_id.zoom() <= source.max_zoom() {
+                match self.fetch_from(source, primary, tile_id, retries).await {
+                    Ok(handle) => return Ok(handle),
+                    Err(err) => error = Some(err),
+                }
+            }
+
+            next = source.fallback();
+            if let (Some(err), Some(_)) = (&error, next) {
+                log::debug!("Falling back for tile {tile_id:?}: {err}");
+            }
+            primary = false;
+        }
+
+        // None of the sources cover the tile
+        Err(error.unwrap_or(TileError::Status(404)))
+    }
+
+    /// Fetch and decode a tile from one source of the chain. Only the tiles of the primary
+    /// source are kept in the disk cache, such that it is tried again later.
+    async fn fetch_from(
+        &self,
+        source: &dyn Source,
+        primary: bool,
+        tile_id: TileCoord,
+        retries: &AtomicU32,
+    ) -> Result<Handle, TileError> {
+        let scheme = source.tiling_scheme();
+        if scheme.grid == TileGrid::Geographic {
+            #[cfg(feature = "decode")]
+            return self
+                .fetch_reprojected(source, primary, tile_id, retries)
+                .await;
+            #[cfg(not(feature = "decode"))]
+            return Err(TileError::Reproject);
+        }
+
+        // Fetch the tile using the numbering of the source
+        let bytes = self
+            .fetch_bytes(source, primary, scheme.request_tile(tile_id), retries)
+            .await?;
+
+        // Decode the image on a worker, rather than when allocating it with the renderer
+        #[cfg(feature = "decode")]
+        let handle = self
+            .decoder
+            .decode(tile_id, bytes, scheme.border, self.processor.clone())
+            .await?;
+        #[cfg(not(feature = "decode"))]
+        let handle = Handle::from_bytes(bytes);
+
+        Ok(handle)
+    }
+
+    /// Fetch a tile into the disk cache, without decoding it unless it must be reprojected.
+    async fn download_tile(&self, tile_id: TileCoord) -> Result<(), TileError> {
+        let retries = AtomicU32::new(0);
+        let scheme = self.source.tiling_scheme();
+        match scheme.grid {
+            TileGrid::Geographic => self
+                .fetch_from(&*self.source, true, tile_id, &retries)
+                .await
+                .map(drop),
+            _ => self
+                .fetch_bytes(&*self.source, true, scheme.request_tile(tile_id), &retries)
+                .await
+                .map(drop),
+        }
+    }
+
+    /// Fetch the encoded image of a tile, given in the numbering of the source. Each retry
+    /// is counted in `retries`.
+    async fn fetch_bytes(
+        &self,
+        source: &dyn Source,
+        primary: bool,
+        request: TileCoord,
+        retries: &AtomicU32,
+    ) -> Result<Bytes, TileError> {
+        // Local sources are read as is, without a disk cache or retries
+        if let Some(tile) = source.load_tile(request) {
+            return tile.await;
+        }
+
+        let hidpi_url = self
+            .hidpi
+            .load(Ordering::Relaxed)
+            .then(|| source.tile_url_hidpi(request))
+            .flatten();
+
+        let path = self.tile_dir(source).filter(|_| primary).map(|dir| {
+            let suffix = if hidpi_url.is_some() { "@2x" } else { "" };
+            dir.join(format!(
+                "{}/{}/{}{suffix}",
+                request.zoom(),
+                request.x(),
+                request.y()
+            ))
+        });
+        // Stale tiles are kept to be revalidated, which spares downloading unchanged ones
+        let mut cached = None;
+        if let Some(path) = &path
+            && let Ok(bytes) = platform::fs::read(path).await
+        {
+            let validators = Validators::read(path).await.unwrap_or_default();
+            if self.is_current(path).await && validators.is_fresh(SystemTime::now()) {
+                return Ok(Bytes::from(bytes));
+            }
+            cached = Some((Bytes::from(bytes), validators));
+        }
+
+        // Stale tiles are better than none while offline
+        if self.offline.load(Ordering::Relaxed) {
+            return match cached {
+                Some((bytes, _)) => Ok(bytes),
+                None => Err(TileError::Offline),
+            };
+        }
+
+        // Signed urls and tokens change when the credentials are renewed
+        let hidpi = hidpi_url.is_some();
+        let prepare = || {
+            let url = match hidpi {
+                true => source.tile_url_hidpi(request),
+                false => None,
+            };
+            let url = url.unwrap_or_else(|| source.tile_url(request));
+            let mut headers = source.headers(request);
+
+            // Ask for the configured encoding, unless the source sets its own `Accept` header
+            if let Some(format) = source.format()
+                && !headers
+                    .iter()
+                    .any(|(name, _)| name.eq_ignore_ascii_case("accept"))
+            {
+                headers.push(("Accept".to_string(), format.mime_type().to_string()));
+            }
+            if let Some((_, validators)) = &cached {
+                headers.extend(validators.conditions());
+            }
+            (url, headers)
+        };
+
+        let (mut url, mut headers) = prepare();
+        let mut attempt = 1;
+        let mut renewed = false;
+        let (validators, bytes) = loop {
+            let credentials = self.credentials.load(Ordering::Relaxed);
+            match self.request(source, &url, &headers).await {
+                Ok(response) => break response,
+                // Rejected credentials may be renewed once, e.g. an expired token
+                Err(err)
+                    if !renewed
+                        && err.is_unauthorized()
+                        && self.renew_credentials(source, credentials).await =>
+                {
+                    renewed = true;
+                    (url, headers) = prepare();
+                }
+                // Client errors will not go away by trying again
+                Err(err)
+                    if attempt < self.retry.attempts
+                        && !matches!(err, TileError::Status(400..500) | TileError::RateLimited) =>
+                {
+                    retries.fetch_add(1, Ordering::Relaxed);
+                    platform::sleep(self.retry.backoff(attempt)).await;
+                    attempt += 1;
+                }
+                Err(err) => return Err(err),
+            }
+        };
+
+        // An unmodified tile is used again, and stays fresh for as long as the server says
+        let (validators, bytes) = match (bytes, cached) {
+            (Some(bytes), _) => (validators, bytes),
+            (None, Some((bytes, cached))) => (cached.revalidated(validators), bytes),
+            (None, None) => return Err(TileError::Status(304)),
+        };
+
+        if let Some(path) = path.filter(|_| !validators.no_store) {
+            let written = match path.parent() {
+                Some(dir) => platform::fs::create_dir_all(dir).await,
+                None => Ok(()),
+            };
+            let written = written
+                .and(platform::fs::write(&path, &bytes).await)
+                .and(validators.write(&path).await);
+            if let Err(err) = written {
+                log::warn!("Unable to write tile to {}: {err}", path.display());
+            }
+        }
+
+        Ok(bytes)
+    }
+
+    /// The directory of the disk cache holding the tiles of a source, which are laid out as
+    /// `{z}/{x}/{y}` in its numbering.
+    fn tile_dir(&self, source: &dyn Source) -> Option<PathBuf> {
+        let dir = self.disk_cache.as_ref()?;
+        // Each frame of a time-dimension source gets a directory of its own
+        Some(match source.time() {
+            Some(time) => dir.join(time.replace(['/', '\\', ':'], "-")),
+            None => dir.clone(),
+        })
+    }
+
+    /// Renew the credentials of a source, unless another request already did so since they
+    /// were rejected. Returns whether the request should be made again.
+    async fn renew_credentials(&self, source: &dyn Source, rejected: usize) -> bool {
+        let _renewal = self.renewal.lock().await;
+        if self.credentials.load(Ordering::Relaxed) != rejected {
+            return true;
+        }
+
+        let renewed = source.refresh_credentials().await;
+        if renewed {
+            self.credentials.fetch_add(1, Ordering::Relaxed);
+        }
+        renewed
+    }
+
+    /// Whether a tile in the disk cache is recent enough to be used, for live sources.
+    async fn is_current(&self, path: &std::path::Path) -> bool {
+        let Some(max_age) = self.refresh_after else {
+            return true;
+        };
+
+        platform::fs::metadata(path)
+            .await
+            .and_then(|metadata| metadata.modified())
+            .is_ok_and(|modified| modified.elapsed().is_ok_and(|age| age < max_age))
+    }
+
+    /// Make a single request for a tile, which is none if a conditional request found the
+    /// cached tile to be unmodified.
+    async fn request(
+        &self,
+        source: &dyn Source,
+        url: &str,
+        headers: &[(String, String)],
+    ) -> Result<(Validators, Option<Bytes>), TileError> {
+        if let Some(rate_limit) = &self.rate_limit {
+            rate_limit.wait().await;
+        }
+
+        // Each request, including retries, goes to the next subdomain
+        let subdomains = source.subdomains();
+        let url = match subdomains.is_empty() {
+            true => url.to_string(),
+            false => {
+                let request = self.requests.fetch_add(1, Ordering::Relaxed);
+                url.replace("{s}", subdomains[request % subdomains.len()])
+            }
+        };
+
+        let response = self.client.get(&url, headers).await?;
+        let validators = Validators::from_response(&response, SystemTime::now());
+        match response.status {
+            304 => Ok((validators, None)),
+            429 => Err(TileError::RateLimited),
+            400.. => Err(TileError::Status(response.status)),
+            _ => Ok((validators, Some(response.body))),
+        }
+    }
+
+    /// Fetch the geographic tiles overlapping a tile, and reproject them into one image.
+    #[cfg(feature = "decode")]
+    async fn fetch_reprojected(
+        &self,
+        source: &dyn Source,
+        primary: bool,
+        tile_id: TileCoord,
+        retries: &AtomicU32,
+    ) -> Result<Handle, TileError> {
+        use crate::reproject;
+
+        let scheme = source.tiling_scheme();
+        let tiles = reproject::geographic_tiles(tile_id);
+        let fetches = tiles.iter().map(|tile| {
+            self.fetch_bytes(
+                source,
+                primary,
+                scheme.request_geographic_tile(tile.x, tile.y, tile.zoom),
+                retries,
+            )
+        });
+
+        let mut images = Vec::with_capacity(tiles.len());
+        for (tile, bytes) in tiles.iter().zip(join_all(fetches).await) {
+            images.push((*tile, bytes?));
+        }
+
+        // Decoding and resampling is too slow for the async runtime
+        let processor = self.processor.clone();
+        let handle = tokio::task::spawn_blocking(move || {
+            let images = images
+                .into_iter()
+                .map(|(tile, bytes)| {
+                    let image = ::image::load_from_memory(&bytes)?.into_rgba8();
+                    Ok((tile, crate::decoder::crop_border(image, scheme.border)))
+                })
+                .collect::<Result<Vec<_>, ::image::ImageError>>()?;
+
+            let mut image = reproject::reproject(tile_id, &images);
+            if let Some(processor) = processor {
+                (processor.0)(tile_id, &mut image);
+            }
+            Ok::<_, ::image::ImageError>(Handle::from_rgba(
+                image.width(),
+                image.height(),
+                image.into_raw(),
+            ))
+        })
+        .await
+        .map_err(|_| TileError::Reproject)?
+        .map_err(crate::decoder::DecodeError::from)?;
+
+        Ok(handle)
+    }
+}
+
+impl Fetcher for HttpFetcher {
+    fn fetch_tile(self: Arc<Self>, tile_id: TileCoord) -> Task<CacheMessage> {
+        Task::future(async move {
+            let retries = AtomicU32::new(0);
+            let result = match self.fetch(tile_id, &retries).await {
+                // The source was replaced while the tile was fetched
+                Ok(_) if self.semaphore.is_closed() => Err(TileError::Closed),
+                result => result,
+            };
+            (result, retries.into_inner())
+        })
+        .map(move |(res, retries)| match res {
+            Ok(tile) => CacheMessage::Loaded {
+                id: tile_id,
+                handle: tile,
+            },
+            Err(error) => CacheMessage::LoadFailed {
+                id: tile_id,
+                error,
+                retries,
+            },
+        })
+    }
+
+    fn source(&self) -> &dyn Source {
+        &*self.source
+    }
+
+    fn focus(&self, _position: Mercator) {
+        #[cfg(feature = "decode")]
+        self.decoder.focus(_position);
+    }
+
+    fn set_hidpi(&self, hidpi: bool) -> bool {
+        self.hidpi.swap(hidpi, Ordering::Relaxed) != hidpi
+    }
+
+    fn set_offline(&self, offline: bool) {
+        self.offline.store(offline, Ordering::Relaxed);
+    }
+
+    #[cfg(feature = "mbtiles")]
+    fn disk_cache(&self) -> Option<PathBuf> {
+        self.tile_dir(&*self.source)
+    }
+
+    fn download(self: Arc<Self>, tiles: Vec<TileCoord>) -> Task<CacheMessage> {
+        if self.disk_cache.is_none() {
+            log::warn!("Unable to download tiles without a disk cache to keep them in");
+            return Task::done(CacheMessage::download_failed(tiles.len()));
+        }
+
+        let total = tiles.len();
+        let downloads = iced::futures::stream::iter(tiles)
+            .map(move |tile_id| {
+                let fetcher = self.clone();
+                async move { fetcher.download_tile(tile_id).await }
+            })
+            .buffer_unordered(DOWNLOAD_CONCURRENCY);
+
+        let (mut done, mut failed) = (0, 0);
+        Task::stream(downloads.map(move |result| {
+            done += 1;
+            if let Err(err) = result {
+                log::debug!("Unable to download tile: {err}");
+                failed += 1;
+            }
+            CacheMessage::DownloadProgress {
+                done,
+                failed,
+                total,
+            }
+        }))
+    }
+
+    fn with_source(
+        &self,
+        source: Box<dyn Source>,
+        refresh_after: Option<Duration>,
+    ) -> Arc<dyn Fetcher> {
+        let tile_dir = |dir: &PathBuf| dir.join(source_dir(&*source));
+        let disk_cache = self.cache_root.as_ref().map(tile_dir);
+
+        Arc::new(Self {
+            semaphore: Semaphore::new(self.concurrency),
+            concurrency: self.concurrency,
+            queue_timeout: self.queue_timeout,
+            source,
+            client: self.client.clone(),
+            hidpi: AtomicBool::new(self.hidpi.load(Ordering::Relaxed)),
+            offline: AtomicBool::new(self.offline.load(Ordering::Relaxed)),
+            requests: AtomicUsize::new(0),
+            credentials: AtomicUsize::new(0),
+            renewal: tokio::sync::Mutex::new(()),
+            disk_cache,
+            cache_root: self.cache_root.clone(),
+            refresh_after,
+            rate_limit: self.rate_limit.as_ref().map(|rate_limit| RateLimit {
+                interval: rate_limit.interval,
+                next: Mutex::new(Instant::now()),
+            }),
+            retry: self.retry,
+            #[cfg(feature = "decode")]
+            decoder: self.decoder.clone(),
+            #[cfg(feature = "decode")]
+            processor: self.processor.clone(),
+        })
+    }
+
+    fn close(&self) {
+        self.semaphore.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retries_back_off() {
+        let policy = RetryPolicy::default();
+        let delays: Vec<_> = (1..6).map(|retry| policy.backoff(retry)).collect();
+        assert_eq!(
+            delays,
+            [500, 1000, 2000, 4000, 4000].map(Duration::from_millis)
+        );
+        assert_eq!(RetryPolicy::NEVER.attempts, 1);
+    }
+}
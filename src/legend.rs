@@ -0,0 +1,251 @@
+//! A legend explaining the symbology of the layers on a map, shown in one of its corners.
+//!
+//! The styles of the layers contribute their own entries, e.g.
+//! [`crate::markers::MarkerStyle::legend`], such that the legend matches what is drawn:
+//!
+//! ```ignore
+//! let legend = Legend::new("Stations")
+//!     .entry(marker_style.legend("Weather station"))
+//!     .entry(LegendEntry::new(Symbol::Ramp(vec![Color::WHITE, Color::BLACK]), "Rainfall"));
+//!
+//! MapProgram::new(&cache)
+//!     .with_children([legend.overlay(Horizontal::Right, Vertical::Bottom).map(Message::Legend)])
+//! ```
+
+use iced::widget::canvas::{self, Frame, Geometry, Path, Stroke, gradient};
+use iced::widget::{button, canvas as widget_canvas, column, container, row, text};
+use iced::{Color, Element, Point, Rectangle, Size, Theme, Vector, alignment, mouse};
+
+use crate::GlobalElement;
+
+/// The size of the swatch drawn in front of each entry.
+const SWATCH: Size = Size::new(24.0, 14.0);
+
+/// The distance between the legend and the edges of the map.
+const MARGIN: f32 = 10.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LegendMessage {
+    /// Collapse the legend to its title, or expand it again.
+    Toggle,
+}
+
+/// How the features of an entry are drawn on the map.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Symbol {
+    Point {
+        color: Color,
+        radius: f32,
+    },
+    Line {
+        color: Color,
+        width: f32,
+    },
+    Area {
+        fill: Color,
+        stroke: Color,
+    },
+    /// A continuous scale of colors, like those of a heatmap, from low to high.
+    Ramp(Vec<Color>),
+}
+
+/// A swatch of some [`Symbol`] along with a label explaining it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LegendEntry {
+    pub symbol: Symbol,
+    pub label: String,
+}
+
+impl LegendEntry {
+    pub fn new(symbol: Symbol, label: impl Into<String>) -> Self {
+        Self {
+            symbol,
+            label: label.into(),
+        }
+    }
+
+    /// The swatch followed by the label.
+    pub(crate) fn view<'a, Message: 'a>(&'a self) -> Element<'a, Message> {
+        row![
+            widget_canvas(Swatch(&self.symbol))
+                .width(SWATCH.width)
+                .height(SWATCH.height),
+            text(&self.label).size(12),
+        ]
+        .spacing(6)
+        .align_y(alignment::Vertical::Center)
+        .into()
+    }
+}
+
+/// A titled list of [`LegendEntry`]s, which can be collapsed to only its title.
+///
+/// Keep the legend in the application state, so it remembers whether it is collapsed, and
+/// glue [`Legend::update`] into the application.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Legend {
+    title: String,
+    entries: Vec<LegendEntry>,
+    collapsed: bool,
+}
+
+impl Legend {
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            entries: Vec::new(),
+            collapsed: false,
+        }
+    }
+
+    pub fn entry(mut self, entry: LegendEntry) -> Self {
+        self.entries.push(entry);
+        self
+    }
+
+    /// Start out collapsed to the title.
+    pub fn collapsed(mut self, collapsed: bool) -> Self {
+        self.collapsed = collapsed;
+        self
+    }
+
+    /// Replace the entries, e.g. when the layers on the map change.
+    pub fn set_entries(&mut self, entries: impl IntoIterator<Item = LegendEntry>) {
+        self.entries = entries.into_iter().collect();
+    }
+
+    pub fn entries(&self) -> &[LegendEntry] {
+        &self.entries
+    }
+
+    pub fn is_collapsed(&self) -> bool {
+        self.collapsed
+    }
+
+    pub fn update(&mut self, message: LegendMessage) {
+        match message {
+            LegendMessage::Toggle => self.collapsed = !self.collapsed,
+        }
+    }
+
+    /// The title with a button to collapse or expand the legend, followed by the entries.
+    pub fn view(&self) -> Element<'_, LegendMessage> {
+        let toggle = if self.collapsed { "+" } else { "−" };
+        let header = row![
+            text(&self.title).size(14),
+            button(text(toggle).size(12))
+                .padding([0, 6])
+                .style(button::text)
+                .on_press(LegendMessage::Toggle),
+        ]
+        .spacing(6)
+        .align_y(alignment::Vertical::Center);
+
+        let mut content = column![header].spacing(4);
+        if !self.collapsed {
+            content = content.extend(self.entries.iter().map(LegendEntry::view));
+        }
+
+        container(content)
+            .padding(8)
+            .style(container::rounded_box)
+            .into()
+    }
+
+    /// The legend placed in a corner of the map, to be added to its children.
+    pub fn overlay(
+        &self,
+        horizontal: alignment::Horizontal,
+        vertical: alignment::Vertical,
+    ) -> GlobalElement<'_, LegendMessage, Theme, iced::Renderer> {
+        let x = match horizontal {
+            alignment::Horizontal::Left => MARGIN,
+            alignment::Horizontal::Center => 0.0,
+            alignment::Horizontal::Right => -MARGIN,
+        };
+        let y = match vertical {
+            alignment::Vertical::Top => MARGIN,
+            alignment::Vertical::Center => 0.0,
+            alignment::Vertical::Bottom => -MARGIN,
+        };
+
+        GlobalElement::screen(self.view(), horizontal, vertical).offset(Vector::new(x, y))
+    }
+}
+
+/// Draws a [`Symbol`] to fill the swatch.
+struct Swatch<'a>(&'a Symbol);
+
+impl<Message> canvas::Program<Message> for Swatch<'_> {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &iced::Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = Frame::new(renderer, bounds.size());
+        let size = bounds.size();
+        let center = frame.center();
+
+        match self.0 {
+            Symbol::Point { color, radius } => {
+                let circle = Path::circle(center, radius.min(size.height / 2.0));
+                frame.fill(&circle, *color);
+            }
+            Symbol::Line { color, width } => {
+                let line = Path::line(Point::new(0.0, center.y), Point::new(size.width, center.y));
+                frame.stroke(
+                    &line,
+                    Stroke::default()
+                        .with_color(*color)
+                        .with_width(width.min(size.height)),
+                );
+            }
+            Symbol::Area { fill, stroke } => {
+                let area = Path::rectangle(Point::new(0.5, 0.5), size - Size::new(1.0, 1.0));
+                frame.fill(&area, *fill);
+                frame.stroke(&area, Stroke::default().with_color(*stroke));
+            }
+            Symbol::Ramp(colors) => {
+                let mut ramp =
+                    gradient::Linear::new(Point::new(0.0, 0.0), Point::new(size.width, 0.0));
+                let last = colors.len().saturating_sub(1).max(1) as f32;
+                for (i, color) in colors.iter().enumerate() {
+                    ramp = ramp.add_stop(i as f32 / last, *color);
+                }
+                frame.fill_rectangle(Point::ORIGIN, size, ramp);
+            }
+        }
+
+        vec![frame.into_geometry()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markers::MarkerStyle;
+
+    #[test]
+    fn toggle_collapses_legend() {
+        let style = MarkerStyle::default();
+        let mut legend = Legend::new("Stations").entry(style.legend("Weather station"));
+        assert_eq!(
+            legend.entries()[0].symbol,
+            Symbol::Point {
+                color: style.color,
+                radius: style.radius,
+            }
+        );
+
+        assert!(!legend.is_collapsed());
+        legend.update(LegendMessage::Toggle);
+        assert!(legend.is_collapsed());
+        legend.update(LegendMessage::Toggle);
+        assert!(!legend.is_collapsed());
+    }
+}
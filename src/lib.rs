@@ -1,24 +1,75 @@
+#[cfg(all(feature = "decode", target_arch = "wasm32"))]
+compile_error!(
+    "The `decode` feature decodes tiles on worker threads, which web builds do not have"
+);
+
+pub mod animation;
+#[cfg(feature = "decode")]
+mod decoder;
 mod draw_cache;
 
+#[cfg(feature = "elevation")]
+pub mod elevation;
+#[cfg(feature = "geojson")]
+pub mod feed;
+pub mod geometry;
+#[cfg(feature = "gps")]
+pub mod gps;
+pub mod grid;
+pub mod legend;
+pub mod markers;
+pub mod measure;
+pub mod print;
+#[cfg(feature = "routing")]
+pub mod routing;
 pub mod sources;
+pub mod terminator;
+pub mod timeline;
+pub mod vehicles;
 
+mod gestures;
 mod global_element;
+#[cfg(feature = "http")]
+mod http_cache;
+#[cfg(feature = "http")]
+mod http_client;
+#[cfg(feature = "http")]
+mod http_fetcher;
 mod map_layers;
 mod map_program;
+mod map_state;
 mod map_widget;
+#[cfg(feature = "decode")]
+mod placeholder;
+#[cfg(feature = "http")]
+mod platform;
 mod position;
 mod projector;
+#[cfg(feature = "decode")]
+mod reproject;
 mod tile_cache;
 mod tile_coord;
 mod viewpoint;
 mod zoom;
 
-pub use global_element::GlobalElement;
-pub use map_program::{Action, MapProgram};
-pub use map_widget::MapWidget;
-pub use position::{Geodetic, Mercator, location};
+pub use gestures::{GestureProfile, Gestures};
+pub use global_element::{Anchor, Declutter, GlobalElement, Popup};
+#[cfg(feature = "http")]
+pub use http_client::{HttpClient, HttpFuture, HttpResponse};
+#[cfg(feature = "http")]
+pub use http_fetcher::RetryPolicy;
+pub use map_program::{Action, DrawLayer, LayerPlacement, MapProgram, NoCache};
+pub use map_state::{MapMessage, MapState};
+pub use map_widget::{MapWidget, Prefetch, PrefetchMargin, ScrollCapture};
+pub use position::{
+    CoordinateFormat, GeoBounds, Geodetic, InvalidGeodetic, InvalidLocator, InvalidMgrs,
+    InvalidPlusCode, InvalidUtm, Mercator, Utm, UtmZone, location,
+};
 pub use projector::Projector;
-pub use tile_cache::{CacheMessage, TileCache};
+pub use tile_cache::{
+    AllocationPolicy, CacheMessage, CooldownPolicy, TileCache, TileCacheBuilder, TileError,
+    TileFailure,
+};
 pub use tile_coord::TileCoord;
 pub use viewpoint::Viewpoint;
 pub use zoom::{InvalidZoom, Zoom};
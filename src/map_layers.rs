@@ -4,12 +4,31 @@ use iced_core::{
     widget::{self, tree},
 };
 
-use crate::{GlobalElement, Projector, Viewpoint};
+use crate::{
+    Anchor, GlobalElement, Projector, Viewpoint,
+    global_element::{Clutter, Declutter, Placement},
+};
 
 pub struct MapLayers<'a, Message, Theme, Renderer> {
     base: Element<'a, Message, Theme, Renderer>,
     children: Vec<GlobalElement<'a, Message, Theme, Renderer>>,
     viewpoint: Viewpoint,
+    declutter: Option<Declutter>,
+}
+
+/// The placement of each child from the most recent layout.
+#[derive(Debug, Default)]
+struct State {
+    placements: Vec<Placement>,
+}
+
+impl State {
+    fn placement(&self, child: usize) -> Placement {
+        self.placements
+            .get(child)
+            .copied()
+            .unwrap_or(Placement::Shown)
+    }
 }
 
 impl<'a, Message, Theme, Renderer> MapLayers<'a, Message, Theme, Renderer>
@@ -20,11 +39,13 @@ where
         base: impl Into<Element<'a, Message, Theme, Renderer>>,
         viewpoint: Viewpoint,
         children: Vec<GlobalElement<'a, Message, Theme, Renderer>>,
+        declutter: Option<Declutter>,
     ) -> Self {
         Self {
             base: base.into(),
             children,
             viewpoint,
+            declutter,
         }
     }
 }
@@ -34,6 +55,14 @@ impl<'a, Message, Theme, Renderer> Widget<Message, Theme, Renderer>
 where
     Renderer: iced_core::Renderer,
 {
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::of::<State>()
+    }
+
+    fn state(&self) -> tree::State {
+        tree::State::new(State::default())
+    }
+
     fn size(&self) -> Size<Length> {
         self.base.as_widget().size()
     }
@@ -72,10 +101,15 @@ where
             );
 
             let child_size = child_node.size();
-            let position = child.position;
 
             // Project geodetical position to relative screen coordinates
-            let screen_pos = projector.mercator_into_screen_space(position);
+            let screen_pos = child.anchor_point(&projector);
+
+            if let Some(popup) = &child.popup {
+                let top_left = popup.place(screen_pos, child_size, bounds);
+                nodes.push(child_node.move_to(top_left));
+                continue;
+            }
 
             let x = match child.horizontal_alignment {
                 alignment::Horizontal::Left => screen_pos.x,
@@ -92,6 +126,32 @@ where
             nodes.push(child_node.move_to(Point::new(x, y)));
         }
 
+        // Move or hide the children which overlap others, once all of them are in place
+        let state = tree.state.downcast_mut::<State>();
+        state.placements.clear();
+        if let Some(declutter) = &self.declutter {
+            let clutter: Vec<Clutter> = self
+                .children
+                .iter()
+                .zip(&nodes[1..])
+                .map(|(child, node)| match (child.anchor, child.popup) {
+                    (Anchor::Screen, _) => Clutter::Ignored,
+                    (Anchor::Map(_), Some(_)) => Clutter::Fixed(node.bounds()),
+                    (Anchor::Map(_), None) => Clutter::Movable {
+                        bounds: node.bounds(),
+                        priority: child.priority,
+                    },
+                })
+                .collect();
+
+            state.placements = declutter.place(&clutter);
+            for (node, placement) in nodes[1..].iter_mut().zip(&state.placements) {
+                if let Placement::Moved(offset) = placement {
+                    node.translate_mut(*offset);
+                }
+            }
+        }
+
         iced_core::layout::Node::with_children(bounds.size(), nodes)
     }
 
@@ -162,17 +222,23 @@ where
     ) {
         let mut children_layout = layout.children();
         let base_layout = children_layout.next().unwrap();
+        let state = tree.state.downcast_ref::<State>();
         let (base_tree, children_trees) = tree.children.split_first_mut().unwrap();
 
         // Update children first (reverse order - top to bottom)
         // This allows children to capture events before the map
-        for ((child, child_tree), child_layout) in self
+        for (i, ((child, child_tree), child_layout)) in self
             .children
             .iter_mut()
+            .zip(children_trees.iter_mut())
+            .zip(children_layout)
+            .enumerate()
             .rev()
-            .zip(children_trees.iter_mut().rev())
-            .zip(children_layout.rev())
         {
+            if state.placement(i) == Placement::Hidden {
+                continue;
+            }
+
             child.element.as_widget_mut().update(
                 child_tree,
                 event,
@@ -210,16 +276,22 @@ where
     ) -> mouse::Interaction {
         let mut children_layout = layout.children();
         let base_layout = children_layout.next().unwrap();
+        let state = tree.state.downcast_ref::<State>();
         let (base_tree, children_trees) = tree.children.split_first().unwrap();
 
         // Check children interactions first (reverse order - top to bottom)
-        for ((child, child_tree), child_layout) in self
+        for (i, ((child, child_tree), child_layout)) in self
             .children
             .iter()
+            .zip(children_trees.iter())
+            .zip(children_layout)
+            .enumerate()
             .rev()
-            .zip(children_trees.iter().rev())
-            .zip(children_layout.rev())
         {
+            if state.placement(i) == Placement::Hidden {
+                continue;
+            }
+
             let interaction = child.element.as_widget().mouse_interaction(
                 child_tree,
                 child_layout,
@@ -251,6 +323,7 @@ where
     ) {
         let mut children_layout = layout.children();
         let base_layout = children_layout.next().unwrap();
+        let state = tree.state.downcast_ref::<State>();
 
         // 1. Draw base map
         self.base.as_widget().draw(
@@ -263,12 +336,36 @@ where
             viewport,
         );
 
+        let projector = Projector {
+            viewpoint: self.viewpoint,
+            bounds: layout.bounds(),
+        };
+
         // 2. Draw children on top
         renderer.with_layer(layout.bounds(), |renderer| {
             for (i, child) in self.children.iter().enumerate() {
                 let child_tree = &tree.children[i + 1];
                 let child_layout = children_layout.next().unwrap();
 
+                // The leader line goes below the popup, or an element moved out of the way
+                let leader = match state.placement(i) {
+                    Placement::Hidden => continue,
+                    Placement::Moved(_) => self.declutter.as_ref().and_then(Declutter::leader),
+                    Placement::Shown => child.popup.as_ref(),
+                };
+                if let Some(popup) = leader {
+                    let anchor = child.anchor_point(&projector);
+                    if let Some(leader) = popup.leader(anchor, child_layout.bounds()) {
+                        renderer.fill_quad(
+                            renderer::Quad {
+                                bounds: leader,
+                                ..Default::default()
+                            },
+                            popup.color,
+                        );
+                    }
+                }
+
                 if child_layout.bounds().intersects(viewport) {
                     child.element.as_widget().draw(
                         child_tree,
@@ -298,6 +395,7 @@ where
         let mut overlays = Vec::new();
 
         // Split tree to access base independently from children
+        let state = tree.state.downcast_ref::<State>();
         let (base_tree, children_trees) = tree.children.split_first_mut().unwrap();
 
         if let Some(overlay) = self.base.as_widget_mut().overlay(
@@ -310,8 +408,17 @@ where
             overlays.push(overlay);
         }
 
-        for (child, child_tree) in self.children.iter_mut().zip(children_trees.iter_mut()) {
+        for (i, (child, child_tree)) in self
+            .children
+            .iter_mut()
+            .zip(children_trees.iter_mut())
+            .enumerate()
+        {
             let child_layout = children_layout.next().unwrap();
+            if state.placement(i) == Placement::Hidden {
+                continue;
+            }
+
             if let Some(overlay) = child.element.as_widget_mut().overlay(
                 child_tree,
                 child_layout,
@@ -1,14 +1,18 @@
+use std::{any::Any, cell::RefCell, collections::VecDeque, time::Duration};
+
 use iced::widget::{
     canvas as widget_canvas,
     canvas::{self, Frame, Geometry},
     stack,
 };
-use iced::{Element, Length, Rectangle};
+use iced::{Color, Element, Length, Rectangle};
 use iced::{Point, mouse};
 
 use crate::{
-    CacheMessage, Projector, TileCache, Viewpoint, global_element::GlobalElement,
-    map_layers::MapLayers, map_widget::MapWidget,
+    CacheMessage, Gestures, Projector, TileCache, TileFailure, Viewpoint,
+    global_element::{Declutter, GlobalElement},
+    map_layers::MapLayers,
+    map_widget::{MapWidget, Prefetch, ScrollCapture},
 };
 
 // ============================================================================
@@ -19,7 +23,15 @@ use crate::{
 ///
 /// This enum allows you to control whether the map underneath should also
 /// receive the event (e.g. for panning) or if it should be captured.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+///
+/// The interaction is offered every event before the map, including keyboard events, and
+/// the gestures of the mouse and touch screen belong to whoever handles their first press:
+///
+/// - Capturing the press keeps the whole gesture from the map, up to its release, so the map
+///   does not pan while the interaction is dragging something.
+/// - Otherwise the map owns the gesture, and the interaction can no longer capture its
+///   events. The messages of the interaction are still published, but the map keeps panning.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Action<Message> {
     /// No action taken. Event propagates to map.
     None,
@@ -27,6 +39,32 @@ pub enum Action<Message> {
     Publish(Message),
     /// Publish a message and capture the event (preventing map interaction).
     Capture(Message),
+    /// Publish several messages in order, letting the event propagate to the map. Only one
+    /// message can be published per event, so the rest follow on the next frames.
+    PublishMany(Vec<Message>),
+    /// Redraw the map on the next frame, which is followed by a
+    /// [`iced::window::Event::RedrawRequested`] event. Returning this for each of those
+    /// events keeps an animation going without involving the application.
+    RequestRedraw,
+    /// Show this cursor while it is over the map, until another one is set. Setting
+    /// [`mouse::Interaction::None`] gives the cursor back to the map.
+    SetCursor(mouse::Interaction),
+}
+
+impl<Message> Action<Message> {
+    /// Convert the message of this action, keeping the propagation behavior.
+    pub fn map<B>(self, mut f: impl FnMut(Message) -> B) -> Action<B> {
+        match self {
+            Action::None => Action::None,
+            Action::Publish(message) => Action::Publish(f(message)),
+            Action::Capture(message) => Action::Capture(f(message)),
+            Action::PublishMany(messages) => {
+                Action::PublishMany(messages.into_iter().map(f).collect())
+            }
+            Action::RequestRedraw => Action::RequestRedraw,
+            Action::SetCursor(interaction) => Action::SetCursor(interaction),
+        }
+    }
 }
 
 /// A builder for creating an interactive map with custom drawing and interaction layers.
@@ -48,51 +86,172 @@ pub enum Action<Message> {
 ///     })
 ///     .build(viewpoint)
 /// ```
-pub struct MapProgram<'a, Message> {
+///
+/// The `on_cache` callback is required, and a program can only be built once it is set.
+pub struct MapProgram<'a, Message, OnCache = fn(CacheMessage) -> Message> {
     tile_cache: &'a TileCache,
 
-    // Required callback for cache messages
-    on_cache: fn(CacheMessage) -> Message,
+    // Required callback for cache messages, or `NoCache` until it is configured
+    on_cache: OnCache,
 
     // Optional callbacks
     on_update: Option<fn(Projector) -> Message>,
+    on_quality: Option<fn(bool) -> Message>,
+    on_tile_error: Option<fn(TileFailure) -> Message>,
+
+    // Scale factor of the display, for snapping tiles to its pixel grid
+    pixel_snapping: Option<f32>,
+
+    // Color of the polar regions which are not covered by the map
+    polar_fill: Option<Color>,
+
+    // Gesture tuning for each kind of input device
+    gestures: Option<Gestures>,
+
+    // When scrolling zooms the map rather than a parent widget
+    scroll_capture: ScrollCapture,
 
-    // User drawing layer
-    draw_layer: Option<Box<dyn Fn(&Projector, &mut Frame<iced::Renderer>) + 'a>>,
+    // How far beyond the edges of the map tiles are loaded
+    prefetch: Option<Prefetch>,
+
+    // Whether the map keeps moving after the input stops
+    inertia: bool,
+
+    // The viewpoint to ease toward, and its time constant
+    follow: Option<(Viewpoint, Duration)>,
+
+    // User drawing layers
+    draw_layers: Vec<DrawLayer<'a>>,
 
     // User interaction layer
-    interact_layer:
-        Option<Box<dyn Fn(&Projector, &mouse::Cursor, &canvas::Event) -> Action<Message> + 'a>>,
+    interact_layer: Option<Interaction<'a, Message>>,
 
     // GlobalElements (markers, widgets at geodetic positions)
     children: Vec<GlobalElement<'a, Message, iced::Theme, iced::Renderer>>,
+
+    // How overlapping elements make way for each other
+    declutter: Option<Declutter>,
+}
+
+/// Where a [`DrawLayer`] is drawn relative to the [`GlobalElement`]s of the map.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LayerPlacement {
+    /// Between the tiles and the elements.
+    BelowElements,
+    /// On top of the elements.
+    #[default]
+    AboveElements,
+}
+
+/// A named drawing layer of a [`MapProgram`].
+pub struct DrawLayer<'a> {
+    name: String,
+    z_index: i32,
+    placement: LayerPlacement,
+    visible: bool,
+    version: Option<u64>,
+    draw: DrawFn<'a>,
+}
+
+type DrawFn<'a> = Box<dyn Fn(&Projector, &mut Frame<iced::Renderer>) + 'a>;
+
+impl<'a> DrawLayer<'a> {
+    pub fn new(
+        name: impl Into<String>,
+        draw: impl Fn(&Projector, &mut Frame<iced::Renderer>) + 'a,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            z_index: 0,
+            placement: LayerPlacement::default(),
+            visible: true,
+            version: None,
+            draw: Box::new(draw),
+        }
+    }
+
+    /// Layers with a higher z-index are drawn on top of those with a lower one.
+    pub fn z_index(mut self, z_index: i32) -> Self {
+        self.z_index = z_index;
+        self
+    }
+
+    pub fn placement(mut self, placement: LayerPlacement) -> Self {
+        self.placement = placement;
+        self
+    }
+
+    pub fn visible(mut self, visible: bool) -> Self {
+        self.visible = visible;
+        self
+    }
+
+    /// Cache the geometry of the layer, which is then only drawn again when the viewpoint,
+    /// the size of the map, or the given version changes. Bump the version whenever the
+    /// content of the layer changes.
+    pub fn version(mut self, version: u64) -> Self {
+        self.version = Some(version);
+        self
+    }
 }
 
 // ============================================================================
 // Builder API
 // ============================================================================
 
-impl<'a, Message: 'a> MapProgram<'a, Message> {
+/// Marks a [`MapProgram`] which does not handle cache messages yet, and can not be built.
+#[derive(Debug, Clone, Copy)]
+pub struct NoCache;
+
+impl<'a, Message: 'a> MapProgram<'a, Message, NoCache> {
     /// Create a new MapProgram with the given tile cache.
     ///
-    /// You must call `.on_cache()` to configure cache message handling.
+    /// You must call `.on_cache()` to configure cache message handling before building it.
     pub fn new(tile_cache: &'a TileCache) -> Self {
         Self {
             tile_cache,
-            on_cache: |_| panic!("MapProgram: on_cache() must be configured"),
+            on_cache: NoCache,
             on_update: None,
-            draw_layer: None,
+            on_quality: None,
+            on_tile_error: None,
+            pixel_snapping: None,
+            polar_fill: None,
+            gestures: None,
+            scroll_capture: ScrollCapture::default(),
+            prefetch: None,
+            inertia: true,
+            follow: None,
+            draw_layers: Vec::new(),
             interact_layer: None,
             children: Vec::new(),
+            declutter: None,
         }
     }
+}
 
+impl<'a, Message: 'a, OnCache> MapProgram<'a, Message, OnCache> {
     /// Set the callback for cache messages (tile loading, etc.).
     ///
-    /// This is required and will panic if not set.
-    pub fn on_cache(mut self, f: fn(CacheMessage) -> Message) -> Self {
-        self.on_cache = f;
-        self
+    /// This is required before the program can be built.
+    pub fn on_cache(self, f: fn(CacheMessage) -> Message) -> MapProgram<'a, Message> {
+        MapProgram {
+            tile_cache: self.tile_cache,
+            on_cache: f,
+            on_update: self.on_update,
+            on_quality: self.on_quality,
+            on_tile_error: self.on_tile_error,
+            pixel_snapping: self.pixel_snapping,
+            polar_fill: self.polar_fill,
+            gestures: self.gestures,
+            scroll_capture: self.scroll_capture,
+            prefetch: self.prefetch,
+            inertia: self.inertia,
+            follow: self.follow,
+            draw_layers: self.draw_layers,
+            interact_layer: self.interact_layer,
+            children: self.children,
+            declutter: self.declutter,
+        }
     }
 
     /// Set the callback for viewpoint updates (pan, zoom).
@@ -103,7 +262,72 @@ impl<'a, Message: 'a> MapProgram<'a, Message> {
         self
     }
 
-    /// Add a custom drawing layer on top of the map tiles.
+    /// Set the callback for changes of the rendering quality.
+    ///
+    /// The callback receives `true` when the map can not keep up with its target frame rate
+    /// while interacting, and `false` once the interaction stops. Expensive draw layers can
+    /// be skipped while the quality is reduced.
+    pub fn on_quality(mut self, f: fn(bool) -> Message) -> Self {
+        self.on_quality = Some(f);
+        self
+    }
+
+    /// Set the callback for tiles which could not be loaded, e.g. to notify the user when
+    /// the tile server starts rejecting requests.
+    pub fn on_tile_error(mut self, f: fn(TileFailure) -> Message) -> Self {
+        self.on_tile_error = Some(f);
+        self
+    }
+
+    /// Snap the map tiles to the physical pixel grid of a display with the given scale
+    /// factor, whenever the zoom level is close to an integer.
+    pub fn pixel_snapping(mut self, scale_factor: f32) -> Self {
+        self.pixel_snapping = Some(scale_factor);
+        self
+    }
+
+    /// Fill the polar regions which are not covered by the map with a solid color.
+    pub fn polar_fill(mut self, color: Color) -> Self {
+        self.polar_fill = Some(color);
+        self
+    }
+
+    /// Tune how the map responds to the mouse, trackpad and touch screen.
+    pub fn gestures(mut self, gestures: Gestures) -> Self {
+        self.gestures = Some(gestures);
+        self
+    }
+
+    /// When scrolling over the map zooms it. Scroll events which are not captured reach the
+    /// parent widgets, such that a map embedded in a `scrollable` does not steal its scrolling.
+    pub fn scroll_capture(mut self, scroll_capture: ScrollCapture) -> Self {
+        self.scroll_capture = scroll_capture;
+        self
+    }
+
+    /// How far beyond the edges of the map tiles are loaded, optionally further ahead in
+    /// the direction of panning.
+    pub fn prefetch(mut self, prefetch: Prefetch) -> Self {
+        self.prefetch = Some(prefetch);
+        self
+    }
+
+    /// Whether the map keeps panning after a fling and animates zooming. Disable it to stop
+    /// the map from moving on its own, while the application controls the viewpoint.
+    pub fn inertia(mut self, inertia: bool) -> Self {
+        self.inertia = inertia;
+        self
+    }
+
+    /// Ease toward a viewpoint from an external feed, e.g. to follow a GPS position without
+    /// jumping at each fix. See [`MapWidget::follow`].
+    pub fn follow(mut self, target: Viewpoint, time_constant: Duration) -> Self {
+        self.follow = Some((target, time_constant));
+        self
+    }
+
+    /// Add a custom drawing layer on top of the map tiles and elements. This can be called
+    /// multiple times, in which case later layers are drawn on top.
     ///
     /// The callback receives a `Projector` for coordinate conversion and a `Frame` for drawing.
     ///
@@ -115,17 +339,44 @@ impl<'a, Message: 'a> MapProgram<'a, Message> {
     ///     frame.fill(&canvas::Path::circle(pos, 10.0), Color::RED);
     /// })
     /// ```
-    pub fn with_draw_layer<F>(mut self, f: F) -> Self
+    pub fn with_draw_layer<F>(self, f: F) -> Self
     where
         F: Fn(&Projector, &mut Frame<iced::Renderer>) + 'a,
     {
-        self.draw_layer = Some(Box::new(f));
+        self.with_layer(DrawLayer::new("", f))
+    }
+
+    /// Add a named drawing layer, with control over its order and placement.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// .with_layer(DrawLayer::new("routes", draw_routes).placement(LayerPlacement::BelowElements))
+    /// .with_layer(DrawLayer::new("labels", draw_labels).z_index(1))
+    /// ```
+    pub fn with_layer(mut self, layer: DrawLayer<'a>) -> Self {
+        self.draw_layers.push(layer);
+        self
+    }
+
+    /// Show or hide all drawing layers with the given name.
+    pub fn layer_visible(mut self, name: &str, visible: bool) -> Self {
+        for layer in self
+            .draw_layers
+            .iter_mut()
+            .filter(|layer| layer.name == name)
+        {
+            layer.visible = visible;
+        }
         self
     }
 
     /// Add a custom interaction layer for handling events.
     ///
-    /// The callback receives events and can return actions via `MapInteraction`.
+    /// The callback receives events and can return actions via `MapInteraction`. It may
+    /// mutate what it captures, but it is rebuilt along with the program whenever the view
+    /// is. State which should outlive the view, like which vertex is hovered, is kept by
+    /// [`MapProgram::with_interaction_state`] instead.
     ///
     /// # Example
     ///
@@ -140,24 +391,77 @@ impl<'a, Message: 'a> MapProgram<'a, Message> {
     ///     MapInteraction::None  // Pass through to MapWidget for panning
     /// })
     /// ```
-    pub fn with_interaction<F>(mut self, f: F) -> Self
+    pub fn with_interaction<F>(mut self, mut f: F) -> Self
     where
-        F: Fn(&Projector, &mouse::Cursor, &canvas::Event) -> Action<Message> + 'a,
+        F: FnMut(&Projector, &mouse::Cursor, &canvas::Event) -> Action<Message> + 'a,
     {
-        self.interact_layer = Some(Box::new(f));
+        self.interact_layer = Some(Interaction {
+            update: RefCell::new(Box::new(move |_, projector, cursor, event| {
+                (f(projector, cursor, event), false)
+            })),
+            draw: None,
+        });
         self
     }
 
-    /// Add globally positioned elements (markers, widgets at geodetic coordinates).
+    /// Add an interaction layer with its own mutable state, which is kept by the map between
+    /// frames and drawn on top of the other layers. This suits state which only matters to
+    /// the interaction itself, like hover highlights, as it changes without going through
+    /// application messages, and is drawn in the same frame as the event.
+    ///
+    /// The map is redrawn whenever the state changes.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// .with_interaction_state(
+    ///     |hovered: &mut Option<usize>, projector, cursor, event| {
+    ///         *hovered = cursor.position().and_then(|cursor| vertex_at(projector, cursor));
+    ///         Action::None
+    ///     },
+    ///     |hovered, projector, frame| draw_highlight(*hovered, projector, frame),
+    /// )
+    /// ```
+    pub fn with_interaction_state<S, F, D>(mut self, mut f: F, draw: D) -> Self
+    where
+        S: Default + Clone + PartialEq + 'static,
+        F: FnMut(&mut S, &Projector, &mouse::Cursor, &canvas::Event) -> Action<Message> + 'a,
+        D: Fn(&S, &Projector, &mut Frame<iced::Renderer>) + 'a,
+    {
+        self.interact_layer = Some(Interaction {
+            update: RefCell::new(Box::new(move |state, projector, cursor, event| {
+                // The state is replaced if the map was previously used with a different type
+                if !state.as_ref().is_some_and(|state| state.is::<S>()) {
+                    *state = Some(Box::new(S::default()));
+                }
+                let Some(state) = state.as_mut().and_then(|state| state.downcast_mut::<S>()) else {
+                    return (Action::None, false);
+                };
+
+                let previous = state.clone();
+                let action = f(state, projector, cursor, event);
+                (action, *state != previous)
+            })),
+            draw: Some(Box::new(move |state, projector, frame| {
+                match state.as_ref().and_then(|state| state.downcast_ref::<S>()) {
+                    Some(state) => draw(state, projector, frame),
+                    None => draw(&S::default(), projector, frame),
+                }
+            })),
+        });
+        self
+    }
+
+    /// Add globally positioned elements (markers, widgets at geodetic coordinates), or
+    /// elements anchored to the screen, like legends.
     ///
     /// # Example
     ///
     /// ```ignore
     /// .with_children(vec![
-    ///     GlobalElement {
-    ///         position: Geodetic::new(2.3522, 48.8566),
-    ///         element: Button::new("Paris").into(),
-    ///     }
+    ///     GlobalElement::new(button("Paris"), Geodetic::new(2.3522, 48.8566).as_mercator()),
+    ///     GlobalElement::screen(legend, Horizontal::Right, Vertical::Bottom)
+    ///         .offset(Vector::new(-20.0, -20.0)),
     /// ])
     /// ```
     pub fn with_children(
@@ -168,12 +472,25 @@ impl<'a, Message: 'a> MapProgram<'a, Message> {
         self
     }
 
+    /// Hide or move the children which overlap others of a higher
+    /// [`GlobalElement::priority`], after they are laid out on the screen.
+    ///
+    /// ```ignore
+    /// .declutter(Declutter::Displace { max_distance: 40.0, leader: Popup::default() })
+    /// ```
+    pub fn declutter(mut self, declutter: Declutter) -> Self {
+        self.declutter = Some(declutter);
+        self
+    }
+}
+
+impl<'a, Message: 'a> MapProgram<'a, Message> {
     /// Build the final widget with the given viewpoint.
     ///
     /// Returns a layered Element with MapWidget at the bottom and Canvas overlay on top.
     pub fn build(self, viewpoint: Viewpoint) -> Element<'a, Message, iced::Theme, iced::Renderer>
     where
-        Message: Clone + 'a,
+        Message: Clone + 'static,
     {
         // Create base map widget with actual tile rendering
         let mut map_widget = MapWidget::new(self.tile_cache, self.on_cache, viewpoint);
@@ -183,14 +500,69 @@ impl<'a, Message: 'a> MapProgram<'a, Message> {
             map_widget = map_widget.on_update(on_update);
         }
 
+        if let Some(on_quality) = self.on_quality {
+            map_widget = map_widget.on_quality(on_quality);
+        }
+
+        if let Some(on_tile_error) = self.on_tile_error {
+            map_widget = map_widget.on_tile_error(on_tile_error);
+        }
+
+        if let Some(scale_factor) = self.pixel_snapping {
+            map_widget = map_widget.pixel_snapping(scale_factor);
+        }
+
+        if let Some(color) = self.polar_fill {
+            map_widget = map_widget.polar_fill(color);
+        }
+
+        if let Some(gestures) = self.gestures {
+            map_widget = map_widget.gestures(gestures);
+        }
+
+        if let Some(prefetch) = self.prefetch {
+            map_widget = map_widget.prefetch(prefetch);
+        }
+
+        map_widget = map_widget.inertia(self.inertia);
+
+        if let Some((target, time_constant)) = self.follow {
+            map_widget = map_widget.follow(target, time_constant);
+        }
+
+        map_widget = map_widget.scroll_capture(self.scroll_capture);
+
+        // Layers are drawn in order of their z-index, and otherwise in the order they were added
+        let mut draw_layers = self.draw_layers;
+        draw_layers.retain(|layer| layer.visible);
+        draw_layers.sort_by_key(|layer| layer.z_index);
+        let (below, above): (Vec<_>, Vec<_>) = draw_layers
+            .into_iter()
+            .partition(|layer| layer.placement == LayerPlacement::BelowElements);
+
+        // Layers below the elements are drawn directly on top of the tiles
+        let base: Element<'a, Message, iced::Theme, iced::Renderer> = if below.is_empty() {
+            map_widget.into()
+        } else {
+            let underlay = widget_canvas(OverlayProgram {
+                draw_layers: below,
+                interaction: None,
+                viewpoint,
+            })
+            .width(Length::Fill)
+            .height(Length::Fill);
+
+            stack![map_widget, underlay].into()
+        };
+
         // Wrap in MapLayers for child positioning
-        let layers = MapLayers::new(map_widget, viewpoint, self.children);
+        let layers = MapLayers::new(base, viewpoint, self.children, self.declutter);
 
         // If there's a draw layer or interaction layer, add a canvas overlay
-        if self.draw_layer.is_some() || self.interact_layer.is_some() {
+        if !above.is_empty() || self.interact_layer.is_some() {
             let overlay = widget_canvas(OverlayProgram {
-                draw_fn: self.draw_layer,
-                interact_fn: self.interact_layer,
+                draw_layers: above,
+                interaction: self.interact_layer,
                 viewpoint,
             })
             .width(Length::Fill)
@@ -208,57 +580,358 @@ impl<'a, Message: 'a> MapProgram<'a, Message> {
 // ============================================================================
 
 struct OverlayProgram<'a, Message> {
-    draw_fn: Option<Box<dyn Fn(&Projector, &mut Frame<iced::Renderer>) + 'a>>,
-    interact_fn:
-        Option<Box<dyn Fn(&Projector, &mouse::Cursor, &canvas::Event) -> Action<Message> + 'a>>,
+    draw_layers: Vec<DrawLayer<'a>>,
+    interaction: Option<Interaction<'a, Message>>,
     viewpoint: Viewpoint,
 }
 
-impl<'a, Message: Clone> canvas::Program<Message> for OverlayProgram<'a, Message> {
-    type State = ();
+/// The state of an interaction layer, kept in the widget tree between frames.
+type InteractionState = Option<Box<dyn Any>>;
+
+/// Handles an event, returning the action along with whether the state changed.
+type InteractionUpdate<'a, Message> = dyn FnMut(
+        &mut InteractionState,
+        &Projector,
+        &mouse::Cursor,
+        &canvas::Event,
+    ) -> (Action<Message>, bool)
+    + 'a;
+
+type InteractionDraw<'a> = dyn Fn(&InteractionState, &Projector, &mut Frame<iced::Renderer>) + 'a;
+
+struct Interaction<'a, Message> {
+    update: RefCell<Box<InteractionUpdate<'a, Message>>>,
+    draw: Option<Box<InteractionDraw<'a>>>,
+}
+
+/// Who a pointer gesture belongs to, from its first press until its last release.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum Gesture {
+    #[default]
+    None,
+    Interaction,
+    Map,
+}
+
+struct OverlayState<Message> {
+    layers: RefCell<Vec<LayerCache>>,
+    gesture: Gesture,
+    fingers: usize,
+    interaction: InteractionState,
+    /// Messages waiting to be published, one per event.
+    pending: VecDeque<Message>,
+    cursor: mouse::Interaction,
+}
+
+impl<Message> Default for OverlayState<Message> {
+    fn default() -> Self {
+        Self {
+            layers: RefCell::default(),
+            gesture: Gesture::default(),
+            fingers: 0,
+            interaction: None,
+            pending: VecDeque::new(),
+            cursor: mouse::Interaction::None,
+        }
+    }
+}
+
+impl<Message> OverlayState<Message> {
+    /// Whether an event is captured from the map, given whether the interaction captured it.
+    fn capture(&mut self, event: &canvas::Event, captured: bool) -> bool {
+        use iced::{Event, touch};
+
+        let press = matches!(
+            event,
+            Event::Mouse(mouse::Event::ButtonPressed(_))
+                | Event::Touch(touch::Event::FingerPressed { .. })
+        );
+        let release = matches!(
+            event,
+            Event::Mouse(mouse::Event::ButtonReleased(_))
+                | Event::Touch(touch::Event::FingerLifted { .. } | touch::Event::FingerLost { .. })
+        );
+        let moved = matches!(
+            event,
+            Event::Mouse(mouse::Event::CursorMoved { .. })
+                | Event::Touch(touch::Event::FingerMoved { .. })
+        );
+
+        if press && self.gesture == Gesture::None {
+            self.gesture = if captured {
+                Gesture::Interaction
+            } else {
+                Gesture::Map
+            };
+        }
+
+        let capture = match self.gesture {
+            Gesture::Interaction if press || release || moved => true,
+            Gesture::Map if press || release || moved => false,
+            _ => captured,
+        };
+
+        // Count the fingers, as a touch gesture lasts until all of them are lifted
+        match event {
+            Event::Touch(touch::Event::FingerPressed { .. }) => self.fingers += 1,
+            Event::Touch(touch::Event::FingerLifted { .. } | touch::Event::FingerLost { .. }) => {
+                self.fingers = self.fingers.saturating_sub(1);
+            }
+            _ => {}
+        }
+        if release && self.fingers == 0 {
+            self.gesture = Gesture::None;
+        }
+
+        capture
+    }
+}
+
+/// The cached geometry of a draw layer, and what it was drawn for.
+#[derive(Default)]
+struct LayerCache {
+    cache: canvas::Cache,
+    key: Option<(Projector, u64)>,
+}
+
+impl<'a, Message: Clone + 'static> canvas::Program<Message> for OverlayProgram<'a, Message> {
+    type State = OverlayState<Message>;
 
     fn update(
         &self,
-        _state: &mut Self::State,
+        state: &mut Self::State,
         event: &canvas::Event,
         bounds: Rectangle,
         cursor: mouse::Cursor,
     ) -> Option<canvas::Action<Message>> {
-        if let Some(ref interact_fn) = self.interact_fn {
-            let projector = Projector {
-                viewpoint: self.viewpoint,
-                bounds,
-            };
+        let interaction = self.interaction.as_ref()?;
+        let projector = Projector {
+            viewpoint: self.viewpoint,
+            bounds,
+        };
 
-            match interact_fn(&projector, &cursor, event) {
-                Action::None => None,
-                Action::Publish(msg) => Some(canvas::Action::publish(msg)),
-                Action::Capture(msg) => Some(canvas::Action::publish(msg).and_capture()),
-            }
-        } else {
-            None
+        let (action, changed) =
+            (interaction.update.borrow_mut())(&mut state.interaction, &projector, &cursor, event);
+        let capture = state.capture(event, matches!(action, Action::Capture(_)));
+
+        let mut redraw = changed;
+        match action {
+            Action::None => {}
+            Action::Publish(msg) | Action::Capture(msg) => state.pending.push_back(msg),
+            Action::PublishMany(messages) => state.pending.extend(messages),
+            Action::RequestRedraw => redraw = true,
+            Action::SetCursor(interaction) => state.cursor = interaction,
         }
+
+        // Publishing a message redraws as well, which publishes the next one
+        let action = if let Some(msg) = state.pending.pop_front() {
+            canvas::Action::publish(msg)
+        } else if redraw {
+            canvas::Action::request_redraw()
+        } else if capture {
+            canvas::Action::capture()
+        } else {
+            return None;
+        };
+
+        Some(if capture {
+            action.and_capture()
+        } else {
+            action
+        })
     }
 
     fn draw(
         &self,
-        _state: &Self::State,
+        state: &Self::State,
         renderer: &iced::Renderer,
         _theme: &iced::Theme,
         bounds: Rectangle,
         _cursor: mouse::Cursor,
     ) -> Vec<Geometry> {
-        if let Some(ref draw_fn) = self.draw_fn {
-            let projector = Projector {
-                viewpoint: self.viewpoint,
-                bounds: Rectangle::new(Point::ORIGIN, bounds.size()),
-            };
+        let projector = Projector {
+            viewpoint: self.viewpoint,
+            bounds: Rectangle::new(Point::ORIGIN, bounds.size()),
+        };
+
+        let mut caches = state.layers.borrow_mut();
+        caches.resize_with(self.draw_layers.len(), LayerCache::default);
+
+        let mut geometry = self
+            .draw_layers
+            .iter()
+            .zip(caches.iter_mut())
+            .map(|(layer, cached)| {
+                let Some(version) = layer.version else {
+                    let mut frame = canvas::Frame::new(renderer, bounds.size());
+                    (layer.draw)(&projector, &mut frame);
+                    return frame.into_geometry();
+                };
+
+                // Only draw the layer again when it would look different
+                let key = Some((projector.clone(), version));
+                if cached.key != key {
+                    cached.cache.clear();
+                    cached.key = key;
+                }
 
+                cached.cache.draw(renderer, bounds.size(), |frame| {
+                    (layer.draw)(&projector, frame);
+                })
+            })
+            .collect::<Vec<_>>();
+
+        if let Some(draw) = self
+            .interaction
+            .as_ref()
+            .and_then(|interaction| interaction.draw.as_ref())
+        {
             let mut frame = canvas::Frame::new(renderer, bounds.size());
-            draw_fn(&projector, &mut frame);
-            vec![frame.into_geometry()]
+            draw(&state.interaction, &projector, &mut frame);
+            geometry.push(frame.into_geometry());
+        }
+
+        geometry
+    }
+
+    fn mouse_interaction(
+        &self,
+        state: &Self::State,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> mouse::Interaction {
+        if cursor.is_over(bounds) {
+            state.cursor
         } else {
-            vec![]
+            mouse::Interaction::None
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iced::{Event, Point, mouse::Button};
+
+    fn press() -> Event {
+        Event::Mouse(mouse::Event::ButtonPressed(Button::Left))
+    }
+
+    fn moved() -> Event {
+        Event::Mouse(mouse::Event::CursorMoved {
+            position: Point::ORIGIN,
+        })
+    }
+
+    fn release() -> Event {
+        Event::Mouse(mouse::Event::ButtonReleased(Button::Left))
+    }
+
+    #[test]
+    fn gestures_belong_to_whoever_handles_the_press() {
+        let mut state = OverlayState::<()>::default();
+
+        // A captured press keeps the whole drag from the map
+        assert!(state.capture(&press(), true));
+        assert!(state.capture(&moved(), false));
+        assert!(state.capture(&release(), false));
+
+        // Without a gesture, moving the cursor is only captured when asked to
+        assert!(!state.capture(&moved(), false));
+        assert!(state.capture(&moved(), true));
+
+        // Once the map pans, the interaction can not capture its events
+        assert!(!state.capture(&press(), false));
+        assert!(!state.capture(&moved(), true));
+        assert!(!state.capture(&release(), true));
+    }
+
+    #[test]
+    fn interaction_state_is_kept_between_events() {
+        let cache = TileCache::new(crate::sources::OpenStreetMap);
+        let program = MapProgram::<(), _>::new(&cache).with_interaction_state(
+            |moves: &mut usize, _, _, event| {
+                if let Event::Mouse(mouse::Event::CursorMoved { .. }) = event {
+                    *moves += 1;
+                }
+                Action::None
+            },
+            |_, _, _| {},
+        );
+
+        let interaction = program.interact_layer.unwrap();
+        let mut update = interaction.update.borrow_mut();
+        let projector = Projector {
+            viewpoint: Viewpoint {
+                position: crate::location::paris().as_mercator(),
+                zoom: crate::Zoom::try_from(4.0).unwrap(),
+            },
+            bounds: Rectangle::new(Point::ORIGIN, iced::Size::new(100.0, 100.0)),
+        };
+
+        // The state starts out as the default, and only changes are reported
+        let mut state = None;
+        for expected in [true, true, false] {
+            let event = if expected { moved() } else { press() };
+            let (_, changed) = update(&mut state, &projector, &mouse::Cursor::Unavailable, &event);
+            assert_eq!(changed, expected);
+        }
+
+        let moves = state
+            .as_ref()
+            .and_then(|state| state.downcast_ref::<usize>());
+        assert_eq!(moves, Some(&2));
+    }
+
+    #[test]
+    fn actions_publish_messages_one_per_event() {
+        use canvas::Program;
+
+        let cache = TileCache::new(crate::sources::OpenStreetMap);
+        let program =
+            MapProgram::<u32, _>::new(&cache).with_interaction(|_, _, event| match event {
+                Event::Mouse(mouse::Event::ButtonPressed(_)) => Action::PublishMany(vec![1, 2]),
+                Event::Mouse(mouse::Event::ButtonReleased(_)) => {
+                    Action::SetCursor(mouse::Interaction::Grabbing)
+                }
+                _ => Action::RequestRedraw,
+            });
+        let overlay = OverlayProgram {
+            draw_layers: Vec::new(),
+            interaction: program.interact_layer,
+            viewpoint: Viewpoint {
+                position: crate::location::paris().as_mercator(),
+                zoom: crate::Zoom::try_from(4.0).unwrap(),
+            },
+        };
+
+        let bounds = Rectangle::new(Point::ORIGIN, iced::Size::new(100.0, 100.0));
+        let cursor = mouse::Cursor::Available(Point::new(50.0, 50.0));
+        let mut state = OverlayState::default();
+        let update = |state: &mut OverlayState<u32>, event: Event| {
+            overlay
+                .update(state, &event, bounds, cursor)
+                .map(|action| action.into_inner())
+        };
+
+        // The queued message is published before redrawing
+        let (message, ..) = update(&mut state, press()).unwrap();
+        assert_eq!(message, Some(1));
+        let (message, ..) = update(&mut state, moved()).unwrap();
+        assert_eq!(message, Some(2));
+        let (message, redraw, _) = update(&mut state, moved()).unwrap();
+        assert_eq!(message, None);
+        assert_eq!(redraw, iced::window::RedrawRequest::NextFrame);
+
+        // The cursor is kept after the event
+        update(&mut state, release());
+        assert_eq!(
+            overlay.mouse_interaction(&state, bounds, cursor),
+            mouse::Interaction::Grabbing
+        );
+        assert_eq!(
+            overlay.mouse_interaction(&state, bounds, mouse::Cursor::Unavailable),
+            mouse::Interaction::None
+        );
+    }
+}
@@ -0,0 +1,88 @@
+use iced::{Element, Subscription, Task};
+
+use crate::{
+    CacheMessage, MapProgram, MapWidget, Projector, TileCache, Viewpoint, sources::Source,
+};
+
+/// The messages of a [`MapState`].
+#[derive(Debug, Clone)]
+pub enum MapMessage {
+    Cache(CacheMessage),
+    Projector(Projector),
+}
+
+/// Bundles the [`TileCache`] and [`Viewpoint`] of a map, along with the glue between them
+/// and the [`MapWidget`], which otherwise every application has to write itself.
+///
+/// The map produces [`MapMessage`]s, which are mapped into the messages of the
+/// application, and passed back into [`MapState::update`]. The maintenance of the cache is
+/// driven by [`MapState::subscription`].
+///
+/// # Example
+///
+/// ```ignore
+/// fn update(&mut self, message: Message) -> Task<Message> {
+///     match message {
+///         Message::Map(message) => self.map.update(message).map(Message::Map),
+///     }
+/// }
+///
+/// fn subscription(&self) -> Subscription<Message> {
+///     self.map.subscription().map(Message::Map)
+/// }
+///
+/// fn view(&self) -> Element<'_, Message> {
+///     self.map.view().map(Message::Map)
+/// }
+/// ```
+#[derive(Debug)]
+pub struct MapState {
+    pub cache: TileCache,
+    pub viewpoint: Viewpoint,
+    /// The most recent projector of the map, once it has been interacted with.
+    pub projector: Option<Projector>,
+}
+
+impl MapState {
+    pub fn new(source: impl Source + 'static, viewpoint: Viewpoint) -> Self {
+        Self {
+            cache: TileCache::new(source),
+            viewpoint,
+            projector: None,
+        }
+    }
+
+    pub fn update(&mut self, message: MapMessage) -> Task<MapMessage> {
+        match message {
+            MapMessage::Cache(message) => self.cache.update(message).map(MapMessage::Cache),
+            // The updated projector contains the new viewpoint
+            MapMessage::Projector(projector) => {
+                self.viewpoint = projector.viewpoint;
+                self.projector = Some(projector);
+                Task::none()
+            }
+        }
+    }
+
+    /// The maintenance of the tile cache, see [`TileCache::subscription`].
+    pub fn subscription(&self) -> Subscription<MapMessage> {
+        self.cache.subscription().map(MapMessage::Cache)
+    }
+
+    /// The map widget, which can be configured further before it is turned into an element.
+    pub fn widget(&self) -> MapWidget<'_, MapMessage> {
+        MapWidget::new(&self.cache, MapMessage::Cache, self.viewpoint)
+            .on_update(MapMessage::Projector)
+    }
+
+    /// A map program, for adding draw layers, interactions or elements before building it.
+    pub fn program(&self) -> MapProgram<'_, MapMessage> {
+        MapProgram::new(&self.cache)
+            .on_cache(MapMessage::Cache)
+            .on_update(MapMessage::Projector)
+    }
+
+    pub fn view(&self) -> Element<'_, MapMessage> {
+        self.widget().into()
+    }
+}
@@ -2,21 +2,25 @@ use core::f32;
 use std::{
     cmp::Ordering,
     collections::{HashMap, hash_map::Entry},
-    time::{Duration, Instant},
+    time::Duration,
 };
 
+use iced::time::Instant;
 use iced::touch::Finger;
 use iced_core::{
-    Element, Image, Point, Rectangle, Shell, Vector, Widget,
-    image::{Allocation, FilterMethod, Handle},
+    Color, Element, Image, Point, Rectangle, Shell, Vector, Widget,
+    image::{FilterMethod, Handle},
+    keyboard::{self, Modifiers},
     widget::tree::State,
 };
 
 use crate::{
     Projector, Viewpoint, Zoom,
-    draw_cache::DrawCache,
+    draw_cache::{DrawCache, DrawData},
+    gestures::{GestureProfile, Gestures},
     position::Mercator,
-    tile_cache::{CacheMessage, TileCache},
+    sources::TilingScheme,
+    tile_cache::{CacheMessage, TileCache, TileFailure},
     tile_coord::TileCoord,
 };
 
@@ -24,20 +28,127 @@ use crate::{
 pub const BASE_SIZE: u32 = 512;
 
 const TOUCH_SMOOTHING_TAU: f32 = 0.03;
-const TOUCH_PAN_VEL_MOMENTUM_THRESHOLD: f32 = 10.0;
 const TOUCH_ZOOM_VEL_MOMENTUM_THRESHOLD: f64 = 0.12;
-const TOUCH_PINCH_ZOOM_GAIN: f64 = 1.0;
 const TOUCH_PINCH_RELEASE_GRACE: Duration = Duration::from_millis(50);
 const TOUCH_MOMENTUM_MAX_GAP: Duration = Duration::from_millis(50);
 
+// Redraws further apart than this are idle time, rather than slow frames
+const QUALITY_FRAME_GAP: Duration = Duration::from_millis(250);
+const QUALITY_SMOOTHING: f32 = 0.1;
+
+// Tiles are only snapped to the pixel grid this close to an integer zoom level
+const SNAP_ZOOM_TOLERANCE: f64 = 0.01;
+
+/// Following a viewpoint stops once it is within this many pixels, and zoom levels, of it.
+const FOLLOW_TOLERANCE: f64 = 0.25;
+const FOLLOW_ZOOM_TOLERANCE: f64 = 0.001;
+/// The longest step eased toward the followed viewpoint, e.g. after the window was hidden.
+const FOLLOW_MAX_STEP: f32 = 0.1;
+
+// Missing tiles are covered by cached tiles up to this many zoom levels further in
+const MAX_CHILD_FALLBACK: u8 = 2;
+
+// The cursor must move this many pixels before tiles are prioritized again
+const PRIORITY_FOCUS_DISTANCE: f32 = 64.0;
+
+// The arrow keys pan the map by this many pixels
+const KEYBOARD_PAN: f32 = 100.0;
+
+/// How far beyond the edges of the map tiles are loaded, such that they are available
+/// when panned into view. A larger margin makes panning smoother, at the cost of fetching
+/// tiles which may never be seen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Prefetch {
+    /// The margin on all sides of the map.
+    pub margin: PrefetchMargin,
+    /// Extend the margin in the direction the map is panning, by the distance it pans in
+    /// this time at its current speed.
+    pub lookahead: Duration,
+}
+
+impl Default for Prefetch {
+    fn default() -> Self {
+        Self {
+            margin: PrefetchMargin::Pixels(32.0),
+            lookahead: Duration::ZERO,
+        }
+    }
+}
+
+impl Prefetch {
+    /// The area to load tiles for, given the width of a tile and the velocity of panning.
+    fn viewport(&self, bounds: Rectangle, tile_width: f32, velocity: Vector) -> Rectangle {
+        let margin = match self.margin {
+            PrefetchMargin::Pixels(pixels) => pixels,
+            PrefetchMargin::Tiles(tiles) => tiles * tile_width,
+        }
+        .max(0.0);
+        let viewport = bounds.expand(margin);
+
+        // The map moves opposite to the velocity of the cursor, revealing that side
+        let ahead = velocity * -self.lookahead.as_secs_f32();
+        Rectangle {
+            x: viewport.x + ahead.x.min(0.0),
+            y: viewport.y + ahead.y.min(0.0),
+            width: viewport.width + ahead.x.abs(),
+            height: viewport.height + ahead.y.abs(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PrefetchMargin {
+    Pixels(f32),
+    /// A number of tiles, at the size they are currently drawn at.
+    Tiles(f32),
+}
+
+/// When the map captures the events of the mouse wheel or trackpad to zoom. Events which
+/// are not captured propagate to the parent widgets, such as a surrounding `scrollable`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ScrollCapture {
+    /// Always zoom when scrolling over the map.
+    #[default]
+    Always,
+    /// Only zoom once the map has been clicked or touched, until something else is.
+    Focused,
+    /// Only zoom while the modifier keys are held, like `Ctrl` when scrolling a page.
+    Modifier(Modifiers),
+    /// Never zoom by scrolling.
+    Never,
+}
+
+impl ScrollCapture {
+    fn captures(self, focused: bool, modifiers: Modifiers) -> bool {
+        match self {
+            ScrollCapture::Always => true,
+            ScrollCapture::Focused => focused,
+            ScrollCapture::Modifier(required) => modifiers.contains(required),
+            ScrollCapture::Never => false,
+        }
+    }
+}
+
 /// A [slippy tile](https://wiki.openstreetmap.org/wiki/Slippy_map) widget
 pub struct MapWidget<'a, Message> {
     tile_cache: &'a TileCache,
     viewpoint: Viewpoint,
     cache_message: fn(CacheMessage) -> Message,
     on_update: Option<Box<dyn Fn(Projector) -> Message + 'a>>,
-    discrete_zoom_step_size: f32,
-    discrete_zoom_step_duration: Duration,
+    on_quality: Option<Box<dyn Fn(bool) -> Message + 'a>>,
+    on_tile_error: Option<Box<dyn Fn(TileFailure) -> Message + 'a>>,
+    gestures: Gestures,
+    scroll_capture: ScrollCapture,
+    prefetch: Prefetch,
+    /// Whether the map keeps moving after the input stops.
+    inertia: bool,
+    /// The viewpoint to ease toward, and the time constant to do so with.
+    follow: Option<(Viewpoint, Duration)>,
+    target_frame_time: Duration,
+    /// The scale factor of the display, if tiles should be snapped to its pixel grid.
+    pixel_snapping: Option<f32>,
+    /// The color of the polar regions, which are not covered by the map.
+    polar_fill: Option<Color>,
 }
 
 impl<'a, Message> MapWidget<'a, Message> {
@@ -50,9 +161,17 @@ impl<'a, Message> MapWidget<'a, Message> {
             tile_cache,
             viewpoint,
             on_update: None,
+            on_quality: None,
+            on_tile_error: None,
             cache_message,
-            discrete_zoom_step_size: 1.0,
-            discrete_zoom_step_duration: Duration::from_millis(250),
+            gestures: Gestures::default(),
+            scroll_capture: ScrollCapture::default(),
+            prefetch: Prefetch::default(),
+            inertia: true,
+            follow: None,
+            target_frame_time: Duration::from_secs_f32(1.0 / 30.0),
+            pixel_snapping: None,
+            polar_fill: None,
         }
     }
 
@@ -64,49 +183,150 @@ impl<'a, Message> MapWidget<'a, Message> {
         }
     }
 
-    pub fn position_of_tile(&self, projector: &Projector, tile_id: &TileCoord) -> Rectangle {
-        let tile_size = self.tile_cache.tile_size() as f64;
-        let scale_offset = (BASE_SIZE as f64 / tile_size).log2();
+    /// This message is emitted when the map switches to reduced quality, because it can not
+    /// keep up with the target frame rate while interacting, and when it switches back once
+    /// the interaction stops. Expensive layers can be skipped while the quality is reduced.
+    pub fn on_quality(self, func: impl Fn(bool) -> Message + 'a) -> Self {
+        Self {
+            on_quality: Some(Box::new(func)),
+            ..self
+        }
+    }
 
-        let scale = 2.0_f64.powf(self.viewpoint.zoom.f64() - tile_id.zoom() as f64);
-        let size = (tile_size * 2.0_f64.powf(scale_offset) * scale) as f32;
+    /// This message is emitted for each tile which could not be loaded, e.g. to notify the
+    /// user when the tile server starts rejecting requests with 403 or 429. Failures which
+    /// happened before the map was shown are not reported.
+    pub fn on_tile_error(self, func: impl Fn(TileFailure) -> Message + 'a) -> Self {
+        Self {
+            on_tile_error: Some(Box::new(func)),
+            ..self
+        }
+    }
 
-        let tile_mercator = tile_id.to_mercator();
-        let screen_pos = projector.mercator_into_screen_space(tile_mercator);
+    /// The frame rate below which the map draws lower resolution tiles while interacting.
+    pub fn target_frame_rate(self, fps: f32) -> Self {
+        Self {
+            target_frame_time: Duration::from_secs_f32(1.0 / fps.max(1.0)),
+            ..self
+        }
+    }
 
-        Rectangle {
-            x: screen_pos.x,
-            y: screen_pos.y,
-            width: size,
-            height: size,
+    /// Snap tiles to the physical pixel grid of a display with the given scale factor, and
+    /// draw them without interpolation, whenever the zoom level is close to an integer. This
+    /// makes the tiles crisp, and avoids sub-pixel seams between them.
+    pub fn pixel_snapping(self, scale_factor: f32) -> Self {
+        Self {
+            pixel_snapping: Some(scale_factor),
+            ..self
         }
     }
 
+    /// How the map responds to the gestures of the mouse, trackpad and touch screen.
+    pub fn gestures(self, gestures: Gestures) -> Self {
+        Self { gestures, ..self }
+    }
+
+    /// When scrolling over the map zooms it, rather than scrolling a parent widget.
+    pub fn scroll_capture(self, scroll_capture: ScrollCapture) -> Self {
+        Self {
+            scroll_capture,
+            ..self
+        }
+    }
+
+    /// How far beyond the edges of the map tiles are loaded ahead of panning to them.
+    pub fn prefetch(self, prefetch: Prefetch) -> Self {
+        Self { prefetch, ..self }
+    }
+
+    /// Whether the map keeps panning after a fling, and animates zooming. Disabling this
+    /// stops any momentum right away, and zooming jumps to its target instead, such that
+    /// the viewpoint is left to the application, e.g. while it flies the camera somewhere
+    /// or shows a modal on top of the map.
+    pub fn inertia(self, inertia: bool) -> Self {
+        Self { inertia, ..self }
+    }
+
+    /// Ease toward a viewpoint provided by the application, e.g. from a GPS fix or a shared
+    /// session, rather than jumping to it. The remaining distance shrinks by about two
+    /// thirds for every `time_constant` which passes. The eased viewpoint is reported
+    /// through [`MapWidget::on_update`], which is required.
+    ///
+    /// The user takes priority: following is suspended as soon as they pan or zoom the map,
+    /// until the application provides a different viewpoint to follow.
+    pub fn follow(self, target: Viewpoint, time_constant: Duration) -> Self {
+        Self {
+            follow: Some((target, time_constant)),
+            ..self
+        }
+    }
+
+    /// Fill the polar regions beyond the latitudes covered by the map with a solid color,
+    /// rather than leaving them empty when they come into view.
+    pub fn polar_fill(self, color: Color) -> Self {
+        Self {
+            polar_fill: Some(color),
+            ..self
+        }
+    }
+
+    /// The scale factor to snap tiles with in the current view, if any.
+    fn snapping_scale(&self) -> Option<f32> {
+        let zoom = self.viewpoint.zoom.f64();
+        self.pixel_snapping
+            .filter(|_| (zoom - zoom.round()).abs() < SNAP_ZOOM_TOLERANCE)
+    }
+
+    /// The zoom level of the tiles to draw, which is one level lower in reduced quality.
+    fn tile_zoom(&self, reduced: bool) -> u8 {
+        let zoom = self
+            .tile_cache
+            .tiling_scheme()
+            .tile_zoom(self.viewpoint.zoom.f64(), self.tile_cache.max_zoom());
+        if reduced {
+            zoom.saturating_sub(1)
+        } else {
+            zoom
+        }
+    }
+
+    pub fn position_of_tile(&self, projector: &Projector, tile_id: &TileCoord) -> Rectangle {
+        // Tiles cover the same area regardless of their size, which only decides their zoom
+        projector.tile_bounds(tile_id)
+    }
+
     /// Use [flood fill algorithm](https://en.wikipedia.org/wiki/Flood_fill) to determine
     /// which tiles need to be drawn..
     pub fn flood_tiles(&self, projector: &Projector) -> Vec<(TileCoord, Rectangle)> {
-        // Slightly expand the bounds to load in tiles which may be panned to
-        let viewport = projector.bounds.expand(32);
-
-        // Allocate for the number of tiles to fill the screen, and then some
-        let capacity = viewport.area() / self.tile_cache.tile_size().pow(2) as f32;
-        let mut tiles = HashMap::with_capacity(capacity.ceil() as usize);
+        self.flood_tiles_at(projector, self.tile_zoom(false), Vector::ZERO)
+    }
 
-        // This ensures tilesets of different sizes
-        let scale_offset = (BASE_SIZE as f64 / self.tile_cache.tile_size() as f64).log2();
+    fn flood_tiles_at(
+        &self,
+        projector: &Projector,
+        zoom: u8,
+        velocity: Vector,
+    ) -> Vec<(TileCoord, Rectangle)> {
+        let central_tile_id = self.viewpoint.position.tile_id(zoom);
 
-        let scaled_zoom =
-            (self.viewpoint.zoom.f64() + scale_offset).min(self.tile_cache.max_zoom() as f64);
+        // Expand the bounds to load in tiles which may be panned to
+        let tile_width = projector.tile_bounds(&central_tile_id).width;
+        let viewport = self
+            .prefetch
+            .viewport(projector.bounds, tile_width, velocity);
 
-        let central_tile_id = self.viewpoint.position.tile_id(scaled_zoom.round() as u8);
+        // Allocate for the number of tiles to fill the screen, and then some
+        let capacity = viewport.area() / tile_width.max(1.0).powi(2);
+        let mut tiles = HashMap::with_capacity(capacity.ceil() as usize);
 
         // Recursively fill up the `tiles` map
         self.flood_tiles_inner(projector, &viewport, central_tile_id, &mut tiles);
 
-        // Convert the map into a vec of id-uv pairs
+        // Convert the map into a vec of id-uv pairs, of the tiles served by the source
         tiles
             .drain()
             .filter_map(|(id, tile)| tile.map(|tile| (id, tile)))
+            .filter(|(id, _)| self.tile_cache.covers(id))
             .collect()
     }
 
@@ -137,32 +357,45 @@ impl<'a, Message> MapWidget<'a, Message> {
         }
     }
 
+    /// Use descendants of a missing tile which are available, up to `levels` zoom levels
+    /// further in. Returns whether they cover the whole tile.
     fn fallback_to_children(
         &self,
         old_draw_cache: &mut DrawCache,
         draw_cache: &mut DrawCache,
         tile_id: TileCoord,
         projector: &Projector,
+        levels: u8,
     ) -> bool {
-        if let Some(children) = tile_id.children() {
-            let mut num_children_available = 0;
-
-            for child_tile_id in &children {
-                if let Some((handle, allocation)) =
-                    self.get_drawable_tile(old_draw_cache, child_tile_id)
-                {
-                    let child_rectangle = self.position_of_tile(projector, child_tile_id);
-                    draw_cache.insert(*child_tile_id, handle, child_rectangle, allocation);
+        let Some(children) = tile_id.children().filter(|_| levels > 0) else {
+            return false;
+        };
 
-                    num_children_available += 1;
-                }
+        let mut num_children_covered = 0;
+        for child_tile_id in children {
+            let child_rectangle = self.position_of_tile(projector, &child_tile_id);
+            if let Some(data) =
+                self.get_drawable_tile(old_draw_cache, &child_tile_id, child_rectangle)
+            {
+                draw_cache.insert(child_tile_id, data);
+                num_children_covered += 1;
+                continue;
             }
 
-            // If we found all children, skip parent fallback
-            return num_children_available == 4;
+            // Otherwise try to cover the missing child with its own children
+            if self.fallback_to_children(
+                old_draw_cache,
+                draw_cache,
+                child_tile_id,
+                projector,
+                levels - 1,
+            ) {
+                num_children_covered += 1;
+            }
         }
 
-        false
+        // If all children are covered, skip parent fallback
+        num_children_covered == 4
     }
 
     fn fallback_to_ancestor(
@@ -173,6 +406,13 @@ impl<'a, Message> MapWidget<'a, Message> {
         projector: &Projector,
         shell: &mut Shell<'_, Message>,
     ) -> bool {
+        // Prefer a blurred placeholder over a heavily stretched ancestor
+        #[cfg(feature = "decode")]
+        if let Some(data) = self.get_placeholder(old_draw_cache, tile_id, projector) {
+            draw_cache.insert(*tile_id, data);
+            return true;
+        }
+
         // If there is not full child coverage, fall back to a parent tile
         let mut new_tile_id = *tile_id;
         while let Some(parent_tile_id) = new_tile_id.parent() {
@@ -183,10 +423,20 @@ impl<'a, Message> MapWidget<'a, Message> {
                 break;
             }
 
-            if let Some((handle, allocation)) = self.get_drawable_tile(old_draw_cache, &new_tile_id)
-            {
-                let rectangle = self.position_of_tile(&projector, &new_tile_id);
-                draw_cache.insert(new_tile_id, handle, rectangle, allocation);
+            let rectangle = self.position_of_tile(projector, &new_tile_id);
+            if let Some(data) = self.get_drawable_tile(old_draw_cache, &new_tile_id, rectangle) {
+                draw_cache.insert(new_tile_id, data);
+
+                #[cfg(feature = "decode")]
+                if tile_id.zoom() - new_tile_id.zoom() >= crate::placeholder::BLUR_UP_LEVELS
+                    && self.tile_cache.should_blur_up(tile_id)
+                {
+                    shell.publish((self.cache_message)(CacheMessage::BlurUp {
+                        id: *tile_id,
+                        ancestor: new_tile_id,
+                    }))
+                }
+
                 return true;
             }
 
@@ -203,14 +453,60 @@ impl<'a, Message> MapWidget<'a, Message> {
         false
     }
 
+    /// Take a tile from the previous draw cache, or from the tile cache if it was not drawn
+    /// before, in which case it starts fading in.
     fn get_drawable_tile(
         &self,
         old_draw_cache: &mut DrawCache,
         tile_id: &TileCoord,
-    ) -> Option<(Handle, Allocation)> {
-        old_draw_cache
-            .remove(tile_id)
-            .or_else(|| self.tile_cache.get_drawable(tile_id))
+        rectangle: Rectangle,
+    ) -> Option<DrawData> {
+        let drawable = self.tile_cache.get_drawable(tile_id);
+
+        if let Some(data) = old_draw_cache.remove(tile_id) {
+            match drawable {
+                // A refreshed tile fades in over its outdated image
+                Some((handle, allocation)) if handle.id() != data.handle.id() => {
+                    old_draw_cache.replaced.insert(*tile_id, data);
+                    return Some(DrawData {
+                        handle,
+                        rectangle,
+                        allocation: Some(allocation),
+                        shown: Instant::now(),
+                    });
+                }
+                _ => return Some(DrawData { rectangle, ..data }),
+            }
+        }
+
+        drawable.map(|(handle, allocation)| DrawData {
+            handle,
+            rectangle,
+            allocation: Some(allocation),
+            shown: Instant::now(),
+        })
+    }
+
+    /// Take the blurred placeholder of a tile from the previous draw cache, or from the tile
+    /// cache if it was not drawn before.
+    #[cfg(feature = "decode")]
+    fn get_placeholder(
+        &self,
+        old_draw_cache: &mut DrawCache,
+        tile_id: &TileCoord,
+        projector: &Projector,
+    ) -> Option<DrawData> {
+        let handle = self.tile_cache.get_placeholder(tile_id)?;
+        let shown = old_draw_cache
+            .remove_placeholder(tile_id)
+            .map_or_else(Instant::now, |data| data.shown);
+
+        Some(DrawData {
+            handle,
+            rectangle: self.position_of_tile(projector, tile_id),
+            allocation: None,
+            shown,
+        })
     }
 
     fn event_cursor_moved(
@@ -272,7 +568,151 @@ struct WidgetState {
     zoom_move: ZoomMove,
     cursor: Option<Point>,
     draw_cache: DrawCache,
+    visible_tiles: VisibleTiles,
+    quality: QualityState,
     touch: TouchState,
+    /// Whether the pointer is a trackpad, as detected from how it last scrolled.
+    trackpad: bool,
+    /// Whether the map was the last thing clicked or touched.
+    focused: bool,
+    modifiers: Modifiers,
+    /// The number of tile failures of the cache which have been reported.
+    failures_seen: Option<u64>,
+    /// The number of refreshed tiles of the cache which have been swapped in.
+    refreshed_seen: u64,
+    follow: FollowState,
+}
+
+impl WidgetState {
+    /// The velocity of the cursor or fingers panning the map, in pixels per second.
+    fn pan_velocity(&self) -> Vector {
+        match self.pan_move {
+            PanMove::Dragging { velocity, .. } | PanMove::Momentum { velocity, .. } => velocity,
+            _ if !self.touch.fingers.is_empty() => self.touch.smoothed_pan_velocity,
+            _ => Vector::ZERO,
+        }
+    }
+}
+
+/// Keeps track of easing toward the viewpoint set by [`MapWidget::follow`].
+#[derive(Default)]
+struct FollowState {
+    last_time: Option<Instant>,
+    /// The followed viewpoint when the user last interacted with the map.
+    suspended: Option<Viewpoint>,
+}
+
+/// Move the viewpoint a fraction `alpha` of the way toward the target, snapping to the
+/// target once it is close enough. Returns whether the target was reached.
+fn ease_toward(viewpoint: &mut Viewpoint, target: Viewpoint, alpha: f64) -> bool {
+    let zoom = viewpoint.zoom.f64();
+    let from = viewpoint.position.into_pixel_space(zoom);
+    let to = target.position.into_pixel_space(zoom);
+
+    let zoom_delta = target.zoom.f64() - zoom;
+    if (to.x - from.x).hypot(to.y - from.y) < FOLLOW_TOLERANCE
+        && zoom_delta.abs() < FOLLOW_ZOOM_TOLERANCE
+    {
+        *viewpoint = target;
+        return true;
+    }
+
+    let east = viewpoint.position.east_x();
+    let south = viewpoint.position.south_y();
+    viewpoint.position = Mercator::new(
+        east + (target.position.east_x() - east) * alpha,
+        south + (target.position.south_y() - south) * alpha,
+    );
+    viewpoint.zoom = Zoom::try_from(zoom + zoom_delta * alpha).unwrap_or(target.zoom);
+    false
+}
+
+/// Keeps track of the frame rate, to reduce the quality when it can not be maintained.
+#[derive(Default)]
+struct QualityState {
+    last_redraw: Option<Instant>,
+    /// Smoothed duration between consecutive redraws, in seconds.
+    frame_time: f32,
+    reduced: bool,
+}
+
+impl QualityState {
+    /// Record a redraw, returning the new quality if it changed. Once reduced, the quality
+    /// is only restored when the interaction stops, to avoid flickering between the two.
+    fn frame(&mut self, at: Instant, interacting: bool, target: Duration) -> Option<bool> {
+        if let Some(last) = self.last_redraw.replace(at) {
+            let elapsed = at.saturating_duration_since(last);
+            if elapsed < QUALITY_FRAME_GAP {
+                self.frame_time += (elapsed.as_secs_f32() - self.frame_time) * QUALITY_SMOOTHING;
+            }
+        }
+
+        let reduced = interacting && (self.reduced || self.frame_time > target.as_secs_f32());
+        (reduced != self.reduced).then(|| {
+            self.reduced = reduced;
+            reduced
+        })
+    }
+}
+
+/// The result of the most recent flood fill, which is reused for as long as the
+/// view and tile source stay the same.
+#[derive(Default)]
+struct VisibleTiles {
+    key: Option<VisibleKey>,
+    tiles: Vec<(TileCoord, Rectangle)>,
+    /// Whether all of the tiles are in the draw cache at their own zoom level, in which
+    /// case no fallbacks need resolving until the view changes.
+    resolved: bool,
+    /// Indices into `tiles`, ordered by distance to the focus they were sorted by.
+    priority: Vec<usize>,
+    focus: Option<Point>,
+}
+
+/// The view and tile layout which the visible tiles follow from: the viewpoint, the bounds
+/// of the map, the tiling scheme of the source and the zoom level of its tiles.
+type VisibleKey = (Viewpoint, Rectangle, TilingScheme, u8);
+
+impl VisibleTiles {
+    /// Flood fill the tiles again, unless the key is the same as that of the last flood
+    /// fill. Returns whether the tiles were filled again.
+    fn update(
+        &mut self,
+        key: VisibleKey,
+        flood: impl FnOnce() -> Vec<(TileCoord, Rectangle)>,
+    ) -> bool {
+        if self.key == Some(key) {
+            return false;
+        }
+
+        *self = VisibleTiles {
+            key: Some(key),
+            tiles: flood(),
+            ..VisibleTiles::default()
+        };
+        true
+    }
+
+    /// The tiles in order of distance to the focus. They are only sorted again when the
+    /// tiles change, or when the focus moves a meaningful distance.
+    fn prioritized(&mut self, focus: Point) -> impl Iterator<Item = &(TileCoord, Rectangle)> {
+        let moved = self
+            .focus
+            .is_none_or(|previous| previous.distance(focus) > PRIORITY_FOCUS_DISTANCE);
+
+        if moved || self.priority.len() != self.tiles.len() {
+            let tiles = &self.tiles;
+            self.priority = (0..tiles.len()).collect();
+            self.priority.sort_by(|&a, &b| {
+                let dist1 = focus.distance(tiles[a].1.center());
+                let dist2 = focus.distance(tiles[b].1.center());
+                dist1.partial_cmp(&dist2).unwrap_or(Ordering::Equal)
+            });
+            self.focus = Some(focus);
+        }
+
+        self.priority.iter().map(|&index| &self.tiles[index])
+    }
 }
 
 #[derive(Default)]
@@ -330,6 +770,82 @@ impl TouchState {
         Some(radius_sum / self.fingers.len() as f32)
     }
 
+    fn moved_recently(&self, now: Instant) -> bool {
+        self.last_motion
+            .is_some_and(|last| now.duration_since(last) <= TOUCH_MOMENTUM_MAX_GAP)
+    }
+
+    /// The velocity to continue panning with as the last finger is lifted, if it was still
+    /// moving fast enough, like the momentum after dragging with the mouse.
+    fn fling_velocity(&self, now: Instant, threshold: f32) -> Option<Vector<f32>> {
+        let velocity = self.smoothed_pan_velocity;
+        let speed = velocity.x.hypot(velocity.y);
+        (self.moved_recently(now) && speed > threshold).then_some(velocity)
+    }
+
+    /// The zoom velocity and the point to keep zooming around as the last finger is lifted,
+    /// if it ends a pinch which was still moving. The other fingers have to be lifted just
+    /// before, as the remaining finger would otherwise have panned the map on its own.
+    fn pinch_momentum(&self, now: Instant) -> Option<(f64, Mercator)> {
+        let pinch_ended = self
+            .second_finger_left
+            .is_some_and(|left| now.duration_since(left) <= TOUCH_PINCH_RELEASE_GRACE);
+
+        self.pinch_release_velocity
+            .zip(self.pinch_release_point)
+            .filter(|(velocity, _)| {
+                pinch_ended && velocity.abs() > TOUCH_ZOOM_VEL_MOMENTUM_THRESHOLD
+            })
+    }
+
+    /// Lift a finger, returning the momentum to continue with once the last one is lifted.
+    fn lift(
+        &mut self,
+        id: Finger,
+        now: Instant,
+        projector: &Projector,
+        profile: &GestureProfile,
+    ) -> TouchRelease {
+        if self.fingers.len() >= 2 {
+            // Only a pinch which is still moving as it ends continues zooming
+            self.pinch_release_velocity = self
+                .moved_recently(now)
+                .then_some(self.smoothed_pinch_velocity);
+
+            if let Some(centroid) = self.centroid() {
+                self.pinch_release_point = Some(projector.screen_space_into_mercator(centroid));
+            }
+        }
+
+        self.fingers.remove(&id);
+
+        match self.fingers.len() {
+            0 => {
+                let release = TouchRelease {
+                    fling: self.fling_velocity(now, profile.fling_threshold),
+                    pinch: self.pinch_momentum(now),
+                };
+                self.clear_after_release();
+                return release;
+            }
+            1 => {
+                self.second_finger_left = Some(now);
+                self.last_centroid = self.centroid();
+                self.last_pinch_distance = None;
+                self.smoothed_pan_velocity = Vector::ZERO;
+                self.smoothed_pinch_velocity = 0.0;
+                self.last_motion = None;
+            }
+            _ => {
+                self.second_finger_left = None;
+                self.last_centroid = self.centroid();
+                self.last_pinch_distance = self.pinch_distance();
+            }
+        }
+
+        TouchRelease::default()
+    }
+
     fn clear_after_release(&mut self) {
         self.second_finger_left = None;
         self.last_centroid = None;
@@ -342,6 +858,15 @@ impl TouchState {
     }
 }
 
+/// The momentum to continue with after the last finger is lifted.
+#[derive(Debug, Default, PartialEq)]
+struct TouchRelease {
+    /// The panning velocity in pixels per second.
+    fling: Option<Vector<f32>>,
+    /// The zoom velocity in levels per second, and the point to zoom around.
+    pinch: Option<(f64, Mercator)>,
+}
+
 struct FingerState {
     position: Point<f32>,
     velocity: Vector<f32>,
@@ -371,6 +896,7 @@ enum PanMove {
     Momentum {
         velocity: Vector,
         last_time: Instant,
+        tau: f32,
     },
     AutoPan {
         origin: iced::Point,
@@ -472,7 +998,72 @@ where
             bounds,
         };
 
+        // Keep track of what decides whether scrolling is captured
         match event {
+            iced::Event::Mouse(iced::mouse::Event::ButtonPressed(_)) => {
+                state.focused = cursor.is_over(bounds);
+            }
+            iced::Event::Touch(iced::touch::Event::FingerPressed { position, .. }) => {
+                state.focused = bounds.contains(*position);
+            }
+            iced::Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) => {
+                state.modifiers = *modifiers;
+            }
+            _ => {}
+        }
+
+        match event {
+            iced::Event::Keyboard(keyboard::Event::KeyPressed { modified_key, .. })
+                if state.focused && self.on_update.is_some() =>
+            {
+                use keyboard::{Key, key::Named};
+
+                let pan = match modified_key.as_ref() {
+                    Key::Named(Named::ArrowLeft) => Some(Vector::new(-KEYBOARD_PAN, 0.0)),
+                    Key::Named(Named::ArrowRight) => Some(Vector::new(KEYBOARD_PAN, 0.0)),
+                    Key::Named(Named::ArrowUp) => Some(Vector::new(0.0, -KEYBOARD_PAN)),
+                    Key::Named(Named::ArrowDown) => Some(Vector::new(0.0, KEYBOARD_PAN)),
+                    _ => None,
+                };
+
+                let zoom = match modified_key.as_ref() {
+                    Key::Character("+" | "=") => Some(1.0),
+                    Key::Character("-") => Some(-1.0),
+                    _ => None,
+                };
+
+                if let Some(offset) = pan {
+                    let center = bounds.center();
+                    self.viewpoint.position.add_sub(
+                        projector.screen_space_into_mercator(center + offset),
+                        projector.screen_space_into_mercator(center),
+                    );
+                }
+
+                if let Some(direction) = zoom {
+                    let step = self.gestures.mouse.zoom_step();
+                    let current_zoom = self.viewpoint.zoom.f64();
+                    let target = ((current_zoom / step).round() + direction) * step;
+
+                    state.zoom_move = ZoomMove::Discrete {
+                        point: None,
+                        start_zoom: current_zoom,
+                        end_zoom: target.clamp(Zoom::MIN.f64(), Zoom::MAX.f64()),
+                        start_time: Instant::now(),
+                        duration: self.gestures.mouse.zoom_inertia,
+                    };
+                }
+
+                if pan.is_some() || zoom.is_some() {
+                    needs_redraw = true;
+                    shell.capture_event();
+                }
+            }
+            iced::Event::Window(iced::window::Event::Rescaled(factor)) => {
+                shell.publish((self.cache_message)(CacheMessage::ScaleFactor {
+                    factor: *factor,
+                }));
+            }
             iced::Event::Window(iced::window::Event::RedrawRequested(at)) => {
                 match &mut state.zoom_move {
                     ZoomMove::Idle => {}
@@ -483,7 +1074,12 @@ where
                         velocity,
                         tau,
                     } => {
-                        let elapsed = (*at - *start_time).as_secs_f64();
+                        // Without inertia, the zoom settles right away
+                        let elapsed = if self.inertia {
+                            (*at - *start_time).as_secs_f64()
+                        } else {
+                            f64::INFINITY
+                        };
 
                         // Analytic position: x(t) = x0 + v0 * tau * (1 - e^(-t/tau))
                         let target_zoom =
@@ -514,7 +1110,11 @@ where
                         duration,
                     } => {
                         let elapsed = *at - *start_time;
-                        let t = (elapsed.as_secs_f64() / duration.as_secs_f64()).min(1.0);
+                        let t = if self.inertia {
+                            (elapsed.as_secs_f64() / duration.as_secs_f64()).min(1.0)
+                        } else {
+                            1.0
+                        };
 
                         // Ease out cubic
                         let ease = 1.0 - (1.0 - t).powi(3);
@@ -587,9 +1187,14 @@ where
                     }
                 }
 
+                if !self.inertia && matches!(state.pan_move, PanMove::Momentum { .. }) {
+                    state.pan_move = PanMove::Idle;
+                }
+
                 if let PanMove::Momentum {
                     velocity,
                     last_time,
+                    tau,
                 } = &mut state.pan_move
                 {
                     let delta = (*at - *last_time).as_secs_f32();
@@ -609,7 +1214,7 @@ where
 
                     // Decay the velocity, less so at higher speeds
                     let norm_velocity = (velocity.x.powi(2) + velocity.y.powi(2)).sqrt();
-                    let dynamic_tau = 0.2 + norm_velocity * 0.00005;
+                    let dynamic_tau = *tau + norm_velocity * 0.00005;
                     let alpha = dynamic_tau / (dynamic_tau + delta);
                     *velocity = *velocity * alpha;
 
@@ -620,8 +1225,41 @@ where
 
                     needs_redraw = true;
                 }
+
+                match self.follow {
+                    Some((target, time_constant)) if self.on_update.is_some() => {
+                        let interacting = !matches!(state.pan_move, PanMove::Idle)
+                            || !matches!(state.zoom_move, ZoomMove::Idle)
+                            || !state.touch.fingers.is_empty();
+
+                        if interacting {
+                            state.follow.suspended = Some(target);
+                        }
+
+                        if state.follow.suspended == Some(target) {
+                            state.follow.last_time = None;
+                        } else {
+                            state.follow.suspended = None;
+
+                            let last_time = state.follow.last_time.replace(*at).unwrap_or(*at);
+                            let delta = (*at - last_time).as_secs_f32().min(FOLLOW_MAX_STEP);
+                            let tau = time_constant.as_secs_f32().max(f32::EPSILON);
+                            let alpha = 1.0 - (-delta / tau).exp();
+
+                            if ease_toward(&mut self.viewpoint, target, alpha as f64) {
+                                state.follow.last_time = None;
+                            } else {
+                                needs_redraw = true;
+                            }
+                        }
+                    }
+                    _ => state.follow = FollowState::default(),
+                }
             }
             iced::Event::Touch(event) => match event {
+                // Only touches starting on the map take part in its gestures
+                iced::touch::Event::FingerPressed { position, .. }
+                    if !bounds.contains(*position) => {}
                 iced::touch::Event::FingerPressed { id, position } => {
                     if matches!(
                         state.pan_move,
@@ -655,27 +1293,21 @@ where
                 }
                 iced::touch::Event::FingerMoved { id, position } => {
                     let now = Instant::now();
-                    let mut delta_time = 0.0f32;
+                    // The finger was pressed outside the map, or its press was captured
+                    let Some(finger_state) = state.touch.fingers.get_mut(id) else {
+                        return;
+                    };
 
-                    match state.touch.fingers.get_mut(&id) {
-                        Some(finger_state) => {
-                            delta_time = (now - finger_state.last_time).as_secs_f32();
-                            let delta_pos = *position - finger_state.position;
-                            finger_state.position = *position;
-                            finger_state.last_time = now;
+                    let delta_time = (now - finger_state.last_time).as_secs_f32();
+                    let delta_pos = *position - finger_state.position;
+                    finger_state.position = *position;
+                    finger_state.last_time = now;
 
-                            if delta_time > 0.0 {
-                                let raw_velocity = delta_pos / delta_time;
-                                let alpha =
-                                    TOUCH_SMOOTHING_TAU / (TOUCH_SMOOTHING_TAU + delta_time);
-                                finger_state.velocity =
-                                    finger_state.velocity * alpha + raw_velocity * (1.0 - alpha);
-                            }
-                        }
-                        None => {
-                            log::warn!("FingerMoved event on non-existent finger");
-                            state.touch.fingers.insert(*id, FingerState::new(*position));
-                        }
+                    if delta_time > 0.0 {
+                        let raw_velocity = delta_pos / delta_time;
+                        let alpha = TOUCH_SMOOTHING_TAU / (TOUCH_SMOOTHING_TAU + delta_time);
+                        finger_state.velocity =
+                            finger_state.velocity * alpha + raw_velocity * (1.0 - alpha);
                     }
 
                     let suppress_single_finger_pan = state.touch.fingers.len() == 1
@@ -727,8 +1359,8 @@ where
                                     let scale = pinch_distance / last_distance;
 
                                     if scale.is_finite() && scale > 0.0 {
-                                        let zoom_delta =
-                                            scale.log2() as f64 * TOUCH_PINCH_ZOOM_GAIN;
+                                        let zoom_delta = scale.log2() as f64
+                                            * self.gestures.touch.zoom_sensitivity;
 
                                         if self.on_update.is_some()
                                             && zoom_delta.abs() > f64::EPSILON
@@ -764,83 +1396,42 @@ where
                     shell.capture_event();
                 }
                 iced::touch::Event::FingerLifted { id, .. }
-                | iced::touch::Event::FingerLost { id, .. } => {
+                | iced::touch::Event::FingerLost { id, .. }
+                    if state.touch.fingers.contains_key(id) =>
+                {
                     let now = Instant::now();
+                    let profile = self.gestures.touch;
+                    let release = state.touch.lift(*id, now, &projector, &profile);
 
-                    if state.touch.fingers.len() >= 2 {
-                        state.touch.pinch_release_velocity =
-                            Some(state.touch.smoothed_pinch_velocity);
-
-                        if let Some(centroid) = state.touch.centroid() {
-                            state.touch.pinch_release_point =
-                                Some(projector.screen_space_into_mercator(centroid));
-                        }
+                    if let Some(velocity) = release.fling {
+                        state.pan_move = PanMove::Momentum {
+                            velocity,
+                            last_time: now,
+                            tau: profile.pan_tau(),
+                        };
+                        needs_redraw = true;
                     }
 
-                    state.touch.fingers.remove(id);
-
-                    match state.touch.fingers.len() {
-                        0 => {
-                            let moved_recently = state.touch.last_motion.is_some_and(|last| {
-                                now.duration_since(last) <= TOUCH_MOMENTUM_MAX_GAP
-                            });
-
-                            let velocity = state.touch.smoothed_pan_velocity;
-                            let velocity_norm = (velocity.x.powi(2) + velocity.y.powi(2)).sqrt();
-
-                            if moved_recently && velocity_norm > TOUCH_PAN_VEL_MOMENTUM_THRESHOLD {
-                                state.pan_move = PanMove::Momentum {
-                                    velocity: state.touch.smoothed_pan_velocity,
-                                    last_time: now,
-                                };
-                                needs_redraw = true;
-                            }
-
-                            let allow_zoom_momentum =
-                                state.touch.second_finger_left.is_some_and(|left| {
-                                    now.duration_since(left) <= TOUCH_PINCH_RELEASE_GRACE
-                                });
-
-                            if moved_recently && allow_zoom_momentum {
-                                if let (Some(velocity), Some(point)) = (
-                                    state.touch.pinch_release_velocity,
-                                    state.touch.pinch_release_point,
-                                ) {
-                                    if velocity.abs() > TOUCH_ZOOM_VEL_MOMENTUM_THRESHOLD {
-                                        state.zoom_move = ZoomMove::Continuous {
-                                            point: Some(point),
-                                            start_time: now,
-                                            start_zoom: self.viewpoint.zoom.f64(),
-                                            velocity,
-                                            tau: 0.2,
-                                        };
-                                        needs_redraw = true;
-                                    }
-                                }
-                            }
-
-                            state.touch.clear_after_release();
-                        }
-                        1 => {
-                            state.touch.second_finger_left = Some(now);
-                            state.touch.last_centroid = state.touch.centroid();
-                            state.touch.last_pinch_distance = None;
-                            state.touch.smoothed_pan_velocity = Vector::ZERO;
-                            state.touch.smoothed_pinch_velocity = 0.0;
-                            state.touch.last_motion = None;
-                        }
-                        _ => {
-                            state.touch.second_finger_left = None;
-                            state.touch.last_centroid = state.touch.centroid();
-                            state.touch.last_pinch_distance = state.touch.pinch_distance();
-                        }
+                    if let Some((velocity, point)) = release.pinch {
+                        state.zoom_move = ZoomMove::Continuous {
+                            point: Some(point),
+                            start_time: now,
+                            start_zoom: self.viewpoint.zoom.f64(),
+                            velocity,
+                            tau: profile.zoom_tau(),
+                        };
+                        needs_redraw = true;
                     }
 
                     shell.capture_event();
                 }
+                _ => {}
             },
             iced::Event::Mouse(event) => match event {
-                iced::mouse::Event::WheelScrolled { delta } if self.on_update.is_some() => {
+                iced::mouse::Event::WheelScrolled { delta }
+                    if self.on_update.is_some()
+                        && self.scroll_capture.captures(state.focused, state.modifiers) =>
+                {
                     let point = cursor
                         .position_over(projector.bounds)
                         .map(|p| projector.screen_space_into_mercator(p));
@@ -853,8 +1444,9 @@ where
 
                     match delta {
                         iced::mouse::ScrollDelta::Lines { y, .. } => {
+                            state.trackpad = false;
                             let current_zoom = self.viewpoint.zoom.f64();
-                            let step = self.discrete_zoom_step_size as f64;
+                            let step = self.gestures.mouse.zoom_step();
 
                             // Determine target based on current state
                             let target =
@@ -871,22 +1463,24 @@ where
                                 start_zoom: current_zoom,
                                 end_zoom: target,
                                 start_time: Instant::now(),
-                                duration: self.discrete_zoom_step_duration,
+                                duration: self.gestures.mouse.zoom_inertia,
                             };
                         }
                         iced::mouse::ScrollDelta::Pixels { y, .. } => {
-                            let mut velocity = *y as f64;
+                            state.trackpad = true;
+                            let profile = self.gestures.trackpad;
+                            let mut velocity = *y as f64 * profile.zoom_sensitivity;
                             let now = Instant::now();
 
                             // Carry over momentum if we were in Continuous mode
                             if let ZoomMove::Continuous {
                                 start_time,
                                 velocity: old_velocity,
+                                tau,
                                 ..
                             } = state.zoom_move
                             {
                                 let elapsed = (now - start_time).as_secs_f64();
-                                let tau = 0.05;
                                 let current_velocity = old_velocity * (-elapsed / tau).exp();
                                 velocity += current_velocity;
                             }
@@ -896,7 +1490,7 @@ where
                                 start_time: now,
                                 start_zoom: self.viewpoint.zoom.f64(),
                                 velocity,
-                                tau: 0.05,
+                                tau: profile.zoom_tau(),
                             };
                         }
                     }
@@ -969,12 +1563,19 @@ where
                             last_time,
                             ..
                         } => {
-                            if (velocity.x.abs() > 10.0 || velocity.y.abs() > 10.0)
+                            let profile = if state.trackpad {
+                                self.gestures.trackpad
+                            } else {
+                                self.gestures.mouse
+                            };
+
+                            if velocity.x.hypot(velocity.y) > profile.fling_threshold
                                 && last_time.elapsed().as_millis() < 50
                             {
                                 state.pan_move = PanMove::Momentum {
                                     velocity,
                                     last_time: Instant::now(),
+                                    tau: profile.pan_tau(),
                                 };
                             } else {
                                 state.pan_move = PanMove::Idle;
@@ -1001,6 +1602,9 @@ where
             shell.request_redraw();
         }
 
+        // Avoid gaps around the world, which is smaller than the viewport when zoomed out
+        self.viewpoint.fit_world(bounds.size());
+
         let new_projector = Projector {
             viewpoint: self.viewpoint,
             bounds,
@@ -1013,54 +1617,123 @@ where
         }
 
         // Only when a redraw is requested do we recalculate the draw cache
-        if !matches!(
-            event,
-            iced::Event::Window(iced::window::Event::RedrawRequested(_))
-        ) {
+        let iced::Event::Window(iced::window::Event::RedrawRequested(at)) = event else {
             return;
+        };
+
+        // Report the failures since the last redraw
+        let count = self.tile_cache.failure_count();
+        let seen = *state.failures_seen.get_or_insert(count);
+        if let Some(on_tile_error) = &self.on_tile_error {
+            for failure in self.tile_cache.failures_since(seen) {
+                shell.publish(on_tile_error(failure.clone()));
+            }
         }
+        state.failures_seen = Some(count);
 
-        // Construct vector of tiles that should be fetched
-        let visible_tiles = self.flood_tiles(&new_projector);
-        let mut to_fetch = visible_tiles
-            .iter()
-            .filter(|(tile_id, _)| self.tile_cache.should_load(&tile_id))
-            .collect::<Vec<_>>();
-
-        // Sort them in order of distance to cursor (if available) or viewport center
-        to_fetch.sort_by(|(_, rect1), (_, rect2)| {
-            let center = state.cursor.unwrap_or_else(|| bounds.center());
-            let dist1 = center.distance(rect1.center());
-            let dist2 = center.distance(rect2.center());
-            dist1.partial_cmp(&dist2).unwrap_or(Ordering::Equal)
-        });
+        let interacting = !matches!(state.pan_move, PanMove::Idle)
+            || !matches!(state.zoom_move, ZoomMove::Idle)
+            || !state.touch.fingers.is_empty();
+
+        if let Some(reduced) = state
+            .quality
+            .frame(*at, interacting, self.target_frame_time)
+        {
+            if reduced {
+                log::debug!("Unable to keep up with the target frame rate, reducing quality");
+            }
+
+            if let Some(on_quality) = &self.on_quality {
+                shell.publish(on_quality(reduced));
+            }
+        }
+
+        // Only flood fill again when the view or the tile source changed
+        let zoom = self.tile_zoom(state.quality.reduced);
+        let key = (
+            self.viewpoint,
+            bounds,
+            self.tile_cache.tiling_scheme(),
+            zoom,
+        );
+
+        let pan_velocity = state.pan_velocity();
+        let flood = || self.flood_tiles_at(&new_projector, zoom, pan_velocity);
+        if state.visible_tiles.update(key, flood) {
+            // Make way for the tiles in view, over those which were panned past
+            let tiles = state
+                .visible_tiles
+                .tiles
+                .iter()
+                .map(|(id, _)| *id)
+                .collect();
+            shell.publish((self.cache_message)(CacheMessage::Visible { tiles }));
+        }
+
+        // Keep the drawn tiles in use, also once resolved, such that their allocations count
+        // toward the budget and the tiles of live sources are refreshed
+        for tile_id in state.draw_cache.tile_ids() {
+            self.tile_cache.touch(&tile_id);
+        }
+
+        // Swap in the new images of refreshed tiles
+        let refreshed = self.tile_cache.refreshed();
+        if state.refreshed_seen != refreshed {
+            state.refreshed_seen = refreshed;
+            state.visible_tiles.resolved = false;
+        }
+
+        if state.visible_tiles.resolved {
+            return;
+        }
+
+        // Tiles that should be fetched, in order of distance to cursor (if available) or
+        // viewport center
+        let focus = state.cursor.unwrap_or_else(|| bounds.center());
+        let mut to_fetch = state
+            .visible_tiles
+            .prioritized(focus)
+            .filter(|(tile_id, _)| self.tile_cache.should_load(tile_id))
+            .peekable();
+
+        // Decode the loaded tiles in the same order as they are fetched
+        if to_fetch.peek().is_some() {
+            shell.publish((self.cache_message)(CacheMessage::Focus {
+                position: new_projector.screen_space_into_mercator(focus),
+            }));
+        }
 
         // Enqueue loading of missing tiles with shell
         for (tile_id, _) in to_fetch {
             shell.publish((self.cache_message)(CacheMessage::Load { id: *tile_id }))
         }
 
+        let visible_tiles = &state.visible_tiles.tiles;
+
+        let mut resolved = true;
+        let mut retained = Vec::new();
         let mut new_draw_cache = DrawCache::new();
-        for (tile_id, rectangle) in visible_tiles.into_iter() {
+        for &(tile_id, rectangle) in visible_tiles {
             // Is the desired tile available, then use it.
-            if let Some((handle, allocation)) =
-                self.get_drawable_tile(&mut state.draw_cache, &tile_id)
-            {
-                new_draw_cache.insert(tile_id, handle, rectangle, allocation);
+            if let Some(data) = self.get_drawable_tile(&mut state.draw_cache, &tile_id, rectangle) {
+                new_draw_cache.insert(tile_id, data);
                 continue;
             }
 
+            resolved = false;
+
             // Otherwise, ensure the tile is allocated on the GPU asap!
             if self.tile_cache.should_alloc(&tile_id) {
                 shell.publish((self.cache_message)(CacheMessage::Allocate { id: tile_id }))
             }
 
-            // Try to use four children as a fallback (too fine resolution)
+            // Try to use cached children as a fallback (too fine resolution)
             if self.fallback_to_children(
                 &mut state.draw_cache,
                 &mut new_draw_cache,
                 tile_id,
-                &projector,
+                &new_projector,
+                MAX_CHILD_FALLBACK,
             ) {
                 continue;
             }
@@ -1070,15 +1743,53 @@ where
                 &mut state.draw_cache,
                 &mut new_draw_cache,
                 &tile_id,
-                &projector,
+                &new_projector,
                 shell,
             ) {
                 continue;
             }
         }
 
+        // Retain previously drawn tiles underneath tiles which are still fading in, such
+        // that they do not fade in from an empty background
+        let now = Instant::now();
+        for (tile_id, data) in state.draw_cache.drain() {
+            let rectangle = self.position_of_tile(&new_projector, &tile_id);
+            if new_draw_cache.is_fading_over(&rectangle, now) {
+                retained.push((tile_id, DrawData { rectangle, ..data }));
+            }
+        }
+
+        // Keep the outdated images of refreshed tiles underneath them while they fade in
+        for (tile_id, data) in state.draw_cache.replaced.drain() {
+            if new_draw_cache.is_replacing(&tile_id, now) {
+                let rectangle = self.position_of_tile(&new_projector, &tile_id);
+                new_draw_cache
+                    .replaced
+                    .insert(tile_id, DrawData { rectangle, ..data });
+            }
+        }
+
+        let fading =
+            !retained.is_empty() || new_draw_cache.iter_tiles().any(|data| data.is_fading(now));
+
+        for (tile_id, data) in retained {
+            if data.is_placeholder() || !new_draw_cache.contains_key(&tile_id) {
+                new_draw_cache.insert(tile_id, data);
+            }
+        }
+
+        // Drop retained tiles which are no longer in view
+        new_draw_cache.retain_intersections(&bounds.expand(32));
+
         // Swap in the new cache, dropping all unused allocations from the old one
         core::mem::swap(&mut new_draw_cache, &mut state.draw_cache);
+
+        // Keep resolving while fading, such that retained tiles are released again
+        state.visible_tiles.resolved = resolved && !fading;
+        if fading {
+            shell.request_redraw();
+        }
     }
 
     fn draw(
@@ -1092,10 +1803,32 @@ where
         _viewport: &iced::Rectangle,
     ) {
         if let Some(state) = WidgetState::get_ref(&tree.state) {
+            let now = Instant::now();
+            let snapping = self.snapping_scale();
+            let filter_method = match snapping {
+                Some(_) => FilterMethod::Nearest,
+                None => FilterMethod::Linear,
+            };
+
             renderer.with_layer(layout.bounds(), |renderer| {
+                if let Some(color) = self.polar_fill {
+                    let projector = Projector {
+                        viewpoint: self.viewpoint,
+                        bounds: layout.bounds(),
+                    };
+                    let world = projector.tile_bounds(&TileCoord::ZERO);
+                    draw_polar_fill(renderer, layout.bounds(), world, color);
+                }
+
                 for data in state.draw_cache.iter_tiles() {
-                    let image = Image::new(&data.handle).filter_method(FilterMethod::Linear);
-                    renderer.draw_image(image, data.rectangle, layout.bounds())
+                    let image = Image::new(&data.handle)
+                        .filter_method(filter_method)
+                        .opacity(data.opacity(now));
+                    let rectangle = match snapping {
+                        Some(scale_factor) => snap_to_pixels(data.rectangle, scale_factor),
+                        None => data.rectangle,
+                    };
+                    renderer.draw_image(image, rectangle, layout.bounds())
                 }
             });
         }
@@ -1149,6 +1882,50 @@ where
     }
 }
 
+/// Fill the parts of the bounds above and below the world.
+fn draw_polar_fill<Renderer: iced_core::Renderer>(
+    renderer: &mut Renderer,
+    bounds: Rectangle,
+    world: Rectangle,
+    color: Color,
+) {
+    let north = Rectangle {
+        height: world.y - bounds.y,
+        ..bounds
+    };
+    let south = Rectangle {
+        y: world.y + world.height,
+        height: bounds.y + bounds.height - (world.y + world.height),
+        ..bounds
+    };
+
+    for region in [north, south] {
+        if region.height > 0.0 {
+            renderer.fill_quad(
+                iced_core::renderer::Quad {
+                    bounds: region,
+                    ..Default::default()
+                },
+                color,
+            );
+        }
+    }
+}
+
+/// Round the edges of a rectangle to the physical pixel grid. Adjacent tiles share their
+/// edges, so they also share the rounded edges, and no seams appear between them.
+fn snap_to_pixels(rectangle: Rectangle, scale_factor: f32) -> Rectangle {
+    let snap = |value: f32| (value * scale_factor).round() / scale_factor;
+    let (x, y) = (snap(rectangle.x), snap(rectangle.y));
+
+    Rectangle {
+        x,
+        y,
+        width: snap(rectangle.x + rectangle.width) - x,
+        height: snap(rectangle.y + rectangle.height) - y,
+    }
+}
+
 impl<'a, Message: 'a, Theme: 'a, Renderer: 'a> From<MapWidget<'a, Message>>
     for Element<'a, Message, Theme, Renderer>
 where
@@ -1158,3 +1935,239 @@ where
         Self::new(value)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::location;
+
+    fn projector() -> Projector {
+        Projector {
+            viewpoint: Viewpoint {
+                position: location::paris().as_mercator(),
+                zoom: Zoom::try_from(10.0).unwrap(),
+            },
+            bounds: Rectangle::new(Point::ORIGIN, iced_core::Size::new(800.0, 600.0)),
+        }
+    }
+
+    /// A tile drawn in the previous frame. It is reused as is, so its image does not need to
+    /// be allocated, as there is no renderer.
+    fn drawn(projector: &Projector, tile_id: TileCoord) -> DrawData {
+        DrawData {
+            handle: Handle::from_rgba(1, 1, vec![0; 4]),
+            rectangle: projector.tile_bounds(&tile_id),
+            allocation: None,
+            shown: Instant::now(),
+        }
+    }
+
+    #[test]
+    fn children_cover_missing_tiles() {
+        let projector = projector();
+        let cache = TileCache::new(crate::sources::OpenStreetMap);
+        let widget = MapWidget::new(&cache, |_| (), projector.viewpoint);
+
+        let tile_id = projector.viewpoint.position.tile_id(10);
+        let [first, second, third, fourth] = tile_id.children().unwrap();
+        let descendants = |id: TileCoord| id.children().unwrap();
+
+        // Children and grandchildren are used, while the fourth child is only covered by
+        // its great-grandchildren, which are too far in
+        let mut old_draw_cache = DrawCache::new();
+        for id in [first, second].into_iter().chain(descendants(third)) {
+            old_draw_cache.insert(id, drawn(&projector, id));
+        }
+        let too_deep: Vec<_> = descendants(fourth)
+            .into_iter()
+            .flat_map(descendants)
+            .collect();
+        for &id in &too_deep {
+            old_draw_cache.insert(id, drawn(&projector, id));
+        }
+
+        let mut draw_cache = DrawCache::new();
+        let covered = widget.fallback_to_children(
+            &mut old_draw_cache,
+            &mut draw_cache,
+            tile_id,
+            &projector,
+            MAX_CHILD_FALLBACK,
+        );
+        assert!(!covered);
+        assert!(draw_cache.contains_key(&first) && draw_cache.contains_key(&second));
+        assert!(
+            descendants(third)
+                .iter()
+                .all(|id| draw_cache.contains_key(id))
+        );
+        assert!(!too_deep.iter().any(|id| draw_cache.contains_key(id)));
+
+        // With the fourth child cached, the whole tile is covered
+        let mut old_draw_cache = DrawCache::new();
+        for id in [first, second, third, fourth] {
+            old_draw_cache.insert(id, drawn(&projector, id));
+        }
+        let covered = widget.fallback_to_children(
+            &mut old_draw_cache,
+            &mut draw_cache,
+            tile_id,
+            &projector,
+            MAX_CHILD_FALLBACK,
+        );
+        assert!(covered);
+    }
+
+    #[test]
+    fn visible_tiles_follow_the_key() {
+        let projector = projector();
+        let key = (
+            projector.viewpoint,
+            projector.bounds,
+            TilingScheme::default(),
+            10,
+        );
+        let tile = |zoom| vec![(TileCoord::new(0, 0, zoom), Rectangle::default())];
+
+        let mut visible = VisibleTiles::default();
+        assert!(visible.update(key, || tile(10)));
+        visible.resolved = true;
+
+        // The flood fill is skipped while nothing changed
+        assert!(!visible.update(key, || unreachable!()));
+        assert!(visible.resolved);
+
+        // Sources with another tiling scheme or zoom range lay out the tiles differently
+        let scheme = TilingScheme::new(512);
+        assert!(visible.update((key.0, key.1, scheme, 10), || tile(9)));
+        assert!(!visible.resolved);
+        assert!(visible.update((key.0, key.1, scheme, 8), || tile(8)));
+        assert_eq!(visible.tiles, tile(8));
+    }
+
+    #[test]
+    fn prefetch_ahead_of_panning() {
+        let bounds = Rectangle::new(Point::ORIGIN, iced_core::Size::new(100.0, 100.0));
+        let prefetch = Prefetch {
+            margin: PrefetchMargin::Tiles(0.5),
+            lookahead: Duration::from_millis(500),
+        };
+
+        // Dragging to the left reveals the right side of the map
+        let viewport = prefetch.viewport(bounds, 64.0, Vector::new(-200.0, 0.0));
+        assert_eq!(viewport.x, -32.0);
+        assert_eq!(viewport.y, -32.0);
+        assert_eq!(viewport.width, 264.0);
+        assert_eq!(viewport.height, 164.0);
+    }
+
+    #[test]
+    fn follow_eases_toward_target() {
+        let mut viewpoint = projector().viewpoint;
+        let target = Viewpoint {
+            position: location::rome().as_mercator(),
+            zoom: Zoom::try_from(12.0).unwrap(),
+        };
+
+        assert!(!ease_toward(&mut viewpoint, target, 0.5));
+        assert!((viewpoint.zoom.f64() - 11.0).abs() < 1e-9);
+
+        let mut steps = 0;
+        while !ease_toward(&mut viewpoint, target, 0.5) {
+            steps += 1;
+            assert!(steps < 100);
+        }
+        assert_eq!(viewpoint, target);
+    }
+
+    #[test]
+    fn scroll_capture_policy() {
+        let none = Modifiers::empty();
+        assert!(ScrollCapture::Always.captures(false, none));
+        assert!(!ScrollCapture::Never.captures(true, Modifiers::CTRL));
+
+        assert!(!ScrollCapture::Focused.captures(false, none));
+        assert!(ScrollCapture::Focused.captures(true, none));
+
+        let ctrl = ScrollCapture::Modifier(Modifiers::CTRL);
+        assert!(!ctrl.captures(true, none));
+        assert!(ctrl.captures(false, Modifiers::CTRL | Modifiers::SHIFT));
+    }
+
+    #[test]
+    fn fling_after_one_finger_pan() {
+        let projector = projector();
+        let profile = Gestures::default().touch;
+        let now = Instant::now();
+
+        let mut touch = TouchState::default();
+        touch
+            .fingers
+            .insert(Finger(0), FingerState::new(Point::new(100.0, 100.0)));
+        touch.smoothed_pan_velocity = Vector::new(400.0, 0.0);
+        touch.last_motion = Some(now);
+
+        let release = touch.lift(
+            Finger(0),
+            now + Duration::from_millis(10),
+            &projector,
+            &profile,
+        );
+        assert_eq!(release.fling, Some(Vector::new(400.0, 0.0)));
+        assert_eq!(release.pinch, None);
+
+        // Holding still before lifting does not fling
+        touch
+            .fingers
+            .insert(Finger(0), FingerState::new(Point::new(100.0, 100.0)));
+        touch.smoothed_pan_velocity = Vector::new(400.0, 0.0);
+        touch.last_motion = Some(now);
+        let release = touch.lift(
+            Finger(0),
+            now + Duration::from_millis(200),
+            &projector,
+            &profile,
+        );
+        assert_eq!(release, TouchRelease::default());
+    }
+
+    #[test]
+    fn zoom_inertia_after_pinch() {
+        let projector = projector();
+        let profile = Gestures::default().touch;
+        let now = Instant::now();
+
+        let mut touch = TouchState::default();
+        touch
+            .fingers
+            .insert(Finger(0), FingerState::new(Point::new(300.0, 300.0)));
+        touch
+            .fingers
+            .insert(Finger(1), FingerState::new(Point::new(500.0, 300.0)));
+        touch.smoothed_pinch_velocity = 2.0;
+        touch.last_motion = Some(now);
+
+        // The fingers are rarely lifted at the exact same time
+        let first = touch.lift(
+            Finger(0),
+            now + Duration::from_millis(5),
+            &projector,
+            &profile,
+        );
+        assert_eq!(first, TouchRelease::default());
+        let release = touch.lift(
+            Finger(1),
+            now + Duration::from_millis(20),
+            &projector,
+            &profile,
+        );
+
+        let (velocity, point) = release.pinch.unwrap();
+        assert_eq!(velocity, 2.0);
+        assert_eq!(
+            point,
+            projector.screen_space_into_mercator(Point::new(400.0, 300.0))
+        );
+        assert_eq!(release.fling, None);
+    }
+}
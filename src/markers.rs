@@ -0,0 +1,1049 @@
+//! Drawing of large amounts of static markers, such as points of interest or sensor
+//! locations.
+//!
+//! The markers are bulk loaded into a [`MarkerIndex`], which is a packed
+//! [R-tree](https://en.wikipedia.org/wiki/R-tree), such that only the markers within view
+//! are visited when drawing. When zoomed out, markers which would overlap are decimated,
+//! which bounds the number of markers drawn per frame regardless of how many are in view.
+//!
+//! Markers which still overlap when the map can not be zoomed in further can be fanned out
+//! with a [`Spiderfy`], such that each of them can be clicked.
+//!
+//! Markers which change individually, e.g. as they are added and dragged around, are kept in
+//! a [`MarkerStore`] instead, which animates each change without rebuilding the others.
+
+use std::collections::{HashMap, HashSet};
+use std::f32::consts::TAU;
+use std::hash::Hash;
+use std::time::Duration;
+
+use iced::time::Instant;
+use iced::widget::canvas::{Frame, Image, Path, Stroke};
+use iced::{Color, Point, Rectangle, Size, Vector};
+use iced_core::image::Handle;
+
+use crate::animation::Easing;
+use crate::legend::{LegendEntry, Symbol};
+use crate::{Geodetic, Mercator, Projector};
+
+/// The maximum number of entries of each node in the tree.
+const NODE_CAPACITY: usize = 16;
+
+/// Spiderfied markers are placed on a circle up to this many, and along a spiral beyond.
+const SPIDER_CIRCLE_MAX: usize = 8;
+
+/// An axis-aligned bounding box in mercator space.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Bounds {
+    min: (f64, f64),
+    max: (f64, f64),
+}
+
+impl Bounds {
+    fn point(position: Mercator) -> Self {
+        let point = (position.east_x(), position.south_y());
+        Self {
+            min: point,
+            max: point,
+        }
+    }
+
+    fn union(self, other: Self) -> Self {
+        Self {
+            min: (self.min.0.min(other.min.0), self.min.1.min(other.min.1)),
+            max: (self.max.0.max(other.max.0), self.max.1.max(other.max.1)),
+        }
+    }
+
+    fn intersects(&self, other: &Self) -> bool {
+        self.min.0 <= other.max.0
+            && other.min.0 <= self.max.0
+            && self.min.1 <= other.max.1
+            && other.min.1 <= self.max.1
+    }
+
+    fn center(&self) -> (f64, f64) {
+        (
+            (self.min.0 + self.max.0) / 2.0,
+            (self.min.1 + self.max.1) / 2.0,
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    bounds: Bounds,
+    /// The range of entries in the level below, or of markers for the leaf level.
+    start: usize,
+    end: usize,
+}
+
+/// Sort the entries with the Sort-Tile-Recursive algorithm, and pack them into nodes.
+fn pack<E>(entries: &mut [E], bounds: impl Fn(&E) -> Bounds) -> Vec<Node> {
+    let leaves = entries.len().div_ceil(NODE_CAPACITY);
+    let slices = (leaves as f64).sqrt().ceil() as usize;
+    let slice_len = (slices * NODE_CAPACITY).max(1);
+
+    let key = |entry: &E, axis: fn((f64, f64)) -> f64| axis(bounds(entry).center());
+    entries.sort_unstable_by(|a, b| key(a, |c| c.0).total_cmp(&key(b, |c| c.0)));
+
+    let mut nodes = Vec::with_capacity(leaves);
+    for (index, slice) in entries.chunks_mut(slice_len).enumerate() {
+        slice.sort_unstable_by(|a, b| key(a, |c| c.1).total_cmp(&key(b, |c| c.1)));
+
+        for (chunk_index, chunk) in slice.chunks(NODE_CAPACITY).enumerate() {
+            let start = index * slice_len + chunk_index * NODE_CAPACITY;
+            let bounds = chunk
+                .iter()
+                .map(&bounds)
+                .reduce(Bounds::union)
+                .expect("chunks are never empty");
+
+            nodes.push(Node {
+                bounds,
+                start,
+                end: start + chunk.len(),
+            });
+        }
+    }
+
+    nodes
+}
+
+/// A spatial index of markers, each carrying some data `T`.
+#[derive(Debug, Clone)]
+pub struct MarkerIndex<T> {
+    markers: Vec<(Mercator, T)>,
+    /// The levels of the tree, from the leaves up to the root.
+    levels: Vec<Vec<Node>>,
+}
+
+impl<T> Default for MarkerIndex<T> {
+    fn default() -> Self {
+        Self {
+            markers: Vec::new(),
+            levels: Vec::new(),
+        }
+    }
+}
+
+impl<T> FromIterator<(Geodetic, T)> for MarkerIndex<T> {
+    fn from_iter<I: IntoIterator<Item = (Geodetic, T)>>(iter: I) -> Self {
+        Self::new(iter)
+    }
+}
+
+impl<T> MarkerIndex<T> {
+    /// Build the index from all markers at once, which results in a better balanced tree
+    /// than inserting them one by one.
+    pub fn new(markers: impl IntoIterator<Item = (Geodetic, T)>) -> Self {
+        let mut markers: Vec<_> = markers
+            .into_iter()
+            .map(|(position, data)| (position.as_mercator(), data))
+            .collect();
+
+        let mut levels = Vec::new();
+        if !markers.is_empty() {
+            levels.push(pack(&mut markers, |(position, _)| Bounds::point(*position)));
+
+            while let Some(level) = levels.last_mut()
+                && level.len() > 1
+            {
+                let parents = pack(level, |node| node.bounds);
+                levels.push(parents);
+            }
+        }
+
+        Self { markers, levels }
+    }
+
+    pub fn len(&self) -> usize {
+        self.markers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.markers.is_empty()
+    }
+
+    /// Iterate over all markers, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (Geodetic, &T)> {
+        self.markers
+            .iter()
+            .map(|(position, data)| (position.as_geodetic(), data))
+    }
+
+    /// Get a marker by its index, as used by [`Spiderfy`].
+    pub fn get(&self, index: usize) -> Option<(Geodetic, &T)> {
+        self.markers
+            .get(index)
+            .map(|(position, data)| (position.as_geodetic(), data))
+    }
+
+    /// Visit the markers within the rectangle spanned by two corners.
+    pub fn query(&self, a: Mercator, b: Mercator) -> impl Iterator<Item = (Mercator, &T)> {
+        self.query_indices(a, b).map(|index| {
+            let (position, data) = &self.markers[index];
+            (*position, data)
+        })
+    }
+
+    /// Visit the indices of the markers within the rectangle spanned by two corners.
+    fn query_indices(&self, a: Mercator, b: Mercator) -> impl Iterator<Item = usize> {
+        let area = Bounds::point(a).union(Bounds::point(b));
+
+        // Each entry of the stack is a level and a range of nodes within it
+        let mut stack = Vec::with_capacity(self.levels.len());
+        if let Some(root) = self.levels.last() {
+            stack.push((self.levels.len() - 1, 0..root.len()));
+        }
+        let mut leaf = 0..0;
+
+        std::iter::from_fn(move || {
+            loop {
+                // Yield markers of the current leaf first
+                for index in leaf.by_ref() {
+                    let (position, _) = &self.markers[index];
+                    if area.intersects(&Bounds::point(*position)) {
+                        return Some(index);
+                    }
+                }
+
+                let (level, range) = stack.last_mut()?;
+                let Some(index) = range.next() else {
+                    stack.pop();
+                    continue;
+                };
+
+                let node = &self.levels[*level][index];
+                if !node.bounds.intersects(&area) {
+                    continue;
+                }
+
+                match *level {
+                    0 => leaf = node.start..node.end,
+                    level => stack.push((level - 1, node.start..node.end)),
+                }
+            }
+        })
+    }
+
+    /// Visit the markers within view of the projector, expanded by some margin in pixels.
+    pub fn in_view(
+        &self,
+        projector: &Projector,
+        margin: f32,
+    ) -> impl Iterator<Item = (Mercator, &T)> {
+        self.in_view_indices(projector, margin).map(|index| {
+            let (position, data) = &self.markers[index];
+            (*position, data)
+        })
+    }
+
+    fn in_view_indices(&self, projector: &Projector, margin: f32) -> impl Iterator<Item = usize> {
+        let viewport = projector.bounds.expand(margin);
+        self.query_indices(
+            projector.screen_space_into_mercator(viewport.position()),
+            projector.screen_space_into_mercator(Point::new(
+                viewport.x + viewport.width,
+                viewport.y + viewport.height,
+            )),
+        )
+    }
+
+    pub fn layer(&self) -> MarkerLayer<'_, T> {
+        MarkerLayer::new(self)
+    }
+}
+
+/// The visual appearance of a [`MarkerLayer`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarkerStyle {
+    pub color: Color,
+    pub radius: f32,
+    /// Draw this image instead of a circle, scaled to the diameter of the marker.
+    pub icon: Option<Handle>,
+    /// Markers closer than this many pixels to an already drawn marker are skipped.
+    pub spacing: f32,
+    /// The maximum number of markers drawn per frame.
+    pub max_markers: usize,
+}
+
+impl Default for MarkerStyle {
+    fn default() -> Self {
+        Self {
+            color: Color::from_rgb(0.1, 0.45, 0.95),
+            radius: 4.0,
+            icon: None,
+            spacing: 6.0,
+            max_markers: 5000,
+        }
+    }
+}
+
+impl MarkerStyle {
+    /// An entry for a [`Legend`](crate::legend::Legend) explaining these markers.
+    pub fn legend(&self, label: impl Into<String>) -> LegendEntry {
+        let symbol = Symbol::Point {
+            color: self.color,
+            radius: self.radius,
+        };
+        LegendEntry::new(symbol, label)
+    }
+}
+
+/// Draws the markers of a [`MarkerIndex`] which are within view.
+#[derive(Debug, Clone)]
+pub struct MarkerLayer<'a, T> {
+    index: &'a MarkerIndex<T>,
+    style: MarkerStyle,
+    spider: Option<&'a Spiderfy>,
+}
+
+impl<'a, T> MarkerLayer<'a, T> {
+    pub fn new(index: &'a MarkerIndex<T>) -> Self {
+        Self {
+            index,
+            style: MarkerStyle::default(),
+            spider: None,
+        }
+    }
+
+    pub fn style(mut self, style: MarkerStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Draw the markers of a [`Spiderfy`] fanned out, instead of at their own positions.
+    pub fn spiderfied(mut self, spider: Option<&'a Spiderfy>) -> Self {
+        self.spider = spider;
+        self
+    }
+
+    /// Fan out the markers drawn under the cursor, if there are several. Typically done when
+    /// clicking overlapping markers while the map can not be zoomed in further.
+    pub fn spiderfy(&self, projector: &Projector, cursor: Point) -> Option<Spiderfy> {
+        let reach = self.style.radius + self.style.spacing / 2.0;
+        let area = Rectangle::new(cursor, Size::ZERO).expand(reach);
+
+        let mut members: Vec<(usize, f32)> = self
+            .index
+            .query_indices(
+                projector.screen_space_into_mercator(area.position()),
+                projector.screen_space_into_mercator(Point::new(
+                    area.x + area.width,
+                    area.y + area.height,
+                )),
+            )
+            .map(|index| {
+                let point = projector.mercator_into_screen_space(self.index.markers[index].0);
+                (index, point.distance(cursor))
+            })
+            .filter(|(_, distance)| *distance <= reach)
+            .collect();
+
+        if members.len() < 2 {
+            return None;
+        }
+        members.sort_unstable_by(|a, b| a.1.total_cmp(&b.1));
+
+        Some(Spiderfy {
+            center: projector.screen_space_into_mercator(cursor),
+            members: members.into_iter().map(|(index, _)| index).collect(),
+            radius: self.style.radius,
+        })
+    }
+
+    /// The screen space positions of the markers which are drawn, after decimation.
+    pub fn visible(&self, projector: &Projector) -> Vec<Point> {
+        let spacing = self.style.spacing.max(1.0);
+
+        // Only a single marker is drawn within each cell of a grid of the given spacing
+        let mut occupied = HashSet::new();
+        let spiderfied: HashSet<usize> = self
+            .spider
+            .map(|spider| spider.members.iter().copied().collect())
+            .unwrap_or_default();
+
+        self.index
+            .in_view_indices(projector, self.style.radius)
+            .filter(|index| !spiderfied.contains(index))
+            .map(|index| projector.mercator_into_screen_space(self.index.markers[index].0))
+            .filter(|point| {
+                let cell = (
+                    (point.x / spacing).floor() as i32,
+                    (point.y / spacing).floor() as i32,
+                );
+                occupied.insert(cell)
+            })
+            .take(self.style.max_markers)
+            .collect()
+    }
+
+    pub fn draw(&self, projector: &Projector, frame: &mut Frame<iced::Renderer>) {
+        let points = self.visible(projector);
+        self.draw_points(points, frame);
+
+        if let Some(spider) = self.spider {
+            let center = projector.mercator_into_screen_space(spider.center);
+            let legs = Path::new(|builder| {
+                for (_, point) in spider.positions(projector) {
+                    builder.move_to(center);
+                    builder.line_to(point);
+                }
+            });
+            frame.stroke(
+                &legs,
+                Stroke::default()
+                    .with_color(Color::from_rgba(0.0, 0.0, 0.0, 0.6))
+                    .with_width(1.5),
+            );
+
+            let points = spider.positions(projector).map(|(_, point)| point);
+            self.draw_points(points.collect(), frame);
+        }
+    }
+
+    fn draw_points(&self, points: Vec<Point>, frame: &mut Frame<iced::Renderer>) {
+        draw_markers(
+            &self.style,
+            points.into_iter().map(|point| (point, 1.0)),
+            frame,
+        );
+    }
+}
+
+/// Draw markers of the style at the points, each scaled by some factor.
+fn draw_markers(
+    style: &MarkerStyle,
+    points: impl IntoIterator<Item = (Point, f32)>,
+    frame: &mut Frame<iced::Renderer>,
+) {
+    if let Some(icon) = &style.icon {
+        for (point, scale) in points {
+            let radius = style.radius * scale;
+            let size = Size::new(radius * 2.0, radius * 2.0);
+            let bounds = Rectangle::new(Point::new(point.x - radius, point.y - radius), size);
+            frame.draw_image(bounds, Image::new(icon));
+        }
+        return;
+    }
+
+    // Draw all circles as a single path
+    let path = Path::new(|builder| {
+        for (point, scale) in points {
+            builder.circle(point, style.radius * scale);
+        }
+    });
+
+    frame.fill(&path, style.color);
+    frame.stroke(
+        &path,
+        Stroke::default().with_color(Color::WHITE).with_width(1.0),
+    );
+}
+
+/// Overlapping markers fanned out around the point they overlap at, with a leg from that
+/// point to each marker. Created by [`MarkerLayer::spiderfy`], and drawn by passing it to
+/// [`MarkerLayer::spiderfied`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spiderfy {
+    center: Mercator,
+    /// The indices of the markers, closest to the center first.
+    members: Vec<usize>,
+    radius: f32,
+}
+
+impl Spiderfy {
+    /// The indices of the fanned out markers, see [`MarkerIndex::get`].
+    pub fn members(&self) -> &[usize] {
+        &self.members
+    }
+
+    /// The screen space position of each fanned out marker, along with its index.
+    pub fn positions(&self, projector: &Projector) -> impl Iterator<Item = (usize, Point)> {
+        let center = projector.mercator_into_screen_space(self.center);
+        let spacing = self.radius * 2.0 + 6.0;
+        let count = self.members.len();
+
+        // Spread evenly around a circle, which grows with the number of markers
+        let circle_radius = spacing * (count + 2) as f32 / TAU;
+
+        // Or wind around a spiral, placing each marker one spacing after the previous
+        let mut angle = 0.0f32;
+        let mut spiral_radius = spacing * 1.5;
+
+        self.members.iter().enumerate().map(move |(i, &index)| {
+            let offset = if count <= SPIDER_CIRCLE_MAX {
+                let angle = TAU * i as f32 / count as f32 - TAU / 4.0;
+                Vector::new(angle.cos(), angle.sin()) * circle_radius
+            } else {
+                angle += spacing / spiral_radius;
+                spiral_radius += spacing / TAU * (spacing / spiral_radius);
+                Vector::new(angle.cos(), angle.sin()) * spiral_radius
+            };
+
+            (index, center + offset)
+        })
+    }
+
+    /// The index of the fanned out marker under the cursor.
+    pub fn hit(&self, projector: &Projector, cursor: Point) -> Option<usize> {
+        self.positions(projector)
+            .find(|(_, point)| point.distance(cursor) <= self.radius + 2.0)
+            .map(|(index, _)| index)
+    }
+
+    /// Whether the cursor is within the fanned out markers. Clicking outside of them
+    /// typically collapses the markers again.
+    pub fn contains(&self, projector: &Projector, cursor: Point) -> bool {
+        let center = projector.mercator_into_screen_space(self.center);
+        let reach = self
+            .positions(projector)
+            .map(|(_, point)| point.distance(center))
+            .fold(0.0, f32::max);
+
+        center.distance(cursor) <= reach + self.radius
+    }
+}
+
+/// The side of the cells of the grid a [`MarkerStore`] is indexed by, in mercator units. This
+/// matches the tiles at zoom level 8.
+const STORE_CELL: f64 = 2.0 / 256.0;
+
+fn store_cell(position: Mercator) -> (i32, i32) {
+    (
+        (position.east_x() / STORE_CELL).floor() as i32,
+        (position.south_y() / STORE_CELL).floor() as i32,
+    )
+}
+
+#[derive(Debug, Clone)]
+struct StoredMarker<T> {
+    position: Mercator,
+    data: T,
+    added: Instant,
+    /// The position the marker is moving from, and when it started moving.
+    moved_from: Option<(Mercator, Instant)>,
+    removed: Option<Instant>,
+}
+
+/// Markers identified by some key `K`, which the application inserts, moves and removes
+/// individually, rather than rebuilding all of them for every view.
+///
+/// Changes are animated: markers grow in when inserted, glide to where they are moved, and
+/// shrink out when removed. While [`MarkerStore::is_animating`], the map should be redrawn,
+/// e.g. from the [`iced::window::frames`] subscription, and [`MarkerStore::prune`] is called
+/// once in a while to forget markers which finished shrinking out.
+///
+/// The markers are indexed by a grid, such that each change only touches the cells of the
+/// marker, and only the markers within view are visited when drawing.
+#[derive(Debug, Clone)]
+pub struct MarkerStore<K, T> {
+    markers: HashMap<K, StoredMarker<T>>,
+    cells: HashMap<(i32, i32), HashSet<K>>,
+    /// The markers which may still be animating, and are therefore drawn wherever they are.
+    animating: HashSet<K>,
+    transition: Duration,
+    easing: Easing,
+    revision: u64,
+}
+
+impl<K, T> Default for MarkerStore<K, T> {
+    fn default() -> Self {
+        Self {
+            markers: HashMap::new(),
+            cells: HashMap::new(),
+            animating: HashSet::new(),
+            transition: Duration::from_millis(300),
+            easing: Easing::EaseOut,
+            revision: 0,
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone, T> MarkerStore<K, T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The duration of the animations of inserted, moved and removed markers. Changes are
+    /// shown right away when it is zero.
+    pub fn transition(mut self, transition: Duration) -> Self {
+        self.transition = transition;
+        self
+    }
+
+    pub fn easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// The number of markers, not counting those which are shrinking out.
+    pub fn len(&self) -> usize {
+        self.markers.len() - self.removing()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Increases with every change, to be used as the [`crate::DrawLayer::version`] of the
+    /// layer while nothing is animating.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    pub fn get(&self, id: &K) -> Option<(Geodetic, &T)> {
+        self.markers
+            .get(id)
+            .filter(|marker| marker.removed.is_none())
+            .map(|marker| (marker.position.as_geodetic(), &marker.data))
+    }
+
+    pub fn get_mut(&mut self, id: &K) -> Option<&mut T> {
+        self.revision += 1;
+        self.markers
+            .get_mut(id)
+            .filter(|marker| marker.removed.is_none())
+            .map(|marker| &mut marker.data)
+    }
+
+    /// Iterate over all markers, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, Geodetic, &T)> {
+        self.markers
+            .iter()
+            .filter(|(_, marker)| marker.removed.is_none())
+            .map(|(id, marker)| (id, marker.position.as_geodetic(), &marker.data))
+    }
+
+    /// Insert a marker, or replace the marker with the same id, which then moves to the new
+    /// position.
+    pub fn insert(&mut self, id: K, position: Geodetic, data: T, now: Instant) {
+        let position = position.as_mercator();
+
+        let marker = match self.markers.remove(&id) {
+            Some(old) if old.removed.is_none() => {
+                self.unindex(&id, old.position);
+                StoredMarker {
+                    position,
+                    data,
+                    added: old.added,
+                    moved_from: Some((self.position_of(&old, now), now)),
+                    removed: None,
+                }
+            }
+            old => {
+                if let Some(old) = old {
+                    self.unindex(&id, old.position);
+                }
+                StoredMarker {
+                    position,
+                    data,
+                    added: now,
+                    moved_from: None,
+                    removed: None,
+                }
+            }
+        };
+
+        self.index(&id, position);
+        self.animating.insert(id.clone());
+        self.markers.insert(id, marker);
+        self.revision += 1;
+    }
+
+    /// Move a marker to a new position, gliding there from where it is now. Returns whether
+    /// the marker exists.
+    pub fn move_to(&mut self, id: &K, position: Geodetic, now: Instant) -> bool {
+        self.relocate(id, position.as_mercator(), Some(now))
+    }
+
+    /// Place a marker at a new position right away, e.g. while it is being dragged. Returns
+    /// whether the marker exists.
+    pub fn set_position(&mut self, id: &K, position: Geodetic) -> bool {
+        self.relocate(id, position.as_mercator(), None)
+    }
+
+    fn relocate(&mut self, id: &K, position: Mercator, now: Option<Instant>) -> bool {
+        let Some(marker) = self.markers.get(id).filter(|m| m.removed.is_none()) else {
+            return false;
+        };
+        let previous = marker.position;
+        let moved_from = now.map(|now| (self.position_of(marker, now), now));
+
+        self.unindex(id, previous);
+        self.index(id, position);
+        if moved_from.is_some() {
+            self.animating.insert(id.clone());
+        }
+
+        let marker = self.markers.get_mut(id).expect("marker exists");
+        marker.position = position;
+        marker.moved_from = moved_from;
+        self.revision += 1;
+        true
+    }
+
+    /// Start shrinking out a marker, after which it is forgotten by [`MarkerStore::prune`].
+    /// Returns whether the marker existed.
+    pub fn remove(&mut self, id: &K, now: Instant) -> bool {
+        let Some(marker) = self.markers.get_mut(id).filter(|m| m.removed.is_none()) else {
+            return false;
+        };
+        self.revision += 1;
+
+        if self.transition.is_zero() {
+            let position = marker.position;
+            self.markers.remove(id);
+            self.unindex(id, position);
+            self.animating.remove(id);
+        } else {
+            marker.removed = Some(now);
+            self.animating.insert(id.clone());
+        }
+        true
+    }
+
+    /// Whether any marker is still growing in, moving or shrinking out.
+    pub fn is_animating(&self, now: Instant) -> bool {
+        self.animating.iter().any(|id| {
+            self.markers
+                .get(id)
+                .is_some_and(|marker| !self.is_settled(marker, now))
+        })
+    }
+
+    /// Forget the markers which finished shrinking out, and stop tracking finished animations.
+    pub fn prune(&mut self, now: Instant) {
+        let finished: Vec<K> = self
+            .animating
+            .iter()
+            .filter(|id| {
+                self.markers
+                    .get(*id)
+                    .is_none_or(|marker| self.is_settled(marker, now))
+            })
+            .cloned()
+            .collect();
+
+        for id in finished {
+            self.animating.remove(&id);
+            if let Some(marker) = self.markers.get(&id)
+                && marker.removed.is_some()
+            {
+                let position = marker.position;
+                self.markers.remove(&id);
+                self.unindex(&id, position);
+            }
+        }
+    }
+
+    pub fn layer(&self, now: Instant) -> MarkerStoreLayer<'_, K, T> {
+        MarkerStoreLayer {
+            store: self,
+            style: MarkerStyle::default(),
+            now,
+        }
+    }
+
+    fn removing(&self) -> usize {
+        self.animating
+            .iter()
+            .filter(|id| self.markers.get(*id).is_some_and(|m| m.removed.is_some()))
+            .count()
+    }
+
+    fn index(&mut self, id: &K, position: Mercator) {
+        self.cells
+            .entry(store_cell(position))
+            .or_default()
+            .insert(id.clone());
+    }
+
+    fn unindex(&mut self, id: &K, position: Mercator) {
+        let cell = store_cell(position);
+        if let Some(ids) = self.cells.get_mut(&cell) {
+            ids.remove(id);
+            if ids.is_empty() {
+                self.cells.remove(&cell);
+            }
+        }
+    }
+
+    /// The progress of an animation started at some point in time.
+    fn progress(&self, started: Instant, now: Instant) -> f32 {
+        if self.transition.is_zero() {
+            return 1.0;
+        }
+        let elapsed = now.saturating_duration_since(started);
+        self.easing
+            .apply(elapsed.as_secs_f32() / self.transition.as_secs_f32())
+    }
+
+    fn is_settled(&self, marker: &StoredMarker<T>, now: Instant) -> bool {
+        let started = [
+            Some(marker.added),
+            marker.moved_from.map(|(_, at)| at),
+            marker.removed,
+        ];
+        started
+            .into_iter()
+            .flatten()
+            .all(|at| now.saturating_duration_since(at) >= self.transition)
+    }
+
+    /// Where a marker is drawn at some point in time.
+    fn position_of(&self, marker: &StoredMarker<T>, now: Instant) -> Mercator {
+        match marker.moved_from {
+            Some((from, started)) => {
+                let t = self.progress(started, now) as f64;
+                Mercator::new(
+                    from.east_x() + (marker.position.east_x() - from.east_x()) * t,
+                    from.south_y() + (marker.position.south_y() - from.south_y()) * t,
+                )
+            }
+            None => marker.position,
+        }
+    }
+
+    /// How large a marker is drawn at some point in time, relative to its full size.
+    fn scale_of(&self, marker: &StoredMarker<T>, now: Instant) -> f32 {
+        let grown = self.progress(marker.added, now);
+        match marker.removed {
+            Some(removed) => grown * (1.0 - self.progress(removed, now)),
+            None => grown,
+        }
+    }
+
+    /// Visit the ids of the markers indexed within the rectangle spanned by two corners,
+    /// along with all markers which may be animating.
+    fn query_ids(&self, a: Mercator, b: Mercator) -> impl Iterator<Item = &K> {
+        let (min_x, min_y) = store_cell(Mercator::new(
+            a.east_x().min(b.east_x()),
+            a.south_y().min(b.south_y()),
+        ));
+        let (max_x, max_y) = store_cell(Mercator::new(
+            a.east_x().max(b.east_x()),
+            a.south_y().max(b.south_y()),
+        ));
+        let in_range =
+            move |(x, y): (i32, i32)| (min_x..=max_x).contains(&x) && (min_y..=max_y).contains(&y);
+
+        // When zoomed out, there are fewer occupied cells than cells in view
+        let span = (max_x - min_x + 1) as u64 * (max_y - min_y + 1) as u64;
+        let cells: Box<dyn Iterator<Item = &HashSet<K>>> = if span > self.cells.len() as u64 {
+            Box::new(
+                self.cells
+                    .iter()
+                    .filter(move |(cell, _)| in_range(**cell))
+                    .map(|(_, ids)| ids),
+            )
+        } else {
+            Box::new(
+                (min_y..=max_y)
+                    .flat_map(move |y| (min_x..=max_x).map(move |x| (x, y)))
+                    .filter_map(|cell| self.cells.get(&cell)),
+            )
+        };
+
+        let animating = self
+            .animating
+            .iter()
+            .filter(move |id| !in_range(store_cell(self.markers[*id].position)));
+
+        cells.flatten().chain(animating)
+    }
+}
+
+/// Draws the markers of a [`MarkerStore`] which are within view, as they are at some point
+/// in time.
+#[derive(Debug, Clone)]
+pub struct MarkerStoreLayer<'a, K, T> {
+    store: &'a MarkerStore<K, T>,
+    style: MarkerStyle,
+    now: Instant,
+}
+
+impl<'a, K: Hash + Eq + Clone, T> MarkerStoreLayer<'a, K, T> {
+    pub fn style(mut self, style: MarkerStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// The markers which are drawn, after decimation, with their screen space position and
+    /// the scale they are drawn at.
+    pub fn visible(&self, projector: &Projector) -> Vec<(&'a K, Point, f32)> {
+        let spacing = self.style.spacing.max(1.0);
+        let viewport = projector.bounds.expand(self.style.radius);
+
+        // Only a single marker is drawn within each cell of a grid of the given spacing
+        let mut occupied = HashSet::new();
+        let store = self.store;
+
+        store
+            .query_ids(
+                projector.screen_space_into_mercator(viewport.position()),
+                projector.screen_space_into_mercator(Point::new(
+                    viewport.x + viewport.width,
+                    viewport.y + viewport.height,
+                )),
+            )
+            .filter_map(|id| {
+                let marker = &store.markers[id];
+                let point =
+                    projector.mercator_into_screen_space(store.position_of(marker, self.now));
+                let scale = store.scale_of(marker, self.now);
+                (viewport.contains(point) && scale > 0.0).then_some((id, point, scale))
+            })
+            .filter(|(_, point, _)| {
+                let cell = (
+                    (point.x / spacing).floor() as i32,
+                    (point.y / spacing).floor() as i32,
+                );
+                occupied.insert(cell)
+            })
+            .take(self.style.max_markers)
+            .collect()
+    }
+
+    /// The id of a drawn marker under the cursor.
+    pub fn marker_at(&self, projector: &Projector, cursor: Point) -> Option<&'a K> {
+        let reach = self.style.radius + 2.0;
+        self.visible(projector)
+            .into_iter()
+            .filter(|(id, _, _)| self.store.markers[*id].removed.is_none())
+            .find(|(_, point, scale)| point.distance(cursor) <= reach * scale)
+            .map(|(id, _, _)| id)
+    }
+
+    pub fn draw(&self, projector: &Projector, frame: &mut Frame<iced::Renderer>) {
+        let points = self.visible(projector);
+        draw_markers(
+            &self.style,
+            points.into_iter().map(|(_, point, scale)| (point, scale)),
+            frame,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_matches_linear_scan() {
+        let markers: Vec<_> = (0..2500)
+            .map(|i| {
+                let (x, y) = (i % 50, i / 50);
+                (
+                    Geodetic::new(x as f64 * 0.1, y as f64 * 0.1 + (x % 7) as f64 * 0.01),
+                    i,
+                )
+            })
+            .collect();
+
+        let index = MarkerIndex::new(markers.iter().copied());
+        assert_eq!(index.len(), markers.len());
+
+        let (a, b) = (Geodetic::new(1.05, 3.95), Geodetic::new(2.55, 1.25));
+        let mut found: Vec<_> = index
+            .query(a.as_mercator(), b.as_mercator())
+            .map(|(_, &i)| i)
+            .collect();
+        found.sort_unstable();
+
+        let expected: Vec<_> = markers
+            .iter()
+            .filter(|(position, _)| {
+                (1.05..=2.55).contains(&position.longitude())
+                    && (1.25..=3.95).contains(&position.latitude())
+            })
+            .map(|&(_, i)| i)
+            .collect();
+
+        assert!(!expected.is_empty());
+        assert_eq!(found, expected);
+    }
+
+    fn spider(count: usize) -> (Spiderfy, Projector) {
+        let projector = Projector {
+            viewpoint: crate::Viewpoint {
+                position: Geodetic::new(2.35, 48.85).as_mercator(),
+                zoom: crate::Zoom::try_from(19.0).unwrap(),
+            },
+            bounds: Rectangle::new(Point::ORIGIN, Size::new(800.0, 600.0)),
+        };
+        let spider = Spiderfy {
+            center: projector.viewpoint.position,
+            members: (0..count).collect(),
+            radius: 4.0,
+        };
+        (spider, projector)
+    }
+
+    #[test]
+    fn spiderfied_markers_do_not_overlap() {
+        for count in [2, 8, 9, 50] {
+            let (spider, projector) = spider(count);
+            let points: Vec<_> = spider.positions(&projector).map(|(_, p)| p).collect();
+            assert_eq!(points.len(), count);
+
+            for (i, a) in points.iter().enumerate() {
+                for b in &points[i + 1..] {
+                    assert!(a.distance(*b) > 8.0, "{count} markers overlap");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn spiderfied_markers_are_hit() {
+        let (spider, projector) = spider(12);
+        let (index, point) = spider.positions(&projector).nth(5).unwrap();
+
+        assert_eq!(spider.hit(&projector, point), Some(index));
+        assert!(spider.contains(&projector, point));
+        assert!(!spider.contains(&projector, Point::ORIGIN));
+    }
+
+    #[test]
+    fn store_animates_changes() {
+        let start = Instant::now();
+        let later = start + Duration::from_secs(1);
+        let mut store = MarkerStore::new().transition(Duration::from_millis(100));
+
+        store.insert(1, Geodetic::new(2.35, 48.85), "a", start);
+        store.insert(2, Geodetic::new(-0.13, 51.51), "b", start);
+        assert!(store.is_animating(start));
+        assert!(!store.is_animating(later));
+
+        let projector = Projector {
+            viewpoint: crate::Viewpoint {
+                position: Geodetic::new(2.35, 48.85).as_mercator(),
+                zoom: crate::Zoom::try_from(10.0).unwrap(),
+            },
+            bounds: Rectangle::new(Point::ORIGIN, Size::new(800.0, 600.0)),
+        };
+        store.prune(later);
+        let visible = store.layer(later).visible(&projector);
+        assert_eq!(visible.len(), 1);
+        assert_eq!(*visible[0].0, 1);
+
+        // The moved marker glides into view
+        store.move_to(&2, Geodetic::new(2.36, 48.85), later);
+        let halfway = later + Duration::from_millis(50);
+        assert!(store.is_animating(halfway));
+        let settled = later + Duration::from_millis(100);
+        assert_eq!(store.layer(settled).visible(&projector).len(), 2);
+
+        // The removed marker shrinks out before it is forgotten
+        store.remove(&1, settled);
+        assert_eq!(store.len(), 1);
+        assert!(store.get(&1).is_none());
+        store.prune(settled + Duration::from_millis(50));
+        assert_eq!(store.markers.len(), 2);
+        store.prune(later + Duration::from_secs(1));
+        assert_eq!(store.markers.len(), 1);
+        assert!(store.cells.values().all(|ids| !ids.contains(&1)));
+    }
+}
@@ -0,0 +1,244 @@
+//! A tool for measuring the bearing and distance between two points, as used for planning
+//! in aviation and marine navigation.
+//!
+//! The first click places the start, after which a line follows the cursor along with a
+//! readout of the bearing and distance, until the second click places the end.
+
+use iced::widget::canvas::{self, Frame, Path, Stroke, stroke};
+use iced::{Color, Point, Vector, keyboard, mouse};
+
+use crate::{Action, Geodetic, Projector};
+
+/// The number of segments of the great circle line between the two points.
+const LINE_SEGMENTS: usize = 64;
+
+/// Meters per nautical mile.
+const NAUTICAL_MILE: f64 = 1852.0;
+
+/// The message that the [`BearingTool`] uses to update.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BearingMessage {
+    /// Place the start, or the end if the start has been placed.
+    Click(Geodetic),
+    /// The cursor moved while choosing the end.
+    Hover(Geodetic),
+    Clear,
+}
+
+/// The bearing and distance between two points.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Measurement {
+    pub from: Geodetic,
+    pub to: Geodetic,
+    /// The initial bearing of the great circle, in degrees clockwise from north.
+    pub bearing: f64,
+    /// The great circle distance in meters.
+    pub distance: f64,
+}
+
+impl Measurement {
+    pub fn new(from: Geodetic, to: Geodetic) -> Self {
+        Self {
+            from,
+            to,
+            bearing: from.bearing_to(to),
+            distance: from.distance_to(to),
+        }
+    }
+
+    /// The bearing along with the distance in both kilometers and nautical miles.
+    pub fn readout(&self) -> String {
+        let distance = if self.distance < 1000.0 {
+            format!("{:.0} m", self.distance)
+        } else {
+            format!("{:.2} km", self.distance / 1000.0)
+        };
+
+        format!(
+            "{:05.1}°  {distance}  {:.2} NM",
+            self.bearing,
+            self.distance / NAUTICAL_MILE
+        )
+    }
+}
+
+/// Holds the points of the measurement. Glue [`BearingTool::update`] into the application,
+/// and use the [`BearingLayer`] from [`BearingTool::layer`] for drawing and interaction.
+#[derive(Debug, Clone, Default)]
+pub struct BearingTool {
+    start: Option<Geodetic>,
+    end: Option<Geodetic>,
+    cursor: Option<Geodetic>,
+}
+
+impl BearingTool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, message: BearingMessage) {
+        match message {
+            BearingMessage::Click(position) => match (self.start, self.end) {
+                (Some(_), None) => self.end = Some(position),
+                // Start over with a new measurement
+                _ => {
+                    self.start = Some(position);
+                    self.end = None;
+                    self.cursor = Some(position);
+                }
+            },
+            BearingMessage::Hover(position) => self.cursor = Some(position),
+            BearingMessage::Clear => *self = Self::default(),
+        }
+    }
+
+    /// Whether the start has been placed, and the end is being chosen.
+    pub fn is_choosing(&self) -> bool {
+        self.start.is_some() && self.end.is_none()
+    }
+
+    /// The completed measurement, or the live one towards the cursor while choosing the end.
+    pub fn measurement(&self) -> Option<Measurement> {
+        let start = self.start?;
+        let end = self.end.or(self.cursor)?;
+        Some(Measurement::new(start, end))
+    }
+
+    pub fn layer(&self) -> BearingLayer<'_> {
+        BearingLayer {
+            tool: self,
+            color: Color::from_rgb(0.9, 0.3, 0.1),
+        }
+    }
+}
+
+/// Draws the measurement of a [`BearingTool`], and places its points.
+#[derive(Debug, Clone)]
+pub struct BearingLayer<'a> {
+    tool: &'a BearingTool,
+    color: Color,
+}
+
+impl<'a> BearingLayer<'a> {
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn draw(&self, projector: &Projector, frame: &mut Frame<iced::Renderer>) {
+        let Some(measurement) = self.tool.measurement() else {
+            return;
+        };
+
+        // Follow the great circle, which is curved on the map
+        let path = Path::new(|builder| {
+            for i in 0..=LINE_SEGMENTS {
+                let distance = measurement.distance * i as f64 / LINE_SEGMENTS as f64;
+                let point = projector.geodetic_into_screen_space(
+                    measurement.from.destination(measurement.bearing, distance),
+                );
+
+                if i == 0 {
+                    builder.move_to(point);
+                } else {
+                    builder.line_to(point);
+                }
+            }
+        });
+
+        // The line is dashed while it follows the cursor
+        let dashes = [8.0, 6.0];
+        let mut line = Stroke::default().with_color(self.color).with_width(2.5);
+        if self.tool.is_choosing() {
+            line.line_dash = stroke::LineDash {
+                segments: &dashes,
+                offset: 0,
+            };
+        }
+        frame.stroke(&path, line);
+
+        let from = projector.geodetic_into_screen_space(measurement.from);
+        let to = projector.geodetic_into_screen_space(measurement.to);
+        for point in [from, to] {
+            let circle = Path::circle(point, 5.0);
+            frame.fill(&circle, self.color);
+            frame.stroke(
+                &circle,
+                Stroke::default().with_color(Color::WHITE).with_width(2.0),
+            );
+        }
+
+        frame.fill_text(canvas::Text {
+            content: measurement.readout(),
+            position: to + Vector::new(10.0, -10.0),
+            color: Color::BLACK,
+            size: 14.0.into(),
+            ..Default::default()
+        });
+    }
+
+    /// Place the points with the left mouse button, and clear them with the right mouse
+    /// button or escape. Clicks are captured, such that the map does not pan.
+    pub fn interact(
+        &self,
+        projector: &Projector,
+        cursor: &mouse::Cursor,
+        event: &canvas::Event,
+    ) -> Action<BearingMessage> {
+        let position = |point: Point| projector.screen_space_into_geodetic(point);
+
+        match event {
+            canvas::Event::Keyboard(keyboard::Event::KeyPressed {
+                key: keyboard::Key::Named(keyboard::key::Named::Escape),
+                ..
+            }) => Action::Publish(BearingMessage::Clear),
+            canvas::Event::Mouse(event) => {
+                let mouse::Cursor::Available(cursor) = *cursor else {
+                    return Action::None;
+                };
+
+                match event {
+                    mouse::Event::ButtonPressed(mouse::Button::Left) => {
+                        Action::Capture(BearingMessage::Click(position(cursor)))
+                    }
+                    mouse::Event::ButtonPressed(mouse::Button::Right) => {
+                        Action::Capture(BearingMessage::Clear)
+                    }
+                    mouse::Event::CursorMoved { .. } if self.tool.is_choosing() => {
+                        Action::Publish(BearingMessage::Hover(position(cursor)))
+                    }
+                    _ => Action::None,
+                }
+            }
+            _ => Action::None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::location;
+
+    #[test]
+    fn two_clicks_measure() {
+        let mut tool = BearingTool::new();
+        assert_eq!(tool.measurement(), None);
+
+        tool.update(BearingMessage::Click(location::paris()));
+        tool.update(BearingMessage::Hover(location::berlin()));
+        assert!(tool.is_choosing());
+        assert_eq!(tool.measurement().unwrap().to, location::berlin());
+
+        tool.update(BearingMessage::Click(location::london()));
+        tool.update(BearingMessage::Hover(location::rome()));
+        let measurement = tool.measurement().unwrap();
+        assert!(!tool.is_choosing());
+        assert_eq!(measurement.to, location::london());
+        assert!((280.0..340.0).contains(&measurement.bearing));
+
+        // A third click starts over
+        tool.update(BearingMessage::Click(location::rome()));
+        assert_eq!(tool.measurement().unwrap().from, location::rome());
+    }
+}
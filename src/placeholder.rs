@@ -0,0 +1,91 @@
+//! Blurred placeholders for tiles which are only covered by a distant ancestor.
+//!
+//! Stretching an ancestor several zoom levels makes its pixels very apparent. Instead, the
+//! part of the ancestor covering the missing tile is cropped out and blurred, which gives
+//! the familiar "blur-up" look while the actual tile is loading.
+
+use iced_core::image::Handle;
+use image::{RgbaImage, imageops};
+
+use crate::tile_coord::TileCoord;
+
+/// Placeholders are used when the closest available ancestor is at least this many zoom
+/// levels above the missing tile.
+pub(crate) const BLUR_UP_LEVELS: u8 = 2;
+
+/// The width and height of the blurred placeholder images.
+const PLACEHOLDER_SIZE: u32 = 64;
+
+/// The standard deviation of the blur, in pixels of the placeholder image.
+const PLACEHOLDER_SIGMA: f32 = 2.5;
+
+/// Create a blurred placeholder for `tile_id` from the image of one of its ancestors.
+pub(crate) fn blur_up(ancestor: TileCoord, handle: &Handle, tile_id: TileCoord) -> Option<Handle> {
+    let levels = tile_id.zoom().checked_sub(ancestor.zoom())?;
+    let image = match handle {
+        Handle::Rgba {
+            width,
+            height,
+            pixels,
+            ..
+        } => RgbaImage::from_raw(*width, *height, pixels.to_vec())?,
+        Handle::Bytes(_, bytes) => image::load_from_memory(bytes).ok()?.into_rgba8(),
+        Handle::Path(_, path) => image::open(path).ok()?.into_rgba8(),
+    };
+
+    // The part of the ancestor which the tile covers
+    let (x, y) = tile_id.x_y();
+    let (ancestor_x, ancestor_y) = ancestor.x_y();
+    let scale = 1 << levels;
+    let (width, height) = (image.width() / scale, image.height() / scale);
+    let crop = imageops::crop_imm(
+        &image,
+        (x - (ancestor_x << levels)) * width,
+        (y - (ancestor_y << levels)) * height,
+        width.max(1),
+        height.max(1),
+    )
+    .to_image();
+
+    let resized = imageops::resize(
+        &crop,
+        PLACEHOLDER_SIZE,
+        PLACEHOLDER_SIZE,
+        imageops::FilterType::Triangle,
+    );
+    let blurred = imageops::blur(&resized, PLACEHOLDER_SIGMA);
+
+    Some(Handle::from_rgba(
+        blurred.width(),
+        blurred.height(),
+        blurred.into_raw(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crops_the_covered_quadrant() {
+        // An ancestor which is red on the left half and blue on the right half
+        let mut image = RgbaImage::new(256, 256);
+        for (x, _, pixel) in image.enumerate_pixels_mut() {
+            *pixel = if x < 128 {
+                image::Rgba([255, 0, 0, 255])
+            } else {
+                image::Rgba([0, 0, 255, 255])
+            };
+        }
+        let handle = Handle::from_rgba(256, 256, image.into_raw());
+
+        let ancestor = TileCoord::new(1, 1, 2);
+        let right = TileCoord::new(7, 5, 4);
+        let placeholder = blur_up(ancestor, &handle, right).unwrap();
+
+        let Handle::Rgba { pixels, .. } = placeholder else {
+            panic!("The placeholder should be decoded");
+        };
+        assert!(pixels.chunks(4).all(|pixel| pixel[2] > 0 && pixel[0] == 0));
+    }
+}
@@ -0,0 +1,66 @@
+//! The parts of fetching tiles which differ between native and web builds. The browser has
+//! no tokio runtime to drive its timers, and no file system for the disk cache.
+
+use std::{future::Future, time::Duration};
+
+/// Wait for some time to pass.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) async fn sleep(duration: Duration) {
+    tokio::time::sleep(duration).await;
+}
+
+/// Wait for some time to pass, with a timer of the browser.
+#[cfg(target_arch = "wasm32")]
+pub(crate) async fn sleep(duration: Duration) {
+    let millis = duration.as_millis().min(i32::MAX as u128) as i32;
+    let timer = js_sys::Promise::new(&mut |resolve, _| {
+        if let Some(window) = web_sys::window() {
+            let _ = window.set_timeout_with_callback_and_timeout_and_arguments_0(&resolve, millis);
+        }
+    });
+    let _ = wasm_bindgen_futures::JsFuture::from(timer).await;
+}
+
+/// Wait for a future, unless it takes longer than the given time.
+pub(crate) async fn timeout<F: Future>(duration: Duration, future: F) -> Option<F::Output> {
+    let future = std::pin::pin!(future);
+    let timer = std::pin::pin!(sleep(duration));
+    match iced::futures::future::select(future, timer).await {
+        iced::futures::future::Either::Left((output, _)) => Some(output),
+        iced::futures::future::Either::Right(_) => None,
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) use tokio::fs;
+
+/// The file system of the standard library, which fails with [`std::io::ErrorKind::Unsupported`]
+/// in the browser, such that the disk cache and local files are skipped.
+#[cfg(target_arch = "wasm32")]
+pub(crate) mod fs {
+    use std::{io, path::Path};
+
+    pub async fn read(path: impl AsRef<Path>) -> io::Result<Vec<u8>> {
+        std::fs::read(path)
+    }
+
+    pub async fn read_to_string(path: impl AsRef<Path>) -> io::Result<String> {
+        std::fs::read_to_string(path)
+    }
+
+    pub async fn write(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> io::Result<()> {
+        std::fs::write(path, contents)
+    }
+
+    pub async fn create_dir_all(path: impl AsRef<Path>) -> io::Result<()> {
+        std::fs::create_dir_all(path)
+    }
+
+    pub async fn remove_file(path: impl AsRef<Path>) -> io::Result<()> {
+        std::fs::remove_file(path)
+    }
+
+    pub async fn metadata(path: impl AsRef<Path>) -> io::Result<std::fs::Metadata> {
+        std::fs::metadata(path)
+    }
+}
@@ -6,12 +6,26 @@
 use crate::{map_widget::BASE_SIZE, tile_coord::TileCoord};
 use std::f64::consts::PI;
 
+pub(crate) mod maidenhead;
+pub(crate) mod mgrs;
+mod plus_code;
+pub(crate) mod utm;
+
+pub use maidenhead::InvalidLocator;
+pub use mgrs::InvalidMgrs;
+pub use plus_code::InvalidPlusCode;
+pub use utm::{InvalidUtm, Utm, UtmZone};
+
+/// Mean radius of the earth in meters.
+pub(crate) const EARTH_RADIUS: f64 = 6_371_008.8;
+
 pub(crate) fn total_tiles(zoom: u8) -> u32 {
     2u32.pow(zoom as u32)
 }
 
 /// A position on the 2D mercator map projection.
-/// Values range from `[-1 .. =1]` in both x (east) and y (south) directions.
+/// Values range from `[-1 .. =1]` in both x (east) and y (south) directions within the
+/// map. Positions outside of the map are kept as is, unless explicitly clamped.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Mercator {
     x: f64,
@@ -20,12 +34,22 @@ pub struct Mercator {
 
 impl Mercator {
     pub const fn new(east: f64, north: f64) -> Self {
+        Self { x: east, y: north }
+    }
+
+    /// Clamp the position to the bounds of the map.
+    pub const fn clamped(&self) -> Self {
         Self {
-            x: east.clamp(-1., 1.),
-            y: north.clamp(-1., 1.),
+            x: self.x.clamp(-1., 1.),
+            y: self.y.clamp(-1., 1.),
         }
     }
 
+    /// Whether the position is within the bounds of the map.
+    pub fn is_within_map(&self) -> bool {
+        (-1.0..=1.0).contains(&self.x) && (-1.0..=1.0).contains(&self.y)
+    }
+
     pub fn as_geodetic(&self) -> Geodetic {
         Geodetic::new(
             (self.x * PI).to_degrees(),
@@ -54,12 +78,13 @@ impl Mercator {
         )
     }
 
-    /// Add the first argument and subtract the second.
+    /// Add the first argument and subtract the second, staying within the map.
     pub(crate) fn add_sub(&mut self, add: Self, sub: Self) {
         *self = Mercator::new(
             add.east_x() - sub.east_x() + self.east_x(),
             add.south_y() - sub.south_y() + self.south_y(),
-        );
+        )
+        .clamped();
     }
 
     /// Get the tile at this position for the given zoom.
@@ -76,8 +101,15 @@ impl Mercator {
     }
 }
 
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+#[error("invalid longitude or latitude")]
+pub struct InvalidGeodetic;
+
 /// A position on a sphere consisting of longitude and latitude components,
-/// ranging from `[-180 .. =180]`  and `[-85.05 .. =85.05]` respectively.
+/// ranging from `[-180 .. =180]`  and `[-90 .. =90]` respectively.
+///
+/// The map itself only covers latitudes up to [`Geodetic::MAX_LATITUDE`]. Positions closer
+/// to the poles are kept as is, and are projected outside of the map.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Geodetic {
     lon: f64,
@@ -85,10 +117,35 @@ pub struct Geodetic {
 }
 
 impl Geodetic {
+    /// The latitude at which the Web Mercator projection is cut off, making the map square.
+    pub const MAX_LATITUDE: f64 = 85.051_128_779_806_59;
+
     pub const fn new(lon: f64, lat: f64) -> Self {
+        Self { lon, lat }
+    }
+
+    /// Create a position, failing if the longitude or latitude is out of range.
+    pub fn try_new(lon: f64, lat: f64) -> Result<Self, InvalidGeodetic> {
+        if (-180.0..=180.0).contains(&lon) && (-90.0..=90.0).contains(&lat) {
+            Ok(Self { lon, lat })
+        } else {
+            Err(InvalidGeodetic)
+        }
+    }
+
+    /// Clamp the latitude to the part of the globe which is covered by the map.
+    pub const fn clamped(&self) -> Self {
         Self {
-            lon: lon.clamp(-180., 180.),
-            lat: lat.clamp(-85.05, 85.05),
+            lon: self.lon,
+            lat: self.lat.clamp(-Self::MAX_LATITUDE, Self::MAX_LATITUDE),
+        }
+    }
+
+    /// Wrap the longitude into `[-180 .. 180)`.
+    pub fn wrapped(&self) -> Self {
+        Self {
+            lon: (self.lon + 180.0).rem_euclid(360.0) - 180.0,
+            lat: self.lat,
         }
     }
 
@@ -114,6 +171,247 @@ impl Geodetic {
     pub fn into_pixel_space(&self, zoom: f64) -> iced::Point<f64> {
         self.as_mercator().into_pixel_space(zoom)
     }
+
+    /// The great-circle distance in meters to another coordinate, using the
+    /// [haversine formula](https://en.wikipedia.org/wiki/Haversine_formula).
+    pub fn distance_to(&self, other: Geodetic) -> f64 {
+        let (lat1, lat2) = (self.lat.to_radians(), other.lat.to_radians());
+        let d_lat = lat2 - lat1;
+        let d_lon = (other.lon - self.lon).to_radians();
+
+        let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+        2.0 * EARTH_RADIUS * a.sqrt().min(1.0).asin()
+    }
+
+    /// The initial bearing in degrees of the great circle to another coordinate, clockwise
+    /// from north in `[0 .. 360)`.
+    pub fn bearing_to(&self, other: Geodetic) -> f64 {
+        let (lat1, lat2) = (self.lat.to_radians(), other.lat.to_radians());
+        let d_lon = (other.lon - self.lon).to_radians();
+
+        let y = d_lon.sin() * lat2.cos();
+        let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * d_lon.cos();
+        y.atan2(x).to_degrees().rem_euclid(360.0)
+    }
+
+    /// The coordinate reached by traveling `distance` meters along the great circle with
+    /// the initial `bearing` in degrees, clockwise from north.
+    pub fn destination(&self, bearing: f64, distance: f64) -> Geodetic {
+        let (lat, lon) = (self.lat.to_radians(), self.lon.to_radians());
+        let bearing = bearing.to_radians();
+        let angle = distance / EARTH_RADIUS;
+
+        let dest_lat = (lat.sin() * angle.cos() + lat.cos() * angle.sin() * bearing.cos()).asin();
+        let dest_lon = lon
+            + (bearing.sin() * angle.sin() * lat.cos())
+                .atan2(angle.cos() - lat.sin() * dest_lat.sin());
+
+        Geodetic::new(dest_lon.to_degrees(), dest_lat.to_degrees()).wrapped()
+    }
+
+    /// The [UTM](https://en.wikipedia.org/wiki/Universal_Transverse_Mercator_coordinate_system)
+    /// coordinate of this position, in the zone it falls within.
+    pub fn to_utm(&self) -> Utm {
+        Utm::from_geodetic(*self)
+    }
+
+    /// The position of a UTM coordinate, failing if the zone does not exist or the easting
+    /// and northing are far outside of it.
+    pub fn from_utm(zone: UtmZone, easting: f64, northing: f64) -> Result<Self, InvalidUtm> {
+        Ok(Utm::try_new(zone, easting, northing)?.to_geodetic())
+    }
+
+    /// The [Maidenhead locator](https://en.wikipedia.org/wiki/Maidenhead_Locator_System) of
+    /// the cell containing this position, with 1 to 4 pairs of characters, e.g. `JN18eu`
+    /// for three pairs.
+    pub fn to_maidenhead(&self, pairs: usize) -> String {
+        maidenhead::encode(*self, pairs)
+    }
+
+    /// The center of the cell of a Maidenhead locator.
+    pub fn from_maidenhead(locator: &str) -> Result<Self, InvalidLocator> {
+        maidenhead::decode(locator)
+    }
+
+    /// The [MGRS](https://en.wikipedia.org/wiki/Military_Grid_Reference_System) reference
+    /// of this position with `digits` digits for each of the easting and northing, from 0
+    /// for the 100 km square to 5 for a precision of one meter, e.g. `31UDQ4825111932`.
+    ///
+    /// Returns `None` outside of the latitudes 80°S to 84°N covered by the grid.
+    pub fn to_mgrs(&self, digits: usize) -> Option<String> {
+        mgrs::encode(*self, digits)
+    }
+
+    /// The center of the square of an MGRS reference, which may contain whitespace.
+    pub fn from_mgrs(reference: &str) -> Result<Self, InvalidMgrs> {
+        mgrs::decode(reference)
+    }
+
+    /// The [Plus Code](https://maps.google.com/pluscodes/) of the area containing this
+    /// position, with 10 digits for an area of about 14 meters across, e.g. `8FW4V75V+8Q`.
+    /// Each digit past 10 makes the area about 5 times smaller, up to 15 digits.
+    pub fn to_plus_code(&self, length: usize) -> String {
+        plus_code::encode(*self, length)
+    }
+
+    /// The center of the area of a full Plus Code.
+    pub fn from_plus_code(code: &str) -> Result<Self, InvalidPlusCode> {
+        plus_code::decode(code)
+    }
+
+    /// The center of the area of a Plus Code which may be shortened, e.g. `V75V+8Q`, in
+    /// which case the area nearest to the reference position is used.
+    pub fn recover_plus_code(code: &str, reference: Geodetic) -> Result<Self, InvalidPlusCode> {
+        plus_code::decode_near(code, reference)
+    }
+
+    /// Recognize a position typed into a search box, as either decimal degrees with the
+    /// latitude first, a Plus Code, an MGRS reference, a UTM coordinate or a Maidenhead
+    /// locator.
+    ///
+    /// Shortened Plus Codes are recovered near the reference position, typically the center
+    /// of the map, and any locality following them is ignored.
+    pub fn recognize(query: &str, reference: Geodetic) -> Option<Self> {
+        let query = query.trim();
+        let words = query
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<_>>();
+
+        if let [lat, lon] = words[..]
+            && let (Ok(lat), Ok(lon)) = (lat.parse(), lon.parse())
+        {
+            return Self::try_new(lon, lat).ok();
+        }
+
+        if let Some(code) = words.first().filter(|word| word.contains('+')) {
+            return Self::recover_plus_code(code, reference).ok();
+        }
+
+        if let [zone, easting, northing] = words[..]
+            && let (Ok(zone), Ok(easting), Ok(northing)) =
+                (zone.parse(), easting.parse(), northing.parse())
+        {
+            return Self::from_utm(zone, easting, northing).ok();
+        }
+
+        Self::from_mgrs(query)
+            .ok()
+            .or_else(|| Self::from_maidenhead(query).ok())
+    }
+
+    /// Write this position in some format. Positions beyond the latitudes covered by MGRS
+    /// fall back to decimal degrees.
+    pub fn format(&self, format: CoordinateFormat) -> String {
+        let decimal = || format!("{:.5}, {:.5}", self.lat, self.lon);
+        match format {
+            CoordinateFormat::Decimal => decimal(),
+            CoordinateFormat::Utm => self.to_utm().to_string(),
+            CoordinateFormat::Mgrs => {
+                self.to_mgrs(mgrs::MAX_DIGITS)
+                    .map_or_else(decimal, |reference| {
+                        // Group the zone, square, easting and northing for legibility
+                        let split = reference.len() - 2 * mgrs::MAX_DIGITS;
+                        let (zone, square) = reference[..split].split_at(split - 2);
+                        let (easting, northing) = reference[split..].split_at(mgrs::MAX_DIGITS);
+                        format!("{zone} {square} {easting} {northing}")
+                    })
+            }
+            CoordinateFormat::Maidenhead => self.to_maidenhead(3),
+            CoordinateFormat::PlusCode => self.to_plus_code(10),
+        }
+    }
+}
+
+/// How a position is written in a coordinate readout, using [`Geodetic::format`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CoordinateFormat {
+    /// The latitude and longitude in decimal degrees, e.g. `48.85820, 2.29450`.
+    #[default]
+    Decimal,
+    /// The UTM zone, easting and northing, e.g. `31N 448252 5411933`.
+    Utm,
+    /// The MGRS reference with a precision of one meter, e.g. `31U DQ 48251 11932`.
+    Mgrs,
+    /// The Maidenhead locator of the subsquare, e.g. `JN18du`.
+    Maidenhead,
+    /// The Plus Code with 10 digits, e.g. `8FW4V75V+8Q`.
+    PlusCode,
+}
+
+impl CoordinateFormat {
+    pub const ALL: [Self; 5] = [
+        Self::Decimal,
+        Self::Utm,
+        Self::Mgrs,
+        Self::Maidenhead,
+        Self::PlusCode,
+    ];
+}
+
+impl std::fmt::Display for CoordinateFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Decimal => "Decimal",
+            Self::Utm => "UTM",
+            Self::Mgrs => "MGRS",
+            Self::Maidenhead => "Maidenhead",
+            Self::PlusCode => "Plus Code",
+        })
+    }
+}
+
+/// A box of longitudes and latitudes, e.g. the area covered by a regional tile source.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeoBounds {
+    pub west: f64,
+    pub south: f64,
+    pub east: f64,
+    pub north: f64,
+}
+
+impl GeoBounds {
+    pub const fn new(west: f64, south: f64, east: f64, north: f64) -> Self {
+        Self {
+            west,
+            south,
+            east,
+            north,
+        }
+    }
+
+    pub fn contains(&self, position: Geodetic) -> bool {
+        (self.west..=self.east).contains(&position.lon)
+            && (self.south..=self.north).contains(&position.lat)
+    }
+
+    /// Whether any part of a tile is within the bounds.
+    pub fn intersects_tile(&self, tile_id: &crate::TileCoord) -> bool {
+        let (x, y) = tile_id.x_y();
+        let north_west = crate::tile_coord::grid_corner(x, y, tile_id.zoom()).as_geodetic();
+        let south_east = crate::tile_coord::grid_corner(x + 1, y + 1, tile_id.zoom()).as_geodetic();
+
+        north_west.lon < self.east
+            && south_east.lon > self.west
+            && south_east.lat < self.north
+            && north_west.lat > self.south
+    }
+
+    /// The tiles of a zoom level which overlap the bounds.
+    pub(crate) fn tiles(&self, zoom: u8) -> impl Iterator<Item = crate::TileCoord> + use<> {
+        let last = 2u32.pow(zoom as u32) - 1;
+        let north_west = Geodetic::new(self.west, self.north)
+            .as_mercator()
+            .tile_id(zoom);
+        let south_east = Geodetic::new(self.east, self.south)
+            .as_mercator()
+            .tile_id(zoom);
+        let (west, north) = (north_west.x().min(last), north_west.y().min(last));
+        let (east, south) = (south_east.x().min(last), south_east.y().min(last));
+
+        (north..=south)
+            .flat_map(move |y| (west..=east).map(move |x| crate::TileCoord::new(x, y, zoom)))
+    }
 }
 
 pub mod location {
@@ -170,6 +468,112 @@ mod position_tests {
         );
     }
 
+    #[test]
+    fn bounds_intersect_tiles() {
+        // Roughly the Netherlands
+        let bounds = GeoBounds::new(3.2, 50.7, 7.3, 53.6);
+        assert!(bounds.contains(Geodetic::new(4.9, 52.4)));
+        assert!(!bounds.contains(location::paris()));
+        assert_eq!(bounds.tiles(0).count(), 1);
+        assert_eq!(bounds.tiles(6).count(), 4);
+
+        assert!(bounds.intersects_tile(&crate::TileCoord::new(0, 0, 0)));
+        let amsterdam = Geodetic::new(4.9, 52.4);
+        assert!(bounds.intersects_tile(&amsterdam.as_mercator().tile_id(6)));
+        assert!(!bounds.intersects_tile(&location::rome().as_mercator().tile_id(6)));
+    }
+
+    #[test]
+    fn haversine_distance() {
+        // Roughly 344 km between Paris and London
+        let distance = location::paris().distance_to(location::london());
+        approx::assert_relative_eq!(distance, 343_500.0, max_relative = 0.01);
+
+        assert_eq!(location::rome().distance_to(location::rome()), 0.0);
+    }
+
+    #[test]
+    fn destination_roundtrip() {
+        let start = location::berlin();
+        let end = start.destination(45.0, 10_000.0);
+
+        approx::assert_relative_eq!(start.distance_to(end), 10_000.0, max_relative = 1e-6);
+        assert!(end.latitude() > start.latitude());
+        assert!(end.longitude() > start.longitude());
+    }
+
+    #[test]
+    fn bearing() {
+        let start = location::paris();
+        approx::assert_relative_eq!(start.bearing_to(Geodetic::new(2.35, 60.0)), 0.0);
+        approx::assert_relative_eq!(start.bearing_to(Geodetic::new(2.35, 30.0)), 180.0);
+
+        // Travelling along the bearing reaches the other point
+        let end = location::rome();
+        let bearing = start.bearing_to(end);
+        let reached = start.destination(bearing, start.distance_to(end));
+        approx::assert_relative_eq!(reached.distance_to(end), 0.0, epsilon = 1e-3);
+    }
+
+    #[test]
+    fn utm_and_readout() {
+        let eiffel = Geodetic::new(2.294_5, 48.858_2);
+        let utm = eiffel.to_utm();
+        let back = Geodetic::from_utm(utm.zone, utm.easting, utm.northing).unwrap();
+        approx::assert_relative_eq!(back.distance_to(eiffel), 0.0, epsilon = 1e-3);
+        assert_eq!(
+            Geodetic::from_utm(UtmZone::north(61), 500_000.0, 0.0),
+            Err(InvalidUtm)
+        );
+
+        assert_eq!(
+            eiffel.format(CoordinateFormat::Decimal),
+            "48.85820, 2.29450"
+        );
+        assert_eq!(eiffel.format(CoordinateFormat::Utm), "31N 448252 5411933");
+        assert_eq!(eiffel.format(CoordinateFormat::Mgrs), "31U DQ 48251 11932");
+        assert_eq!(eiffel.format(CoordinateFormat::Maidenhead), "JN18du");
+        assert_eq!(eiffel.format(CoordinateFormat::PlusCode), "8FW4V75V+7R");
+        assert_eq!(
+            Geodetic::new(0.0, 85.0).format(CoordinateFormat::Mgrs),
+            "85.00000, 0.00000"
+        );
+    }
+
+    #[test]
+    fn recognize_search_queries() {
+        let eiffel = Geodetic::new(2.294_5, 48.858_2);
+        let near = |query: &str| {
+            let position = Geodetic::recognize(query, location::paris())
+                .unwrap_or_else(|| panic!("{query} was not recognized"));
+            position.distance_to(eiffel)
+        };
+
+        assert!(near("48.8582, 2.2945") < 1.0);
+        assert!(near("48.8582 2.2945") < 1.0);
+        assert!(near("8FW4V75V+7R") < 10.0);
+        assert!(near("V75V+7R Paris") < 10.0);
+        assert!(near("31U DQ 48251 11932") < 2.0);
+        assert!(near("31N 448252 5411933") < 2.0);
+        assert!(near("JN18du") < 5_000.0);
+
+        assert_eq!(Geodetic::recognize("Paris", location::paris()), None);
+        assert_eq!(Geodetic::recognize("95.0, 2.0", location::paris()), None);
+    }
+
+    #[test]
+    fn polar_positions_are_kept() {
+        let pole = Geodetic::new(10.0, 89.0);
+        assert_eq!(pole.latitude(), 89.0);
+        assert!(!pole.as_mercator().is_within_map());
+
+        let clamped = pole.clamped();
+        approx::assert_relative_eq!(clamped.as_mercator().south_y(), -1.0, epsilon = 1e-9);
+
+        assert_eq!(Geodetic::try_new(0.0, 91.0), Err(InvalidGeodetic));
+        assert_eq!(Geodetic::new(190.0, 0.0).wrapped().longitude(), -170.0);
+    }
+
     #[test]
     fn pixel_space_conversion() {
         let position = Mercator::new(1.0, 1.0);
@@ -0,0 +1,106 @@
+//! Maidenhead locators, as used by amateur radio operators, which divide the earth into
+//! fields of 20 by 10 degrees, squares of 2 by 1 degrees, subsquares of 5 by 2.5 minutes,
+//! and extended squares of 30 by 15 seconds.
+
+use super::Geodetic;
+
+/// The number of divisions of each pair of characters, starting with the field.
+const DIVISIONS: [u32; 4] = [18, 10, 24, 10];
+
+/// The most pairs of characters a locator can have.
+pub(crate) const MAX_PAIRS: usize = DIVISIONS.len();
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+#[error("invalid Maidenhead locator")]
+pub struct InvalidLocator;
+
+/// The size in degrees of longitude and latitude of the cells with some number of pairs.
+pub(crate) fn cell_size(pairs: usize) -> (f64, f64) {
+    let divisions = DIVISIONS[..pairs].iter().product::<u32>() as f64;
+    (360.0 / divisions, 180.0 / divisions)
+}
+
+/// The character of a pair of characters at some index, which alternates between letters and
+/// digits, where subsquares are written in lower case.
+fn symbol(pair: usize, value: u32) -> char {
+    match pair {
+        0 => (b'A' + value as u8) as char,
+        2 => (b'a' + value as u8) as char,
+        _ => char::from_digit(value, 10).unwrap_or('0'),
+    }
+}
+
+fn value(pair: usize, symbol: char) -> Option<u32> {
+    let value = match pair % 2 {
+        0 => (symbol.to_ascii_uppercase() as u32).checked_sub('A' as u32)?,
+        _ => symbol.to_digit(10)?,
+    };
+    (value < DIVISIONS[pair]).then_some(value)
+}
+
+pub(crate) fn encode(position: Geodetic, pairs: usize) -> String {
+    let pairs = pairs.clamp(1, MAX_PAIRS);
+    let position = position.wrapped();
+
+    // Offset to positive ranges, staying just within the last field at the edges
+    let mut lon = (position.longitude() + 180.0).clamp(0.0, 360.0 - 1e-9) / 360.0;
+    let mut lat = (position.latitude() + 90.0).clamp(0.0, 180.0 - 1e-9) / 180.0;
+
+    let mut locator = String::with_capacity(pairs * 2);
+    for (pair, &divisions) in DIVISIONS[..pairs].iter().enumerate() {
+        let (x, y) = (
+            (lon * divisions as f64) as u32,
+            (lat * divisions as f64) as u32,
+        );
+        locator.push(symbol(pair, x.min(divisions - 1)));
+        locator.push(symbol(pair, y.min(divisions - 1)));
+
+        lon = lon * divisions as f64 - x as f64;
+        lat = lat * divisions as f64 - y as f64;
+    }
+
+    locator
+}
+
+/// The center of the cell of a locator.
+pub(crate) fn decode(locator: &str) -> Result<Geodetic, InvalidLocator> {
+    let symbols = locator.trim().chars().collect::<Vec<_>>();
+    if symbols.is_empty() || symbols.len() % 2 != 0 || symbols.len() > MAX_PAIRS * 2 {
+        return Err(InvalidLocator);
+    }
+
+    let (mut lon, mut lat) = (-180.0, -90.0);
+    for (pair, chunk) in symbols.chunks(2).enumerate() {
+        let (width, height) = cell_size(pair + 1);
+        lon += value(pair, chunk[0]).ok_or(InvalidLocator)? as f64 * width;
+        lat += value(pair, chunk[1]).ok_or(InvalidLocator)? as f64 * height;
+    }
+
+    let (width, height) = cell_size(symbols.len() / 2);
+    Ok(Geodetic::new(lon + width / 2.0, lat + height / 2.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::location;
+
+    #[test]
+    fn known_locators() {
+        assert_eq!(encode(location::paris(), 3), "JN18eu");
+        assert_eq!(encode(location::london(), 2), "IO91");
+        assert_eq!(encode(Geodetic::new(179.99, 90.0), 1), "RR");
+        assert_eq!(encode(Geodetic::new(-180.0, -90.0), 4), "AA00aa00");
+    }
+
+    #[test]
+    fn decode_cell_center() {
+        let center = decode("jn18EU").unwrap();
+        assert_eq!(encode(center, 3), "JN18eu");
+        assert_eq!(decode("JN"), Ok(Geodetic::new(10.0, 45.0)));
+
+        for invalid in ["", "J", "JN1", "SA", "JNA8", "JN18eu00xx"] {
+            assert_eq!(decode(invalid), Err(InvalidLocator), "{invalid}");
+        }
+    }
+}
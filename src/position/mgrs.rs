@@ -0,0 +1,151 @@
+//! The Military Grid Reference System, which names the 100 km squares of each UTM zone and
+//! latitude band with a pair of letters, followed by the easting and northing within the
+//! square.
+
+use super::Geodetic;
+use super::utm::{self, BANDS, Utm, UtmZone};
+
+/// The column letters of the 100 km squares, repeating every three zones.
+const COLUMNS: [&[u8; 8]; 3] = [b"ABCDEFGH", b"JKLMNPQR", b"STUVWXYZ"];
+/// The row letters of the 100 km squares, repeating every 2000 km.
+const ROWS: &[u8; 20] = b"ABCDEFGHJKLMNPQRSTUV";
+
+/// The size of a square in meters.
+pub(crate) const SQUARE: f64 = 100_000.0;
+
+/// The most digits of each of the easting and northing, at a precision of one meter.
+pub(crate) const MAX_DIGITS: usize = 5;
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+#[error("invalid MGRS reference")]
+pub struct InvalidMgrs;
+
+/// The letters of the 100 km square at some easting and northing in a zone.
+pub(crate) fn square_id(zone: u8, easting: f64, northing: f64) -> [char; 2] {
+    let column = ((easting / SQUARE).floor() as i64 - 1).clamp(0, 7) as usize;
+    let columns = COLUMNS[(zone as usize - 1) % 3];
+
+    // Even zones have their rows offset by five letters
+    let offset = if zone.is_multiple_of(2) { 5 } else { 0 };
+    let row = ((northing / SQUARE).floor() as i64 + offset).rem_euclid(20) as usize;
+
+    [columns[column] as char, ROWS[row] as char]
+}
+
+pub(crate) fn encode(position: Geodetic, digits: usize) -> Option<String> {
+    let band = utm::band(position.latitude())?;
+    let utm = Utm::from_geodetic(position);
+    let [column, row] = square_id(utm.zone.number, utm.easting, utm.northing);
+
+    let digits = digits.min(MAX_DIGITS);
+    let scale = 10f64.powi((MAX_DIGITS - digits) as i32);
+    let easting = (utm.easting.rem_euclid(SQUARE) / scale) as u32;
+    let northing = (utm.northing.rem_euclid(SQUARE) / scale) as u32;
+
+    let mut reference = format!("{}{band}{column}{row}", utm.zone.number);
+    if digits > 0 {
+        reference += &format!("{easting:0digits$}{northing:0digits$}");
+    }
+    Some(reference)
+}
+
+/// The center of the square of a reference, at the precision it is given with.
+pub(crate) fn decode(reference: &str) -> Result<Geodetic, InvalidMgrs> {
+    let reference = reference
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_ascii_uppercase();
+
+    let split = reference
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or(InvalidMgrs)?;
+    let zone = reference[..split].parse::<u8>().map_err(|_| InvalidMgrs)?;
+    if !(1..=60).contains(&zone) {
+        return Err(InvalidMgrs);
+    }
+
+    let mut letters = reference[split..].bytes();
+    let (Some(band), Some(column), Some(row)) = (letters.next(), letters.next(), letters.next())
+    else {
+        return Err(InvalidMgrs);
+    };
+    let band = BANDS.iter().position(|&b| b == band).ok_or(InvalidMgrs)?;
+    let column = COLUMNS[(zone as usize - 1) % 3]
+        .iter()
+        .position(|&c| c == column)
+        .ok_or(InvalidMgrs)?;
+    let row = ROWS.iter().position(|&r| r == row).ok_or(InvalidMgrs)?;
+
+    let numbers = &reference[split + 3..];
+    let digits = numbers.len() / 2;
+    if numbers.len() % 2 != 0 || digits > MAX_DIGITS || !numbers.bytes().all(|b| b.is_ascii_digit())
+    {
+        return Err(InvalidMgrs);
+    }
+    let scale = 10f64.powi((MAX_DIGITS - digits) as i32);
+    let parse =
+        |digits: &str| digits.parse::<f64>().map_or(0.0, |value| value * scale) + scale / 2.0;
+
+    let offset = if zone.is_multiple_of(2) { 5 } else { 0 };
+    let easting = (column + 1) as f64 * SQUARE + parse(&numbers[..digits]);
+    let mut northing =
+        (row as i64 - offset).rem_euclid(20) as f64 * SQUARE + parse(&numbers[digits..]);
+
+    // The rows repeat every 2000 km, so pick the first repetition north of the southern
+    // edge of the latitude band
+    let south = -80.0 + band as f64 * 8.0;
+    let north = south >= 0.0;
+    let edge = Utm::from_geodetic_in_zone(Geodetic::new(utm::central_meridian(zone), south), zone)
+        .northing;
+    while northing < edge - SQUARE {
+        northing += 20.0 * SQUARE;
+    }
+
+    Ok(Utm {
+        zone: UtmZone {
+            number: zone,
+            north,
+        },
+        easting,
+        northing,
+    }
+    .to_geodetic())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::location;
+
+    #[test]
+    fn known_references() {
+        // The Eiffel tower
+        assert_eq!(
+            encode(Geodetic::new(2.294_5, 48.858_2), 5).as_deref(),
+            Some("31UDQ4825111932")
+        );
+        assert_eq!(encode(location::berlin(), 0).as_deref(), Some("33UUU"));
+        assert_eq!(encode(Geodetic::new(0.0, 85.0), 5), None);
+    }
+
+    #[test]
+    fn roundtrip() {
+        for position in [
+            location::paris(),
+            location::vienna(),
+            Geodetic::new(-70.6, -33.45),
+            Geodetic::new(151.2, -33.87),
+            Geodetic::new(18.0, 78.2),
+        ] {
+            let reference = encode(position, 5).unwrap();
+            let back = decode(&reference).unwrap();
+            assert!(position.distance_to(back) < 2.0, "{reference}");
+            assert_eq!(encode(back, 5), Some(reference));
+        }
+
+        for invalid in ["", "31", "61UDQ", "31IDQ", "31UDI", "31UDQ123"] {
+            assert_eq!(decode(invalid), Err(InvalidMgrs), "{invalid}");
+        }
+    }
+}
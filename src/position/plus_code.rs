@@ -0,0 +1,246 @@
+//! [Open Location Codes](https://github.com/google/open-location-code), also known as Plus
+//! Codes, which name areas down to a few meters across with a short string of characters,
+//! e.g. `8FW4V75V+8Q` for the Eiffel tower.
+//!
+//! Codes may be shortened by leaving out the first characters, e.g. `V75V+8Q`, in which case
+//! they are recovered from a nearby reference position.
+
+use super::Geodetic;
+
+const ALPHABET: &[u8; 20] = b"23456789CFGHJMPQRVWX";
+const SEPARATOR: char = '+';
+const PADDING: char = '0';
+
+/// The position of the separator in a full code.
+const SEPARATOR_POSITION: usize = 8;
+/// The number of digits encoded in pairs of latitude and longitude digits.
+const PAIR_LENGTH: usize = 10;
+/// The most digits of a code, where the digits past the pairs each refine a 4 by 5 grid.
+const MAX_LENGTH: usize = 15;
+const GRID_COLUMNS: i64 = 4;
+const GRID_ROWS: i64 = 5;
+
+/// The resolution of the first pair of digits in degrees.
+const FIRST_PAIR: f64 = 20.0;
+
+/// The digits of the full length code per degree of latitude and longitude.
+const LAT_PRECISION: f64 = 8000.0 * 3125.0;
+const LON_PRECISION: f64 = 8000.0 * 1024.0;
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+#[error("invalid Plus Code")]
+pub struct InvalidPlusCode;
+
+fn value(digit: char) -> Option<i64> {
+    ALPHABET
+        .iter()
+        .position(|&c| c as char == digit.to_ascii_uppercase())
+        .map(|value| value as i64)
+}
+
+/// The code of the area containing a position, with 2 to 15 digits where lengths below 10
+/// must be even.
+pub(crate) fn encode(position: Geodetic, length: usize) -> String {
+    let length = if length < PAIR_LENGTH {
+        length.max(2) & !1
+    } else {
+        length.min(MAX_LENGTH)
+    };
+
+    // Work in integers of the finest grid, such that the digits are exact
+    let scaled = |degrees: f64, precision: f64| ((degrees * precision * 1e6).round() / 1e6).floor();
+    let mut lat = (scaled(position.latitude().clamp(-90.0, 90.0) + 90.0, LAT_PRECISION) as i64)
+        .min((180.0 * LAT_PRECISION) as i64 - 1);
+    let mut lon = (scaled(position.longitude() + 180.0, LON_PRECISION) as i64)
+        .rem_euclid((360.0 * LON_PRECISION) as i64);
+
+    let mut digits = [0u8; MAX_LENGTH];
+    for digit in digits[PAIR_LENGTH..].iter_mut().rev() {
+        *digit = ALPHABET[((lat % GRID_ROWS) * GRID_COLUMNS + lon % GRID_COLUMNS) as usize];
+        lat /= GRID_ROWS;
+        lon /= GRID_COLUMNS;
+    }
+    for pair in digits[..PAIR_LENGTH].chunks_mut(2).rev() {
+        pair[0] = ALPHABET[(lat % 20) as usize];
+        pair[1] = ALPHABET[(lon % 20) as usize];
+        lat /= 20;
+        lon /= 20;
+    }
+
+    let mut code = String::with_capacity(MAX_LENGTH + 1);
+    for (i, &digit) in digits
+        .iter()
+        .enumerate()
+        .take(length.max(SEPARATOR_POSITION))
+    {
+        if i == SEPARATOR_POSITION {
+            code.push(SEPARATOR);
+        }
+        code.push(if i < length { digit as char } else { PADDING });
+    }
+    if length <= SEPARATOR_POSITION {
+        code.push(SEPARATOR);
+    }
+
+    code
+}
+
+/// The digits of a valid code, without the separator and padding, and the position of the
+/// separator.
+fn digits(code: &str) -> Result<(Vec<i64>, usize), InvalidPlusCode> {
+    let code = code.trim();
+    let separator = code.find(SEPARATOR).ok_or(InvalidPlusCode)?;
+    if separator > SEPARATOR_POSITION || separator % 2 != 0 || code.matches(SEPARATOR).count() > 1 {
+        return Err(InvalidPlusCode);
+    }
+
+    let (before, after) = (&code[..separator], &code[separator + 1..]);
+    if after.len() == 1 || after.len() > MAX_LENGTH - SEPARATOR_POSITION {
+        return Err(InvalidPlusCode);
+    }
+
+    // Padding fills up the digits before the separator in pairs, with nothing after
+    let padded = before.trim_end_matches(PADDING);
+    let padding = before.len() - padded.len();
+    if padding > 0
+        && (separator != SEPARATOR_POSITION
+            || padding % 2 != 0
+            || padded.is_empty()
+            || !after.is_empty())
+    {
+        return Err(InvalidPlusCode);
+    }
+
+    let digits = padded
+        .chars()
+        .chain(after.chars())
+        .map(value)
+        .collect::<Option<Vec<_>>>()
+        .ok_or(InvalidPlusCode)?;
+
+    Ok((digits, separator))
+}
+
+/// The center of the area of a full code.
+pub(crate) fn decode(code: &str) -> Result<Geodetic, InvalidPlusCode> {
+    let (digits, separator) = digits(code)?;
+    if separator != SEPARATOR_POSITION {
+        return Err(InvalidPlusCode);
+    }
+
+    // The first latitude digit can only go up to 90 degrees north, and the first longitude
+    // digit up to 180 degrees east
+    if digits[0] >= (180.0 / FIRST_PAIR) as i64
+        || digits
+            .get(1)
+            .is_some_and(|&lon| lon >= (360.0 / FIRST_PAIR) as i64)
+    {
+        return Err(InvalidPlusCode);
+    }
+
+    let (mut lat, mut lon) = (-90.0, -180.0);
+    let (mut height, mut width) = (FIRST_PAIR * 20.0, FIRST_PAIR * 20.0);
+
+    for pair in digits.chunks(2).take(PAIR_LENGTH / 2) {
+        (height, width) = (height / 20.0, width / 20.0);
+        lat += pair[0] as f64 * height;
+        lon += pair.get(1).copied().unwrap_or(0) as f64 * width;
+    }
+    for &digit in digits.iter().skip(PAIR_LENGTH) {
+        (height, width) = (height / GRID_ROWS as f64, width / GRID_COLUMNS as f64);
+        lat += (digit / GRID_COLUMNS) as f64 * height;
+        lon += (digit % GRID_COLUMNS) as f64 * width;
+    }
+
+    Ok(Geodetic::new(
+        lon + width / 2.0,
+        (lat + height / 2.0).min(90.0),
+    ))
+}
+
+/// The center of the area of a full or shortened code, where shortened codes are recovered
+/// as the area nearest to the reference position.
+pub(crate) fn decode_near(code: &str, reference: Geodetic) -> Result<Geodetic, InvalidPlusCode> {
+    let (_, separator) = digits(code)?;
+    if separator == SEPARATOR_POSITION {
+        return decode(code);
+    }
+
+    // Fill in the missing digits from the reference
+    let missing = SEPARATOR_POSITION - separator;
+    let prefix = encode(reference, PAIR_LENGTH);
+    let center = decode(&format!("{}{}", &prefix[..missing], code.trim()))?;
+
+    // The area of the missing digits, which may have to be moved to be nearest to the
+    // reference across its edges
+    let resolution = FIRST_PAIR / 20f64.powi(missing as i32 / 2 - 1);
+    let nearest = |value: f64, reference: f64| {
+        if reference + resolution / 2.0 < value {
+            value - resolution
+        } else if reference - resolution / 2.0 > value {
+            value + resolution
+        } else {
+            value
+        }
+    };
+
+    let mut lat = nearest(center.latitude(), reference.latitude());
+    if !(-90.0..=90.0).contains(&lat) {
+        lat = center.latitude();
+    }
+    let lon = nearest(center.longitude(), reference.longitude());
+
+    Ok(Geodetic::new(lon, lat).wrapped())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_codes() {
+        let eiffel = Geodetic::new(2.294_5, 48.858_2);
+        assert_eq!(encode(eiffel, 10), "8FW4V75V+7R");
+        assert_eq!(encode(eiffel, 11).len(), 12);
+        assert!(decode(&encode(eiffel, 11)).unwrap().distance_to(eiffel) < 3.0);
+        assert_eq!(encode(eiffel, 4), "8FW40000+");
+        assert_eq!(encode(eiffel, 8), "8FW4V75V+");
+        assert_eq!(encode(Geodetic::new(180.0, 90.0), 10), "C2X2X2X2+X2");
+
+        let center = decode("8fw4v75v+7r").unwrap();
+        assert!(center.distance_to(eiffel) < 10.0);
+        assert_eq!(encode(center, 10), "8FW4V75V+7R");
+        assert_eq!(decode("8FW40000+"), Ok(Geodetic::new(2.5, 48.5)));
+    }
+
+    #[test]
+    fn invalid_codes() {
+        for invalid in [
+            "",
+            "8FW4V75V",
+            "8FW4V75V+8",
+            "8FW4V7+5V",
+            "8FW4V75V+8Q+",
+            "8FW40000+8Q",
+            "8FW0000+",
+            "8FW4V75A+8Q",
+            "X2000000+",
+        ] {
+            assert_eq!(decode(invalid), Err(InvalidPlusCode), "{invalid}");
+        }
+        assert_eq!(decode("V75V+8Q"), Err(InvalidPlusCode));
+    }
+
+    #[test]
+    fn recover_short_codes() {
+        let eiffel = Geodetic::new(2.294_5, 48.858_2);
+        let recovered = decode_near("V75V+7R", Geodetic::new(2.35, 48.86)).unwrap();
+        assert!(recovered.distance_to(eiffel) < 10.0);
+
+        // Across the edge of the area of the reference
+        let reference = Geodetic::new(2.0, 49.05);
+        let code = encode(Geodetic::new(2.0, 48.95), 10);
+        let recovered = decode_near(&code[4..], reference).unwrap();
+        approx::assert_relative_eq!(recovered.latitude(), 48.95, epsilon = 1e-3);
+    }
+}
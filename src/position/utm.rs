@@ -0,0 +1,311 @@
+//! The Universal Transverse Mercator projection on the WGS 84 ellipsoid, using the series
+//! expansions of Snyder's "Map Projections: A Working Manual", which are accurate to well
+//! within a meter inside each zone.
+
+use std::fmt;
+use std::str::FromStr;
+
+use super::Geodetic;
+
+/// The semi-major axis of the WGS 84 ellipsoid in meters.
+const SEMI_MAJOR_AXIS: f64 = 6_378_137.0;
+/// The flattening of the WGS 84 ellipsoid.
+const FLATTENING: f64 = 1.0 / 298.257_223_563;
+/// The scale factor along the central meridian of each zone.
+const SCALE: f64 = 0.9996;
+const FALSE_EASTING: f64 = 500_000.0;
+/// Added to the northing in the southern hemisphere, to keep it positive.
+const FALSE_NORTHING: f64 = 10_000_000.0;
+
+/// The latitude bands of 8 degrees from 80°S, where the last band `X` spans 12 degrees.
+pub(crate) const BANDS: &[u8; 20] = b"CDEFGHJKLMNPQRSTUVWX";
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+#[error("invalid UTM coordinate")]
+pub struct InvalidUtm;
+
+/// One of the 60 zones of 6 degrees of longitude, in either the northern or the southern
+/// hemisphere.
+///
+/// Parses from the zone number followed by either the hemisphere, e.g. `31N`, or the
+/// latitude band letter, e.g. `31U`, where the bands from `N` are in the northern
+/// hemisphere.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct UtmZone {
+    pub number: u8,
+    pub north: bool,
+}
+
+impl UtmZone {
+    pub fn north(number: u8) -> Self {
+        Self {
+            number,
+            north: true,
+        }
+    }
+
+    pub fn south(number: u8) -> Self {
+        Self {
+            number,
+            north: false,
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        (1..=60).contains(&self.number)
+    }
+}
+
+impl fmt::Display for UtmZone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hemisphere = if self.north { 'N' } else { 'S' };
+        write!(f, "{}{hemisphere}", self.number)
+    }
+}
+
+impl FromStr for UtmZone {
+    type Err = InvalidUtm;
+
+    fn from_str(zone: &str) -> Result<Self, Self::Err> {
+        let zone = zone.trim();
+        let split = zone.find(|c: char| !c.is_ascii_digit()).ok_or(InvalidUtm)?;
+        let number = zone[..split].parse().map_err(|_| InvalidUtm)?;
+
+        let letter = zone[split..].to_ascii_uppercase();
+        let band = BANDS
+            .iter()
+            .position(|&band| letter == (band as char).to_string())
+            .ok_or(InvalidUtm)?;
+        let zone = Self {
+            number,
+            north: band >= BANDS.len() / 2,
+        };
+
+        zone.is_valid().then_some(zone).ok_or(InvalidUtm)
+    }
+}
+
+/// A position in the Universal Transverse Mercator system, as the easting and northing in
+/// meters within a zone.
+///
+/// Displays as e.g. `31N 448252 5411933`, where the letter is the hemisphere.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Utm {
+    pub zone: UtmZone,
+    pub easting: f64,
+    pub northing: f64,
+}
+
+impl fmt::Display for Utm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {:.0} {:.0}", self.zone, self.easting, self.northing)
+    }
+}
+
+/// The squared eccentricity of the ellipsoid.
+fn eccentricity2() -> f64 {
+    FLATTENING * (2.0 - FLATTENING)
+}
+
+/// The longitude of the central meridian of a zone.
+pub(crate) fn central_meridian(zone: u8) -> f64 {
+    zone as f64 * 6.0 - 183.0
+}
+
+/// The zone of a position, including the exceptions around Norway and Svalbard.
+pub(crate) fn zone(position: Geodetic) -> u8 {
+    let (lon, lat) = (position.wrapped().longitude(), position.latitude());
+
+    if (56.0..64.0).contains(&lat) && (3.0..12.0).contains(&lon) {
+        return 32;
+    }
+    if (72.0..84.0).contains(&lat) && (0.0..42.0).contains(&lon) {
+        return match lon {
+            lon if lon < 9.0 => 31,
+            lon if lon < 21.0 => 33,
+            lon if lon < 33.0 => 35,
+            _ => 37,
+        };
+    }
+
+    (((lon + 180.0) / 6.0).floor() as u8).min(59) + 1
+}
+
+/// The latitude band letter of a latitude, if within the bands.
+pub(crate) fn band(latitude: f64) -> Option<char> {
+    if !(-80.0..=84.0).contains(&latitude) {
+        return None;
+    }
+
+    let index = (((latitude + 80.0) / 8.0).floor() as usize).min(BANDS.len() - 1);
+    Some(BANDS[index] as char)
+}
+
+/// The meridional arc length from the equator to a latitude in radians.
+fn meridian_arc(lat: f64) -> f64 {
+    let e2 = eccentricity2();
+    let (e4, e6) = (e2 * e2, e2 * e2 * e2);
+
+    SEMI_MAJOR_AXIS
+        * ((1.0 - e2 / 4.0 - 3.0 * e4 / 64.0 - 5.0 * e6 / 256.0) * lat
+            - (3.0 * e2 / 8.0 + 3.0 * e4 / 32.0 + 45.0 * e6 / 1024.0) * (2.0 * lat).sin()
+            + (15.0 * e4 / 256.0 + 45.0 * e6 / 1024.0) * (4.0 * lat).sin()
+            - (35.0 * e6 / 3072.0) * (6.0 * lat).sin())
+}
+
+impl Utm {
+    /// Create a coordinate, failing if the zone does not exist, or the easting or northing
+    /// are far outside of the zone.
+    pub fn try_new(zone: UtmZone, easting: f64, northing: f64) -> Result<Self, InvalidUtm> {
+        let within =
+            (0.0..1_000_000.0).contains(&easting) && (0.0..=FALSE_NORTHING).contains(&northing);
+
+        (zone.is_valid() && within)
+            .then_some(Self {
+                zone,
+                easting,
+                northing,
+            })
+            .ok_or(InvalidUtm)
+    }
+
+    pub fn from_geodetic(position: Geodetic) -> Self {
+        Self::from_geodetic_in_zone(position, zone(position))
+    }
+
+    /// Project a position into a given zone, which may be a neighbor of its own zone.
+    pub(crate) fn from_geodetic_in_zone(position: Geodetic, zone: u8) -> Self {
+        let e2 = eccentricity2();
+        let ep2 = e2 / (1.0 - e2);
+
+        let lat = position.latitude().to_radians();
+        let d_lon =
+            (position.longitude() - central_meridian(zone) + 180.0).rem_euclid(360.0) - 180.0;
+
+        let n = SEMI_MAJOR_AXIS / (1.0 - e2 * lat.sin().powi(2)).sqrt();
+        let t = lat.tan().powi(2);
+        let c = ep2 * lat.cos().powi(2);
+        let a = lat.cos() * d_lon.to_radians();
+
+        let easting = SCALE
+            * n
+            * (a + (1.0 - t + c) * a.powi(3) / 6.0
+                + (5.0 - 18.0 * t + t * t + 72.0 * c - 58.0 * ep2) * a.powi(5) / 120.0)
+            + FALSE_EASTING;
+
+        let northing = SCALE
+            * (meridian_arc(lat)
+                + n * lat.tan()
+                    * (a * a / 2.0
+                        + (5.0 - t + 9.0 * c + 4.0 * c * c) * a.powi(4) / 24.0
+                        + (61.0 - 58.0 * t + t * t + 600.0 * c - 330.0 * ep2) * a.powi(6) / 720.0));
+
+        let north = position.latitude() >= 0.0;
+        Self {
+            zone: UtmZone {
+                number: zone,
+                north,
+            },
+            easting,
+            northing: if north {
+                northing
+            } else {
+                northing + FALSE_NORTHING
+            },
+        }
+    }
+
+    pub fn to_geodetic(self) -> Geodetic {
+        let e2 = eccentricity2();
+        let ep2 = e2 / (1.0 - e2);
+        let e1 = (1.0 - (1.0 - e2).sqrt()) / (1.0 + (1.0 - e2).sqrt());
+
+        let northing = if self.zone.north {
+            self.northing
+        } else {
+            self.northing - FALSE_NORTHING
+        };
+
+        // The footpoint latitude, which has the same meridional arc as the northing
+        let arc = northing / SCALE;
+        let mu = arc
+            / (SEMI_MAJOR_AXIS
+                * (1.0 - e2 / 4.0 - 3.0 * e2 * e2 / 64.0 - 5.0 * e2 * e2 * e2 / 256.0));
+        let lat1 = mu
+            + (3.0 * e1 / 2.0 - 27.0 * e1.powi(3) / 32.0) * (2.0 * mu).sin()
+            + (21.0 * e1 * e1 / 16.0 - 55.0 * e1.powi(4) / 32.0) * (4.0 * mu).sin()
+            + (151.0 * e1.powi(3) / 96.0) * (6.0 * mu).sin()
+            + (1097.0 * e1.powi(4) / 512.0) * (8.0 * mu).sin();
+
+        let sin2 = lat1.sin().powi(2);
+        let n1 = SEMI_MAJOR_AXIS / (1.0 - e2 * sin2).sqrt();
+        let r1 = SEMI_MAJOR_AXIS * (1.0 - e2) / (1.0 - e2 * sin2).powf(1.5);
+        let t1 = lat1.tan().powi(2);
+        let c1 = ep2 * lat1.cos().powi(2);
+        let d = (self.easting - FALSE_EASTING) / (n1 * SCALE);
+
+        let lat = lat1
+            - (n1 * lat1.tan() / r1)
+                * (d * d / 2.0
+                    - (5.0 + 3.0 * t1 + 10.0 * c1 - 4.0 * c1 * c1 - 9.0 * ep2) * d.powi(4) / 24.0
+                    + (61.0 + 90.0 * t1 + 298.0 * c1 + 45.0 * t1 * t1
+                        - 252.0 * ep2
+                        - 3.0 * c1 * c1)
+                        * d.powi(6)
+                        / 720.0);
+        let lon = (d - (1.0 + 2.0 * t1 + c1) * d.powi(3) / 6.0
+            + (5.0 - 2.0 * c1 + 28.0 * t1 - 3.0 * c1 * c1 + 8.0 * ep2 + 24.0 * t1 * t1)
+                * d.powi(5)
+                / 120.0)
+            / lat1.cos();
+
+        Geodetic::new(
+            central_meridian(self.zone.number) + lon.to_degrees(),
+            lat.to_degrees(),
+        )
+        .wrapped()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::location;
+
+    #[test]
+    fn known_positions() {
+        // The Eiffel tower is at 31U 448251 5411932
+        let utm = Utm::from_geodetic(Geodetic::new(2.294_5, 48.858_2));
+        assert_eq!(utm.zone, UtmZone::north(31));
+        assert_eq!(utm.to_string(), "31N 448252 5411933");
+        approx::assert_relative_eq!(utm.easting, 448_251.0, epsilon = 5.0);
+        approx::assert_relative_eq!(utm.northing, 5_411_932.0, epsilon = 5.0);
+
+        assert_eq!(zone(Geodetic::new(5.0, 60.0)), 32);
+        assert_eq!(zone(Geodetic::new(20.0, 78.0)), 33);
+        assert_eq!(band(48.0), Some('U'));
+        assert_eq!(band(83.0), Some('X'));
+        assert_eq!(band(-81.0), None);
+
+        assert_eq!("31U".parse(), Ok(UtmZone::north(31)));
+        assert_eq!("19h".parse(), Ok(UtmZone::south(19)));
+        assert_eq!("61N".parse::<UtmZone>(), Err(InvalidUtm));
+        assert_eq!("31".parse::<UtmZone>(), Err(InvalidUtm));
+        assert!(Utm::try_new(UtmZone::north(31), -1.0, 0.0).is_err());
+    }
+
+    #[test]
+    fn roundtrip() {
+        for position in [
+            location::paris(),
+            location::madrid(),
+            Geodetic::new(-70.6, -33.45),
+            Geodetic::new(151.2, -33.87),
+            Geodetic::new(179.9, 0.0),
+        ] {
+            let back = Utm::from_geodetic(position).to_geodetic();
+            approx::assert_relative_eq!(back.longitude(), position.longitude(), epsilon = 1e-6);
+            approx::assert_relative_eq!(back.latitude(), position.latitude(), epsilon = 1e-6);
+        }
+    }
+}
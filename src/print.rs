@@ -0,0 +1,447 @@
+//! Compose a map with a title, north arrow, scale bar, legend and attribution into a page at
+//! some paper size, e.g. to generate reports.
+//!
+//! The page is an element of exactly [`PrintLayout::size`], which is shown in a window of that
+//! size and captured with [`iced::window::screenshot`]. The captured pixels are the exported
+//! image, and [`PrintLayout::pdf`] wraps them in a PDF page of the chosen paper size:
+//!
+//! ```ignore
+//! let layout = PrintLayout::new(Paper::A4)
+//!     .title("Stations")
+//!     .legend(legend.clone())
+//!     .attribution(cache.attribution_text());
+//!
+//! // In the view of the print window
+//! layout.view(MapProgram::new(&cache).with_draw_layer(..).build(viewpoint), viewpoint)
+//!
+//! // Once the tiles are loaded
+//! window::screenshot(id).map(Message::Printed)
+//!
+//! Message::Printed(shot) => {
+//!     let pdf = layout.pdf(&shot.rgba, shot.size.width, shot.size.height);
+//!     std::fs::write("report.pdf", pdf)?;
+//! }
+//! ```
+
+use std::fmt::Write as _;
+
+use iced::widget::canvas::{self, Frame, Geometry, Path, Stroke};
+use iced::widget::{canvas as widget_canvas, column, container, row, space, stack, text};
+use iced::{Color, Element, Length, Point, Rectangle, Size, Theme, Vector, alignment, mouse};
+
+use crate::legend::Legend;
+use crate::{Projector, Viewpoint};
+
+const MM_PER_INCH: f32 = 25.4;
+const POINTS_PER_INCH: f32 = 72.0;
+
+/// The scale bar covers at most this fraction of the width of the map.
+const SCALE_BAR_FRACTION: f32 = 0.25;
+
+/// The distance between the decorations and the edges of the map, in pixels.
+const INSET: f32 = 16.0;
+
+/// The size of a sheet of paper.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Paper {
+    A3,
+    A4,
+    A5,
+    Letter,
+    Legal,
+    /// The width and height in millimeters, in portrait orientation.
+    Custom {
+        width: f32,
+        height: f32,
+    },
+}
+
+impl Paper {
+    /// The width and height in millimeters, in portrait orientation.
+    pub fn millimeters(&self) -> Size {
+        match *self {
+            Paper::A3 => Size::new(297.0, 420.0),
+            Paper::A4 => Size::new(210.0, 297.0),
+            Paper::A5 => Size::new(148.0, 210.0),
+            Paper::Letter => Size::new(215.9, 279.4),
+            Paper::Legal => Size::new(215.9, 355.6),
+            Paper::Custom { width, height } => Size::new(width, height),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Orientation {
+    #[default]
+    Portrait,
+    Landscape,
+}
+
+/// A page with a map surrounded by the elements that make it readable on paper.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrintLayout {
+    paper: Paper,
+    orientation: Orientation,
+    dpi: f32,
+    /// The margin around the page contents, in millimeters.
+    margin: f32,
+    title: Option<String>,
+    north_arrow: bool,
+    scale_bar: bool,
+    legend: Option<Legend>,
+    attribution: Option<String>,
+}
+
+impl PrintLayout {
+    pub fn new(paper: Paper) -> Self {
+        Self {
+            paper,
+            orientation: Orientation::default(),
+            dpi: 150.0,
+            margin: 10.0,
+            title: None,
+            north_arrow: true,
+            scale_bar: true,
+            legend: None,
+            attribution: None,
+        }
+    }
+
+    pub fn orientation(mut self, orientation: Orientation) -> Self {
+        self.orientation = orientation;
+        self
+    }
+
+    /// The resolution of the exported page, in pixels per inch.
+    pub fn dpi(mut self, dpi: f32) -> Self {
+        self.dpi = dpi.max(1.0);
+        self
+    }
+
+    /// The margin around the page contents, in millimeters.
+    pub fn margin(mut self, margin: f32) -> Self {
+        self.margin = margin.max(0.0);
+        self
+    }
+
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    pub fn north_arrow(mut self, north_arrow: bool) -> Self {
+        self.north_arrow = north_arrow;
+        self
+    }
+
+    pub fn scale_bar(mut self, scale_bar: bool) -> Self {
+        self.scale_bar = scale_bar;
+        self
+    }
+
+    /// Show the entries of the legend below the map. It is shown expanded, regardless of
+    /// whether it is collapsed on screen.
+    pub fn legend(mut self, legend: Legend) -> Self {
+        self.legend = Some(legend);
+        self
+    }
+
+    /// The attribution of the tile source, and of any data on the map.
+    pub fn attribution(mut self, attribution: impl Into<String>) -> Self {
+        self.attribution = Some(attribution.into());
+        self
+    }
+
+    /// The size of the paper in millimeters, in the chosen orientation.
+    pub fn millimeters(&self) -> Size {
+        let size = self.paper.millimeters();
+        match self.orientation {
+            Orientation::Portrait => size,
+            Orientation::Landscape => Size::new(size.height, size.width),
+        }
+    }
+
+    /// The size of the page in pixels at the chosen resolution. The window showing the
+    /// layout should be of this size, with a scale factor of one.
+    pub fn size(&self) -> Size {
+        let size = self.millimeters() * (self.dpi / MM_PER_INCH);
+        Size::new(size.width.round(), size.height.round())
+    }
+
+    /// The page, with the map shown at the given viewpoint between the title and the legend.
+    ///
+    /// The map element is typically built by a [`crate::MapProgram`] with the same viewpoint,
+    /// such that the scale bar matches it.
+    pub fn view<'a, Message: 'a>(
+        &'a self,
+        map: impl Into<Element<'a, Message>>,
+        viewpoint: Viewpoint,
+    ) -> Element<'a, Message> {
+        let size = self.size();
+        let margin = self.margin * self.dpi / MM_PER_INCH;
+
+        let decorations = widget_canvas(Decorations {
+            viewpoint,
+            north_arrow: self.north_arrow,
+            scale_bar: self.scale_bar,
+        })
+        .width(Length::Fill)
+        .height(Length::Fill);
+
+        let mut page = column![].spacing(margin / 2.0);
+
+        if let Some(title) = &self.title {
+            page = page.push(
+                container(text(title).size(28))
+                    .width(Length::Fill)
+                    .align_x(alignment::Horizontal::Center),
+            );
+        }
+
+        page = page.push(
+            stack![map.into(), decorations]
+                .width(Length::Fill)
+                .height(Length::Fill),
+        );
+
+        let legend = self
+            .legend
+            .iter()
+            .flat_map(|legend| legend.entries().iter().map(|entry| entry.view()));
+        let mut footer = row![column![].spacing(4).extend(legend), space::horizontal()]
+            .align_y(alignment::Vertical::Bottom);
+        if let Some(attribution) = &self.attribution {
+            footer = footer.push(text(attribution).size(11));
+        }
+        page = page.push(footer);
+
+        container(page)
+            .padding(margin)
+            .width(size.width)
+            .height(size.height)
+            .style(|_| container::Style {
+                text_color: Some(Color::BLACK),
+                background: Some(Color::WHITE.into()),
+                ..Default::default()
+            })
+            .into()
+    }
+
+    /// A single page PDF document of the paper size, covered by the captured pixels of the
+    /// layout. The image is stored uncompressed, and its alpha channel is dropped.
+    pub fn pdf(&self, rgba: &[u8], width: u32, height: u32) -> Vec<u8> {
+        let page = self.millimeters() * (POINTS_PER_INCH / MM_PER_INCH);
+        let rgb: Vec<u8> = rgba
+            .chunks_exact(4)
+            .flat_map(|pixel| [pixel[0], pixel[1], pixel[2]])
+            .collect();
+
+        let mut pdf = PdfWriter::default();
+        pdf.object(b"<< /Type /Catalog /Pages 2 0 R >>");
+        pdf.object(b"<< /Type /Pages /Kids [3 0 R] /Count 1 >>");
+        pdf.object(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.2} {:.2}] \
+                 /Resources << /XObject << /Map 4 0 R >> >> /Contents 5 0 R >>",
+                page.width, page.height
+            )
+            .as_bytes(),
+        );
+        pdf.stream(
+            &format!(
+                "/Type /XObject /Subtype /Image /Width {width} /Height {height} \
+                 /ColorSpace /DeviceRGB /BitsPerComponent 8"
+            ),
+            &rgb,
+        );
+        pdf.stream(
+            "",
+            format!(
+                "q {:.2} 0 0 {:.2} 0 0 cm /Map Do Q",
+                page.width, page.height
+            )
+            .as_bytes(),
+        );
+        pdf.finish()
+    }
+}
+
+/// Writes the objects of a PDF document, keeping track of their offsets for the
+/// cross-reference table.
+struct PdfWriter {
+    bytes: Vec<u8>,
+    offsets: Vec<usize>,
+}
+
+impl Default for PdfWriter {
+    fn default() -> Self {
+        Self {
+            bytes: b"%PDF-1.4\n".to_vec(),
+            offsets: Vec::new(),
+        }
+    }
+}
+
+impl PdfWriter {
+    fn object(&mut self, body: &[u8]) {
+        self.offsets.push(self.bytes.len());
+        let number = self.offsets.len();
+        self.bytes
+            .extend_from_slice(format!("{number} 0 obj\n").as_bytes());
+        self.bytes.extend_from_slice(body);
+        self.bytes.extend_from_slice(b"\nendobj\n");
+    }
+
+    fn stream(&mut self, dictionary: &str, data: &[u8]) {
+        let mut body = format!("<< {dictionary} /Length {} >>\nstream\n", data.len()).into_bytes();
+        body.extend_from_slice(data);
+        body.extend_from_slice(b"\nendstream");
+        self.object(&body);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        let xref = self.bytes.len();
+        let mut table = format!("xref\n0 {}\n0000000000 65535 f \n", self.offsets.len() + 1);
+        for offset in &self.offsets {
+            let _ = writeln!(table, "{offset:010} 00000 n ");
+        }
+        let _ = write!(
+            table,
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+            self.offsets.len() + 1
+        );
+        self.bytes.extend_from_slice(table.as_bytes());
+        self.bytes
+    }
+}
+
+/// The length of the scale bar in pixels, along with its label, for a map at least
+/// `max_width` pixels wide. The length is rounded to 1, 2 or 5 times a power of ten meters.
+fn scale_bar(projector: &Projector, max_width: f32) -> (f32, String) {
+    let center = projector.bounds.center();
+    let a = projector.screen_space_into_geodetic(center);
+    let b = projector.screen_space_into_geodetic(center + Vector::new(max_width, 0.0));
+    let meters = a.distance_to(b);
+
+    let magnitude = 10f64.powf(meters.log10().floor());
+    let step = [5.0, 2.0, 1.0]
+        .into_iter()
+        .map(|step| step * magnitude)
+        .find(|step| *step <= meters)
+        .unwrap_or(magnitude);
+
+    let label = if step >= 1000.0 {
+        format!("{} km", step / 1000.0)
+    } else {
+        format!("{step} m")
+    };
+
+    (max_width * (step / meters) as f32, label)
+}
+
+/// Draws the north arrow and the scale bar on top of the map.
+struct Decorations {
+    viewpoint: Viewpoint,
+    north_arrow: bool,
+    scale_bar: bool,
+}
+
+impl<Message> canvas::Program<Message> for Decorations {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &iced::Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = Frame::new(renderer, bounds.size());
+        let outline = Stroke::default().with_color(Color::WHITE).with_width(2.0);
+
+        // North is always up on the web mercator projection
+        if self.north_arrow {
+            let tip = Point::new(bounds.width - INSET - 12.0, INSET + 16.0);
+            let arrow = Path::new(|builder| {
+                builder.move_to(tip);
+                builder.line_to(tip + Vector::new(12.0, 36.0));
+                builder.line_to(tip + Vector::new(0.0, 28.0));
+                builder.line_to(tip + Vector::new(-12.0, 36.0));
+                builder.close();
+            });
+            frame.stroke(&arrow, outline);
+            frame.fill(&arrow, Color::BLACK);
+            frame.fill_text(canvas::Text {
+                content: "N".to_string(),
+                position: tip - Vector::new(0.0, 4.0),
+                color: Color::BLACK,
+                size: 16.0.into(),
+                align_x: alignment::Horizontal::Center.into(),
+                align_y: alignment::Vertical::Bottom,
+                ..Default::default()
+            });
+        }
+
+        if self.scale_bar {
+            let projector = Projector {
+                viewpoint: self.viewpoint,
+                bounds: Rectangle::with_size(bounds.size()),
+            };
+            let (width, label) = scale_bar(&projector, bounds.width * SCALE_BAR_FRACTION);
+
+            let origin = Point::new(INSET, bounds.height - INSET);
+            let bar = Path::new(|builder| {
+                builder.move_to(origin - Vector::new(0.0, 8.0));
+                builder.line_to(origin);
+                builder.line_to(origin + Vector::new(width, 0.0));
+                builder.line_to(origin + Vector::new(width, -8.0));
+            });
+            frame.stroke(&bar, outline.with_width(5.0));
+            frame.stroke(
+                &bar,
+                Stroke::default().with_color(Color::BLACK).with_width(2.0),
+            );
+            frame.fill_text(canvas::Text {
+                content: label,
+                position: origin + Vector::new(width / 2.0, -6.0),
+                color: Color::BLACK,
+                size: 13.0.into(),
+                align_x: alignment::Horizontal::Center.into(),
+                align_y: alignment::Vertical::Bottom,
+                ..Default::default()
+            });
+        }
+
+        vec![frame.into_geometry()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Zoom, location};
+
+    #[test]
+    fn page_size_and_scale_bar() {
+        let layout = PrintLayout::new(Paper::A4)
+            .orientation(Orientation::Landscape)
+            .dpi(MM_PER_INCH);
+        assert_eq!(layout.size(), Size::new(297.0, 210.0));
+
+        let projector = Projector {
+            viewpoint: Viewpoint {
+                position: location::paris().as_mercator(),
+                zoom: Zoom::try_from(10.0).unwrap(),
+            },
+            bounds: Rectangle::with_size(Size::new(800.0, 600.0)),
+        };
+        let (width, label) = scale_bar(&projector, 200.0);
+        assert!(width > 80.0 && width <= 200.0);
+        assert!(label.ends_with(" km"));
+
+        let pdf = layout.pdf(&[255; 4 * 6], 3, 2);
+        assert!(pdf.starts_with(b"%PDF-1.4"));
+        assert!(pdf.ends_with(b"%%EOF\n"));
+    }
+}
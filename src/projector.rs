@@ -1,6 +1,8 @@
 use iced::{Point, Rectangle, Vector};
 
-use crate::{Geodetic, Mercator, Viewpoint};
+use crate::{
+    Geodetic, Mercator, TileCoord, Viewpoint, sources::TilingScheme, tile_coord::grid_corner,
+};
 
 /// Utility for projecting between points in screen space, pixel space or global coordinates.
 ///
@@ -97,6 +99,105 @@ impl Projector {
     pub fn screen_space_into_geodetic(&self, point: Point<f32>) -> Geodetic {
         self.screen_space_into_mercator(point).as_geodetic()
     }
+
+    /// Whether a screen space point, such as the cursor, is within a radius in pixels around
+    /// a [`Mercator`] position. This is the typical hit test for markers and vertices.
+    pub fn is_within_radius(&self, mercator: Mercator, point: Point<f32>, radius: f32) -> bool {
+        self.mercator_into_screen_space(mercator).distance(point) <= radius
+    }
+
+    /// The distance in pixels from a screen space point to the nearest segment of a
+    /// polyline, or `None` if the polyline has no points. Compare the distance with a
+    /// tolerance to test if a line is hit.
+    pub fn distance_to_polyline(&self, polyline: &[Mercator], point: Point<f32>) -> Option<f32> {
+        let points = polyline
+            .iter()
+            .map(|&mercator| self.mercator_into_screen_space(mercator))
+            .collect::<Vec<_>>();
+
+        match points.as_slice() {
+            [] => None,
+            [single] => Some(single.distance(point)),
+            _ => points
+                .windows(2)
+                .map(|segment| distance_to_segment(point, segment[0], segment[1]))
+                .min_by(f32::total_cmp),
+        }
+    }
+
+    /// Whether a screen space point is inside a polygon ring of [`Mercator`] positions. The
+    /// ring may be open or closed, and overlapping parts are treated by the even-odd rule.
+    pub fn ring_contains(&self, ring: &[Mercator], point: Point<f32>) -> bool {
+        let point = self.screen_space_into_mercator(point);
+        let (x, y) = (point.east_x(), point.south_y());
+
+        let mut inside = false;
+        for (i, a) in ring.iter().enumerate() {
+            let b = ring[(i + 1) % ring.len()];
+            let (ax, ay, bx, by) = (a.east_x(), a.south_y(), b.east_x(), b.south_y());
+
+            // Count the edges crossed by a ray towards the east
+            if (ay > y) != (by > y) && x < ax + (y - ay) / (by - ay) * (bx - ax) {
+                inside = !inside;
+            }
+        }
+
+        inside
+    }
+
+    /// The zoom level of tiles from a source with the given tile size that matches the
+    /// resolution of the current view, in the same way as the [`crate::MapWidget`] picks tiles.
+    pub fn tile_zoom(&self, tile_size: u32, max_zoom: u8) -> u8 {
+        TilingScheme::new(tile_size).tile_zoom(self.viewpoint.zoom.f64(), max_zoom)
+    }
+
+    /// The screen space bounds of a tile, regardless of the tile size of its source.
+    ///
+    /// Both corners are projected from the tile grid, rather than adding the size of the
+    /// tile to its position, such that adjacent tiles share the exact same edges. This
+    /// avoids hairline seams between tiles at fractional zoom levels.
+    pub fn tile_bounds(&self, tile_id: &TileCoord) -> Rectangle {
+        let (x, y) = tile_id.x_y();
+        let top_left = self.mercator_into_screen_space(tile_id.to_mercator());
+        let bottom_right =
+            self.mercator_into_screen_space(grid_corner(x + 1, y + 1, tile_id.zoom()));
+
+        Rectangle {
+            x: top_left.x,
+            y: top_left.y,
+            width: bottom_right.x - top_left.x,
+            height: bottom_right.y - top_left.y,
+        }
+    }
+
+    /// Get all tiles of the given zoom level which are at least partially within the viewport.
+    pub fn tiles_in_view(&self, zoom: u8) -> Vec<TileCoord> {
+        let top_left = self.screen_space_into_mercator(self.bounds.position());
+        let bottom_right = self.screen_space_into_mercator(Point::new(
+            self.bounds.x + self.bounds.width,
+            self.bounds.y + self.bounds.height,
+        ));
+
+        let min = top_left.tile_id(zoom);
+        let max = bottom_right.tile_id(zoom);
+
+        (min.y()..=max.y())
+            .flat_map(|y| (min.x()..=max.x()).map(move |x| TileCoord::new(x, y, zoom)))
+            .collect()
+    }
+}
+
+/// The distance from a point to the nearest point on the segment between `a` and `b`.
+fn distance_to_segment(point: Point<f32>, a: Point<f32>, b: Point<f32>) -> f32 {
+    let segment = b - a;
+    let length = segment.x * segment.x + segment.y * segment.y;
+    if length == 0.0 {
+        return point.distance(a);
+    }
+
+    let offset = point - a;
+    let t = ((offset.x * segment.x + offset.y * segment.y) / length).clamp(0.0, 1.0);
+    point.distance(a + segment * t)
 }
 
 #[cfg(test)]
@@ -129,4 +230,96 @@ mod tests {
         assert_eq!(original_point, projected_point);
         assert_eq!(geodetic_first, geodetic_second);
     }
+
+    #[test]
+    fn tiles_in_view() {
+        let projector = Projector {
+            viewpoint: crate::Viewpoint {
+                position: Mercator::new(0.0, 0.0),
+                zoom: Zoom::try_from(1.0).unwrap(),
+            },
+            bounds: Rectangle {
+                x: 0.0,
+                y: 0.0,
+                width: 100.0,
+                height: 100.0,
+            },
+        };
+
+        // The center of the world is the corner between the four tiles at zoom 1
+        let tiles = projector.tiles_in_view(1);
+        assert_eq!(tiles.len(), 4);
+
+        // Only the single tile at zoom 0
+        assert_eq!(projector.tiles_in_view(0), vec![crate::TileCoord::ZERO]);
+    }
+
+    #[test]
+    fn adjacent_tiles_share_edges() {
+        let projector = Projector {
+            viewpoint: crate::Viewpoint {
+                position: Mercator::new(0.1234, -0.4321),
+                zoom: Zoom::try_from(13.37).unwrap(),
+            },
+            bounds: Rectangle {
+                x: 17.0,
+                y: 3.0,
+                width: 1280.0,
+                height: 720.0,
+            },
+        };
+
+        let tile = projector
+            .screen_space_into_mercator(projector.bounds.center())
+            .tile_id(13);
+        let (east, south) = (tile.east().unwrap(), tile.south().unwrap());
+
+        let bounds = projector.tile_bounds(&tile);
+        assert_eq!(bounds.x + bounds.width, projector.tile_bounds(&east).x);
+        assert_eq!(bounds.y + bounds.height, projector.tile_bounds(&south).y);
+    }
+
+    #[test]
+    fn hit_tests() {
+        let projector = Projector {
+            viewpoint: crate::Viewpoint {
+                position: Mercator::new(0.0, 0.0),
+                zoom: Zoom::try_from(4.0).unwrap(),
+            },
+            bounds: Rectangle {
+                x: 0.0,
+                y: 0.0,
+                width: 800.0,
+                height: 600.0,
+            },
+        };
+        let at = |x: f32, y: f32| projector.screen_space_into_mercator(Point::new(x, y));
+
+        let marker = at(400.0, 300.0);
+        assert!(projector.is_within_radius(marker, Point::new(405.0, 300.0), 10.0));
+        assert!(!projector.is_within_radius(marker, Point::new(415.0, 300.0), 10.0));
+
+        let line = [at(100.0, 100.0), at(300.0, 100.0), at(300.0, 300.0)];
+        let distance = |x, y| projector.distance_to_polyline(&line, Point::new(x, y));
+        approx::assert_relative_eq!(distance(200.0, 110.0).unwrap(), 10.0, epsilon = 1e-2);
+        approx::assert_relative_eq!(distance(320.0, 200.0).unwrap(), 20.0, epsilon = 1e-2);
+        approx::assert_relative_eq!(distance(90.0, 100.0).unwrap(), 10.0, epsilon = 1e-2);
+        assert_eq!(projector.distance_to_polyline(&[], Point::ORIGIN), None);
+
+        // A concave ring, shaped like the letter U
+        let ring = [
+            at(100.0, 100.0),
+            at(200.0, 100.0),
+            at(200.0, 200.0),
+            at(300.0, 200.0),
+            at(300.0, 100.0),
+            at(400.0, 100.0),
+            at(400.0, 300.0),
+            at(100.0, 300.0),
+        ];
+        assert!(projector.ring_contains(&ring, Point::new(150.0, 150.0)));
+        assert!(projector.ring_contains(&ring, Point::new(250.0, 250.0)));
+        assert!(!projector.ring_contains(&ring, Point::new(250.0, 150.0)));
+        assert!(!projector.ring_contains(&ring, Point::new(500.0, 150.0)));
+    }
 }
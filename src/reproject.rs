@@ -0,0 +1,141 @@
+//! Reprojection of tiles in the plate carrée projection (EPSG:4326) into the Web Mercator
+//! tiles which the map is made of.
+//!
+//! Each column of Web Mercator tiles at zoom level `z` lines up with a column of geographic
+//! tiles at zoom level `z - 1`, so only the rows differ. The geographic tiles overlapping a
+//! Web Mercator tile are fetched, and resampled row by row into a single image.
+
+use image::{Rgba, RgbaImage};
+
+use crate::{Mercator, tile_coord::TileCoord};
+
+/// A tile of the geographic grid, which has twice as many columns as rows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct GeographicTile {
+    pub x: u32,
+    pub y: u32,
+    pub zoom: u8,
+}
+
+impl GeographicTile {
+    /// The size of the tile in degrees, both in longitude and latitude.
+    fn degrees(zoom: u8) -> f64 {
+        180.0 / (1u32 << zoom) as f64
+    }
+}
+
+/// The longitude and latitude of the top left and bottom right corners of a tile.
+fn geodetic_bounds(tile_id: TileCoord) -> ((f64, f64), (f64, f64)) {
+    let size = 2.0 / (1u32 << tile_id.zoom()) as f64;
+    let corner = tile_id.to_mercator();
+    let top_left = corner.as_geodetic();
+    let bottom_right = Mercator::new(corner.east_x() + size, corner.south_y() + size).as_geodetic();
+
+    (
+        (top_left.longitude(), top_left.latitude()),
+        (bottom_right.longitude(), bottom_right.latitude()),
+    )
+}
+
+/// The geographic tiles which overlap a Web Mercator tile, at a matching resolution.
+pub(crate) fn geographic_tiles(tile_id: TileCoord) -> Vec<GeographicTile> {
+    let zoom = tile_id.zoom().saturating_sub(1);
+    let degrees = GeographicTile::degrees(zoom);
+    let ((west, north), (east, south)) = geodetic_bounds(tile_id);
+
+    let columns = 2 << zoom;
+    let rows = 1 << zoom;
+    let first_x = ((west + 180.0) / degrees).floor() as u32;
+    let last_x = (((east + 180.0) / degrees).ceil() as u32).clamp(first_x + 1, columns);
+    let first_y = ((90.0 - north) / degrees).floor() as u32;
+    let last_y = (((90.0 - south) / degrees).ceil() as u32).clamp(first_y + 1, rows);
+
+    (first_y..last_y)
+        .flat_map(|y| (first_x..last_x).map(move |x| GeographicTile { x, y, zoom }))
+        .collect()
+}
+
+/// Resample the geographic tiles into the image of a Web Mercator tile. Parts which are not
+/// covered by any of the given tiles are left transparent.
+pub(crate) fn reproject(tile_id: TileCoord, tiles: &[(GeographicTile, RgbaImage)]) -> RgbaImage {
+    let size = tiles
+        .first()
+        .map_or(256, |(_, image)| image.width().max(image.height()));
+
+    let tile_size = 2.0 / (1u32 << tile_id.zoom()) as f64;
+    let corner = tile_id.to_mercator();
+
+    let mut output = RgbaImage::new(size, size);
+    for row in 0..size {
+        // Rows are evenly spaced in mercator space, but not in latitude
+        let y = corner.south_y() + (row as f64 + 0.5) / size as f64 * tile_size;
+        let latitude = Mercator::new(corner.east_x(), y).as_geodetic().latitude();
+
+        for column in 0..size {
+            let x = corner.east_x() + (column as f64 + 0.5) / size as f64 * tile_size;
+            let longitude = x * 180.0;
+
+            if let Some(pixel) = sample(tiles, longitude, latitude) {
+                output.put_pixel(column, row, pixel);
+            }
+        }
+    }
+
+    output
+}
+
+/// The pixel of the geographic tiles at some longitude and latitude.
+fn sample(
+    tiles: &[(GeographicTile, RgbaImage)],
+    longitude: f64,
+    latitude: f64,
+) -> Option<Rgba<u8>> {
+    tiles.iter().find_map(|(tile, image)| {
+        let degrees = GeographicTile::degrees(tile.zoom);
+        let u = (longitude + 180.0) / degrees - tile.x as f64;
+        let v = (90.0 - latitude) / degrees - tile.y as f64;
+
+        ((0.0..1.0).contains(&u) && (0.0..1.0).contains(&v)).then(|| {
+            let x = (u * image.width() as f64) as u32;
+            let y = (v * image.height() as f64) as u32;
+            *image.get_pixel(x, y)
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tile(x: u32, y: u32, zoom: u8) -> GeographicTile {
+        GeographicTile { x, y, zoom }
+    }
+
+    #[test]
+    fn world_is_covered_by_two_tiles() {
+        let tiles = geographic_tiles(TileCoord::ZERO);
+        assert_eq!(tiles, [tile(0, 0, 0), tile(1, 0, 0)]);
+    }
+
+    #[test]
+    fn columns_line_up() {
+        // The north-eastern quarter of the world
+        let tiles = geographic_tiles(TileCoord::new(1, 0, 1));
+        assert_eq!(tiles, [tile(1, 0, 0)]);
+
+        let tiles = geographic_tiles(TileCoord::new(5, 7, 4));
+        assert!(tiles.iter().all(|tile| tile.x == 5 && tile.zoom == 3));
+    }
+
+    #[test]
+    fn reprojected_halves() {
+        // The western hemisphere is red, and the eastern is blue
+        let red = RgbaImage::from_pixel(16, 16, Rgba([255, 0, 0, 255]));
+        let blue = RgbaImage::from_pixel(16, 16, Rgba([0, 0, 255, 255]));
+        let tiles = [(tile(0, 0, 0), red), (tile(1, 0, 0), blue)];
+
+        let image = reproject(TileCoord::ZERO, &tiles);
+        assert_eq!(image.get_pixel(2, 8), &Rgba([255, 0, 0, 255]));
+        assert_eq!(image.get_pixel(13, 8), &Rgba([0, 0, 255, 255]));
+    }
+}
@@ -0,0 +1,173 @@
+use iced::widget::canvas::{self, Frame, Path, Stroke, stroke};
+use iced::{Color, mouse};
+
+use super::{Route, RoutingMessage};
+use crate::legend::{LegendEntry, Symbol};
+use crate::{Action, Geodetic, Projector, animation::partial_polyline};
+
+/// The visual appearance of a [`RouteLayer`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RouteStyle {
+    pub color: Color,
+    pub width: f32,
+    /// Drawn underneath the route line to make it stand out from the map.
+    pub casing: Option<(Color, f32)>,
+    pub waypoint_color: Color,
+    pub waypoint_radius: f32,
+}
+
+impl Default for RouteStyle {
+    fn default() -> Self {
+        Self {
+            color: Color::from_rgb(0.1, 0.45, 0.95),
+            width: 5.0,
+            casing: Some((Color::WHITE, 8.0)),
+            waypoint_color: Color::from_rgb(0.9, 0.2, 0.2),
+            waypoint_radius: 7.0,
+        }
+    }
+}
+
+impl RouteStyle {
+    /// An entry for a [`Legend`](crate::legend::Legend) explaining the route line.
+    pub fn legend(&self, label: impl Into<String>) -> LegendEntry {
+        let symbol = Symbol::Line {
+            color: self.color,
+            width: self.width,
+        };
+        LegendEntry::new(symbol, label)
+    }
+}
+
+/// Draws a [`Route`] along with its waypoints, and lets the waypoints be dragged around.
+///
+/// Typically created with [`super::Router::layer`], and used from within the draw and
+/// interaction layers of a [`crate::MapProgram`].
+#[derive(Debug, Clone)]
+pub struct RouteLayer<'a> {
+    route: Option<&'a Route>,
+    waypoints: &'a [Geodetic],
+    dragging: Option<usize>,
+    style: RouteStyle,
+    reveal: f32,
+}
+
+impl<'a> RouteLayer<'a> {
+    pub fn new(
+        route: Option<&'a Route>,
+        waypoints: &'a [Geodetic],
+        dragging: Option<usize>,
+    ) -> Self {
+        Self {
+            route,
+            waypoints,
+            dragging,
+            style: RouteStyle::default(),
+            reveal: 1.0,
+        }
+    }
+
+    pub fn style(mut self, style: RouteStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Only draw the route up to a fraction of its length, see [`crate::animation::Reveal`].
+    pub fn reveal(mut self, fraction: f32) -> Self {
+        self.reveal = fraction;
+        self
+    }
+
+    pub fn draw(&self, projector: &Projector, frame: &mut Frame<iced::Renderer>) {
+        if let Some(route) = self.route
+            && route.geometry.len() > 1
+        {
+            let points: Vec<_> = route
+                .geometry
+                .iter()
+                .map(|g| projector.geodetic_into_screen_space(*g))
+                .collect();
+            let points = partial_polyline(&points, self.reveal);
+
+            let path = Path::new(|builder| {
+                let mut points = points.into_iter();
+                if let Some(first) = points.next() {
+                    builder.move_to(first);
+                    points.for_each(|point| builder.line_to(point));
+                }
+            });
+
+            if let Some((color, width)) = self.style.casing {
+                frame.stroke(&path, route_stroke(color, width));
+            }
+            frame.stroke(&path, route_stroke(self.style.color, self.style.width));
+        }
+
+        for (i, waypoint) in self.waypoints.iter().enumerate() {
+            let position = projector.geodetic_into_screen_space(*waypoint);
+
+            let radius = if self.dragging == Some(i) {
+                self.style.waypoint_radius * 1.3
+            } else {
+                self.style.waypoint_radius
+            };
+
+            let circle = Path::circle(position, radius);
+            frame.fill(&circle, self.style.waypoint_color);
+            frame.stroke(
+                &circle,
+                Stroke::default().with_color(Color::WHITE).with_width(2.0),
+            );
+        }
+    }
+
+    /// Handle dragging of waypoints. The event is captured while a waypoint is being dragged,
+    /// such that the map does not pan underneath.
+    pub fn interact(
+        &self,
+        projector: &Projector,
+        cursor: &mouse::Cursor,
+        event: &canvas::Event,
+    ) -> Action<RoutingMessage> {
+        let mouse::Cursor::Available(cursor) = *cursor else {
+            return Action::None;
+        };
+
+        match event {
+            canvas::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                match self.waypoint_at(projector, cursor) {
+                    Some(index) => Action::Capture(RoutingMessage::DragStart(index)),
+                    None => Action::None,
+                }
+            }
+            canvas::Event::Mouse(mouse::Event::CursorMoved { .. }) if self.dragging.is_some() => {
+                let position = projector.screen_space_into_geodetic(cursor);
+                Action::Capture(RoutingMessage::DragMove(position))
+            }
+            canvas::Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left))
+                if self.dragging.is_some() =>
+            {
+                Action::Capture(RoutingMessage::DragEnd)
+            }
+            _ => Action::None,
+        }
+    }
+
+    /// Get the index of the top-most waypoint under the screen-space point.
+    pub fn waypoint_at(&self, projector: &Projector, point: iced::Point) -> Option<usize> {
+        // Slightly larger hit area than the drawn circle
+        let hit_radius = self.style.waypoint_radius + 4.0;
+
+        self.waypoints.iter().rposition(|waypoint| {
+            projector.is_within_radius(waypoint.as_mercator(), point, hit_radius)
+        })
+    }
+}
+
+fn route_stroke(color: Color, width: f32) -> Stroke<'static> {
+    Stroke {
+        line_cap: stroke::LineCap::Round,
+        line_join: stroke::LineJoin::Round,
+        ..Stroke::default().with_color(color).with_width(width)
+    }
+}
@@ -0,0 +1,381 @@
+//! Query a routing engine ([OSRM](https://project-osrm.org/) or [Valhalla](https://valhalla.github.io/valhalla/))
+//! for a route between a set of waypoints, and draw the result on the map.
+//!
+//! The [`Router`] works like the [`crate::TileCache`]: it is held in the application state,
+//! and its [`Router::update`] function must be glued into the application update loop.
+
+use std::time::Duration;
+
+use iced::{Subscription, Task, time::Instant};
+
+use crate::{Geodetic, animation::Reveal};
+
+mod layer;
+mod polyline;
+
+pub use layer::{RouteLayer, RouteStyle};
+pub use polyline::{decode_polyline, encode_polyline};
+
+/// The routing engine and endpoint to query.
+#[derive(Debug, Clone)]
+pub enum RoutingService {
+    /// An [OSRM](https://project-osrm.org/docs/v5.24.0/api/) server, e.g. `https://router.project-osrm.org`
+    /// with a profile such as `driving`, `cycling` or `foot`.
+    Osrm { url: String, profile: String },
+    /// A [Valhalla](https://valhalla.github.io/valhalla/api/turn-by-turn/api-reference/) server
+    /// with a costing model such as `auto`, `bicycle` or `pedestrian`.
+    Valhalla { url: String, costing: String },
+}
+
+impl RoutingService {
+    /// The public OSRM demo server. Make sure to follow its
+    /// [usage policy](https://github.com/Project-OSRM/osrm-backend/wiki/Api-usage-policy).
+    pub fn osrm_demo() -> Self {
+        Self::Osrm {
+            url: "https://router.project-osrm.org".into(),
+            profile: "driving".into(),
+        }
+    }
+}
+
+/// A route returned by the routing engine.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Route {
+    /// The full geometry of the route, passing through all waypoints.
+    pub geometry: Vec<Geodetic>,
+    /// Total distance of the route in meters.
+    pub distance: f64,
+    /// Expected travel time along the route.
+    pub duration: Duration,
+}
+
+/// The message that the [`Router`] uses to update.
+#[derive(Debug, Clone)]
+pub enum RoutingMessage {
+    AddWaypoint(Geodetic),
+    MoveWaypoint {
+        index: usize,
+        position: Geodetic,
+    },
+    RemoveWaypoint(usize),
+    ClearWaypoints,
+    DragStart(usize),
+    DragMove(Geodetic),
+    DragEnd,
+    Query,
+    Routed {
+        request: u64,
+        route: Route,
+    },
+    RoutingFailed {
+        request: u64,
+    },
+    /// Reveal the current route from its start, if the router is animated.
+    Reveal,
+    /// Advance the reveal animation, produced by [`Router::subscription`].
+    Frame(Instant),
+}
+
+#[derive(thiserror::Error, Debug)]
+enum RoutingError {
+    #[error(transparent)]
+    Reqwest(#[from] reqwest::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error("The routing engine responded with: {0}")]
+    NoRoute(String),
+    #[error("The route geometry could not be decoded")]
+    Geometry,
+}
+
+/// Holds the waypoints and the most recent route between them.
+#[derive(Debug)]
+pub struct Router {
+    service: RoutingService,
+    client: reqwest::Client,
+    waypoints: Vec<Geodetic>,
+    route: Option<Route>,
+    dragging: Option<usize>,
+    // Incremented for each query, such that stale responses can be discarded
+    request: u64,
+    reveal_duration: Option<Duration>,
+    reveal: Option<Reveal>,
+    revealed: f32,
+}
+
+impl Router {
+    pub fn new(service: RoutingService) -> Self {
+        Self {
+            service,
+            client: reqwest::ClientBuilder::new()
+                .user_agent("lib-slippery")
+                .build()
+                .unwrap(),
+            waypoints: Vec::new(),
+            route: None,
+            dragging: None,
+            request: 0,
+            reveal_duration: None,
+            reveal: None,
+            revealed: 1.0,
+        }
+    }
+
+    /// Reveal each new route from its start to its end over some duration, rather than
+    /// drawing it all at once. Glue [`Router::subscription`] into the application to run
+    /// the animation.
+    pub fn animate(mut self, duration: Duration) -> Self {
+        self.reveal_duration = Some(duration);
+        self
+    }
+
+    /// Produces a message for each frame while the route is being revealed.
+    pub fn subscription(&self) -> Subscription<RoutingMessage> {
+        if self.reveal.is_some() {
+            iced::window::frames().map(RoutingMessage::Frame)
+        } else {
+            Subscription::none()
+        }
+    }
+
+    pub fn waypoints(&self) -> &[Geodetic] {
+        &self.waypoints
+    }
+
+    /// The most recent route, if any.
+    pub fn route(&self) -> Option<&Route> {
+        self.route.as_ref()
+    }
+
+    /// The index of the waypoint currently being dragged, if any.
+    pub fn dragging(&self) -> Option<usize> {
+        self.dragging
+    }
+
+    /// Create a [`RouteLayer`] for drawing the route and its waypoints.
+    pub fn layer(&self) -> RouteLayer<'_> {
+        RouteLayer::new(self.route.as_ref(), &self.waypoints, self.dragging).reveal(self.revealed)
+    }
+
+    pub fn update(&mut self, message: RoutingMessage) -> Task<RoutingMessage> {
+        match message {
+            RoutingMessage::AddWaypoint(position) => {
+                self.waypoints.push(position);
+                Task::done(RoutingMessage::Query)
+            }
+            RoutingMessage::MoveWaypoint { index, position } => {
+                if let Some(waypoint) = self.waypoints.get_mut(index) {
+                    *waypoint = position;
+                    Task::done(RoutingMessage::Query)
+                } else {
+                    Task::none()
+                }
+            }
+            RoutingMessage::RemoveWaypoint(index) => {
+                if index < self.waypoints.len() {
+                    self.waypoints.remove(index);
+                    Task::done(RoutingMessage::Query)
+                } else {
+                    Task::none()
+                }
+            }
+            RoutingMessage::ClearWaypoints => {
+                self.waypoints.clear();
+                self.route = None;
+                self.dragging = None;
+                Task::none()
+            }
+            RoutingMessage::DragStart(index) => {
+                if index < self.waypoints.len() {
+                    self.dragging = Some(index);
+                }
+                Task::none()
+            }
+            RoutingMessage::DragMove(position) => {
+                if let Some(waypoint) = self.dragging.and_then(|i| self.waypoints.get_mut(i)) {
+                    *waypoint = position;
+                }
+                Task::none()
+            }
+            RoutingMessage::DragEnd => {
+                // Only query once the waypoint has been dropped
+                if self.dragging.take().is_some() {
+                    Task::done(RoutingMessage::Query)
+                } else {
+                    Task::none()
+                }
+            }
+            RoutingMessage::Query => {
+                self.request += 1;
+
+                if self.waypoints.len() < 2 {
+                    self.route = None;
+                    return Task::none();
+                }
+
+                let request = self.request;
+                let query = query_route(
+                    self.client.clone(),
+                    self.service.clone(),
+                    self.waypoints.clone(),
+                );
+
+                Task::future(query).map(move |result| match result {
+                    Ok(route) => RoutingMessage::Routed { request, route },
+                    Err(err) => {
+                        log::warn!("Unable to query route: {err}");
+                        RoutingMessage::RoutingFailed { request }
+                    }
+                })
+            }
+            RoutingMessage::Routed { request, route } => {
+                if request == self.request {
+                    self.route = Some(route);
+                    return Task::done(RoutingMessage::Reveal);
+                }
+                Task::none()
+            }
+            RoutingMessage::RoutingFailed { request } => {
+                if request == self.request {
+                    self.route = None;
+                }
+                Task::none()
+            }
+            RoutingMessage::Reveal => {
+                if let Some(duration) = self.reveal_duration {
+                    self.reveal = Some(Reveal::new(duration));
+                    self.revealed = 0.0;
+                }
+                Task::none()
+            }
+            RoutingMessage::Frame(now) => {
+                if let Some(reveal) = &self.reveal {
+                    self.revealed = reveal.progress(now);
+                    if reveal.is_finished(now) {
+                        self.reveal = None;
+                    }
+                }
+                Task::none()
+            }
+        }
+    }
+}
+
+async fn query_route(
+    client: reqwest::Client,
+    service: RoutingService,
+    waypoints: Vec<Geodetic>,
+) -> Result<Route, RoutingError> {
+    match service {
+        RoutingService::Osrm { url, profile } => {
+            let coordinates = waypoints
+                .iter()
+                .map(|w| format!("{},{}", w.longitude(), w.latitude()))
+                .collect::<Vec<_>>()
+                .join(";");
+
+            let url = format!(
+                "{}/route/v1/{profile}/{coordinates}?overview=full&geometries=polyline",
+                url.trim_end_matches('/'),
+            );
+
+            let bytes = client
+                .get(url)
+                .send()
+                .await?
+                .error_for_status()?
+                .bytes()
+                .await?;
+
+            let response: osrm::Response = serde_json::from_slice(&bytes)?;
+            let route = match response.routes.into_iter().next() {
+                Some(route) if response.code == "Ok" => route,
+                _ => return Err(RoutingError::NoRoute(response.code)),
+            };
+
+            Ok(Route {
+                geometry: decode_polyline(&route.geometry, 5).ok_or(RoutingError::Geometry)?,
+                distance: route.distance,
+                duration: Duration::from_secs_f64(route.duration.max(0.0)),
+            })
+        }
+        RoutingService::Valhalla { url, costing } => {
+            let locations = waypoints
+                .iter()
+                .map(|w| serde_json::json!({ "lat": w.latitude(), "lon": w.longitude() }))
+                .collect::<Vec<_>>();
+
+            let body = serde_json::json!({
+                "locations": locations,
+                "costing": costing,
+                "units": "kilometers",
+            });
+
+            let bytes = client
+                .post(format!("{}/route", url.trim_end_matches('/')))
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(body.to_string())
+                .send()
+                .await?
+                .error_for_status()?
+                .bytes()
+                .await?;
+
+            let response: valhalla::Response = serde_json::from_slice(&bytes)?;
+
+            // Each leg is encoded separately, and shares its first point with the previous leg
+            let mut geometry = Vec::new();
+            for leg in &response.trip.legs {
+                let shape = decode_polyline(&leg.shape, 6).ok_or(RoutingError::Geometry)?;
+                let skip = usize::from(!geometry.is_empty());
+                geometry.extend(shape.into_iter().skip(skip));
+            }
+
+            Ok(Route {
+                geometry,
+                distance: response.trip.summary.length * 1000.0,
+                duration: Duration::from_secs_f64(response.trip.summary.time.max(0.0)),
+            })
+        }
+    }
+}
+
+mod osrm {
+    #[derive(serde::Deserialize)]
+    pub struct Response {
+        pub code: String,
+        #[serde(default)]
+        pub routes: Vec<Route>,
+    }
+
+    #[derive(serde::Deserialize)]
+    pub struct Route {
+        pub geometry: String,
+        pub distance: f64,
+        pub duration: f64,
+    }
+}
+
+mod valhalla {
+    #[derive(serde::Deserialize)]
+    pub struct Response {
+        pub trip: Trip,
+    }
+
+    #[derive(serde::Deserialize)]
+    pub struct Trip {
+        pub legs: Vec<Leg>,
+        pub summary: Summary,
+    }
+
+    #[derive(serde::Deserialize)]
+    pub struct Leg {
+        pub shape: String,
+    }
+
+    #[derive(serde::Deserialize)]
+    pub struct Summary {
+        pub length: f64,
+        pub time: f64,
+    }
+}
@@ -0,0 +1,113 @@
+//! Encoding and decoding of the [polyline algorithm](https://developers.google.com/maps/documentation/utilities/polylinealgorithm)
+//! used by most routing engines to compress route geometries.
+//!
+//! OSRM uses a precision of 5 decimals by default, while Valhalla uses 6.
+
+use crate::Geodetic;
+
+/// Decode a polyline string into a list of [`Geodetic`] coordinates.
+///
+/// Returns `None` if the string is malformed.
+pub fn decode_polyline(encoded: &str, precision: u32) -> Option<Vec<Geodetic>> {
+    let factor = 10f64.powi(precision as i32);
+    let mut bytes = encoded.bytes().peekable();
+    let mut coordinates = Vec::new();
+
+    let (mut lat, mut lon) = (0i64, 0i64);
+    while bytes.peek().is_some() {
+        lat += decode_value(&mut bytes)?;
+        lon += decode_value(&mut bytes)?;
+        coordinates.push(Geodetic::new(lon as f64 / factor, lat as f64 / factor));
+    }
+
+    Some(coordinates)
+}
+
+/// Encode a list of [`Geodetic`] coordinates into a polyline string.
+pub fn encode_polyline(coordinates: &[Geodetic], precision: u32) -> String {
+    let factor = 10f64.powi(precision as i32);
+    let mut encoded = String::new();
+
+    let (mut last_lat, mut last_lon) = (0i64, 0i64);
+    for coordinate in coordinates {
+        let lat = (coordinate.latitude() * factor).round() as i64;
+        let lon = (coordinate.longitude() * factor).round() as i64;
+        encode_value(lat - last_lat, &mut encoded);
+        encode_value(lon - last_lon, &mut encoded);
+        (last_lat, last_lon) = (lat, lon);
+    }
+
+    encoded
+}
+
+fn decode_value(bytes: &mut impl Iterator<Item = u8>) -> Option<i64> {
+    let mut result = 0i64;
+    let mut shift = 0;
+
+    loop {
+        let byte = (bytes.next()? as i64).checked_sub(63)?;
+        if shift > 60 {
+            return None;
+        }
+
+        result |= (byte & 0x1f) << shift;
+        shift += 5;
+
+        if byte < 0x20 {
+            break;
+        }
+    }
+
+    // The lowest bit indicates whether the value is negative
+    Some(if result & 1 == 1 {
+        !(result >> 1)
+    } else {
+        result >> 1
+    })
+}
+
+fn encode_value(value: i64, encoded: &mut String) {
+    let mut value = if value < 0 { !(value << 1) } else { value << 1 };
+
+    while value >= 0x20 {
+        encoded.push((((value & 0x1f) | 0x20) as u8 + 63) as char);
+        value >>= 5;
+    }
+
+    encoded.push((value as u8 + 63) as char);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_reference_polyline() {
+        // Example from the reference documentation of the algorithm
+        let decoded = decode_polyline("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 5).unwrap();
+
+        let expected = [(38.5, -120.2), (40.7, -120.95), (43.252, -126.453)];
+        assert_eq!(decoded.len(), expected.len());
+        for (geodetic, (lat, lon)) in decoded.iter().zip(expected) {
+            approx::assert_relative_eq!(geodetic.latitude(), lat);
+            approx::assert_relative_eq!(geodetic.longitude(), lon);
+        }
+    }
+
+    #[test]
+    fn encode_decode_roundtrip() {
+        let coordinates = vec![
+            Geodetic::new(2.352222, 48.856613),
+            Geodetic::new(-0.127758, 51.507351),
+            Geodetic::new(13.404954, 52.520008),
+        ];
+
+        let encoded = encode_polyline(&coordinates, 6);
+        assert_eq!(decode_polyline(&encoded, 6).unwrap(), coordinates);
+    }
+
+    #[test]
+    fn decode_truncated_polyline() {
+        assert_eq!(decode_polyline("_p~iF~ps|U_ulLnnqC_mqNvxq", 5), None);
+    }
+}
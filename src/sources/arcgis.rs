@@ -14,16 +14,13 @@ impl super::Source for ArcGisWorldMap {
         )
     }
 
-    fn attribution(&self) -> Attribution {
-        Attribution {
-            text: "OpenStreetMap contributors",
-            url: "https://www.openstreetmap.org/copyright",
-            logo_light: None,
-            logo_dark: None,
-        }
+    fn attribution(&self) -> Vec<Attribution> {
+        vec![Attribution::new(
+            "Esri, Maxar, Earthstar Geographics, and the GIS User Community",
+            "https://www.esri.com/",
+        )]
     }
 
-    /// Size of each tile, should be a multiple of 256.
     fn tile_size(&self) -> u32 {
         256
     }
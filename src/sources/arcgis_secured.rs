@@ -0,0 +1,198 @@
+use std::sync::RwLock;
+
+use iced::futures::future::BoxFuture;
+
+use super::{Attribution, Source};
+use crate::tile_coord::TileCoord;
+
+/// The portal which issues tokens for ArcGIS Online.
+const ARCGIS_ONLINE: &str = "https://www.arcgis.com/sharing/rest/generateToken";
+
+/// Minutes a generated token stays valid, after which it is generated again.
+const TOKEN_EXPIRATION: u32 = 60;
+
+#[derive(thiserror::Error, Debug)]
+enum TokenError {
+    #[error(transparent)]
+    Reqwest(#[from] reqwest::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error("The portal refused to generate a token: {0}")]
+    Refused(String),
+}
+
+/// Cached tiles of a secured ArcGIS map service, which requires a token. The token is
+/// generated with the credentials of an ArcGIS account, and generated again once the service
+/// rejects it, such as when it expired.
+///
+/// ```ignore
+/// let source = ArcGisSecured::new(
+///     "https://tiles.arcgis.com/tiles/abc123/arcgis/rest/services/Parcels/MapServer",
+/// )
+/// .credentials("username", "password")
+/// .attribution("County GIS", "https://gis.example.com");
+/// let cache = TileCache::new(source);
+/// ```
+#[derive(Debug)]
+pub struct ArcGisSecured {
+    service: String,
+    portal: String,
+    credentials: Option<(String, String)>,
+    referer: String,
+    token: RwLock<Option<String>>,
+    client: reqwest::Client,
+    max_zoom: u8,
+    attribution: Vec<Attribution>,
+}
+
+impl ArcGisSecured {
+    /// The url of the `MapServer` of a cached map service.
+    pub fn new(service: impl Into<String>) -> Self {
+        Self {
+            service: service.into().trim_end_matches('/').to_string(),
+            portal: ARCGIS_ONLINE.to_string(),
+            credentials: None,
+            referer: "https://github.com/peterkrull/slippery".to_string(),
+            token: RwLock::new(None),
+            client: reqwest::Client::new(),
+            max_zoom: 19,
+            attribution: Vec::new(),
+        }
+    }
+
+    /// The account with which tokens are generated.
+    pub fn credentials(mut self, username: impl Into<String>, password: impl Into<String>) -> Self {
+        self.credentials = Some((username.into(), password.into()));
+        self
+    }
+
+    /// A token which was generated beforehand. Without credentials it is never renewed.
+    pub fn token(self, token: impl Into<String>) -> Self {
+        *self.token.write().unwrap() = Some(token.into());
+        self
+    }
+
+    /// The `generateToken` endpoint of an ArcGIS Enterprise portal, instead of ArcGIS Online.
+    pub fn portal(mut self, url: impl Into<String>) -> Self {
+        self.portal = url.into();
+        self
+    }
+
+    /// The referer which tokens are bound to, and which is sent along with each request.
+    pub fn referer(mut self, referer: impl Into<String>) -> Self {
+        self.referer = referer.into();
+        self
+    }
+
+    pub fn max_zoom(mut self, max_zoom: u8) -> Self {
+        self.max_zoom = max_zoom;
+        self
+    }
+
+    /// Credit the provider of the tiles, which can be repeated for several providers.
+    pub fn attribution(mut self, text: impl Into<String>, url: impl Into<String>) -> Self {
+        self.attribution.push(Attribution::new(text, url));
+        self
+    }
+
+    async fn generate_token(&self, username: &str, password: &str) -> Result<String, TokenError> {
+        // Encode the form like a query, keeping the password out of the url itself
+        let mut form = reqwest::Url::parse("https://localhost/").unwrap();
+        form.query_pairs_mut()
+            .append_pair("username", username)
+            .append_pair("password", password)
+            .append_pair("client", "referer")
+            .append_pair("referer", &self.referer)
+            .append_pair("expiration", &TOKEN_EXPIRATION.to_string())
+            .append_pair("f", "json");
+
+        let bytes = self
+            .client
+            .post(&self.portal)
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .body(form.query().unwrap_or_default().to_string())
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+
+        // Errors are reported in the body, along with a successful status
+        let response: serde_json::Value = serde_json::from_slice(&bytes)?;
+        match response["token"].as_str() {
+            Some(token) => Ok(token.to_string()),
+            None => Err(TokenError::Refused(
+                response["error"]["message"]
+                    .as_str()
+                    .unwrap_or("unknown error")
+                    .to_string(),
+            )),
+        }
+    }
+}
+
+impl Source for ArcGisSecured {
+    fn tile_url(&self, tile_id: TileCoord) -> String {
+        let mut url = format!(
+            "{}/tile/{}/{}/{}",
+            self.service,
+            tile_id.zoom(),
+            tile_id.y(),
+            tile_id.x()
+        );
+        if let Some(token) = &*self.token.read().unwrap() {
+            url.push_str("?token=");
+            url.push_str(token);
+        }
+        url
+    }
+
+    fn attribution(&self) -> Vec<Attribution> {
+        self.attribution.clone()
+    }
+
+    fn max_zoom(&self) -> u8 {
+        self.max_zoom
+    }
+
+    fn headers(&self, _tile_id: TileCoord) -> Vec<(String, String)> {
+        vec![("Referer".to_string(), self.referer.clone())]
+    }
+
+    fn refresh_credentials(&self) -> BoxFuture<'_, bool> {
+        Box::pin(async {
+            let Some((username, password)) = &self.credentials else {
+                return false;
+            };
+
+            match self.generate_token(username, password).await {
+                Ok(token) => {
+                    *self.token.write().unwrap() = Some(token);
+                    true
+                }
+                Err(err) => {
+                    log::warn!("Unable to generate an ArcGIS token: {err}");
+                    false
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_in_tile_url() {
+        let source = ArcGisSecured::new("https://example.com/rest/services/Parcels/MapServer/");
+        let tile_id = TileCoord::new(3, 1, 2);
+        assert_eq!(
+            source.tile_url(tile_id),
+            "https://example.com/rest/services/Parcels/MapServer/tile/2/1/3"
+        );
+
+        let source = source.token("abc");
+        assert!(source.tile_url(tile_id).ends_with("/tile/2/1/3?token=abc"));
+    }
+}
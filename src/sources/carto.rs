@@ -1,5 +1,7 @@
 use crate::{TileCoord, sources::Attribution};
 
+/// The pixel density of the tiles. On high density displays, tiles of twice this density
+/// are fetched instead, see [`crate::TileCacheBuilder::hidpi_threshold`].
 #[derive(Debug, Clone, Copy)]
 pub enum Scale {
     X1 = 1,
@@ -8,31 +10,48 @@ pub enum Scale {
     X8 = 8,
 }
 
+impl Scale {
+    /// The scale with twice the pixel density, if there is one.
+    fn doubled(self) -> Option<Self> {
+        match self {
+            Self::X1 => Some(Self::X2),
+            Self::X2 => Some(Self::X4),
+            Self::X4 => Some(Self::X8),
+            Self::X8 => None,
+        }
+    }
+}
+
+fn carto_url(style: &str, tile_id: TileCoord, scale: Scale) -> String {
+    format!(
+        "https://basemaps.cartocdn.com/{style}/{}/{}/{}@{}x.png",
+        tile_id.zoom(),
+        tile_id.x(),
+        tile_id.y(),
+        scale as u32,
+    )
+}
+
 /// <https://www.openstreetmap.org/about>
 #[derive(Debug)]
 pub struct CartoLight(pub Scale);
 
 impl super::Source for CartoLight {
     fn tile_url(&self, tile_id: TileCoord) -> String {
-        format!(
-            "https://basemaps.cartocdn.com/light_all/{}/{}/{}@{}x.png",
-            tile_id.zoom(),
-            tile_id.x(),
-            tile_id.y(),
-            self.0 as u32,
-        )
+        carto_url("light_all", tile_id, self.0)
     }
 
-    fn attribution(&self) -> Attribution {
-        Attribution {
-            text: "OpenStreetMap contributors",
-            url: "https://www.openstreetmap.org/copyright",
-            logo_light: None,
-            logo_dark: None,
-        }
+    fn tile_url_hidpi(&self, tile_id: TileCoord) -> Option<String> {
+        Some(carto_url("light_all", tile_id, self.0.doubled()?))
+    }
+
+    fn attribution(&self) -> Vec<Attribution> {
+        vec![
+            Attribution::new("© CARTO", "https://carto.com/attributions"),
+            Attribution::openstreetmap(),
+        ]
     }
 
-    /// Size of each tile, should be a multiple of 256.
     fn tile_size(&self) -> u32 {
         256 * self.0 as u32
     }
@@ -44,25 +63,20 @@ pub struct CartoDark(pub Scale);
 
 impl super::Source for CartoDark {
     fn tile_url(&self, tile_id: TileCoord) -> String {
-        format!(
-            "https://basemaps.cartocdn.com/dark_all/{}/{}/{}@{}x.png",
-            tile_id.zoom(),
-            tile_id.x(),
-            tile_id.y(),
-            self.0 as u32,
-        )
+        carto_url("dark_all", tile_id, self.0)
     }
 
-    fn attribution(&self) -> Attribution {
-        Attribution {
-            text: "OpenStreetMap contributors",
-            url: "https://www.openstreetmap.org/copyright",
-            logo_light: None,
-            logo_dark: None,
-        }
+    fn tile_url_hidpi(&self, tile_id: TileCoord) -> Option<String> {
+        Some(carto_url("dark_all", tile_id, self.0.doubled()?))
+    }
+
+    fn attribution(&self) -> Vec<Attribution> {
+        vec![
+            Attribution::new("© CARTO", "https://carto.com/attributions"),
+            Attribution::openstreetmap(),
+        ]
     }
 
-    /// Size of each tile, should be a multiple of 256.
     fn tile_size(&self) -> u32 {
         256 * self.0 as u32
     }
@@ -74,25 +88,20 @@ pub struct CartoVoyager(pub Scale);
 
 impl super::Source for CartoVoyager {
     fn tile_url(&self, tile_id: TileCoord) -> String {
-        format!(
-            "https://basemaps.cartocdn.com/rastertiles/voyager/{}/{}/{}@{}x.png",
-            tile_id.zoom(),
-            tile_id.x(),
-            tile_id.y(),
-            self.0 as u32,
-        )
+        carto_url("rastertiles/voyager", tile_id, self.0)
     }
 
-    fn attribution(&self) -> Attribution {
-        Attribution {
-            text: "OpenStreetMap contributors",
-            url: "https://www.openstreetmap.org/copyright",
-            logo_light: None,
-            logo_dark: None,
-        }
+    fn tile_url_hidpi(&self, tile_id: TileCoord) -> Option<String> {
+        Some(carto_url("rastertiles/voyager", tile_id, self.0.doubled()?))
+    }
+
+    fn attribution(&self) -> Vec<Attribution> {
+        vec![
+            Attribution::new("© CARTO", "https://carto.com/attributions"),
+            Attribution::openstreetmap(),
+        ]
     }
 
-    /// Size of each tile, should be a multiple of 256.
     fn tile_size(&self) -> u32 {
         256 * self.0 as u32
     }
@@ -112,16 +121,14 @@ impl super::Source for OpenTopo {
         )
     }
 
-    fn attribution(&self) -> Attribution {
-        Attribution {
-            text: "OpenStreetMap contributors",
-            url: "https://www.openstreetmap.org/copyright",
-            logo_light: None,
-            logo_dark: None,
-        }
+    fn attribution(&self) -> Vec<Attribution> {
+        vec![
+            Attribution::new("© OpenTopoMap (CC-BY-SA)", "https://opentopomap.org/about"),
+            Attribution::new("SRTM", "https://www2.jpl.nasa.gov/srtm/"),
+            Attribution::openstreetmap(),
+        ]
     }
 
-    /// Size of each tile, should be a multiple of 256.
     fn tile_size(&self) -> u32 {
         256
     }
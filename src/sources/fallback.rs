@@ -0,0 +1,140 @@
+use std::time::Duration;
+
+use iced::futures::future::BoxFuture;
+use iced_core::Bytes;
+
+use super::{Attribution, Source, TileFormat, TilingScheme};
+use crate::{GeoBounds, TileError, tile_coord::TileCoord};
+
+/// Fetch tiles from a secondary source whenever the primary one fails, e.g. because its
+/// server is down, or does not cover them, such as a regional source at the edge of its
+/// area. Fallbacks can be chained by nesting them.
+///
+/// ```ignore
+/// let source = FallbackSource(
+///     TemplateSource::new("https://tiles.example.com/{z}/{x}/{y}.png").max_zoom(16),
+///     OpenStreetMap,
+/// );
+/// let cache = TileCache::new(source);
+/// ```
+///
+/// The layout of the tiles is that of the primary source, so the secondary source should
+/// match it.
+#[derive(Debug, Clone)]
+pub struct FallbackSource<P, S>(pub P, pub S);
+
+impl<P: Source, S: Source> Source for FallbackSource<P, S> {
+    fn tile_url(&self, tile_id: TileCoord) -> String {
+        self.0.tile_url(tile_id)
+    }
+
+    /// The attributions of both sources, as either may be shown.
+    fn attribution(&self) -> Vec<Attribution> {
+        let mut attribution = self.0.attribution();
+        for other in self.1.attribution() {
+            if !attribution.iter().any(|known| known.text == other.text) {
+                attribution.push(other);
+            }
+        }
+        attribution
+    }
+
+    fn tile_size(&self) -> u32 {
+        self.0.tile_size()
+    }
+
+    fn tiling_scheme(&self) -> TilingScheme {
+        self.0.tiling_scheme()
+    }
+
+    fn max_zoom(&self) -> u8 {
+        self.0.max_zoom().max(self.1.max_zoom())
+    }
+
+    fn min_zoom(&self) -> u8 {
+        self.0.min_zoom().min(self.1.min_zoom())
+    }
+
+    /// The area covered by either source.
+    fn bounds(&self) -> Option<GeoBounds> {
+        let (first, second) = (self.0.bounds()?, self.1.bounds()?);
+        Some(GeoBounds::new(
+            first.west.min(second.west),
+            first.south.min(second.south),
+            first.east.max(second.east),
+            first.north.max(second.north),
+        ))
+    }
+
+    fn refresh_after(&self) -> Option<Duration> {
+        self.0.refresh_after()
+    }
+
+    fn subdomains(&self) -> &[&str] {
+        self.0.subdomains()
+    }
+
+    fn headers(&self, tile_id: TileCoord) -> Vec<(String, String)> {
+        self.0.headers(tile_id)
+    }
+
+    fn format(&self) -> Option<TileFormat> {
+        self.0.format()
+    }
+
+    fn load_tile(&self, tile_id: TileCoord) -> Option<BoxFuture<'_, Result<Bytes, TileError>>> {
+        self.0.load_tile(tile_id)
+    }
+
+    fn time(&self) -> Option<String> {
+        self.0.time()
+    }
+
+    fn set_time(&self, time: &str) -> bool {
+        // Both sources follow the time
+        self.0.set_time(time) | self.1.set_time(time)
+    }
+
+    fn refresh_credentials(&self) -> BoxFuture<'_, bool> {
+        self.0.refresh_credentials()
+    }
+
+    fn fallback(&self) -> Option<&dyn Source> {
+        Some(&self.1)
+    }
+
+    fn tile_url_hidpi(&self, tile_id: TileCoord) -> Option<String> {
+        self.0.tile_url_hidpi(tile_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sources::{OpenStreetMap, TemplateSource};
+
+    #[test]
+    fn fallback_coverage() {
+        let regional = TemplateSource::new("https://example.com/{z}/{x}/{y}.png")
+            .min_zoom(6)
+            .bounds(GeoBounds::new(3.2, 50.7, 7.3, 53.6))
+            .attribution("© Example", "https://example.com")
+            .attribution("© OpenStreetMap contributors", "");
+        let source = FallbackSource(regional, OpenStreetMap);
+
+        // Both are credited, but only once
+        let credits: Vec<_> = source.attribution().into_iter().map(|a| a.text).collect();
+        assert_eq!(credits, ["© Example", "© OpenStreetMap contributors"]);
+
+        // Everything the fallback covers is requested
+        assert_eq!((source.min_zoom(), source.bounds()), (0, None));
+        assert_eq!(
+            source.fallback().unwrap().tile_url(TileCoord::ZERO),
+            OpenStreetMap.tile_url(TileCoord::ZERO)
+        );
+
+        let tile_id = crate::location::rome().as_mercator().tile_id(8);
+        assert!(!crate::sources::covers(&source.0, &tile_id));
+        assert!(crate::sources::covers(source.fallback().unwrap(), &tile_id));
+    }
+}
@@ -0,0 +1,92 @@
+use std::path::PathBuf;
+
+use iced::futures::future::BoxFuture;
+use iced_core::Bytes;
+
+use super::{Attribution, Source, unblock};
+use crate::{TileError, tile_coord::TileCoord};
+
+/// Tiles read from a local directory laid out as `{z}/{x}/{y}.png`, as written by most tile
+/// rendering and download tools. This needs no network, and also works without the `http`
+/// feature.
+///
+/// ```ignore
+/// let cache = TileCache::new(FileSource::new("/srv/tiles").extension("jpg"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct FileSource {
+    root: PathBuf,
+    extension: String,
+    tile_size: u32,
+    max_zoom: u8,
+    attribution: Vec<Attribution>,
+}
+
+impl FileSource {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            extension: "png".to_string(),
+            tile_size: 256,
+            max_zoom: 19,
+            attribution: Vec::new(),
+        }
+    }
+
+    /// The extension of the tile files, `png` unless set otherwise.
+    pub fn extension(mut self, extension: impl Into<String>) -> Self {
+        self.extension = extension.into();
+        self
+    }
+
+    pub fn tile_size(mut self, tile_size: u32) -> Self {
+        self.tile_size = tile_size;
+        self
+    }
+
+    pub fn max_zoom(mut self, max_zoom: u8) -> Self {
+        self.max_zoom = max_zoom;
+        self
+    }
+
+    /// Credit the provider of the tiles, which can be repeated for several providers.
+    pub fn attribution(mut self, text: impl Into<String>, url: impl Into<String>) -> Self {
+        self.attribution.push(Attribution::new(text, url));
+        self
+    }
+
+    fn tile_path(&self, tile_id: TileCoord) -> PathBuf {
+        self.root.join(format!(
+            "{}/{}/{}.{}",
+            tile_id.zoom(),
+            tile_id.x(),
+            tile_id.y(),
+            self.extension
+        ))
+    }
+}
+
+impl Source for FileSource {
+    fn tile_url(&self, tile_id: TileCoord) -> String {
+        format!("file://{}", self.tile_path(tile_id).display())
+    }
+
+    fn load_tile(&self, tile_id: TileCoord) -> Option<BoxFuture<'_, Result<Bytes, TileError>>> {
+        let path = self.tile_path(tile_id);
+        Some(Box::pin(unblock(move || {
+            Ok(Bytes::from(std::fs::read(path)?))
+        })))
+    }
+
+    fn attribution(&self) -> Vec<Attribution> {
+        self.attribution.clone()
+    }
+
+    fn tile_size(&self) -> u32 {
+        self.tile_size
+    }
+
+    fn max_zoom(&self) -> u8 {
+        self.max_zoom
+    }
+}
@@ -1,19 +1,43 @@
-use super::{Attribution, Source};
-use crate::tile_coord::TileCoord;
+use super::{Attribution, Source, WmsSource};
+use crate::{GeoBounds, tile_coord::TileCoord};
 
-/// Orthophotomap layer from Poland's Geoportal.
+/// The area of Poland, which the Geoportal covers.
+const POLAND: GeoBounds = GeoBounds::new(14.07, 49.0, 24.15, 54.84);
+
+/// The layers of Poland's Geoportal.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GeoportalLayer {
+    /// Aerial imagery of the whole country.
+    #[default]
+    Orthophoto,
+    /// Topographic maps, as printed at scales down to 1:10 000.
+    Topographic,
+    /// Land parcels and their numbers from the cadastre, to be drawn on top of another layer.
+    Cadastral,
+}
+
+/// Layers from Poland's Geoportal, the orthophotomap unless chosen otherwise.
 /// <https://www.geoportal.gov.pl/uslugi/usluga-przegladania-wms>
-#[derive(Debug)]
-pub struct Geoportal;
+#[derive(Debug, Default)]
+pub struct Geoportal(pub GeoportalLayer);
 
-impl Source for Geoportal {
-    fn tile_url(&self, tile_id: TileCoord) -> String {
+impl Geoportal {
+    /// The parcels are only served by the national integration of the cadastre, over WMS.
+    fn cadastre() -> WmsSource {
+        WmsSource::new(
+            "https://integracja.gugik.gov.pl/cgi-bin/KrajowaIntegracjaEwidencjiGruntow",
+            ["dzialki", "numery_dzialek"],
+        )
+        .transparent(true)
+    }
+
+    fn wmts_url(service: &str, layer: &str, tile_id: TileCoord) -> String {
         format!(
-            "https://mapy.geoportal.gov.pl/wss/service/PZGIK/ORTO/WMTS/StandardResolution?\
+            "https://mapy.geoportal.gov.pl/wss/service/{service}?\
             &SERVICE=WMTS\
             &REQUEST=GetTile\
             &VERSION=1.0.0\
-            &LAYER=ORTOFOTOMAPA\
+            &LAYER={layer}\
             &TILEMATRIXSET=EPSG:3857\
             &TILEMATRIX=EPSG:3857:{}\
             &TILEROW={}\
@@ -23,13 +47,57 @@ impl Source for Geoportal {
             tile_id.x()
         )
     }
+}
+
+impl Source for Geoportal {
+    fn tile_url(&self, tile_id: TileCoord) -> String {
+        match self.0 {
+            GeoportalLayer::Orthophoto => Self::wmts_url(
+                "PZGIK/ORTO/WMTS/StandardResolution",
+                "ORTOFOTOMAPA",
+                tile_id,
+            ),
+            GeoportalLayer::Topographic => {
+                Self::wmts_url("WMTS/guest/wmts/TOPO", "MAPA%20TOPOGRAFICZNA", tile_id)
+            }
+            GeoportalLayer::Cadastral => Self::cadastre().tile_url(tile_id),
+        }
+    }
+
+    fn tile_url_hidpi(&self, tile_id: TileCoord) -> Option<String> {
+        match self.0 {
+            GeoportalLayer::Cadastral => Self::cadastre().tile_url_hidpi(tile_id),
+            _ => None,
+        }
+    }
+
+    fn attribution(&self) -> Vec<Attribution> {
+        let source = match self.0 {
+            GeoportalLayer::Orthophoto | GeoportalLayer::Topographic => {
+                "Główny Urząd Geodezji i Kartografii"
+            }
+            GeoportalLayer::Cadastral => "Krajowa Integracja Ewidencji Gruntów, GUGiK",
+        };
+        vec![Attribution::new(source, "https://www.geoportal.gov.pl/")]
+    }
+
+    fn max_zoom(&self) -> u8 {
+        match self.0 {
+            GeoportalLayer::Orthophoto => 19,
+            GeoportalLayer::Topographic => 17,
+            GeoportalLayer::Cadastral => 20,
+        }
+    }
 
-    fn attribution(&self) -> Attribution {
-        Attribution {
-            text: "Główny Urząd Geodezji i Kartografii",
-            url: "https://www.geoportal.gov.pl/",
-            logo_light: None,
-            logo_dark: None,
+    /// Parcels are too small to be told apart when zoomed out further.
+    fn min_zoom(&self) -> u8 {
+        match self.0 {
+            GeoportalLayer::Cadastral => 13,
+            _ => 0,
         }
     }
+
+    fn bounds(&self) -> Option<GeoBounds> {
+        Some(POLAND)
+    }
 }
@@ -0,0 +1,131 @@
+use super::{Attribution, Source, TileFormat};
+use crate::tile_coord::TileCoord;
+
+/// Imagery layers of NASA GIBS. Each is only available down to the zoom level of its
+/// resolution, which the tile matrix set in its urls is named after.
+/// <https://nasa-gibs.github.io/gibs-api-docs/available-visualizations/>
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GibsLayer {
+    /// True color imagery of the MODIS instrument on the Terra satellite, taken mornings.
+    ModisTerraTrueColor,
+    /// True color imagery of the MODIS instrument on the Aqua satellite, taken afternoons.
+    ModisAquaTrueColor,
+    /// True color imagery of the VIIRS instrument on the Suomi NPP satellite.
+    ViirsTrueColor,
+    /// The Black Marble composite of the earth at night, which is not updated daily.
+    ViirsNightLights,
+    /// Any other layer offered in Web Mercator, with the zoom level of its
+    /// `GoogleMapsCompatible_Level` tile matrix set and its format.
+    Other {
+        name: String,
+        max_zoom: u8,
+        format: TileFormat,
+    },
+}
+
+impl GibsLayer {
+    fn name(&self) -> &str {
+        match self {
+            Self::ModisTerraTrueColor => "MODIS_Terra_CorrectedReflectance_TrueColor",
+            Self::ModisAquaTrueColor => "MODIS_Aqua_CorrectedReflectance_TrueColor",
+            Self::ViirsTrueColor => "VIIRS_SNPP_CorrectedReflectance_TrueColor",
+            Self::ViirsNightLights => "VIIRS_Black_Marble",
+            Self::Other { name, .. } => name,
+        }
+    }
+
+    fn max_zoom(&self) -> u8 {
+        match self {
+            Self::ModisTerraTrueColor | Self::ModisAquaTrueColor | Self::ViirsTrueColor => 9,
+            Self::ViirsNightLights => 8,
+            Self::Other { max_zoom, .. } => *max_zoom,
+        }
+    }
+
+    fn format(&self) -> TileFormat {
+        match self {
+            Self::ModisTerraTrueColor | Self::ModisAquaTrueColor | Self::ViirsTrueColor => {
+                TileFormat::Jpeg
+            }
+            Self::ViirsNightLights => TileFormat::Png,
+            Self::Other { format, .. } => *format,
+        }
+    }
+}
+
+/// Free satellite imagery from NASA's Global Imagery Browse Services, of a given day.
+///
+/// ```ignore
+/// let source = NasaGibs::new(GibsLayer::ModisTerraTrueColor).date("2024-06-01");
+/// let cache = TileCache::new(source);
+/// ```
+///
+/// Wrap it in a [`super::TimedSource`] with the date `{time}` to step through the days.
+#[derive(Debug, Clone)]
+pub struct NasaGibs {
+    layer: GibsLayer,
+    date: String,
+}
+
+impl NasaGibs {
+    /// The layer at its most recent date.
+    pub fn new(layer: GibsLayer) -> Self {
+        Self {
+            layer,
+            date: "default".to_string(),
+        }
+    }
+
+    /// The day of the imagery, as `YYYY-MM-DD`.
+    pub fn date(mut self, date: impl Into<String>) -> Self {
+        self.date = date.into();
+        self
+    }
+}
+
+impl Source for NasaGibs {
+    fn tile_url(&self, tile_id: TileCoord) -> String {
+        format!(
+            "https://gibs.earthdata.nasa.gov/wmts/epsg3857/best/{}/default/{}/\
+            GoogleMapsCompatible_Level{}/{}/{}/{}.{}",
+            self.layer.name(),
+            self.date,
+            self.layer.max_zoom(),
+            tile_id.zoom(),
+            tile_id.y(),
+            tile_id.x(),
+            self.layer.format().extension()
+        )
+    }
+
+    fn attribution(&self) -> Vec<Attribution> {
+        vec![Attribution::new(
+            "NASA EOSDIS GIBS",
+            "https://earthdata.nasa.gov/gibs",
+        )]
+    }
+
+    fn max_zoom(&self) -> u8 {
+        self.layer.max_zoom()
+    }
+
+    fn format(&self) -> Option<TileFormat> {
+        Some(self.layer.format())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tile_matrix_of_layer() {
+        let source = NasaGibs::new(GibsLayer::ModisTerraTrueColor).date("2024-06-01");
+        assert_eq!(
+            source.tile_url(TileCoord::new(3, 1, 2)),
+            "https://gibs.earthdata.nasa.gov/wmts/epsg3857/best/\
+            MODIS_Terra_CorrectedReflectance_TrueColor/default/2024-06-01/\
+            GoogleMapsCompatible_Level9/2/1/3.jpg"
+        );
+    }
+}
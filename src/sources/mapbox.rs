@@ -2,9 +2,9 @@ use crate::tile_coord::TileCoord;
 
 use super::{Attribution, Source};
 
-/// Predefined Mapbox styles.
+/// Predefined Mapbox styles, or one designed in Mapbox Studio.
 /// <https://docs.mapbox.com/api/maps/styles/#classic-mapbox-styles>
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Default)]
 pub enum MapboxStyle {
     #[default]
     Streets,
@@ -15,11 +15,14 @@ pub enum MapboxStyle {
     SatelliteStreets,
     NavigationDay,
     NavigationNight,
+    /// A custom style, given as `username/style_id` like in the style url of Mapbox Studio.
+    Custom(String),
 }
 
 impl MapboxStyle {
-    fn api_slug(&self) -> &'static str {
-        match self {
+    /// The owner and id of the style.
+    fn api_path(&self) -> String {
+        let slug = match self {
             Self::Streets => "streets-v12",
             Self::Outdoors => "outdoors-v12",
             Self::Light => "light-v11",
@@ -28,48 +31,95 @@ impl MapboxStyle {
             Self::SatelliteStreets => "satellite-streets-v12",
             Self::NavigationDay => "navigation-day-v1",
             Self::NavigationNight => "navigation-night-v1",
-        }
+            Self::Custom(path) => return path.trim_start_matches("mapbox://styles/").to_string(),
+        };
+        format!("mapbox/{slug}")
     }
 }
 
 /// Mapbox static tile source.
 /// <https://docs.mapbox.com/api/maps/static-tiles/>
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct Mapbox {
-    /// Predefined style to use
+    /// Predefined or custom style to use
     pub style: MapboxStyle,
-    /// Render tiles at 1024x1024 instead of 512x512 (@2x)
+    /// Render tiles at twice their size, e.g. 1024x1024 instead of 512x512 (@2x)
     pub high_resolution: bool,
     /// Mapbox API key, required
     pub access_token: String,
+    /// Size of the tiles, either 256 or 512
+    pub tile_size: u32,
 }
 
-impl Source for Mapbox {
-    fn tile_url(&self, tile_id: TileCoord) -> String {
+impl Default for Mapbox {
+    fn default() -> Self {
+        Self {
+            style: MapboxStyle::default(),
+            high_resolution: false,
+            access_token: String::new(),
+            tile_size: 512,
+        }
+    }
+}
+
+impl Mapbox {
+    fn url(&self, tile_id: TileCoord, high_resolution: bool) -> String {
         format!(
-            "https://api.mapbox.com/styles/v1/mapbox/{}/tiles/512/{}/{}/{}{}?access_token={}",
-            self.style.api_slug(),
+            "https://api.mapbox.com/styles/v1/{}/tiles/{}/{}/{}/{}{}?access_token={}",
+            self.style.api_path(),
+            self.tile_size,
             tile_id.zoom(),
             tile_id.x(),
             tile_id.y(),
-            if self.high_resolution { "@2x" } else { "" },
+            if high_resolution { "@2x" } else { "" },
             self.access_token
         )
     }
+}
 
-    fn attribution(&self) -> Attribution {
-        // TODO: Proper linking (https://docs.mapbox.com/help/getting-started/attribution/))
-        Attribution {
-            text: "© Mapbox, © OpenStreetMap",
-            url: "https://www.mapbox.com/about/maps/",
-            logo_light: None,
-            logo_dark: None,
-            // logo_light: Some(egui::include_image!("../../assets/mapbox-logo-white.svg")),
-            // logo_dark: Some(egui::include_image!("../../assets/mapbox-logo-black.svg")),
-        }
+impl Source for Mapbox {
+    fn tile_url(&self, tile_id: TileCoord) -> String {
+        self.url(tile_id, self.high_resolution)
+    }
+
+    fn tile_url_hidpi(&self, tile_id: TileCoord) -> Option<String> {
+        (!self.high_resolution).then(|| self.url(tile_id, true))
+    }
+
+    /// <https://docs.mapbox.com/help/getting-started/attribution/>
+    fn attribution(&self) -> Vec<Attribution> {
+        vec![
+            Attribution::new("© Mapbox", "https://www.mapbox.com/about/maps/"),
+            Attribution::openstreetmap(),
+            Attribution::new("Improve this map", "https://www.mapbox.com/map-feedback/"),
+        ]
     }
 
     fn tile_size(&self) -> u32 {
-        512
+        self.tile_size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn custom_style() {
+        let source = Mapbox {
+            style: MapboxStyle::Custom("mapbox://styles/someone/abc123".to_string()),
+            access_token: "token".to_string(),
+            tile_size: 256,
+            ..Default::default()
+        };
+        assert_eq!(
+            source.tile_url_hidpi(TileCoord::new(3, 1, 2)).unwrap(),
+            "https://api.mapbox.com/styles/v1/someone/abc123/tiles/256/2/3/1@2x?access_token=token"
+        );
+        assert!(
+            Mapbox::default().tile_url(TileCoord::ZERO).starts_with(
+                "https://api.mapbox.com/styles/v1/mapbox/streets-v12/tiles/512/0/0/0?"
+            )
+        );
     }
 }
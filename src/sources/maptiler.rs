@@ -0,0 +1,111 @@
+use crate::tile_coord::TileCoord;
+
+use super::{Attribution, Source, TileFormat};
+
+/// Predefined MapTiler maps.
+/// <https://docs.maptiler.com/cloud/api/maps/>
+#[derive(Debug, Clone, Copy, Default)]
+pub enum MapTilerStyle {
+    #[default]
+    Streets,
+    Basic,
+    Bright,
+    Outdoor,
+    Topo,
+    Winter,
+    Satellite,
+    Hybrid,
+    Dataviz,
+    Toner,
+}
+
+impl MapTilerStyle {
+    fn api_slug(&self) -> &'static str {
+        match self {
+            Self::Streets => "streets-v2",
+            Self::Basic => "basic-v2",
+            Self::Bright => "bright-v2",
+            Self::Outdoor => "outdoor-v2",
+            Self::Topo => "topo-v2",
+            Self::Winter => "winter-v2",
+            Self::Satellite => "satellite",
+            Self::Hybrid => "hybrid",
+            Self::Dataviz => "dataviz",
+            Self::Toner => "toner-v2",
+        }
+    }
+
+    /// Imagery is served as JPEG, while the rendered maps are PNG.
+    fn format(&self) -> TileFormat {
+        match self {
+            Self::Satellite | Self::Hybrid => TileFormat::Jpeg,
+            _ => TileFormat::Png,
+        }
+    }
+}
+
+/// MapTiler raster tile source.
+/// <https://docs.maptiler.com/cloud/api/maps/#raster-xyz-tiles>
+#[derive(Debug, Default)]
+pub struct MapTiler {
+    /// Predefined map to use
+    pub style: MapTilerStyle,
+    /// Render tiles at 1024x1024 instead of 512x512 (@2x)
+    pub high_resolution: bool,
+    /// Encoding of the tiles, where WebP roughly halves the size of imagery. Defaults to
+    /// JPEG for imagery and PNG otherwise
+    pub format: Option<TileFormat>,
+    /// MapTiler API key, required
+    pub api_key: String,
+}
+
+impl MapTiler {
+    fn tile_format(&self) -> TileFormat {
+        self.format.unwrap_or(self.style.format())
+    }
+
+    fn url(&self, tile_id: TileCoord, high_resolution: bool) -> String {
+        format!(
+            "https://api.maptiler.com/maps/{}/{}/{}/{}{}.{}?key={}",
+            self.style.api_slug(),
+            tile_id.zoom(),
+            tile_id.x(),
+            tile_id.y(),
+            if high_resolution { "@2x" } else { "" },
+            self.tile_format().extension(),
+            self.api_key
+        )
+    }
+}
+
+impl Source for MapTiler {
+    fn tile_url(&self, tile_id: TileCoord) -> String {
+        self.url(tile_id, self.high_resolution)
+    }
+
+    fn tile_url_hidpi(&self, tile_id: TileCoord) -> Option<String> {
+        (!self.high_resolution).then(|| self.url(tile_id, true))
+    }
+
+    fn attribution(&self) -> Vec<Attribution> {
+        vec![
+            Attribution::new("© MapTiler", "https://www.maptiler.com/copyright/"),
+            Attribution::openstreetmap(),
+        ]
+    }
+
+    fn format(&self) -> Option<TileFormat> {
+        Some(self.tile_format())
+    }
+
+    fn tile_size(&self) -> u32 {
+        512
+    }
+
+    fn max_zoom(&self) -> u8 {
+        match self.style {
+            MapTilerStyle::Satellite | MapTilerStyle::Hybrid => 20,
+            _ => 22,
+        }
+    }
+}
@@ -0,0 +1,315 @@
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use iced::futures::future::BoxFuture;
+use iced_core::Bytes;
+use rusqlite::{Connection, OpenFlags, OptionalExtension};
+
+use super::{Attribution, Source, TileFormat, unblock};
+#[cfg(feature = "http")]
+use super::{TileGrid, TileOrigin, TilingScheme};
+use crate::{GeoBounds, TileError, tile_coord::TileCoord};
+
+#[derive(thiserror::Error, Debug)]
+pub enum MbTilesError {
+    #[error(transparent)]
+    Sqlite(#[from] rusqlite::Error),
+    /// Only raster tilesets can be drawn, while this one holds vector tiles.
+    #[error("The tileset holds vector tiles")]
+    VectorTiles,
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// Tiles can only be exported from a [`crate::TileCacheBuilder::disk_cache`].
+    #[error("The tile cache has no disk cache to export")]
+    NoDiskCache,
+    /// Tiles of a geographic grid are reprojected when drawn, and can not be exported.
+    #[error("Only tiles of the Web Mercator grid can be exported")]
+    Grid,
+}
+
+/// Tiles read from an [MBTiles](https://github.com/mapbox/mbtiles-spec) file, a SQLite
+/// database holding a pre-rendered tileset, such that a basemap can be shipped along with an
+/// offline application. The zoom levels, bounds and format are taken from its metadata.
+///
+/// ```ignore
+/// let source = MbTiles::open("basemap.mbtiles")?
+///     .attribution("OpenStreetMap contributors", "https://www.openstreetmap.org/copyright");
+/// let cache = TileCache::new(source);
+/// ```
+#[derive(Debug)]
+pub struct MbTiles {
+    // Shared with the blocking threads which query it
+    connection: Arc<Mutex<Connection>>,
+    format: Option<TileFormat>,
+    min_zoom: u8,
+    max_zoom: u8,
+    bounds: Option<GeoBounds>,
+    tile_size: u32,
+    attribution: Vec<Attribution>,
+}
+
+impl MbTiles {
+    /// Open a tileset for reading.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, MbTilesError> {
+        let connection = Connection::open_with_flags(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+
+        // Both a missing row and an empty value mean that the tileset does not say
+        let metadata = |name: &str| {
+            connection
+                .query_row(
+                    "SELECT value FROM metadata WHERE name = ?1",
+                    [name],
+                    |row| row.get::<_, Option<String>>(0),
+                )
+                .optional()
+                .map(|value| value.flatten().filter(|value| !value.is_empty()))
+        };
+
+        let format = match metadata("format")?.as_deref() {
+            Some("pbf") => return Err(MbTilesError::VectorTiles),
+            Some("png") => Some(TileFormat::Png),
+            Some("jpg" | "jpeg") => Some(TileFormat::Jpeg),
+            Some("webp") => Some(TileFormat::WebP),
+            _ => None,
+        };
+
+        let zoom = |name| metadata(name).map(|zoom| zoom.and_then(|zoom| zoom.parse().ok()));
+        let min_zoom = zoom("minzoom")?.unwrap_or(0);
+        let max_zoom = zoom("maxzoom")?.unwrap_or(19);
+
+        // Given as `west,south,east,north`
+        let bounds = metadata("bounds")?.and_then(|bounds| {
+            let values: Vec<f64> = bounds
+                .split(',')
+                .map(|value| value.trim().parse())
+                .collect::<Result<_, _>>()
+                .ok()?;
+            match values[..] {
+                [west, south, east, north] => Some(GeoBounds::new(west, south, east, north)),
+                _ => None,
+            }
+        });
+
+        Ok(Self {
+            connection: Arc::new(Mutex::new(connection)),
+            format,
+            min_zoom,
+            max_zoom,
+            bounds,
+            tile_size: 256,
+            attribution: Vec::new(),
+        })
+    }
+
+    pub fn tile_size(mut self, tile_size: u32) -> Self {
+        self.tile_size = tile_size;
+        self
+    }
+
+    /// Credit the provider of the tiles, which can be repeated for several providers.
+    pub fn attribution(mut self, text: impl Into<String>, url: impl Into<String>) -> Self {
+        self.attribution.push(Attribution::new(text, url));
+        self
+    }
+}
+
+impl Source for MbTiles {
+    fn tile_url(&self, tile_id: TileCoord) -> String {
+        format!(
+            "mbtiles://{}/{}/{}",
+            tile_id.zoom(),
+            tile_id.x(),
+            tile_id.y()
+        )
+    }
+
+    fn load_tile(&self, tile_id: TileCoord) -> Option<BoxFuture<'_, Result<Bytes, TileError>>> {
+        // Rows are counted from the bottom, as in the TMS specification
+        let row = (1u32 << tile_id.zoom()) - 1 - tile_id.y();
+        let connection = self.connection.clone();
+        Some(Box::pin(unblock(move || {
+            let connection = connection
+                .lock()
+                .map_err(|_| TileError::Io("A query of the tileset panicked".to_string()))?;
+            connection
+                .query_row(
+                    "SELECT tile_data FROM tiles \
+                    WHERE zoom_level = ?1 AND tile_column = ?2 AND tile_row = ?3",
+                    (tile_id.zoom(), tile_id.x(), row),
+                    |row| row.get::<_, Vec<u8>>(0),
+                )
+                .optional()
+                .map_err(|err| TileError::Io(err.to_string()))?
+                .map(Bytes::from)
+                .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::NotFound).into())
+        })))
+    }
+
+    fn attribution(&self) -> Vec<Attribution> {
+        self.attribution.clone()
+    }
+
+    fn tile_size(&self) -> u32 {
+        self.tile_size
+    }
+
+    fn max_zoom(&self) -> u8 {
+        self.max_zoom
+    }
+
+    fn min_zoom(&self) -> u8 {
+        self.min_zoom
+    }
+
+    fn bounds(&self) -> Option<GeoBounds> {
+        self.bounds
+    }
+
+    fn format(&self) -> Option<TileFormat> {
+        self.format
+    }
+}
+
+/// Write the tiles of a disk cache, laid out as `{z}/{x}/{y}` in the numbering of their
+/// source, into an MBTiles file with the given metadata. Returns the number of tiles written.
+#[cfg(feature = "http")]
+pub(crate) fn export_mbtiles(
+    dir: &Path,
+    path: &Path,
+    scheme: TilingScheme,
+    metadata: &[(&str, String)],
+) -> Result<usize, MbTilesError> {
+    if scheme.grid != TileGrid::WebMercator {
+        return Err(MbTilesError::Grid);
+    }
+
+    let mut connection = Connection::open(path)?;
+    let transaction = connection.transaction()?;
+    transaction.execute_batch(
+        "CREATE TABLE IF NOT EXISTS metadata (name TEXT PRIMARY KEY, value TEXT);
+        CREATE TABLE IF NOT EXISTS tiles (zoom_level INTEGER, tile_column INTEGER, tile_row INTEGER, tile_data BLOB);
+        CREATE UNIQUE INDEX IF NOT EXISTS tile_index ON tiles (zoom_level, tile_column, tile_row);",
+    )?;
+    for (name, value) in metadata {
+        transaction.execute(
+            "INSERT OR REPLACE INTO metadata VALUES (?1, ?2)",
+            (name, value),
+        )?;
+    }
+
+    // Only numbered entries are tiles, leaving out high density tiles and their validators
+    let numbered = |dir: &Path| -> std::io::Result<Vec<(u32, std::path::PathBuf)>> {
+        let mut entries = Vec::new();
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            if let Some(number) = entry
+                .file_name()
+                .to_str()
+                .and_then(|name| name.parse().ok())
+            {
+                entries.push((number, entry.path()));
+            }
+        }
+        Ok(entries)
+    };
+
+    let mut written = 0;
+    for (zoom, zoom_dir) in numbered(dir)? {
+        // The zoom level of the map, rather than that of the requests
+        let Some(zoom) = zoom
+            .checked_sub(scheme.zoom_offset as u32)
+            .filter(|zoom| *zoom < 32 && zoom_dir.is_dir())
+        else {
+            continue;
+        };
+        for (x, column_dir) in numbered(&zoom_dir)? {
+            for (y, tile) in numbered(&column_dir)? {
+                // Rows are counted from the bottom, as in the TMS specification
+                let row = match scheme.origin {
+                    TileOrigin::BottomLeft => y,
+                    TileOrigin::TopLeft => (1u32 << zoom).saturating_sub(1 + y),
+                };
+                transaction.execute(
+                    "INSERT OR REPLACE INTO tiles VALUES (?1, ?2, ?3, ?4)",
+                    (zoom, x, row, std::fs::read(tile)?),
+                )?;
+                written += 1;
+            }
+        }
+    }
+
+    transaction.commit()?;
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(source: &MbTiles, tile_id: TileCoord) -> Result<Bytes, TileError> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(source.load_tile(tile_id).unwrap())
+    }
+
+    #[test]
+    fn read_tiles_and_metadata() {
+        let path = std::env::temp_dir().join(format!("slippery-{}.mbtiles", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        {
+            let connection = Connection::open(&path).unwrap();
+            connection
+                .execute_batch(
+                    "CREATE TABLE metadata (name TEXT, value TEXT);
+                    CREATE TABLE tiles (zoom_level INTEGER, tile_column INTEGER, tile_row INTEGER, tile_data BLOB);
+                    INSERT INTO metadata VALUES ('format', 'png'), ('minzoom', '1'), ('maxzoom', '5'), ('bounds', '3.2,50.7,7.3,53.6');
+                    INSERT INTO tiles VALUES (1, 1, 0, x'0102');",
+                )
+                .unwrap();
+        }
+
+        let source = MbTiles::open(&path).unwrap();
+        assert_eq!((source.min_zoom(), source.max_zoom()), (1, 5));
+        assert_eq!(source.bounds(), Some(GeoBounds::new(3.2, 50.7, 7.3, 53.6)));
+        assert_eq!(Source::format(&source), Some(TileFormat::Png));
+
+        // The bottom right tile of the first zoom level
+        let tile = load(&source, TileCoord::new(1, 1, 1));
+        assert_eq!(tile.unwrap(), vec![1u8, 2]);
+        let missing = load(&source, TileCoord::new(0, 0, 1));
+        assert!(matches!(missing, Err(TileError::Io(_))));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "http")]
+    fn export_disk_cache() {
+        let dir = std::env::temp_dir().join(format!("slippery-export-{}", std::process::id()));
+        let path = dir.with_extension("mbtiles");
+        let _ = std::fs::remove_file(&path);
+        std::fs::create_dir_all(dir.join("1/1")).unwrap();
+        std::fs::write(dir.join("1/1/0"), [1, 2]).unwrap();
+        std::fs::write(dir.join("1/1/0.meta"), "etag abc").unwrap();
+        std::fs::write(dir.join("1/1/0@2x"), [3]).unwrap();
+
+        let metadata = [("format", "png".to_string()), ("maxzoom", "5".to_string())];
+        let written = export_mbtiles(&dir, &path, TilingScheme::new(256), &metadata).unwrap();
+        assert_eq!(written, 1);
+
+        // The top right tile of the first zoom level
+        let source = MbTiles::open(&path).unwrap();
+        assert_eq!(source.max_zoom(), 5);
+        let tile = load(&source, TileCoord::new(1, 0, 1));
+        assert_eq!(tile.unwrap(), vec![1u8, 2]);
+
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
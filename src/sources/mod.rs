@@ -1,41 +1,282 @@
 //! Some common HTTP tile sources. Make sure you follow terms of usage of the particular source.
 
-use iced_core::image::Image;
+use std::time::Duration;
+
+use iced::futures::future::BoxFuture;
+use iced_core::{Bytes, image::Image};
 
 mod arcgis;
+#[cfg(feature = "arcgis")]
+mod arcgis_secured;
 mod carto;
+mod fallback;
+mod file;
 mod geoportal;
+mod gibs;
 mod mapbox;
+mod maptiler;
+#[cfg(feature = "mbtiles")]
+mod mbtiles;
+mod openseamap;
 mod openstreetmap;
+mod rainviewer;
 mod stadia;
+mod template;
+mod terrain;
+mod thunderforest;
+mod tiling;
+mod timed;
+mod wms;
 
-use crate::tile_coord::TileCoord;
+use crate::{GeoBounds, TileError, tile_coord::TileCoord};
 pub use arcgis::ArcGisWorldMap;
+#[cfg(feature = "arcgis")]
+pub use arcgis_secured::ArcGisSecured;
 pub use carto::*;
-pub use geoportal::Geoportal;
+pub use fallback::FallbackSource;
+pub use file::FileSource;
+pub use geoportal::{Geoportal, GeoportalLayer};
+pub use gibs::{GibsLayer, NasaGibs};
 pub use mapbox::{Mapbox, MapboxStyle};
+pub use maptiler::{MapTiler, MapTilerStyle};
+#[cfg(all(feature = "mbtiles", feature = "http"))]
+pub(crate) use mbtiles::export_mbtiles;
+#[cfg(feature = "mbtiles")]
+pub use mbtiles::{MbTiles, MbTilesError};
+pub use openseamap::OpenSeaMap;
 pub use openstreetmap::OpenStreetMap;
+pub use rainviewer::RainViewer;
 pub use stadia::StadiaBright;
+pub use template::TemplateSource;
+pub use terrain::{MapboxTerrain, Terrarium};
+pub use thunderforest::{
+    OpenCycleMap, ThunderforestLandscape, ThunderforestOutdoors, ThunderforestTransport,
+};
+pub use tiling::{TileGrid, TileOrigin, TilingScheme, Tms};
+pub use timed::TimedSource;
+pub use wms::{WmsSource, WmsVersion};
 
-#[derive(Clone)]
+/// Credit to the provider of tiles or of the data rendered in them, usually with a link to
+/// its terms. Sources return several of them, e.g. for the provider and for OpenStreetMap.
+#[derive(Debug, Clone)]
 pub struct Attribution {
-    pub text: &'static str,
-    pub url: &'static str,
+    pub text: String,
+    pub url: String,
+    /// Whether the terms of the provider require the attribution to be shown with the map.
+    pub required: bool,
     pub logo_light: Option<Image>,
     pub logo_dark: Option<Image>,
 }
 
-/// Remote tile server definition, source for the [`crate::HttpTiles`].
+impl Attribution {
+    pub fn new(text: impl Into<String>, url: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            url: url.into(),
+            required: true,
+            logo_light: None,
+            logo_dark: None,
+        }
+    }
+
+    /// The contributors of OpenStreetMap, whose data is rendered by most tile providers.
+    pub fn openstreetmap() -> Self {
+        Self::new(
+            "© OpenStreetMap contributors",
+            "https://www.openstreetmap.org/copyright",
+        )
+    }
+
+    /// Whether the terms require the attribution, rather than only appreciate it.
+    pub fn required(mut self, required: bool) -> Self {
+        self.required = required;
+        self
+    }
+
+    /// Logos shown on light and dark maps respectively.
+    pub fn logo(mut self, light: Image, dark: Image) -> Self {
+        self.logo_light = Some(light);
+        self.logo_dark = Some(dark);
+        self
+    }
+}
+
+/// The encoding of tile images, for sources which serve several of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TileFormat {
+    Png,
+    Jpeg,
+    /// Roughly half the size of the other encodings, especially for imagery.
+    WebP,
+}
+
+impl TileFormat {
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            Self::Png => "image/png",
+            Self::Jpeg => "image/jpeg",
+            Self::WebP => "image/webp",
+        }
+    }
+
+    /// The usual extension of the files, without the leading dot.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Jpeg => "jpg",
+            Self::WebP => "webp",
+        }
+    }
+
+    /// The format of a MIME type, such as `image/webp`.
+    pub fn from_mime_type(mime_type: &str) -> Option<Self> {
+        match mime_type.to_ascii_lowercase().as_str() {
+            "image/png" => Some(Self::Png),
+            "image/jpeg" | "image/jpg" => Some(Self::Jpeg),
+            "image/webp" => Some(Self::WebP),
+            _ => None,
+        }
+    }
+}
+
+/// Remote tile server definition, or a local directory of tiles, source for the
+/// [`crate::TileCache`].
 pub trait Source: core::fmt::Debug + Send + Sync {
     fn tile_url(&self, tile_id: TileCoord) -> String;
-    fn attribution(&self) -> Attribution;
+    fn attribution(&self) -> Vec<Attribution>;
 
-    /// Size of each tile, should be a multiple of 256.
+    /// Size of each tile in pixels, preferably a power of two. This is a shorthand for
+    /// sources which otherwise use the usual tiling scheme of web maps.
     fn tile_size(&self) -> u32 {
         256
     }
 
+    /// How the tiles of the source are laid out.
+    fn tiling_scheme(&self) -> TilingScheme {
+        TilingScheme::new(self.tile_size())
+    }
+
     fn max_zoom(&self) -> u8 {
         19
     }
+
+    /// The lowest zoom level the source serves tiles for. The map is left empty when zoomed
+    /// out further.
+    fn min_zoom(&self) -> u8 {
+        0
+    }
+
+    /// The area covered by the source, for regional sources. Tiles outside of it are never
+    /// requested.
+    fn bounds(&self) -> Option<GeoBounds> {
+        None
+    }
+
+    /// Live sources, such as traffic or weather radar, return how long their tiles stay
+    /// current. Tiles older than this are fetched again while they are in view.
+    fn refresh_after(&self) -> Option<Duration> {
+        None
+    }
+
+    /// The hosts of servers spread over several subdomains, e.g. `a`, `b` and `c`. The urls
+    /// of such sources contain `{s}`, which the fetcher replaces with each subdomain in turn,
+    /// so that requests are spread over all of them.
+    fn subdomains(&self) -> &[&str] {
+        &[]
+    }
+
+    /// Headers added to the request of a tile, for providers which require e.g. an
+    /// `Authorization` or `Referer` header. The name and value of each header are given as
+    /// strings, and invalid headers fail the request.
+    fn headers(&self, _tile_id: TileCoord) -> Vec<(String, String)> {
+        Vec::new()
+    }
+
+    /// The encoding of the tiles, if the source is configured for one of several. The
+    /// fetcher then asks for it in the `Accept` header of its requests.
+    fn format(&self) -> Option<TileFormat> {
+        None
+    }
+
+    /// Sources which produce their tiles themselves, such as local files like a
+    /// [`FileSource`], a database or rasterized vector data, return the encoded image of a
+    /// tile in the future. It is awaited on the async runtime instead of requesting the url
+    /// of the tile, so any blocking work should be moved off of it, e.g. with
+    /// `spawn_blocking`.
+    fn load_tile(&self, _tile_id: TileCoord) -> Option<BoxFuture<'_, Result<Bytes, TileError>>> {
+        None
+    }
+
+    /// The timestamp of the frame shown by time-dimension sources, such as a
+    /// [`TimedSource`]. Tiles of different frames are kept apart in the disk cache.
+    fn time(&self) -> Option<String> {
+        None
+    }
+
+    /// Switch a time-dimension source to another frame, returning whether this changed
+    /// the frame. Use [`crate::TileCache::set_time`], which also fetches the tiles again.
+    fn set_time(&self, _time: &str) -> bool {
+        false
+    }
+
+    /// Renew the credentials of the source, such as the token of signed urls, after the
+    /// server rejected a request with 401 or 403, or the 498 and 499 of Esri. This is awaited
+    /// by the fetcher, which makes the request once more if it returns `true`. Concurrent
+    /// rejections renew the credentials only once.
+    fn refresh_credentials(&self) -> BoxFuture<'_, bool> {
+        Box::pin(async { false })
+    }
+
+    /// A source to fetch tiles from when this one fails, or does not cover them, such as a
+    /// [`FallbackSource`].
+    fn fallback(&self) -> Option<&dyn Source> {
+        None
+    }
+
+    /// The url of a tile with twice the pixel density, covering the same area. This is used
+    /// instead of [`Source::tile_url`] on high density displays, if the source offers it.
+    fn tile_url_hidpi(&self, _tile_id: TileCoord) -> Option<String> {
+        None
+    }
+}
+
+/// Whether a source serves a tile, given its lowest zoom level and the area it covers.
+pub(crate) fn covers(source: &dyn Source, tile_id: &TileCoord) -> bool {
+    tile_id.zoom() >= source.min_zoom()
+        && source
+            .bounds()
+            .is_none_or(|bounds| bounds.intersects_tile(tile_id))
+}
+
+/// Do blocking work for the tile of a local source, such as reading a file or querying a
+/// database, on a blocking thread of tokio, as tiles are loaded on the async runtime.
+#[cfg(all(feature = "http", not(target_arch = "wasm32")))]
+pub(crate) async fn unblock<T: Send + 'static>(
+    work: impl FnOnce() -> Result<T, TileError> + Send + 'static,
+) -> Result<T, TileError> {
+    tokio::task::spawn_blocking(work)
+        .await
+        .unwrap_or_else(|err| Err(TileError::Io(err.to_string())))
+}
+
+/// Do blocking work for the tile of a local source on a thread of its own, as there is no
+/// tokio runtime without the `http` feature.
+#[cfg(all(not(feature = "http"), not(target_arch = "wasm32")))]
+pub(crate) async fn unblock<T: Send + 'static>(
+    work: impl FnOnce() -> Result<T, TileError> + Send + 'static,
+) -> Result<T, TileError> {
+    let (sender, receiver) = iced::futures::channel::oneshot::channel();
+    std::thread::spawn(move || sender.send(work()));
+    receiver
+        .await
+        .unwrap_or_else(|_| Err(TileError::Io("The thread reading the tile panicked".into())))
+}
+
+/// The browser has no threads to move blocking work to, nor a file system, so the work is
+/// done right away.
+#[cfg(target_arch = "wasm32")]
+pub(crate) async fn unblock<T: Send + 'static>(
+    work: impl FnOnce() -> Result<T, TileError> + Send + 'static,
+) -> Result<T, TileError> {
+    work()
 }
@@ -0,0 +1,30 @@
+use super::{Attribution, Source};
+use crate::tile_coord::TileCoord;
+
+/// Seamarks, such as buoys, lights and harbours, from OpenSeaMap. The tiles are transparent,
+/// so they are meant to be drawn on top of a basemap.
+/// <https://wiki.openstreetmap.org/wiki/OpenSeaMap>
+#[derive(Debug)]
+pub struct OpenSeaMap;
+
+impl Source for OpenSeaMap {
+    fn tile_url(&self, tile_id: TileCoord) -> String {
+        format!(
+            "https://tiles.openseamap.org/seamark/{}/{}/{}.png",
+            tile_id.zoom(),
+            tile_id.x(),
+            tile_id.y()
+        )
+    }
+
+    fn attribution(&self) -> Vec<Attribution> {
+        vec![
+            Attribution::new("© OpenSeaMap contributors", "https://www.openseamap.org/"),
+            Attribution::openstreetmap(),
+        ]
+    }
+
+    fn max_zoom(&self) -> u8 {
+        18
+    }
+}
@@ -15,12 +15,7 @@ impl Source for OpenStreetMap {
         )
     }
 
-    fn attribution(&self) -> Attribution {
-        Attribution {
-            text: "OpenStreetMap contributors",
-            url: "https://www.openstreetmap.org/copyright",
-            logo_light: None,
-            logo_dark: None,
-        }
+    fn attribution(&self) -> Vec<Attribution> {
+        vec![Attribution::openstreetmap()]
     }
 }
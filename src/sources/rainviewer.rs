@@ -0,0 +1,45 @@
+use super::{Attribution, Source};
+use crate::tile_coord::TileCoord;
+
+/// Precipitation radar composites from RainViewer, one source per timestamp.
+/// <https://www.rainviewer.com/api/weather-maps-api.html>
+#[derive(Debug)]
+pub struct RainViewer {
+    /// Unix timestamp of the radar frame, a multiple of 10 minutes
+    pub timestamp: u64,
+    /// One of the RainViewer color schemes, see their documentation
+    pub color_scheme: u8,
+}
+
+impl RainViewer {
+    pub fn new(timestamp: u64) -> Self {
+        Self {
+            timestamp,
+            color_scheme: 2,
+        }
+    }
+}
+
+impl Source for RainViewer {
+    fn tile_url(&self, tile_id: TileCoord) -> String {
+        format!(
+            "https://tilecache.rainviewer.com/v2/radar/{}/256/{}/{}/{}/{}/1_1.png",
+            self.timestamp,
+            tile_id.zoom(),
+            tile_id.x(),
+            tile_id.y(),
+            self.color_scheme
+        )
+    }
+
+    fn attribution(&self) -> Vec<Attribution> {
+        vec![Attribution::new(
+            "RainViewer",
+            "https://www.rainviewer.com/",
+        )]
+    }
+
+    fn max_zoom(&self) -> u8 {
+        7
+    }
+}
@@ -0,0 +1,223 @@
+use super::{Attribution, Source, TileFormat, TilingScheme};
+use crate::{GeoBounds, tile_coord::TileCoord};
+
+/// A piece of a parsed url template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    X,
+    Y,
+    /// The row counted from the bottom, as in the TMS specification.
+    FlippedY,
+    Zoom,
+}
+
+/// Tiles of a server whose urls follow a template, like those given by most tile services:
+///
+/// ```ignore
+/// let source = TemplateSource::new("https://{s}.tile.example.com/{z}/{x}/{y}@2x.png")
+///     .tile_size(512)
+///     .attribution("Example", "https://example.com/copyright");
+/// ```
+///
+/// The placeholders `{x}`, `{y}` and `{z}` are replaced by the column, row and zoom level of
+/// a tile, while `{-y}` counts the rows from the bottom instead. `{s}` is kept for the
+/// fetcher, which rotates through the [`TemplateSource::subdomains`], which are `a`, `b`
+/// and `c` unless set otherwise. Anything else is kept as is.
+#[derive(Debug, Clone)]
+pub struct TemplateSource {
+    parts: Vec<Part>,
+    subdomains: &'static [&'static str],
+    headers: Vec<(String, String)>,
+    format: Option<TileFormat>,
+    scheme: TilingScheme,
+    max_zoom: u8,
+    min_zoom: u8,
+    bounds: Option<GeoBounds>,
+    attribution: Vec<Attribution>,
+}
+
+impl TemplateSource {
+    pub fn new(template: &str) -> Self {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut rest = template;
+
+        while let Some(start) = rest.find('{') {
+            let placeholder = rest[start..]
+                .find('}')
+                .map(|end| &rest[start..=start + end]);
+            let part = match placeholder {
+                Some("{x}") => Part::X,
+                Some("{y}") => Part::Y,
+                Some("{-y}") => Part::FlippedY,
+                Some("{z}") => Part::Zoom,
+                _ => {
+                    text.push_str(&rest[..=start]);
+                    rest = &rest[start + 1..];
+                    continue;
+                }
+            };
+
+            text.push_str(&rest[..start]);
+            if !text.is_empty() {
+                parts.push(Part::Text(std::mem::take(&mut text)));
+            }
+            parts.push(part);
+            rest = &rest[start + placeholder.map_or(0, str::len)..];
+        }
+
+        text.push_str(rest);
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+
+        Self {
+            parts,
+            subdomains: &["a", "b", "c"],
+            headers: Vec::new(),
+            format: None,
+            scheme: TilingScheme::new(256),
+            max_zoom: 19,
+            min_zoom: 0,
+            bounds: None,
+            attribution: Vec::new(),
+        }
+    }
+
+    /// The subdomains which replace `{s}`, spreading the requests over several servers.
+    pub fn subdomains(mut self, subdomains: &'static [&'static str]) -> Self {
+        self.subdomains = subdomains;
+        self
+    }
+
+    /// Add a header to each request, e.g. the `Authorization` required by the server.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// The encoding of the tiles, for servers which pick it from the `Accept` header.
+    pub fn format(mut self, format: TileFormat) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    pub fn tile_size(mut self, tile_size: u32) -> Self {
+        self.scheme.tile_size = tile_size;
+        self
+    }
+
+    /// How the tiles are laid out, for servers which do not follow the usual grid of web
+    /// maps, such as WMTS services only offering geographic (EPSG:4326) tiles:
+    ///
+    /// ```ignore
+    /// let source = TemplateSource::new("https://example.com/wmts/4326/{z}/{y}/{x}.jpg")
+    ///     .tiling_scheme(TilingScheme::new(512).grid(TileGrid::Geographic));
+    /// ```
+    pub fn tiling_scheme(mut self, scheme: TilingScheme) -> Self {
+        self.scheme = scheme;
+        self
+    }
+
+    pub fn max_zoom(mut self, max_zoom: u8) -> Self {
+        self.max_zoom = max_zoom;
+        self
+    }
+
+    pub fn min_zoom(mut self, min_zoom: u8) -> Self {
+        self.min_zoom = min_zoom;
+        self
+    }
+
+    /// The area covered by the server, outside of which no tiles are requested.
+    pub fn bounds(mut self, bounds: GeoBounds) -> Self {
+        self.bounds = Some(bounds);
+        self
+    }
+
+    /// Credit the provider of the tiles, which can be repeated for several providers.
+    pub fn attribution(mut self, text: impl Into<String>, url: impl Into<String>) -> Self {
+        self.attribution.push(Attribution::new(text, url));
+        self
+    }
+}
+
+impl Source for TemplateSource {
+    fn tile_url(&self, tile_id: TileCoord) -> String {
+        let mut url = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => url.push_str(text),
+                Part::X => url.push_str(&tile_id.x().to_string()),
+                Part::Y => url.push_str(&tile_id.y().to_string()),
+                Part::FlippedY => {
+                    let rows = 1u32 << tile_id.zoom();
+                    url.push_str(&(rows - 1 - tile_id.y()).to_string());
+                }
+                Part::Zoom => url.push_str(&tile_id.zoom().to_string()),
+            }
+        }
+        url
+    }
+
+    fn attribution(&self) -> Vec<Attribution> {
+        self.attribution.clone()
+    }
+
+    fn tile_size(&self) -> u32 {
+        self.scheme.tile_size
+    }
+
+    fn tiling_scheme(&self) -> TilingScheme {
+        self.scheme
+    }
+
+    fn subdomains(&self) -> &[&str] {
+        self.subdomains
+    }
+
+    fn headers(&self, _tile_id: TileCoord) -> Vec<(String, String)> {
+        self.headers.clone()
+    }
+
+    fn format(&self) -> Option<TileFormat> {
+        self.format
+    }
+
+    fn max_zoom(&self) -> u8 {
+        self.max_zoom
+    }
+
+    fn min_zoom(&self) -> u8 {
+        self.min_zoom
+    }
+
+    fn bounds(&self) -> Option<GeoBounds> {
+        self.bounds
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn template_placeholders() {
+        let source = TemplateSource::new("https://{s}.example.com/{z}/{x}/{y}@2x.png?key={key}");
+        assert_eq!(
+            source.tile_url(TileCoord::new(3, 1, 2)),
+            "https://{s}.example.com/2/3/1@2x.png?key={key}"
+        );
+
+        assert_eq!(Source::subdomains(&source), ["a", "b", "c"]);
+
+        let source = TemplateSource::new("{z}/{x}/{-y}.png").subdomains(&["one"]);
+        assert_eq!(source.tile_url(TileCoord::new(3, 1, 2)), "2/3/2.png");
+
+        // The tile size is kept along with the rest of the scheme
+        let geographic = |size| TilingScheme::new(size).grid(crate::sources::TileGrid::Geographic);
+        let source = source.tiling_scheme(geographic(512)).tile_size(256);
+        assert_eq!(Source::tiling_scheme(&source), geographic(256));
+    }
+}
@@ -0,0 +1,67 @@
+use super::{Attribution, Source};
+use crate::tile_coord::TileCoord;
+
+/// Mapbox terrain-RGB elevation tiles, encoded as `-10000 + (R * 256² + G * 256 + B) * 0.1` meters.
+/// <https://docs.mapbox.com/data/tilesets/reference/mapbox-terrain-rgb-v1/>
+#[derive(Debug, Default)]
+pub struct MapboxTerrain {
+    /// Fetch tiles at 512x512 instead of 256x256 (@2x)
+    pub high_resolution: bool,
+    /// Mapbox API key, required
+    pub access_token: String,
+}
+
+impl Source for MapboxTerrain {
+    fn tile_url(&self, tile_id: TileCoord) -> String {
+        format!(
+            "https://api.mapbox.com/v4/mapbox.terrain-rgb/{}/{}/{}{}.pngraw?access_token={}",
+            tile_id.zoom(),
+            tile_id.x(),
+            tile_id.y(),
+            if self.high_resolution { "@2x" } else { "" },
+            self.access_token
+        )
+    }
+
+    fn attribution(&self) -> Vec<Attribution> {
+        vec![Attribution::new(
+            "© Mapbox",
+            "https://www.mapbox.com/about/maps/",
+        )]
+    }
+
+    fn tile_size(&self) -> u32 {
+        if self.high_resolution { 512 } else { 256 }
+    }
+
+    fn max_zoom(&self) -> u8 {
+        15
+    }
+}
+
+/// Terrarium elevation tiles hosted on AWS, encoded as `(R * 256 + G + B / 256) - 32768` meters.
+/// <https://registry.opendata.aws/terrain-tiles/>
+#[derive(Debug)]
+pub struct Terrarium;
+
+impl Source for Terrarium {
+    fn tile_url(&self, tile_id: TileCoord) -> String {
+        format!(
+            "https://s3.amazonaws.com/elevation-tiles-prod/terrarium/{}/{}/{}.png",
+            tile_id.zoom(),
+            tile_id.x(),
+            tile_id.y()
+        )
+    }
+
+    fn attribution(&self) -> Vec<Attribution> {
+        vec![Attribution::new(
+            "Mapzen, OpenStreetMap contributors, USGS and others",
+            "https://github.com/tilezen/joerd/blob/master/docs/attribution.md",
+        )]
+    }
+
+    fn max_zoom(&self) -> u8 {
+        15
+    }
+}
@@ -0,0 +1,110 @@
+use crate::{TileCoord, sources::Attribution};
+
+fn thunderforest_url(style: &str, tile_id: TileCoord, api_key: &str, hidpi: bool) -> String {
+    format!(
+        "https://tile.thunderforest.com/{style}/{}/{}/{}{}.png?apikey={api_key}",
+        tile_id.zoom(),
+        tile_id.x(),
+        tile_id.y(),
+        if hidpi { "@2x" } else { "" },
+    )
+}
+
+fn thunderforest_attribution() -> Vec<Attribution> {
+    vec![
+        Attribution::new("Maps © Thunderforest", "https://www.thunderforest.com/"),
+        Attribution::openstreetmap(),
+    ]
+}
+
+/// Cycling routes and infrastructure, with the API key of a Thunderforest account.
+/// <https://www.thunderforest.com/maps/opencyclemap/>
+#[derive(Debug)]
+pub struct OpenCycleMap(pub String);
+
+impl super::Source for OpenCycleMap {
+    fn tile_url(&self, tile_id: TileCoord) -> String {
+        thunderforest_url("cycle", tile_id, &self.0, false)
+    }
+
+    fn tile_url_hidpi(&self, tile_id: TileCoord) -> Option<String> {
+        Some(thunderforest_url("cycle", tile_id, &self.0, true))
+    }
+
+    fn attribution(&self) -> Vec<Attribution> {
+        thunderforest_attribution()
+    }
+
+    fn max_zoom(&self) -> u8 {
+        22
+    }
+}
+
+/// Public transport lines and stations, with the API key of a Thunderforest account.
+/// <https://www.thunderforest.com/maps/transport/>
+#[derive(Debug)]
+pub struct ThunderforestTransport(pub String);
+
+impl super::Source for ThunderforestTransport {
+    fn tile_url(&self, tile_id: TileCoord) -> String {
+        thunderforest_url("transport", tile_id, &self.0, false)
+    }
+
+    fn tile_url_hidpi(&self, tile_id: TileCoord) -> Option<String> {
+        Some(thunderforest_url("transport", tile_id, &self.0, true))
+    }
+
+    fn attribution(&self) -> Vec<Attribution> {
+        thunderforest_attribution()
+    }
+
+    fn max_zoom(&self) -> u8 {
+        22
+    }
+}
+
+/// Terrain and natural features, with the API key of a Thunderforest account.
+/// <https://www.thunderforest.com/maps/landscape/>
+#[derive(Debug)]
+pub struct ThunderforestLandscape(pub String);
+
+impl super::Source for ThunderforestLandscape {
+    fn tile_url(&self, tile_id: TileCoord) -> String {
+        thunderforest_url("landscape", tile_id, &self.0, false)
+    }
+
+    fn tile_url_hidpi(&self, tile_id: TileCoord) -> Option<String> {
+        Some(thunderforest_url("landscape", tile_id, &self.0, true))
+    }
+
+    fn attribution(&self) -> Vec<Attribution> {
+        thunderforest_attribution()
+    }
+
+    fn max_zoom(&self) -> u8 {
+        22
+    }
+}
+
+/// Hiking trails and outdoor activities, with the API key of a Thunderforest account.
+/// <https://www.thunderforest.com/maps/outdoors/>
+#[derive(Debug)]
+pub struct ThunderforestOutdoors(pub String);
+
+impl super::Source for ThunderforestOutdoors {
+    fn tile_url(&self, tile_id: TileCoord) -> String {
+        thunderforest_url("outdoors", tile_id, &self.0, false)
+    }
+
+    fn tile_url_hidpi(&self, tile_id: TileCoord) -> Option<String> {
+        Some(thunderforest_url("outdoors", tile_id, &self.0, true))
+    }
+
+    fn attribution(&self) -> Vec<Attribution> {
+        thunderforest_attribution()
+    }
+
+    fn max_zoom(&self) -> u8 {
+        22
+    }
+}
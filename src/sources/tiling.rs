@@ -0,0 +1,235 @@
+use std::time::Duration;
+
+use iced::futures::future::BoxFuture;
+use iced_core::Bytes;
+
+use super::{Attribution, Source, TileFormat};
+use crate::{GeoBounds, TileError, map_widget::BASE_SIZE, tile_coord::TileCoord};
+
+/// The corner of the world which tile rows are counted from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TileOrigin {
+    /// Rows are counted southwards from the top, as used by most web maps (XYZ).
+    #[default]
+    TopLeft,
+    /// Rows are counted northwards from the bottom, as in the Tile Map Service
+    /// specification (TMS).
+    BottomLeft,
+}
+
+/// The grid which tiles are laid out in, which is also the projection of their images.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TileGrid {
+    /// Square tiles in the Web Mercator projection (EPSG:3857), with a single tile
+    /// covering the world at zoom level 0.
+    #[default]
+    WebMercator,
+    /// Tiles in the plate carrée projection (EPSG:4326), with two tiles side by side
+    /// covering the world at zoom level 0. The tiles are reprojected when loaded, which
+    /// requires the `decode` feature.
+    Geographic,
+}
+
+/// Describes how the tiles of a [`super::Source`] are laid out, such that tiles of any
+/// size and numbering can be placed on the map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TilingScheme {
+    /// The width and height of each tile in pixels, without its border. Any size works, as
+    /// tiles are scaled to cover their area, but tiles are sharpest when the size is a power
+    /// of two.
+    pub tile_size: u32,
+    /// The corner of the world which tile rows are counted from.
+    pub origin: TileOrigin,
+    /// The grid and projection of the tiles.
+    pub grid: TileGrid,
+    /// Added to the zoom level of requested tiles, for sources which do not start counting
+    /// at a single tile covering the world.
+    pub zoom_offset: u8,
+    /// Pixels around the edges of each tile which overlap with its neighbours, as added by
+    /// some servers to avoid seams. They are cropped once the tile is decoded, which requires
+    /// the `decode` feature, and are drawn as part of the tile otherwise.
+    pub border: u32,
+}
+
+impl Default for TilingScheme {
+    fn default() -> Self {
+        Self::new(256)
+    }
+}
+
+impl TilingScheme {
+    /// A scheme of tiles of the given size, numbered as usual for web maps.
+    pub const fn new(tile_size: u32) -> Self {
+        Self {
+            tile_size,
+            origin: TileOrigin::TopLeft,
+            grid: TileGrid::WebMercator,
+            zoom_offset: 0,
+            border: 0,
+        }
+    }
+
+    pub const fn origin(self, origin: TileOrigin) -> Self {
+        Self { origin, ..self }
+    }
+
+    pub const fn grid(self, grid: TileGrid) -> Self {
+        Self { grid, ..self }
+    }
+
+    pub const fn zoom_offset(self, zoom_offset: u8) -> Self {
+        Self {
+            zoom_offset,
+            ..self
+        }
+    }
+
+    pub const fn border(self, border: u32) -> Self {
+        Self { border, ..self }
+    }
+
+    /// The difference between the zoom level of the map, and the zoom level of tiles which
+    /// are drawn at their native resolution.
+    pub fn scale_offset(&self) -> f64 {
+        (BASE_SIZE as f64 / self.tile_size.max(1) as f64).log2()
+    }
+
+    /// The zoom level of tiles which best matches the resolution of the map.
+    pub fn tile_zoom(&self, zoom: f64, max_zoom: u8) -> u8 {
+        (zoom + self.scale_offset())
+            .round()
+            .clamp(0.0, max_zoom as f64) as u8
+    }
+
+    /// Convert a tile into the numbering used when requesting it from the source.
+    pub fn request_tile(&self, tile_id: TileCoord) -> TileCoord {
+        let zoom = tile_id.zoom() + self.zoom_offset;
+        let y = match self.origin {
+            TileOrigin::TopLeft => tile_id.y(),
+            TileOrigin::BottomLeft => (1 << tile_id.zoom()) - 1 - tile_id.y(),
+        };
+
+        TileCoord::new(tile_id.x(), y, zoom)
+    }
+
+    /// Convert a tile of the geographic grid into the numbering used when requesting it
+    /// from the source. Such tiles have twice as many columns as rows.
+    #[cfg(feature = "decode")]
+    pub(crate) fn request_geographic_tile(&self, x: u32, y: u32, zoom: u8) -> TileCoord {
+        let y = match self.origin {
+            TileOrigin::TopLeft => y,
+            TileOrigin::BottomLeft => (1 << zoom) - 1 - y,
+        };
+
+        TileCoord::new_unclamped(x, y, zoom + self.zoom_offset)
+    }
+}
+
+/// Wraps a [`Source`] whose tile rows are counted northwards from the bottom, as in the
+/// Tile Map Service specification (TMS), such as the TMS endpoints of GeoServer. Sources of
+/// their own can instead return a [`TilingScheme`] with [`TileOrigin::BottomLeft`].
+///
+/// ```ignore
+/// let cache = TileCache::new(Tms(my_source));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Tms<S>(pub S);
+
+impl<S: Source> Source for Tms<S> {
+    fn tile_url(&self, tile_id: TileCoord) -> String {
+        self.0.tile_url(tile_id)
+    }
+
+    fn attribution(&self) -> Vec<Attribution> {
+        self.0.attribution()
+    }
+
+    fn tile_size(&self) -> u32 {
+        self.0.tile_size()
+    }
+
+    fn tiling_scheme(&self) -> TilingScheme {
+        self.0.tiling_scheme().origin(TileOrigin::BottomLeft)
+    }
+
+    fn max_zoom(&self) -> u8 {
+        self.0.max_zoom()
+    }
+
+    fn min_zoom(&self) -> u8 {
+        self.0.min_zoom()
+    }
+
+    fn bounds(&self) -> Option<GeoBounds> {
+        self.0.bounds()
+    }
+
+    fn refresh_after(&self) -> Option<Duration> {
+        self.0.refresh_after()
+    }
+
+    fn subdomains(&self) -> &[&str] {
+        self.0.subdomains()
+    }
+
+    fn headers(&self, tile_id: TileCoord) -> Vec<(String, String)> {
+        self.0.headers(tile_id)
+    }
+
+    fn format(&self) -> Option<TileFormat> {
+        self.0.format()
+    }
+
+    fn load_tile(&self, tile_id: TileCoord) -> Option<BoxFuture<'_, Result<Bytes, TileError>>> {
+        self.0.load_tile(tile_id)
+    }
+
+    fn time(&self) -> Option<String> {
+        self.0.time()
+    }
+
+    fn set_time(&self, time: &str) -> bool {
+        self.0.set_time(time)
+    }
+
+    fn refresh_credentials(&self) -> BoxFuture<'_, bool> {
+        self.0.refresh_credentials()
+    }
+
+    fn fallback(&self) -> Option<&dyn Source> {
+        self.0.fallback()
+    }
+
+    fn tile_url_hidpi(&self, tile_id: TileCoord) -> Option<String> {
+        self.0.tile_url_hidpi(tile_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_tile() {
+        let tile_id = TileCoord::new(3, 1, 3);
+        assert_eq!(TilingScheme::new(256).request_tile(tile_id), tile_id);
+
+        let tms = TilingScheme::new(256).origin(TileOrigin::BottomLeft);
+        assert_eq!(tms.request_tile(tile_id), TileCoord::new(3, 6, 3));
+
+        let wrapped = Tms(crate::sources::OpenStreetMap).tiling_scheme();
+        assert_eq!(wrapped.request_tile(tile_id), TileCoord::new(3, 6, 3));
+
+        let offset = TilingScheme::new(256).zoom_offset(1);
+        assert_eq!(offset.request_tile(tile_id), TileCoord::new(3, 1, 4));
+    }
+
+    #[test]
+    fn tile_zoom() {
+        assert_eq!(TilingScheme::new(256).tile_zoom(10.0, 19), 11);
+        assert_eq!(TilingScheme::new(512).tile_zoom(10.0, 19), 10);
+        assert_eq!(TilingScheme::new(1024).tile_zoom(0.2, 19), 0);
+        assert_eq!(TilingScheme::new(256).tile_zoom(19.0, 19), 19);
+        assert_eq!(TilingScheme::new(200).tile_zoom(10.0, 19), 11);
+    }
+}
@@ -0,0 +1,148 @@
+use std::{sync::RwLock, time::Duration};
+
+use iced::futures::future::BoxFuture;
+use iced_core::Bytes;
+
+use super::{Attribution, Source, TileFormat, TilingScheme};
+use crate::{GeoBounds, TileError, tile_coord::TileCoord};
+
+/// A time-dimension source, such as weather radar or daily imagery, whose urls contain the
+/// placeholder `{time}` for the timestamp of the shown frame. Switching to another frame
+/// with [`crate::TileCache::set_time`] fetches the tiles in view again, while the cache
+/// keeps drawing those of the old frame until they are replaced.
+///
+/// ```ignore
+/// let radar = TemplateSource::new(
+///     "https://tilecache.rainviewer.com/v2/radar/{time}/256/{z}/{x}/{y}/2/1_1.png",
+/// )
+/// .max_zoom(7);
+/// let mut cache = TileCache::new(TimedSource::new(radar, "1700000000"));
+///
+/// // Later on, show the next frame
+/// let task = cache.set_time("1700000600");
+/// ```
+///
+/// The placeholder is kept as is by [`super::TemplateSource`], and works just as well in
+/// the parameters of a [`super::WmsSource`], e.g. `.parameter("TIME", "{time}")`.
+#[derive(Debug)]
+pub struct TimedSource<S> {
+    source: S,
+    time: RwLock<String>,
+}
+
+impl<S: Source> TimedSource<S> {
+    pub fn new(source: S, time: impl Into<String>) -> Self {
+        Self {
+            source,
+            time: RwLock::new(time.into()),
+        }
+    }
+
+    fn with_time(&self, url: String) -> String {
+        url.replace("{time}", &self.time.read().unwrap())
+    }
+}
+
+impl<S: Source> Source for TimedSource<S> {
+    fn tile_url(&self, tile_id: TileCoord) -> String {
+        self.with_time(self.source.tile_url(tile_id))
+    }
+
+    fn attribution(&self) -> Vec<Attribution> {
+        self.source.attribution()
+    }
+
+    fn tile_size(&self) -> u32 {
+        self.source.tile_size()
+    }
+
+    fn tiling_scheme(&self) -> TilingScheme {
+        self.source.tiling_scheme()
+    }
+
+    fn max_zoom(&self) -> u8 {
+        self.source.max_zoom()
+    }
+
+    fn min_zoom(&self) -> u8 {
+        self.source.min_zoom()
+    }
+
+    fn bounds(&self) -> Option<GeoBounds> {
+        self.source.bounds()
+    }
+
+    fn refresh_after(&self) -> Option<Duration> {
+        self.source.refresh_after()
+    }
+
+    fn subdomains(&self) -> &[&str] {
+        self.source.subdomains()
+    }
+
+    fn headers(&self, tile_id: TileCoord) -> Vec<(String, String)> {
+        self.source.headers(tile_id)
+    }
+
+    fn format(&self) -> Option<TileFormat> {
+        self.source.format()
+    }
+
+    fn refresh_credentials(&self) -> BoxFuture<'_, bool> {
+        self.source.refresh_credentials()
+    }
+
+    fn fallback(&self) -> Option<&dyn Source> {
+        self.source.fallback()
+    }
+
+    fn load_tile(&self, tile_id: TileCoord) -> Option<BoxFuture<'_, Result<Bytes, TileError>>> {
+        self.source.load_tile(tile_id)
+    }
+
+    fn time(&self) -> Option<String> {
+        Some(self.time.read().unwrap().clone())
+    }
+
+    fn set_time(&self, time: &str) -> bool {
+        let mut current = self.time.write().unwrap();
+        if *current == time {
+            return false;
+        }
+        *current = time.to_string();
+        true
+    }
+
+    fn tile_url_hidpi(&self, tile_id: TileCoord) -> Option<String> {
+        self.source
+            .tile_url_hidpi(tile_id)
+            .map(|url| self.with_time(url))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sources::TemplateSource;
+
+    #[test]
+    fn switch_time() {
+        let source = TimedSource::new(
+            TemplateSource::new("https://example.com/{time}/{z}/{x}/{y}.png"),
+            "0900",
+        );
+        let tile_id = TileCoord::new(1, 0, 1);
+        assert_eq!(
+            source.tile_url(tile_id),
+            "https://example.com/0900/1/1/0.png"
+        );
+
+        assert!(!source.set_time("0900"));
+        assert!(source.set_time("1000"));
+        assert_eq!(source.time().as_deref(), Some("1000"));
+        assert_eq!(
+            source.tile_url(tile_id),
+            "https://example.com/1000/1/1/0.png"
+        );
+    }
+}
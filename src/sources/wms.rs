@@ -0,0 +1,250 @@
+use std::f64::consts::PI;
+
+use super::{Attribution, Source, TileFormat};
+use crate::{GeoBounds, tile_coord::TileCoord};
+
+/// Half the width of the world in the Web Mercator projection, in meters.
+const HALF_WORLD: f64 = PI * 6_378_137.0;
+
+/// The version of the WMS protocol spoken by the server.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WmsVersion {
+    V1_1_1,
+    #[default]
+    V1_3_0,
+}
+
+impl WmsVersion {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::V1_1_1 => "1.1.1",
+            Self::V1_3_0 => "1.3.0",
+        }
+    }
+
+    /// The name of the parameter of the coordinate reference system, which was renamed.
+    fn crs_parameter(&self) -> &'static str {
+        match self {
+            Self::V1_1_1 => "SRS",
+            Self::V1_3_0 => "CRS",
+        }
+    }
+}
+
+/// Tiles rendered by a [Web Map Service](https://www.ogc.org/standard/wms/), which are
+/// requested as `GetMap` images covering the bounds of each tile in Web Mercator
+/// (EPSG:3857). The server must offer this projection for the layers.
+///
+/// ```ignore
+/// let source = WmsSource::new("https://maps.example.com/wms", ["roads", "buildings"])
+///     .transparent(true)
+///     .attribution("Example Mapping Agency", "https://maps.example.com");
+/// let cache = TileCache::new(source);
+/// ```
+#[derive(Debug, Clone)]
+pub struct WmsSource {
+    url: String,
+    layers: Vec<String>,
+    styles: Vec<String>,
+    format: String,
+    version: WmsVersion,
+    transparent: bool,
+    tile_size: u32,
+    max_zoom: u8,
+    min_zoom: u8,
+    bounds: Option<GeoBounds>,
+    parameters: Vec<(String, String)>,
+    attribution: Vec<Attribution>,
+}
+
+impl WmsSource {
+    /// The layers of the service at the given url, drawn from the first to the last.
+    pub fn new(
+        url: impl Into<String>,
+        layers: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        Self {
+            url: url.into(),
+            layers: layers.into_iter().map(Into::into).collect(),
+            styles: Vec::new(),
+            format: "image/png".to_string(),
+            version: WmsVersion::default(),
+            transparent: false,
+            tile_size: 256,
+            max_zoom: 19,
+            min_zoom: 0,
+            bounds: None,
+            parameters: Vec::new(),
+            attribution: Vec::new(),
+        }
+    }
+
+    /// The style of each layer, in the same order. The default styles are used otherwise.
+    pub fn styles(mut self, styles: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.styles = styles.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// The image format of the tiles, `image/png` unless set otherwise, e.g. `image/webp`
+    /// for smaller tiles if the server offers it.
+    pub fn format(mut self, format: impl Into<String>) -> Self {
+        self.format = format.into();
+        self
+    }
+
+    pub fn version(mut self, version: WmsVersion) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Request images with a transparent background, for layers drawn on top of others.
+    pub fn transparent(mut self, transparent: bool) -> Self {
+        self.transparent = transparent;
+        self
+    }
+
+    /// The width and height of the requested images, preferably a power of two.
+    pub fn tile_size(mut self, tile_size: u32) -> Self {
+        self.tile_size = tile_size;
+        self
+    }
+
+    pub fn max_zoom(mut self, max_zoom: u8) -> Self {
+        self.max_zoom = max_zoom;
+        self
+    }
+
+    pub fn min_zoom(mut self, min_zoom: u8) -> Self {
+        self.min_zoom = min_zoom;
+        self
+    }
+
+    /// The area covered by the server, outside of which no tiles are requested.
+    pub fn bounds(mut self, bounds: GeoBounds) -> Self {
+        self.bounds = Some(bounds);
+        self
+    }
+
+    /// Add a vendor specific parameter to each request, e.g. `TIME` or `DPI`.
+    pub fn parameter(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.parameters.push((name.into(), value.into()));
+        self
+    }
+
+    /// Credit the provider of the tiles, which can be repeated for several providers.
+    pub fn attribution(mut self, text: impl Into<String>, url: impl Into<String>) -> Self {
+        self.attribution.push(Attribution::new(text, url));
+        self
+    }
+
+    /// The bounds of a tile in Web Mercator meters, as west, south, east and north.
+    fn bbox(tile_id: TileCoord) -> [f64; 4] {
+        let tiles = 2u32.pow(tile_id.zoom() as u32) as f64;
+        let (x, y) = tile_id.x_y();
+        let east = |x: u32| (x as f64 / tiles * 2.0 - 1.0) * HALF_WORLD;
+        let north = |y: u32| (1.0 - y as f64 / tiles * 2.0) * HALF_WORLD;
+
+        [east(x), north(y + 1), east(x + 1), north(y)]
+    }
+
+    fn url(&self, tile_id: TileCoord, size: u32) -> String {
+        let [west, south, east, north] = Self::bbox(tile_id);
+        let separator = match self.url.contains('?') {
+            true if self.url.ends_with(['?', '&']) => "",
+            true => "&",
+            false => "?",
+        };
+
+        let mut url = format!(
+            "{}{separator}SERVICE=WMS&REQUEST=GetMap&VERSION={}&LAYERS={}&STYLES={}&FORMAT={}\
+            &TRANSPARENT={}&{}=EPSG:3857&BBOX={west},{south},{east},{north}&WIDTH={size}&HEIGHT={size}",
+            self.url,
+            self.version.as_str(),
+            self.layers.join(","),
+            self.styles.join(","),
+            self.format,
+            if self.transparent { "TRUE" } else { "FALSE" },
+            self.version.crs_parameter(),
+        );
+
+        for (name, value) in &self.parameters {
+            url.push_str(&format!("&{name}={value}"));
+        }
+
+        url
+    }
+}
+
+impl Source for WmsSource {
+    fn tile_url(&self, tile_id: TileCoord) -> String {
+        self.url(tile_id, self.tile_size)
+    }
+
+    /// The server renders the same bounds at any size, so twice the pixels are requested.
+    fn tile_url_hidpi(&self, tile_id: TileCoord) -> Option<String> {
+        Some(self.url(tile_id, self.tile_size * 2))
+    }
+
+    fn attribution(&self) -> Vec<Attribution> {
+        self.attribution.clone()
+    }
+
+    fn format(&self) -> Option<TileFormat> {
+        TileFormat::from_mime_type(&self.format)
+    }
+
+    fn tile_size(&self) -> u32 {
+        self.tile_size
+    }
+
+    fn max_zoom(&self) -> u8 {
+        self.max_zoom
+    }
+
+    fn min_zoom(&self) -> u8 {
+        self.min_zoom
+    }
+
+    fn bounds(&self) -> Option<GeoBounds> {
+        self.bounds
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_map_request() {
+        let source = WmsSource::new("https://example.com/wms?map=roads", ["roads", "rivers"])
+            .transparent(true);
+
+        // The north eastern quarter of the world
+        assert_eq!(
+            source.tile_url(TileCoord::new(1, 0, 1)),
+            format!(
+                "https://example.com/wms?map=roads&SERVICE=WMS&REQUEST=GetMap&VERSION=1.3.0\
+                &LAYERS=roads,rivers&STYLES=&FORMAT=image/png&TRANSPARENT=TRUE&CRS=EPSG:3857\
+                &BBOX=0,0,{HALF_WORLD},{HALF_WORLD}&WIDTH=256&HEIGHT=256"
+            )
+        );
+
+        let source = source
+            .version(WmsVersion::V1_1_1)
+            .parameter("TIME", "2024-01-01");
+        let url = source.tile_url_hidpi(TileCoord::new(0, 0, 0)).unwrap();
+        assert!(url.contains("&SRS=EPSG:3857&"));
+        assert!(url.contains(&format!(
+            "BBOX=-{HALF_WORLD},-{HALF_WORLD},{HALF_WORLD},{HALF_WORLD}"
+        )));
+        assert!(url.ends_with("&WIDTH=512&HEIGHT=512&TIME=2024-01-01"));
+
+        let source = source.format("image/webp");
+        assert_eq!(Source::format(&source), Some(TileFormat::WebP));
+        assert!(
+            source
+                .tile_url(TileCoord::ZERO)
+                .contains("&FORMAT=image/webp&")
+        );
+    }
+}
@@ -0,0 +1,235 @@
+//! The day/night terminator, the line separating the part of the earth lit by the sun from
+//! the part in darkness, optionally with bands of civil, nautical and astronomical twilight.
+//!
+//! The position of the sun is computed with the low precision formulas of the
+//! [Astronomical Almanac](https://aa.usno.navy.mil/faq/sun_approx), which are accurate to
+//! within a fraction of a degree for several centuries around the year 2000.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use iced::Color;
+use iced::widget::canvas::{Frame, Path};
+
+use crate::{Geodetic, Projector};
+
+/// The night side is drawn as this many strips of longitude.
+const STRIPS: usize = 360;
+
+/// The altitudes of the sun in degrees at which civil, nautical and astronomical twilight
+/// end.
+const TWILIGHT: [f64; 3] = [-6.0, -12.0, -18.0];
+
+/// The point on the earth where the sun is directly overhead at some point in time.
+pub fn subsolar_point(time: SystemTime) -> Geodetic {
+    let seconds = match time.duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_secs_f64(),
+        Err(err) => -err.duration().as_secs_f64(),
+    };
+
+    // Days since the J2000.0 epoch
+    let days = seconds / 86_400.0 + 2_440_587.5 - 2_451_545.0;
+
+    let mean_longitude = 280.460 + 0.985_647_4 * days;
+    let mean_anomaly = (357.528 + 0.985_600_3 * days).to_radians();
+    let ecliptic_longitude =
+        (mean_longitude + 1.915 * mean_anomaly.sin() + 0.020 * (2.0 * mean_anomaly).sin())
+            .to_radians();
+    let obliquity = (23.439 - 0.000_000_4 * days).to_radians();
+
+    let declination = (obliquity.sin() * ecliptic_longitude.sin()).asin();
+    let right_ascension =
+        (obliquity.cos() * ecliptic_longitude.sin()).atan2(ecliptic_longitude.cos());
+
+    // The sun is overhead where the local sidereal time matches its right ascension
+    let sidereal_time = 280.460_618_37 + 360.985_647_366_29 * days;
+    let longitude = right_ascension.to_degrees() - sidereal_time;
+
+    Geodetic::new(longitude, declination.to_degrees()).wrapped()
+}
+
+/// The altitude of the sun in degrees above the horizon at some position, given the
+/// [`subsolar_point`].
+pub fn solar_altitude(sun: Geodetic, position: Geodetic) -> f64 {
+    let (lat, declination) = (
+        position.latitude().to_radians(),
+        sun.latitude().to_radians(),
+    );
+    let hour_angle = (position.longitude() - sun.longitude()).to_radians();
+
+    (lat.sin() * declination.sin() + lat.cos() * declination.cos() * hour_angle.cos())
+        .asin()
+        .to_degrees()
+}
+
+/// The ranges of latitude along a meridian where the sun is below some altitude, in degrees.
+fn below_altitude(sun: Geodetic, longitude: f64, altitude: f64) -> Vec<(f64, f64)> {
+    // The altitude along the meridian is `asin(r * sin(lat + phase))`
+    let declination = sun.latitude().to_radians();
+    let hour_angle = (longitude - sun.longitude()).to_radians();
+    let (a, b) = (declination.sin(), declination.cos() * hour_angle.cos());
+    let r = a.hypot(b);
+    let phase = b.atan2(a).to_degrees();
+
+    let threshold = altitude.to_radians().sin() / r;
+    if threshold >= 1.0 {
+        return vec![(-90.0, 90.0)];
+    } else if threshold <= -1.0 {
+        return Vec::new();
+    }
+
+    // Below the altitude where `sin(x) < sin(alpha)`, so for `x` within
+    // `(180 - alpha, 360 + alpha)`, repeating every full turn
+    let alpha = threshold.asin().to_degrees();
+    let (start, end) = (phase - 90.0, phase + 90.0);
+
+    [-360.0, 0.0, 360.0]
+        .into_iter()
+        .filter_map(|turn| {
+            let low = (180.0 - alpha + turn).max(start);
+            let high = (360.0 + alpha + turn).min(end);
+            (low < high).then_some((low - phase, high - phase))
+        })
+        .collect()
+}
+
+/// Shades the night side of the earth, as seen at some point in time.
+///
+/// The terminator moves by a quarter of a degree each minute, so redrawing with a new
+/// [`Terminator::now`] every minute or so, e.g. from [`iced::time::every`], is sufficient.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Terminator {
+    sun: Geodetic,
+    twilight: bool,
+    color: Color,
+}
+
+impl Terminator {
+    pub fn now() -> Self {
+        Self::at(SystemTime::now())
+    }
+
+    pub fn at(time: SystemTime) -> Self {
+        Self {
+            sun: subsolar_point(time),
+            twilight: false,
+            color: Color::from_rgba(0.0, 0.0, 0.1, 0.45),
+        }
+    }
+
+    /// Shade the bands of civil, nautical and astronomical twilight in increasingly dark
+    /// steps, rather than only the night side.
+    pub fn twilight(mut self, twilight: bool) -> Self {
+        self.twilight = twilight;
+        self
+    }
+
+    /// The color of the darkest part of the night.
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// The point where the sun is directly overhead.
+    pub fn sun(&self) -> Geodetic {
+        self.sun
+    }
+
+    /// Whether the sun is below the horizon at some position.
+    pub fn is_night(&self, position: Geodetic) -> bool {
+        solar_altitude(self.sun, position) < 0.0
+    }
+
+    pub fn draw(&self, projector: &Projector, frame: &mut Frame<iced::Renderer>) {
+        let bands: &[f64] = if self.twilight {
+            &[0.0, TWILIGHT[0], TWILIGHT[1], TWILIGHT[2]]
+        } else {
+            &[0.0]
+        };
+
+        // The bands overlap, such that the night is darkest past all of them
+        let color = self.color.scale_alpha(1.0 / bands.len() as f32);
+        for &altitude in bands {
+            frame.fill(&self.band(projector, altitude), color);
+        }
+    }
+
+    /// The area where the sun is below some altitude, made of strips of longitude.
+    fn band(&self, projector: &Projector, altitude: f64) -> Path {
+        let point = |longitude: f64, latitude: f64| {
+            let latitude = latitude.clamp(-Geodetic::MAX_LATITUDE, Geodetic::MAX_LATITUDE);
+            projector.geodetic_into_screen_space(Geodetic::new(longitude, latitude))
+        };
+
+        let longitude = |i: usize| -180.0 + 360.0 * i as f64 / STRIPS as f64;
+        let mut previous = below_altitude(self.sun, longitude(0), altitude);
+
+        Path::new(|builder| {
+            for i in 1..=STRIPS {
+                let (west, east) = (longitude(i - 1), longitude(i));
+                let next = below_altitude(self.sun, east, altitude);
+
+                // Connect the ranges on both sides of the strip where they match up, which
+                // is everywhere but where the ranges split or merge
+                let ranges = if next.len() == previous.len() {
+                    previous.iter().zip(&next).map(|(&w, &e)| (w, e)).collect()
+                } else {
+                    below_altitude(self.sun, (west + east) / 2.0, altitude)
+                        .into_iter()
+                        .map(|range| (range, range))
+                        .collect::<Vec<_>>()
+                };
+
+                for ((west_low, west_high), (east_low, east_high)) in ranges {
+                    builder.move_to(point(west, west_low));
+                    builder.line_to(point(east, east_low));
+                    builder.line_to(point(east, east_high));
+                    builder.line_to(point(west, west_high));
+                    builder.close();
+                }
+
+                previous = next;
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn time(seconds: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(seconds)
+    }
+
+    #[test]
+    fn subsolar_point_at_solstice_and_equinox() {
+        // 2024-06-20 20:51 UTC
+        let sun = subsolar_point(time(1_718_916_660));
+        approx::assert_relative_eq!(sun.latitude(), 23.44, epsilon = 0.05);
+
+        // 2024-03-20 12:00 UTC, when the sun is roughly overhead the prime meridian
+        let sun = subsolar_point(time(1_710_936_000));
+        approx::assert_relative_eq!(sun.latitude(), 0.0, epsilon = 0.5);
+        approx::assert_relative_eq!(sun.longitude(), 0.0, epsilon = 3.0);
+    }
+
+    #[test]
+    fn night_below_horizon() {
+        let sun = Geodetic::new(30.0, 20.0);
+        for longitude in [-170.0, -90.0, 0.0, 29.0, 100.0] {
+            for (low, high) in below_altitude(sun, longitude, 0.0) {
+                for latitude in [low + 0.01, (low + high) / 2.0, high - 0.01] {
+                    let position = Geodetic::new(longitude, latitude);
+                    assert!(solar_altitude(sun, position) < 0.0);
+                }
+            }
+        }
+
+        let terminator = Terminator::at(time(1_718_916_660));
+        let sun = terminator.sun();
+        assert!(!terminator.is_night(sun));
+        assert!(terminator.is_night(Geodetic::new(sun.longitude() + 180.0, -sun.latitude())));
+    }
+}
@@ -1,39 +1,128 @@
+#[cfg(feature = "http")]
+use std::path::PathBuf;
 use std::{
     cell::Cell,
-    collections::HashMap,
+    collections::{HashMap, HashSet, VecDeque},
+    ops::RangeInclusive,
     sync::{
         Arc,
         atomic::{AtomicU32, Ordering},
     },
-    time::{Duration, Instant},
+    time::Duration,
 };
 
-use iced::Task;
+use iced::time::Instant;
+use iced::{Subscription, Task, task};
 use iced_core::image::{self, Allocation, Handle};
-use tokio::sync::Semaphore;
 
 use crate::{
-    sources::{Attribution, Source},
+    GeoBounds, Mercator,
+    sources::{Attribution, Source, TilingScheme},
     tile_coord::TileCoord,
 };
+#[cfg(feature = "http")]
+use crate::{
+    HttpClient,
+    http_fetcher::{HttpConfig, HttpFetcher, RetryPolicy},
+};
 
 const PRUNE_TIME: Duration = Duration::from_secs(60);
-const PRUNE_THRESH: usize = 1024;
 
+/// The default number of tiles kept in memory before the least recently used are pruned.
+const DEFAULT_MAX_TILES: usize = 1024;
+
+/// The default [`AllocationPolicy::retention`].
+const ALLOCATION_RETENTION: Duration = Duration::from_secs(2);
+
+/// How often the maintenance of [`TileCache::subscription`] runs.
+const MAINTENANCE_INTERVAL: Duration = Duration::from_millis(250);
+
+/// The number of failures kept for [`TileCache::recent_failures`].
+const MAX_FAILURES: usize = 64;
+
+/// The default scale factor from which high density tiles are fetched, if the source offers
+/// them. Below it, regular tiles are sharp enough.
+const HIDPI_SCALE_FACTOR: f32 = 1.5;
 
 /// The message that the [`TileCache`] uses to update. It is typically produced when
 /// interacting with a [`crate::map_widget::MapWidget`] in order to fetch new tiles,
 /// or when the fetching future resolves and responds with its result.
 #[derive(Debug, Clone)]
 pub enum CacheMessage {
-    Load { id: TileCoord },
-    Loaded { id: TileCoord, handle: Handle },
-    LoadFailed { id: TileCoord },
-    Allocate { id: TileCoord },
-    Allocated { id: TileCoord, alloc: Allocation },
-    AllocFailed { id: TileCoord, err: image::Error },
-    Deallocate { id: TileCoord },
+    Load {
+        id: TileCoord,
+    },
+    Loaded {
+        id: TileCoord,
+        handle: Handle,
+    },
+    LoadFailed {
+        id: TileCoord,
+        error: TileError,
+        /// The number of times the request was retried before giving up.
+        retries: u32,
+    },
+    Allocate {
+        id: TileCoord,
+    },
+    Allocated {
+        id: TileCoord,
+        alloc: Allocation,
+    },
+    AllocFailed {
+        id: TileCoord,
+        err: image::Error,
+    },
+    Deallocate {
+        id: TileCoord,
+    },
+    /// The progress of [`TileCache::download_region`], after each tile which was downloaded
+    /// or `failed`. The download is complete once `done` reaches the `total`.
+    DownloadProgress {
+        done: usize,
+        failed: usize,
+        total: usize,
+    },
+    /// Prioritize decoding of the tiles closest to this position.
+    Focus {
+        position: Mercator,
+    },
+    /// The tiles in view of the map, produced by the [`crate::MapWidget`] as it moves. Fetches
+    /// of tiles which are no longer in view are cancelled, making way for those which are.
+    Visible {
+        tiles: Vec<TileCoord>,
+    },
     Prune,
+    /// Periodic maintenance of the cache, produced by [`TileCache::subscription`].
+    Maintain(Instant),
+    /// The scale factor of the window changed. Tiles are fetched with a higher pixel
+    /// density from a scale factor of 1.5, if the source offers it, see
+    /// [`TileCacheBuilder::hidpi_threshold`].
+    ScaleFactor {
+        factor: f32,
+    },
+    /// Create a blurred placeholder for a tile from the image of one of its ancestors.
+    #[cfg(feature = "decode")]
+    BlurUp {
+        id: TileCoord,
+        ancestor: TileCoord,
+    },
+    #[cfg(feature = "decode")]
+    BlurredUp {
+        id: TileCoord,
+        handle: Option<Handle>,
+    },
+}
+
+impl CacheMessage {
+    /// The progress of a download of which all tiles failed right away.
+    pub(crate) fn download_failed(total: usize) -> Self {
+        Self::DownloadProgress {
+            done: total,
+            failed: total,
+            total,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -48,13 +137,23 @@ pub enum State {
 struct Entry {
     state: State,
     last_used: Cell<Instant>,
+    /// When the tile was loaded, for refreshing the tiles of live sources.
+    fetched: Instant,
+    /// Whether the tile is being fetched again, while its current image is still drawn.
+    refreshing: bool,
+    /// Whether the image replaced that of a refreshed tile, and has not been allocated yet.
+    replaced: bool,
 }
 
 impl Entry {
     fn new(entry: State) -> Self {
+        let now = Instant::now();
         Self {
             state: entry,
-            last_used: Cell::new(Instant::now()),
+            last_used: Cell::new(now),
+            fetched: now,
+            refreshing: false,
+            replaced: false,
         }
     }
 
@@ -69,50 +168,240 @@ impl Entry {
 pub struct TileCache {
     cache: HashMap<TileCoord, Entry>,
     fetcher: Arc<dyn Fetcher>,
+    /// The fetches which are still in flight, such that they can be cancelled.
+    fetches: HashMap<TileCoord, task::Handle>,
+    /// The tiles last reported in view with [`CacheMessage::Visible`]. They are fetched again
+    /// on a reload, even when the map has been still for longer than the retention.
+    visible: HashSet<TileCoord>,
     cleanup_timer: Instant,
+    max_tiles: usize,
+    allocation: AllocationPolicy,
+    /// Blurred placeholders of tiles which are not loaded yet, or `None` while pending.
+    #[cfg(feature = "decode")]
+    placeholders: HashMap<TileCoord, Option<Handle>>,
+    failures: VecDeque<TileFailure>,
+    /// Tiles which failed to load, and are not requested again for a while.
+    cooldowns: HashMap<TileCoord, Cooldown>,
+    cooldown: CooldownPolicy,
+    /// The number of failures ever recorded, for widgets to tell which ones are new.
+    failure_count: u64,
+    /// Tiles older than this are fetched again while they are in view.
+    refresh_after: Option<Duration>,
+    /// The age set with [`TileCacheBuilder::refresh_after`], over that of the source.
+    refresh_override: Option<Duration>,
+    /// The number of refreshed tiles whose new image became drawable, for widgets to tell
+    /// when to swap them in.
+    refreshed: u64,
+    /// The scale factor from which high density tiles are fetched.
+    hidpi_threshold: f32,
+    /// Whether no requests are made, set with [`TileCache::set_offline`].
+    offline: bool,
+}
+
+/// How many tiles a [`TileCache`] keeps allocated with the renderer, and which ones are
+/// released first, set with [`TileCacheBuilder::allocation`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AllocationPolicy {
+    /// The most tiles allocated at once, beyond which the least recently used are released.
+    pub max_tiles: usize,
+    /// The most bytes of tile images allocated at once.
+    pub max_bytes: usize,
+    /// Tiles used more recently than this are considered visible, and are released by
+    /// [`TileCache::subscription`] once they are no longer visible. This keeps tiles which
+    /// cycle in and out of view from being uploaded to the renderer again.
+    pub retention: Duration,
+    /// Keep visible tiles allocated, even when that exceeds the budget.
+    pub keep_visible: bool,
+}
+
+impl Default for AllocationPolicy {
+    fn default() -> Self {
+        Self {
+            max_tiles: 512,
+            max_bytes: 256 * 1024 * 1024,
+            retention: ALLOCATION_RETENTION,
+            keep_visible: true,
+        }
+    }
+}
+
+impl AllocationPolicy {
+    /// Choose which of the allocated tiles, given by when they were last used and their size
+    /// in bytes, to release.
+    fn release(
+        &self,
+        mut allocated: Vec<(Instant, TileCoord, usize)>,
+        now: Instant,
+        release_unused: bool,
+    ) -> Vec<TileCoord> {
+        let mut count = allocated.len();
+        let mut bytes: usize = allocated.iter().map(|(_, _, bytes)| bytes).sum();
+        let over_budget =
+            |count: usize, bytes: usize| count > self.max_tiles || bytes > self.max_bytes;
+
+        if !release_unused && !over_budget(count, bytes) {
+            return Vec::new();
+        }
+
+        // Least recently used first
+        allocated.sort_unstable_by_key(|(last_used, _, _)| *last_used);
+
+        let mut released = Vec::new();
+        for (last_used, id, size) in allocated {
+            let visible = now.saturating_duration_since(last_used) < self.retention;
+            let release = if over_budget(count, bytes) {
+                !(visible && self.keep_visible)
+            } else {
+                release_unused && !visible
+            };
+
+            // Except for the lowest zoom levels, keep those allocated as a last resort
+            if release && id.zoom() > 1 {
+                released.push(id);
+                count -= 1;
+                bytes -= size;
+            }
+        }
+
+        released
+    }
+}
+
+/// How long tiles which could not be loaded are left alone before they are requested again,
+/// such that a broken tile is not requested over and over while it is in view. Set with
+/// [`TileCacheBuilder::cooldown`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CooldownPolicy {
+    /// The time before a failed tile is requested again. It doubles with each failure.
+    pub cooldown: Duration,
+    /// The longest time between requests.
+    pub max_cooldown: Duration,
+    /// The number of failures after which the tile is no longer requested, until the source
+    /// of the cache changes.
+    pub max_failures: u32,
+}
+
+impl Default for CooldownPolicy {
+    fn default() -> Self {
+        Self {
+            cooldown: Duration::from_secs(5),
+            max_cooldown: Duration::from_secs(5 * 60),
+            max_failures: 8,
+        }
+    }
+}
+
+impl CooldownPolicy {
+    /// When a tile may be requested again, after failing for the given number of times.
+    fn retry_at(&self, now: Instant, failures: u32) -> Option<Instant> {
+        if failures >= self.max_failures {
+            return None;
+        }
+
+        let factor = 2u32.saturating_pow(failures.saturating_sub(1));
+        Some(now + self.cooldown.saturating_mul(factor).min(self.max_cooldown))
+    }
+}
+
+/// A tile which failed to load, which is not requested again until it cooled down.
+#[derive(Debug)]
+struct Cooldown {
+    failures: u32,
+    /// When the tile may be requested again, or never.
+    until: Option<Instant>,
+}
+
+/// A tile which could not be loaded, as reported by [`crate::MapWidget::on_tile_error`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TileFailure {
+    pub id: TileCoord,
+    pub error: TileError,
+    /// The number of times the request was retried before giving up.
+    pub retries: u32,
 }
 
 static PARALLEL_IMAGE_ALLOCS: AtomicU32 = AtomicU32::new(0);
 const MAX_PARALLEL_IMAGE_ALLOCS: u32 = 10;
 
 impl TileCache {
-    /// The [`MapState`] acts acts as a stateful backend for the [`crate::MapWidget`]. It returns an instance
-    /// of itself, which should be helt along with the map state, as well as a [`iced::Task`] that should be
-    /// executed in order allow the backend to request the application to redraw.
+    /// The [`TileCache`] acts as a stateful backend for the [`crate::MapWidget`], and should
+    /// be held along with the map state. Use [`TileCache::builder`] to configure how tiles are
+    /// fetched and kept.
     pub fn new(source: impl Source + 'static) -> Self {
-        // For receiving tiles
-        Self {
-            cache: HashMap::new(),
-            fetcher: Arc::new(HttpFetcher {
-                semaphore: Semaphore::new(6),
-                source: Box::new(source),
-                client: reqwest::ClientBuilder::new()
-                    .user_agent("lib-slippery")
-                    .build()
-                    .unwrap(),
-            }),
-            cleanup_timer: Instant::now(),
-        }
+        Self::builder(source).build()
     }
 
-    pub fn attribution(&self) -> Attribution {
+    pub fn builder(source: impl Source + 'static) -> TileCacheBuilder {
+        TileCacheBuilder::new(source)
+    }
+
+    pub fn attribution(&self) -> Vec<Attribution> {
         self.fetcher.source().attribution()
     }
 
+    /// The text of all attributions in a single line, e.g. for the caption of a printed map.
+    pub fn attribution_text(&self) -> String {
+        self.attribution()
+            .iter()
+            .map(|attribution| attribution.text.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Query the scale factor of the most recently opened window, such that the tiles are
+    /// fetched with a matching pixel density from the start. Later changes are picked up by
+    /// the [`crate::MapWidget`].
+    pub fn detect_scale_factor() -> Task<CacheMessage> {
+        iced::window::latest()
+            .and_then(iced::window::scale_factor)
+            .map(|factor| CacheMessage::ScaleFactor { factor })
+    }
+
+    /// Periodically produces [`CacheMessage::Maintain`], which releases the renderer
+    /// allocations of tiles that are no longer drawn, and prunes the cache when it grows
+    /// large. Without it, allocations are only released once they exceed the budget of the
+    /// [`AllocationPolicy`].
+    pub fn subscription(&self) -> Subscription<CacheMessage> {
+        if self.cache.is_empty() {
+            Subscription::none()
+        } else {
+            iced::time::every(MAINTENANCE_INTERVAL).map(CacheMessage::Maintain)
+        }
+    }
+
     pub fn tile_size(&self) -> u32 {
-        self.fetcher.source().tile_size()
+        self.tiling_scheme().tile_size
+    }
+
+    pub fn tiling_scheme(&self) -> TilingScheme {
+        self.fetcher.source().tiling_scheme()
     }
 
     pub fn max_zoom(&self) -> u8 {
         self.fetcher.source().max_zoom()
     }
 
+    pub fn min_zoom(&self) -> u8 {
+        self.fetcher.source().min_zoom()
+    }
+
+    /// Whether the source serves a tile, given its zoom levels and the area it covers.
+    pub fn covers(&self, tile_id: &TileCoord) -> bool {
+        crate::sources::covers(self.fetcher.source(), tile_id)
+    }
+
     pub fn should_load(&self, tile_id: &TileCoord) -> bool {
+        if !self.covers(tile_id) {
+            return false;
+        }
+
         if let Some(entry) = self.cache.get(tile_id) {
             entry.touch();
             false
         } else {
-            true
+            self.cooldowns
+                .get(tile_id)
+                .is_none_or(|cooldown| cooldown.until.is_some_and(|until| Instant::now() >= until))
         }
     }
 
@@ -139,21 +428,296 @@ impl TileCache {
         }
     }
 
+    /// Get the image handle of a tile which has finished loading, regardless of
+    /// whether it is currently allocated with the renderer.
+    pub fn get_loaded(&self, tile_id: &TileCoord) -> Option<Handle> {
+        let entry = self.cache.get(tile_id)?;
+        match &entry.state {
+            State::Loading => None,
+            State::Loaded(handle) | State::Allocating(handle) | State::Allocated(handle, _) => {
+                entry.touch();
+                Some(handle.clone())
+            }
+        }
+    }
+
+    /// Check whether a blurred placeholder should be created for a tile.
+    #[cfg(feature = "decode")]
+    pub fn should_blur_up(&self, tile_id: &TileCoord) -> bool {
+        !self.placeholders.contains_key(tile_id) && !self.is_loaded(tile_id)
+    }
+
+    /// Get the blurred placeholder of a tile, if it has been created.
+    #[cfg(feature = "decode")]
+    pub fn get_placeholder(&self, tile_id: &TileCoord) -> Option<Handle> {
+        self.placeholders.get(tile_id)?.clone()
+    }
+
+    /// The most recent tiles which could not be loaded, oldest first.
+    pub fn recent_failures(&self) -> impl DoubleEndedIterator<Item = &TileFailure> {
+        self.failures.iter()
+    }
+
+    /// The number of failures ever recorded.
+    pub(crate) fn failure_count(&self) -> u64 {
+        self.failure_count
+    }
+
+    /// The failures recorded after the first `seen` ones, as far as they are still kept.
+    pub(crate) fn failures_since(&self, seen: u64) -> impl Iterator<Item = &TileFailure> {
+        let new = self.failure_count.saturating_sub(seen) as usize;
+        let skip = self.failures.len().saturating_sub(new);
+        self.failures.iter().skip(skip)
+    }
+
+    /// How old the tiles may get before they are fetched again, if the source is live.
+    pub fn refresh_after(&self) -> Option<Duration> {
+        self.refresh_after
+    }
+
+    /// Stop making requests, e.g. on a metered connection or in airplane mode. Only the
+    /// tiles in memory, in the disk cache and of local sources are drawn, however old they
+    /// are, until the cache is set online again.
+    pub fn set_offline(&mut self, offline: bool) {
+        self.offline = offline;
+        self.fetcher.set_offline(offline);
+    }
+
+    pub fn is_offline(&self) -> bool {
+        self.offline
+    }
+
+    /// Download the tiles of a region at the given zoom levels into the disk cache, for use
+    /// while offline. The tiles are fetched a few at a time, following the rate limit, and the
+    /// task reports its progress with [`CacheMessage::DownloadProgress`]. Abort the task
+    /// with [`Task::abortable`] to stop the download.
+    ///
+    /// This requires a [`TileCacheBuilder::disk_cache`], without which all tiles are reported
+    /// as failed right away. Many tile servers forbid bulk downloads in their usage policies,
+    /// such as that of OpenStreetMap, so use a source which allows them.
+    pub fn download_region(
+        &self,
+        bounds: GeoBounds,
+        zooms: RangeInclusive<u8>,
+    ) -> Task<CacheMessage> {
+        let source = self.fetcher.source();
+        let zooms = (*zooms.start()).max(source.min_zoom())..=(*zooms.end()).min(source.max_zoom());
+        let tiles: Vec<_> = zooms
+            .flat_map(|zoom| bounds.tiles(zoom))
+            .filter(|tile_id| self.covers(tile_id))
+            .collect();
+
+        if tiles.is_empty() {
+            return Task::done(CacheMessage::DownloadProgress {
+                done: 0,
+                failed: 0,
+                total: 0,
+            });
+        }
+        self.fetcher.clone().download(tiles)
+    }
+
+    /// Write the tiles in the disk cache into an [MBTiles](https://github.com/mapbox/mbtiles-spec)
+    /// file, along with the metadata of the source, such that they can be opened elsewhere
+    /// with [`crate::sources::MbTiles`]. Tiles already in the file are replaced. Combine it
+    /// with [`TileCache::download_region`] to bundle a region for offline use. The task
+    /// resolves to the number of tiles written.
+    #[cfg(all(feature = "http", feature = "mbtiles"))]
+    pub fn export_mbtiles(
+        &self,
+        path: impl Into<PathBuf>,
+    ) -> Task<Result<usize, crate::sources::MbTilesError>> {
+        let path = path.into();
+        let dir = self.fetcher.disk_cache();
+        let source = self.fetcher.source();
+        let scheme = source.tiling_scheme();
+        let mut metadata = vec![
+            ("name", self.attribution_text()),
+            ("type", "baselayer".to_string()),
+            ("attribution", self.attribution_text()),
+            ("minzoom", source.min_zoom().to_string()),
+            ("maxzoom", source.max_zoom().to_string()),
+        ];
+        if let Some(format) = source.format() {
+            metadata.push(("format", format.extension().to_string()));
+        }
+        if let Some(bounds) = source.bounds() {
+            let GeoBounds {
+                west,
+                south,
+                east,
+                north,
+            } = bounds;
+            metadata.push(("bounds", format!("{west},{south},{east},{north}")));
+        }
+
+        Task::future(async move {
+            let Some(dir) = dir else {
+                return Err(crate::sources::MbTilesError::NoDiskCache);
+            };
+            tokio::task::spawn_blocking(move || {
+                crate::sources::export_mbtiles(&dir, &path, scheme, &metadata)
+            })
+            .await
+            .map_err(std::io::Error::other)?
+        })
+    }
+
+    /// Switch a time-dimension source, such as a [`crate::sources::TimedSource`], to another
+    /// frame. The tiles in view are fetched again, while their images of the old frame are
+    /// drawn until replaced, and the other tiles of the old frame are dropped.
+    pub fn set_time(&mut self, time: &str) -> Task<CacheMessage> {
+        if !self.fetcher.source().set_time(time) {
+            return Task::none();
+        }
+
+        self.reload()
+    }
+
+    /// Replace the source of the cache, e.g. for switching between basemaps. The tiles in
+    /// view are fetched from the new source, while their old images are drawn until replaced,
+    /// unless the tiles are laid out differently. The new source keeps its tiles in a
+    /// directory of its own in the disk cache of the [`TileCacheBuilder`].
+    pub fn set_source(&mut self, source: impl Source + 'static) -> Task<CacheMessage> {
+        let same_layout = source.tiling_scheme() == self.tiling_scheme();
+        self.refresh_after = self.refresh_override.or(source.refresh_after());
+
+        // Tiles still being fetched from the old source are dropped
+        let fetcher = self
+            .fetcher
+            .with_source(Box::new(source), self.refresh_after);
+        std::mem::replace(&mut self.fetcher, fetcher).close();
+
+        if same_layout {
+            self.reload()
+        } else {
+            self.cache.clear();
+            self.fetches.clear();
+            self.visible.clear();
+            self.cooldowns.clear();
+            #[cfg(feature = "decode")]
+            self.placeholders.clear();
+            Task::none()
+        }
+    }
+
+    /// Fetch the tiles in view again, while drawing their current images until replaced,
+    /// and drop the others. Tiles are in view when the widget last reported them to be, or
+    /// when they were used within the retention, such as the fallbacks drawn in their place.
+    fn reload(&mut self) -> Task<CacheMessage> {
+        #[cfg(feature = "decode")]
+        self.placeholders.clear();
+        self.cooldowns.clear();
+
+        let now = Instant::now();
+        let source = self.fetcher.source();
+        let mut refresh = Vec::new();
+        self.cache.retain(|id, entry| {
+            let in_use = now
+                .checked_duration_since(entry.last_used.get())
+                .is_none_or(|unused| unused < self.allocation.retention);
+
+            let keep =
+                (in_use || self.visible.contains(id) || matches!(entry.state, State::Loading))
+                    && crate::sources::covers(source, id);
+            if keep {
+                entry.refreshing = true;
+                refresh.push(*id);
+            }
+            keep
+        });
+
+        Task::batch(refresh.into_iter().map(|id| self.fetch(id)))
+    }
+
+    /// Fetch a tile, replacing any fetch of it which is still in flight.
+    fn fetch(&mut self, id: TileCoord) -> Task<CacheMessage> {
+        let (task, handle) = self.fetcher.clone().fetch_tile(id).abortable();
+        if let Some(previous) = self.fetches.insert(id, handle) {
+            previous.abort();
+        }
+        task
+    }
+
+    /// Cancel the fetches of the tiles which are not in view. Tiles which were not loaded
+    /// yet are dropped, and refreshed ones keep their current image.
+    fn cancel_hidden(&mut self, visible: &HashSet<TileCoord>) {
+        self.fetches.retain(|id, handle| {
+            if visible.contains(id) {
+                return true;
+            }
+
+            handle.abort();
+            match self.cache.get_mut(id) {
+                Some(Entry {
+                    state: State::Loading,
+                    ..
+                }) => {
+                    self.cache.remove(id);
+                }
+                Some(entry) => entry.refreshing = false,
+                None => {}
+            }
+            false
+        });
+    }
+
+    /// The number of refreshed tiles which became drawable.
+    pub(crate) fn refreshed(&self) -> u64 {
+        self.refreshed
+    }
+
+    /// Mark a tile as used, such as one which is still drawn.
+    pub(crate) fn touch(&self, tile_id: &TileCoord) {
+        if let Some(entry) = self.cache.get(tile_id) {
+            entry.touch();
+        }
+    }
+
+    /// Check whether a tile has finished loading, without marking it as used.
+    pub fn is_loaded(&self, tile_id: &TileCoord) -> bool {
+        self.cache
+            .get(tile_id)
+            .is_some_and(|entry| !matches!(entry.state, State::Loading))
+    }
+
     pub fn update(&mut self, update: CacheMessage) -> Task<CacheMessage> {
         // Periodically schedule a prune
         let mut cleanup_task = Task::none();
-        if self.cache.len() > PRUNE_THRESH && self.cleanup_timer.elapsed() > Duration::from_secs(5)
+        if self.cache.len() > self.max_tiles
+            && self.cleanup_timer.elapsed() > Duration::from_secs(5)
         {
             self.cleanup_timer = Instant::now();
             cleanup_task = Task::done(CacheMessage::Prune);
         }
 
         let task = match update {
+            CacheMessage::Focus { position } => {
+                self.fetcher.focus(position);
+                Task::none()
+            }
+            CacheMessage::ScaleFactor { factor } => {
+                let changed = self.fetcher.set_hidpi(factor >= self.hidpi_threshold);
+
+                // Fetch the tiles again at the new density, drawing the current ones until then
+                if changed
+                    && self
+                        .fetcher
+                        .source()
+                        .tile_url_hidpi(TileCoord::ZERO)
+                        .is_some()
+                {
+                    self.cancel_hidden(&HashSet::new());
+                    self.reload()
+                } else {
+                    Task::none()
+                }
+            }
             CacheMessage::Prune => {
                 let start_time = Instant::now();
                 let start_size = self.cache.len();
                 let mut prune_count = 0;
-                let prune_target = start_size - PRUNE_THRESH;
+                let prune_target = start_size.saturating_sub(self.max_tiles);
                 self.cache.retain(|id, v| {
                     if prune_count >= prune_target {
                         return true;
@@ -172,36 +736,140 @@ impl TileCache {
                     }
                     retain
                 });
+
+                // Placeholders are cheap to create again, so drop all which are not pending
+                #[cfg(feature = "decode")]
+                self.placeholders.retain(|_, handle| handle.is_none());
+
+                // Forget the failures of tiles which were not requested again for long
+                let max_cooldown = self.cooldown.max_cooldown;
+                self.cooldowns.retain(|_, cooldown| {
+                    cooldown
+                        .until
+                        .is_none_or(|until| start_time < until + max_cooldown)
+                });
+
                 Task::none()
             }
+            CacheMessage::Maintain(now) => {
+                self.release_allocations(now, true);
+
+                let mut refresh = Vec::new();
+                for (id, entry) in &mut self.cache {
+                    let in_use = now
+                        .checked_duration_since(entry.last_used.get())
+                        .is_none_or(|unused| unused < self.allocation.retention);
+
+                    // Fetch outdated tiles of live sources again, while still drawing them
+                    if let Some(max_age) = self.refresh_after
+                        && !self.offline
+                        && in_use
+                        && !entry.refreshing
+                        && !matches!(entry.state, State::Loading)
+                        && now.saturating_duration_since(entry.fetched) >= max_age
+                    {
+                        entry.refreshing = true;
+                        refresh.push(*id);
+                    }
+                }
+
+                Task::batch(refresh.into_iter().map(|id| self.fetch(id)))
+            }
             CacheMessage::Load { id } => {
                 if self.cache.contains_key(&id) {
                     Task::none()
                 } else {
                     // Insert entry to indicate the tile is being loaded
                     self.cache.insert(id, Entry::new(State::Loading));
-
-                    let fetcher = self.fetcher.clone();
-                    fetcher.fetch_tile(id)
+                    self.fetch(id)
                 }
             }
+            CacheMessage::DownloadProgress { .. } => Task::none(),
+            CacheMessage::Visible { tiles } => {
+                let visible = tiles.into_iter().collect();
+                self.cancel_hidden(&visible);
+                self.visible = visible;
+                Task::none()
+            }
             CacheMessage::Loaded { id, handle } => {
-                self.cache
-                    .insert(id, Entry::new(State::Loaded(handle.clone())));
+                self.fetches.remove(&id);
+                self.cooldowns.remove(&id);
+                let mut entry = Entry::new(State::Loaded(handle.clone()));
+                entry.replaced = self.cache.get(&id).is_some_and(|old| old.refreshing);
+                self.cache.insert(id, entry);
+
+                #[cfg(feature = "decode")]
+                self.placeholders.remove(&id);
 
                 // Immediately allocate tile with the renderer
                 Task::done(CacheMessage::Allocate { id })
             }
-            CacheMessage::LoadFailed { id } => {
-                if let Some(Entry {
-                    state: State::Loading,
-                    ..
-                }) = self.cache.get(&id)
-                {
-                    self.cache.remove(&id);
+            // Tiles of a replaced source, or of a decoder which stopped. Those which are fetched
+            // again from the new source are left alone, and the others are requested again.
+            CacheMessage::LoadFailed {
+                id,
+                error: TileError::Closed,
+                ..
+            } => {
+                match self.cache.get(&id) {
+                    Some(entry) if entry.refreshing => {}
+                    Some(Entry {
+                        state: State::Loading,
+                        ..
+                    }) => {
+                        self.cache.remove(&id);
+                        self.fetches.remove(&id);
+                    }
+                    _ => {
+                        self.fetches.remove(&id);
+                    }
                 }
                 Task::none()
             }
+            CacheMessage::LoadFailed { id, error, retries } => {
+                self.fetches.remove(&id);
+                log::debug!("Unable to load tile {id:?} after {retries} retries: {error}");
+                match self.cache.get_mut(&id) {
+                    Some(Entry {
+                        state: State::Loading,
+                        ..
+                    }) => {
+                        self.cache.remove(&id);
+
+                        // Leave the tile alone for a while, unless it was merely busy or
+                        // the cache is offline, which end on their own
+                        let expected = error == TileError::Busy
+                            || (self.offline && error == TileError::Offline);
+                        if !expected {
+                            let now = Instant::now();
+                            let cooldown = self.cooldowns.entry(id).or_insert(Cooldown {
+                                failures: 0,
+                                until: None,
+                            });
+                            cooldown.failures += 1;
+                            cooldown.until = self.cooldown.retry_at(now, cooldown.failures);
+                        }
+                    }
+                    // Keep the outdated tile, and try again once it is outdated once more
+                    Some(entry) if entry.refreshing => {
+                        entry.refreshing = false;
+                        entry.fetched = Instant::now();
+                    }
+                    _ => {}
+                }
+
+                // Tiles waiting too long for a fetch are only a sign of panning quickly, and
+                // missing tiles are expected while offline
+                if error != TileError::Busy && !(self.offline && error == TileError::Offline) {
+                    if self.failures.len() == MAX_FAILURES {
+                        self.failures.pop_front();
+                    }
+                    self.failures.push_back(TileFailure { id, error, retries });
+                    self.failure_count += 1;
+                }
+
+                Task::none()
+            }
             CacheMessage::Allocate { id } => {
                 if let Some(entry) = self.cache.get_mut(&id)
                     && let State::Loaded(handle) = &entry.state
@@ -234,28 +902,21 @@ impl TileCache {
                 id,
                 alloc: allocation,
             } => {
-                if let Some(entry) = self.cache.get_mut(&id) {
-                    let mut auto_dealloc_task = Task::none();
-                    match &entry.state {
-                        State::Allocating(handle) | State::Loaded(handle) => {
-                            entry.state = State::Allocated(handle.clone(), allocation);
-                            entry.touch();
-
-                            // The allocation is Arc, so widgets will hold on if they need it longer
-                            // Except for the lowest zoom levels, keep those allocated as a last resort
-                            if id.zoom() > 1 {
-                                auto_dealloc_task = Task::future(async move {
-                                    tokio::time::sleep(Duration::from_millis(100)).await;
-                                    CacheMessage::Deallocate { id }
-                                });
-                            }
-                        }
-                        _ => {}
+                if let Some(entry) = self.cache.get_mut(&id)
+                    && let State::Allocating(handle) | State::Loaded(handle) = &entry.state
+                {
+                    entry.state = State::Allocated(handle.clone(), allocation);
+                    entry.touch();
+
+                    if entry.replaced {
+                        entry.replaced = false;
+                        self.refreshed += 1;
                     }
-                    auto_dealloc_task
-                } else {
-                    Task::none()
+
+                    // The allocation is Arc, so widgets will hold on if they need it longer
+                    self.release_allocations(Instant::now(), false);
                 }
+                Task::none()
             }
             CacheMessage::AllocFailed { id, err } => {
                 log::error!("Unable to allocate tile {id:?} with renderer: {err:?}");
@@ -268,11 +929,45 @@ impl TileCache {
                 Task::none()
             }
             CacheMessage::Deallocate { id } => {
-                if let Some(entry) = self.cache.get_mut(&id) {
+                if let Some(entry) = self.cache.get_mut(&id)
+                    && let State::Allocated(handle, _) = &entry.state
+                {
+                    // Keep the allocation around while the tile is still being used
+                    if entry.last_used.get().elapsed() < self.allocation.retention {
+                        return cleanup_task;
+                    }
+
                     // Downgrade from Allocated to Loaded by dropping the Allocation
-                    if let State::Allocated(handle, _) = &entry.state {
-                        entry.state = State::Loaded(handle.clone());
+                    entry.state = State::Loaded(handle.clone());
+                }
+                Task::none()
+            }
+            #[cfg(feature = "decode")]
+            CacheMessage::BlurUp { id, ancestor } => {
+                let Some(handle) = self.get_loaded(&ancestor) else {
+                    return cleanup_task;
+                };
+
+                self.placeholders.insert(id, None);
+                Task::future(async move {
+                    let blurred = tokio::task::spawn_blocking(move || {
+                        crate::placeholder::blur_up(ancestor, &handle, id)
+                    })
+                    .await;
+
+                    CacheMessage::BlurredUp {
+                        id,
+                        handle: blurred.ok().flatten(),
                     }
+                })
+            }
+            #[cfg(feature = "decode")]
+            CacheMessage::BlurredUp { id, handle } => {
+                // The tile itself may have been loaded in the meantime
+                if self.is_loaded(&id) {
+                    self.placeholders.remove(&id);
+                } else {
+                    self.placeholders.insert(id, handle);
                 }
                 Task::none()
             }
@@ -280,11 +975,268 @@ impl TileCache {
 
         Task::batch([cleanup_task, task])
     }
+
+    /// Release the allocations of the least recently used tiles beyond the budget of the
+    /// [`AllocationPolicy`], and optionally those which are no longer visible.
+    fn release_allocations(&mut self, now: Instant, release_unused: bool) {
+        let allocated = self
+            .cache
+            .iter()
+            .filter_map(|(id, entry)| match &entry.state {
+                State::Allocated(_, allocation) => {
+                    let size = allocation.size();
+                    let bytes = size.width as usize * size.height as usize * 4;
+                    Some((entry.last_used.get(), *id, bytes))
+                }
+                _ => None,
+            })
+            .collect();
+
+        for id in self.allocation.release(allocated, now, release_unused) {
+            if let Some(entry) = self.cache.get_mut(&id)
+                && let State::Allocated(handle, _) = &entry.state
+            {
+                entry.state = State::Loaded(handle.clone());
+            }
+        }
+    }
+
+    /// The number of tiles which are allocated with the renderer.
+    pub fn allocated(&self) -> usize {
+        self.cache
+            .values()
+            .filter(|entry| matches!(entry.state, State::Allocated(..)))
+            .count()
+    }
+}
+
+/// Configures how a [`TileCache`] fetches and keeps its tiles.
+///
+/// ```ignore
+/// let cache = TileCache::builder(OpenStreetMap)
+///     .user_agent("my-application")
+///     .concurrency(12)
+///     .disk_cache("/tmp/tiles")
+///     .build();
+/// ```
+#[derive(Debug)]
+pub struct TileCacheBuilder {
+    source: Box<dyn Source>,
+    max_tiles: usize,
+    allocation: AllocationPolicy,
+    cooldown: CooldownPolicy,
+    refresh_after: Option<Duration>,
+    hidpi_threshold: f32,
+    #[cfg(feature = "http")]
+    http: HttpConfig,
 }
 
-trait Fetcher {
+impl TileCacheBuilder {
+    fn new(source: impl Source + 'static) -> Self {
+        Self {
+            source: Box::new(source),
+            max_tiles: DEFAULT_MAX_TILES,
+            allocation: AllocationPolicy::default(),
+            cooldown: CooldownPolicy::default(),
+            refresh_after: None,
+            hidpi_threshold: HIDPI_SCALE_FACTOR,
+            #[cfg(feature = "http")]
+            http: HttpConfig::default(),
+        }
+    }
+
+    /// The number of tiles kept in memory before the least recently used are pruned.
+    pub fn memory_budget(mut self, max_tiles: usize) -> Self {
+        self.max_tiles = max_tiles;
+        self
+    }
+
+    /// How many tiles are kept allocated with the renderer, and which ones are released.
+    pub fn allocation(mut self, allocation: AllocationPolicy) -> Self {
+        self.allocation = allocation;
+        self
+    }
+
+    /// How long tiles which could not be loaded are left alone, before they are requested
+    /// again.
+    pub fn cooldown(mut self, cooldown: CooldownPolicy) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    /// Fetch tiles again once they are older than this, while they are in view, which is
+    /// useful for live layers such as traffic or weather radar. This overrides
+    /// [`Source::refresh_after`], and requires [`TileCache::subscription`].
+    pub fn refresh_after(mut self, max_age: Duration) -> Self {
+        self.refresh_after = Some(max_age);
+        self
+    }
+
+    /// The scale factor of the window from which tiles are fetched with twice the pixel
+    /// density, if the source offers them with [`Source::tile_url_hidpi`]. This is 1.5 by
+    /// default, and [`f32::INFINITY`] never fetches them.
+    pub fn hidpi_threshold(mut self, scale_factor: f32) -> Self {
+        self.hidpi_threshold = scale_factor;
+        self
+    }
+
+    /// The user agent sent along with each request. Many tile servers require this to
+    /// identify the application. It must be a valid header value, as
+    /// [`TileCacheBuilder::build`] panics otherwise.
+    #[cfg(feature = "http")]
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.http.user_agent = user_agent.into();
+        self
+    }
+
+    /// Make the requests with a client of your own, e.g. one using another TLS stack or with
+    /// instrumentation. The [`TileCacheBuilder::user_agent`] and
+    /// [`TileCacheBuilder::request_timeout`] then do not apply, and are up to the client.
+    #[cfg(feature = "http")]
+    pub fn http_client(mut self, client: impl HttpClient + 'static) -> Self {
+        self.http.client = Some(Arc::new(client));
+        self
+    }
+
+    /// The number of tiles which are fetched at the same time, 6 by default. Desktop
+    /// applications with many maps may raise it, though servers may limit it as well.
+    #[cfg(feature = "http")]
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.http.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// How long a tile waits for one of the concurrent fetches to become available. Tiles
+    /// waiting longer are likely no longer in view, and are requested again if they are.
+    /// It is 50 ms by default, which slow connections may need to lengthen.
+    #[cfg(feature = "http")]
+    pub fn queue_timeout(mut self, timeout: Duration) -> Self {
+        self.http.queue_timeout = timeout;
+        self
+    }
+
+    /// How long a single request may take, from connecting until the tile is received.
+    #[cfg(feature = "http")]
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.http.request_timeout = Some(timeout);
+        self
+    }
+
+    /// Keep the fetched tiles in a directory, such that they are not fetched again on the
+    /// next run. The tiles of each source are kept in a directory of their own within it,
+    /// named after the host of the tiles, also for sources set with [`TileCache::set_source`].
+    ///
+    /// Tiles are kept for as long as the `Cache-Control` or `Expires` headers of the server
+    /// allow, after which they are revalidated with their `ETag` or `Last-Modified` date, and
+    /// only downloaded again if they have changed.
+    #[cfg(feature = "http")]
+    pub fn disk_cache(mut self, path: impl Into<PathBuf>) -> Self {
+        self.http.disk_cache = Some(path.into());
+        self
+    }
+
+    /// Limit the number of requests made per second, as required by some tile servers.
+    #[cfg(feature = "http")]
+    pub fn rate_limit(mut self, requests_per_second: f32) -> Self {
+        self.http.rate_limit = Some(requests_per_second);
+        self
+    }
+
+    /// Change the pixels of each tile once it is decoded, before it is drawn, e.g. to recolor
+    /// a basemap for a dark theme, strip a watermark or composite another image onto it. It
+    /// runs on the decoder threads, for the tiles of any source of the cache. This requires
+    /// the `decode` feature, as tiles are otherwise decoded by the renderer, whose pixels
+    /// can not be changed.
+    ///
+    /// ```ignore
+    /// let cache = TileCache::builder(OpenStreetMap)
+    ///     .processor(|_tile_id, image| image::imageops::grayscale_alpha(image))
+    ///     .build();
+    /// ```
+    #[cfg(feature = "decode")]
+    pub fn processor(
+        mut self,
+        processor: impl Fn(TileCoord, &mut ::image::RgbaImage) + Send + Sync + 'static,
+    ) -> Self {
+        self.http.processor = Some(crate::decoder::Processor(Arc::new(processor)));
+        self
+    }
+
+    /// How failed requests are retried. By default they are attempted 3 times, unless the
+    /// server rejected them. Use [`RetryPolicy::NEVER`] to give up after the first attempt.
+    #[cfg(feature = "http")]
+    pub fn retry(mut self, retry: RetryPolicy) -> Self {
+        self.http.retry = retry;
+        self
+    }
+
+    pub fn build(self) -> TileCache {
+        let refresh_after = self.refresh_after.or(self.source.refresh_after());
+
+        #[cfg(feature = "http")]
+        let fetcher = Arc::new(HttpFetcher::new(
+            self.source,
+            HttpConfig {
+                refresh_after,
+                ..self.http
+            },
+        ));
+
+        #[cfg(not(feature = "http"))]
+        let fetcher = Arc::new(OfflineFetcher {
+            source: self.source,
+        });
+
+        TileCache {
+            cache: HashMap::new(),
+            fetcher,
+            fetches: HashMap::new(),
+            visible: HashSet::new(),
+            cleanup_timer: Instant::now(),
+            max_tiles: self.max_tiles,
+            allocation: self.allocation,
+            #[cfg(feature = "decode")]
+            placeholders: HashMap::new(),
+            failures: VecDeque::new(),
+            cooldowns: HashMap::new(),
+            cooldown: self.cooldown,
+            failure_count: 0,
+            refresh_after,
+            refresh_override: self.refresh_after,
+            refreshed: 0,
+            hidpi_threshold: self.hidpi_threshold,
+            offline: false,
+        }
+    }
+}
+
+pub(crate) trait Fetcher {
     fn fetch_tile(self: Arc<Self>, tile: TileCoord) -> Task<CacheMessage>;
     fn source(&self) -> &dyn Source;
+    fn focus(&self, position: Mercator);
+    /// Set whether tiles should be fetched with a higher pixel density, returning whether
+    /// this changed.
+    fn set_hidpi(&self, hidpi: bool) -> bool;
+    /// Set whether only tiles which are cached or local are used, without making requests.
+    fn set_offline(&self, _offline: bool) {}
+    /// The directory of the disk cache holding the tiles of the source, if any.
+    #[cfg(all(feature = "http", feature = "mbtiles"))]
+    fn disk_cache(&self) -> Option<PathBuf> {
+        None
+    }
+    /// Fetch tiles into the disk cache, reporting the progress with
+    /// [`CacheMessage::DownloadProgress`]. Without a disk cache, all of them fail.
+    fn download(self: Arc<Self>, tiles: Vec<TileCoord>) -> Task<CacheMessage> {
+        Task::done(CacheMessage::download_failed(tiles.len()))
+    }
+    /// A fetcher for another source, sharing the configuration and workers of this one.
+    fn with_source(
+        &self,
+        source: Box<dyn Source>,
+        refresh_after: Option<Duration>,
+    ) -> Arc<dyn Fetcher>;
+    /// Stop fetching, such that the tiles which are still being fetched are dropped.
+    fn close(&self) {}
 }
 
 impl core::fmt::Debug for dyn Fetcher {
@@ -293,59 +1245,512 @@ impl core::fmt::Debug for dyn Fetcher {
     }
 }
 
-/// The fetcher is cloned and moved into an async task to fetch a tile.
+/// Used when the crate is built without the `http` feature. Only the tiles of local sources
+/// are read, but others can still be inserted into the cache with [`CacheMessage::Loaded`].
+#[cfg(not(feature = "http"))]
 #[derive(Debug)]
-struct HttpFetcher {
-    semaphore: Semaphore,
+struct OfflineFetcher {
     source: Box<dyn Source>,
-    client: reqwest::Client,
 }
 
-#[derive(thiserror::Error, Debug)]
-enum FetcherError {
-    #[error(transparent)]
-    Reqwest(#[from] reqwest::Error),
-    #[error("The semaphore timed out")]
-    SemaphoreTimeout,
-    #[error("The samaphore was closed")]
-    SemaphoreClosed,
+#[cfg(not(feature = "http"))]
+impl Fetcher for OfflineFetcher {
+    fn fetch_tile(self: Arc<Self>, id: TileCoord) -> Task<CacheMessage> {
+        Task::perform(
+            async move {
+                // Read the tile from the first source of the chain which produces it locally
+                let mut read = None;
+                let mut next = Some(&*self.source);
+                while let Some(source) = next {
+                    let request = source.tiling_scheme().request_tile(id);
+                    if let Some(tile) = source.load_tile(request) {
+                        read = Some(tile.await);
+                    }
+                    if read.as_ref().is_some_and(Result::is_ok) {
+                        break;
+                    }
+                    next = source.fallback();
+                }
+                read
+            },
+            move |read| match read {
+                Some(Ok(bytes)) => CacheMessage::Loaded {
+                    id,
+                    handle: Handle::from_bytes(bytes),
+                },
+                Some(Err(error)) => CacheMessage::LoadFailed {
+                    id,
+                    error,
+                    retries: 0,
+                },
+                None => CacheMessage::LoadFailed {
+                    id,
+                    error: TileError::Offline,
+                    retries: 0,
+                },
+            },
+        )
+    }
+
+    fn source(&self) -> &dyn Source {
+        &*self.source
+    }
+
+    fn focus(&self, _position: Mercator) {}
+
+    fn set_hidpi(&self, _hidpi: bool) -> bool {
+        false
+    }
+
+    fn with_source(
+        &self,
+        source: Box<dyn Source>,
+        _refresh_after: Option<Duration>,
+    ) -> Arc<dyn Fetcher> {
+        Arc::new(OfflineFetcher { source })
+    }
 }
 
-impl Fetcher for HttpFetcher {
-    fn fetch_tile(self: Arc<Self>, tile_id: TileCoord) -> Task<CacheMessage> {
-        Task::future(async move {
-            // Semaphore ensures we are not making too many requests.
-            // Assume that if we have been waiting for a while, that the
-            // viewpoint may have moved and the tile in no longer needed.
-            // If it was needed, another fetch request will just be made.
-            let _permit = tokio::time::timeout(
-                std::time::Duration::from_millis(50),
-                self.semaphore.acquire(),
-            )
-            .await
-            .map_err(|_| FetcherError::SemaphoreTimeout)?
-            .map_err(|_| FetcherError::SemaphoreClosed)?;
+/// The reason a tile could not be loaded, carried by [`CacheMessage::LoadFailed`].
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum TileError {
+    /// The server responded with an error status, such as 404 for a tile which does not
+    /// exist, or 401 and 403 for a missing or invalid API key.
+    #[error("The server responded with status {0}")]
+    Status(u16),
+    /// The server responded with 429 Too Many Requests.
+    #[error("The server is rate limiting requests")]
+    RateLimited,
+    #[error("The request timed out")]
+    Timeout,
+    #[error("Unable to reach the server: {0}")]
+    Network(String),
+    #[error("Unable to decode the tile: {0}")]
+    Decode(String),
+    #[error("Unable to reproject the tile")]
+    Reproject,
+    /// Too many tiles were being fetched at once. The tile is likely no longer in view by
+    /// now, and is requested again if it is.
+    #[error("Too many tiles are being fetched")]
+    Busy,
+    #[error("The fetcher was shut down")]
+    Closed,
+    /// The tile of a local source could not be read, e.g. as the file does not exist.
+    #[error("Unable to read the tile: {0}")]
+    Io(String),
+    /// Tiles can not be fetched, as the cache was set offline with
+    /// [`TileCache::set_offline`], or the crate was built without the `http` feature.
+    #[error("Fetching tiles is not available")]
+    Offline,
+}
 
-            // Construct the http request
-            let source = self.source.tile_url(tile_id);
+impl TileError {
+    /// Whether the server rejected the credentials of the source, such as an API key.
+    pub fn is_unauthorized(&self) -> bool {
+        // Esri responds with 498 for invalid tokens, and with 499 for missing ones
+        matches!(self, Self::Status(401 | 403 | 498 | 499))
+    }
+}
 
-            // Make request to tile source and get response
-            let response = self.client.get(source).send().await?.error_for_status()?;
+impl From<std::io::Error> for TileError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err.to_string())
+    }
+}
 
-            // Returns the bytes as an image handle
-            let bytes = response.bytes().await?;
-            Ok::<_, FetcherError>(Handle::from_bytes(bytes))
-        })
-        .map(move |res| match res {
-            Ok(tile) => CacheMessage::Loaded {
-                id: tile_id,
-                handle: tile,
-            },
-            Err(_) => CacheMessage::LoadFailed { id: tile_id },
-        })
+#[cfg(feature = "decode")]
+impl From<crate::decoder::DecodeError> for TileError {
+    fn from(err: crate::decoder::DecodeError) -> Self {
+        match err {
+            crate::decoder::DecodeError::Closed => Self::Closed,
+            err => Self::Decode(err.to_string()),
+        }
     }
+}
 
-    fn source(&self) -> &dyn Source {
-        &*self.source
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sources::OpenStreetMap;
+
+    /// Run a task to completion, collecting its messages.
+    #[cfg(feature = "http")]
+    fn run<T: 'static>(task: Task<T>) -> Vec<T> {
+        use iced::futures::StreamExt;
+
+        let Some(stream) = iced_runtime::task::into_stream(task) else {
+            return Vec::new();
+        };
+        let outputs = stream
+            .filter_map(|action| async move {
+                match action {
+                    iced_runtime::Action::Output(output) => Some(output),
+                    _ => None,
+                }
+            })
+            .collect();
+
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(outputs)
+    }
+
+    /// Serves the same tile for every request.
+    #[cfg(feature = "http")]
+    struct TileServer;
+
+    #[cfg(feature = "http")]
+    impl HttpClient for TileServer {
+        fn get<'a>(
+            &'a self,
+            _url: &'a str,
+            _headers: &'a [(String, String)],
+        ) -> crate::HttpFuture<'a> {
+            Box::pin(async {
+                Ok(crate::HttpResponse {
+                    status: 200,
+                    body: iced_core::Bytes::from_static(b"tile"),
+                    ..Default::default()
+                })
+            })
+        }
+    }
+
+    #[test]
+    fn failures_are_reported_once() {
+        let mut cache = TileCache::new(OpenStreetMap);
+        let fail = |error| CacheMessage::LoadFailed {
+            id: TileCoord::ZERO,
+            error,
+            retries: 2,
+        };
+
+        let _ = cache.update(fail(TileError::RateLimited));
+        let seen = cache.failure_count();
+        let _ = cache.update(fail(TileError::Busy));
+        let _ = cache.update(fail(TileError::Status(403)));
+
+        // Busy tiles are not failures of the server
+        let new: Vec<_> = cache.failures_since(seen).collect();
+        assert_eq!(
+            new,
+            [&TileFailure {
+                id: TileCoord::ZERO,
+                error: TileError::Status(403),
+                retries: 2,
+            }]
+        );
+        assert_eq!(cache.failures_since(cache.failure_count()).count(), 0);
+        assert_eq!(cache.recent_failures().count(), 2);
+    }
+
+    #[test]
+    fn failed_tiles_cool_down() {
+        let mut cache = TileCache::builder(OpenStreetMap)
+            .cooldown(CooldownPolicy {
+                max_failures: 2,
+                ..CooldownPolicy::default()
+            })
+            .build();
+        let fail = |cache: &mut TileCache, id, error| {
+            let _ = cache.update(CacheMessage::Load { id });
+            let _ = cache.update(CacheMessage::LoadFailed {
+                id,
+                error,
+                retries: 0,
+            });
+        };
+
+        let (busy, missing) = (TileCoord::new(0, 0, 1), TileCoord::ZERO);
+        fail(&mut cache, busy, TileError::Busy);
+        fail(&mut cache, missing, TileError::Status(404));
+        assert!(cache.should_load(&busy));
+        assert!(!cache.should_load(&missing));
+
+        // Requested again once cooled down, and given up on after the last failure
+        cache.cooldowns.get_mut(&missing).unwrap().until = Some(Instant::now());
+        assert!(cache.should_load(&missing));
+        fail(&mut cache, missing, TileError::Timeout);
+        assert_eq!(cache.cooldowns[&missing].until, None);
+        assert!(!cache.should_load(&missing));
+
+        // Until the source changes
+        let _ = cache.set_source(OpenStreetMap);
+        assert!(cache.should_load(&missing));
+    }
+
+    #[test]
+    fn closed_tiles_are_requested_again() {
+        let mut cache = TileCache::new(OpenStreetMap);
+        let _ = cache.update(CacheMessage::Load {
+            id: TileCoord::ZERO,
+        });
+        assert!(!cache.should_load(&TileCoord::ZERO));
+
+        let _ = cache.update(CacheMessage::LoadFailed {
+            id: TileCoord::ZERO,
+            error: TileError::Closed,
+            retries: 0,
+        });
+        assert!(cache.should_load(&TileCoord::ZERO));
+        assert!(cache.fetches.is_empty());
+        assert_eq!(cache.failure_count(), 0);
+    }
+
+    #[test]
+    fn missing_tiles_are_no_failures_while_offline() {
+        let mut cache = TileCache::new(OpenStreetMap);
+        cache.set_offline(true);
+        let _ = cache.update(CacheMessage::Load {
+            id: TileCoord::ZERO,
+        });
+        let _ = cache.update(CacheMessage::LoadFailed {
+            id: TileCoord::ZERO,
+            error: TileError::Offline,
+            retries: 0,
+        });
+        assert_eq!(cache.failure_count(), 0);
+
+        // The tile is requested again once online
+        cache.set_offline(false);
+        assert!(cache.should_load(&TileCoord::ZERO));
+    }
+
+    #[test]
+    fn outdated_tiles_in_use_are_refreshed() {
+        let mut cache = TileCache::builder(OpenStreetMap)
+            .refresh_after(Duration::from_secs(1))
+            .build();
+        let handle = Handle::from_rgba(1, 1, vec![0; 4]);
+        let id = TileCoord::new(1, 1, 2);
+        let _ = cache.update(CacheMessage::Loaded { id, handle });
+
+        let refreshing = |cache: &TileCache| cache.cache[&id].refreshing;
+        let _ = cache.update(CacheMessage::Maintain(Instant::now()));
+        assert!(!refreshing(&cache));
+
+        // Still in use, but outdated
+        let later = Instant::now() + Duration::from_millis(1500);
+        let _ = cache.update(CacheMessage::Maintain(later));
+        assert!(refreshing(&cache));
+
+        // The outdated image is kept when the refresh fails
+        let _ = cache.update(CacheMessage::LoadFailed {
+            id,
+            error: TileError::Timeout,
+            retries: 0,
+        });
+        assert!(!refreshing(&cache));
+        assert!(cache.is_loaded(&id));
+    }
+
+    #[test]
+    fn switching_time_refreshes_tiles_in_view() {
+        let source = crate::sources::TimedSource::new(
+            crate::sources::TemplateSource::new("https://example.com/{time}/{z}/{x}/{y}.png"),
+            "0900",
+        );
+        let mut cache = TileCache::new(source);
+        let handle = Handle::from_rgba(1, 1, vec![0; 4]);
+        let (shown, hidden) = (TileCoord::new(1, 1, 2), TileCoord::new(2, 1, 2));
+        for id in [shown, hidden] {
+            let _ = cache.update(CacheMessage::Loaded {
+                id,
+                handle: handle.clone(),
+            });
+        }
+
+        // The map has been still for a while, so neither tile was used recently
+        let _ = cache.update(CacheMessage::Visible { tiles: vec![shown] });
+        for id in [shown, hidden] {
+            cache.cache[&id]
+                .last_used
+                .set(Instant::now() - 2 * ALLOCATION_RETENTION);
+        }
+
+        let _ = cache.set_time("0900");
+        assert!(!cache.cache[&shown].refreshing);
+
+        // The old frame is still drawn while the new one loads
+        let _ = cache.set_time("1000");
+        assert!(cache.cache[&shown].refreshing);
+        assert!(cache.fetches.contains_key(&shown));
+        assert!(cache.is_loaded(&shown));
+        assert!(!cache.cache.contains_key(&hidden));
+    }
+
+    #[test]
+    fn replaced_sources_keep_drawing_until_refreshed() {
+        let mut cache = TileCache::new(OpenStreetMap);
+        let handle = Handle::from_rgba(1, 1, vec![0; 4]);
+        let id = TileCoord::new(1, 1, 2);
+        let _ = cache.update(CacheMessage::Loaded { id, handle });
+
+        let _ = cache.set_source(crate::sources::CartoDark(crate::sources::Scale::X1));
+        assert!(cache.cache[&id].refreshing);
+        assert!(cache.is_loaded(&id));
+
+        // Tiles of the old source which arrive late are dropped
+        let _ = cache.update(CacheMessage::LoadFailed {
+            id,
+            error: TileError::Closed,
+            retries: 0,
+        });
+        assert!(cache.cache[&id].refreshing);
+        assert_eq!(cache.recent_failures().count(), 0);
+
+        // Tiles in view of a map which has been still for a while are fetched again too
+        let idle = TileCoord::new(2, 1, 2);
+        let _ = cache.update(CacheMessage::Loaded {
+            id: idle,
+            handle: Handle::from_rgba(1, 1, vec![0; 4]),
+        });
+        let _ = cache.update(CacheMessage::Visible { tiles: vec![idle] });
+        cache.cache[&idle]
+            .last_used
+            .set(Instant::now() - 2 * ALLOCATION_RETENTION);
+        let _ = cache.set_source(OpenStreetMap);
+        assert!(cache.cache[&idle].refreshing);
+        assert!(cache.fetches.contains_key(&idle));
+
+        // Tiles laid out differently can not be drawn in place
+        let _ = cache.set_source(
+            crate::sources::TemplateSource::new("https://example.com/{z}/{x}/{y}.png")
+                .tile_size(512),
+        );
+        assert!(!cache.cache.contains_key(&id));
+    }
+
+    #[test]
+    fn least_recently_used_allocations_are_released() {
+        let policy = AllocationPolicy {
+            max_tiles: 2,
+            ..AllocationPolicy::default()
+        };
+        let now = Instant::now();
+        let ago = |secs| now - Duration::from_secs(secs);
+        let allocated = vec![
+            (ago(0), TileCoord::new(0, 0, 3), 1),
+            (ago(5), TileCoord::new(1, 0, 3), 1),
+            (ago(9), TileCoord::new(0, 0, 1), 1),
+            (ago(7), TileCoord::new(2, 0, 3), 1),
+        ];
+
+        // The lowest zoom levels are kept as a last resort
+        assert_eq!(
+            policy.release(allocated.clone(), now, false),
+            [TileCoord::new(2, 0, 3), TileCoord::new(1, 0, 3)]
+        );
+
+        // Visible tiles are kept even beyond the budget, unless asked not to
+        let visible = vec![(ago(0), TileCoord::new(0, 0, 3), 1); 3];
+        assert!(policy.release(visible.clone(), now, true).is_empty());
+        let strict = AllocationPolicy {
+            keep_visible: false,
+            ..policy
+        };
+        assert_eq!(strict.release(visible, now, false).len(), 1);
+    }
+
+    #[test]
+    fn tiles_outside_the_source_are_not_loaded() {
+        let source = crate::sources::TemplateSource::new("https://example.com/{z}/{x}/{y}.png")
+            .min_zoom(2)
+            .bounds(crate::GeoBounds::new(3.2, 50.7, 7.3, 53.6));
+        let cache = TileCache::new(source);
+
+        let tile_id = |zoom| crate::Geodetic::new(4.9, 52.4).as_mercator().tile_id(zoom);
+        assert!(!cache.should_load(&tile_id(1)));
+        assert!(cache.should_load(&tile_id(6)));
+        assert!(!cache.should_load(&crate::location::rome().as_mercator().tile_id(6)));
+    }
+
+    #[test]
+    fn fetches_of_hidden_tiles_are_cancelled() {
+        let mut cache = TileCache::new(OpenStreetMap);
+        let (kept, hidden) = (TileCoord::new(0, 0, 1), TileCoord::new(1, 0, 1));
+        let _ = cache.update(CacheMessage::Load { id: kept });
+        let _ = cache.update(CacheMessage::Load { id: hidden });
+
+        let _ = cache.update(CacheMessage::Visible { tiles: vec![kept] });
+        assert!(!cache.should_load(&kept));
+        assert!(cache.should_load(&hidden));
+        assert_eq!(cache.fetches.keys().collect::<Vec<_>>(), [&kept]);
+    }
+
+    #[test]
+    #[cfg(feature = "http")]
+    fn regions_are_downloaded_after_replacing_the_source() {
+        let dir = std::env::temp_dir().join(format!("slippery-download-{}", std::process::id()));
+        let mut cache = TileCache::builder(OpenStreetMap)
+            .http_client(TileServer)
+            .disk_cache(&dir)
+            .build();
+
+        let source = crate::sources::CartoDark(crate::sources::Scale::X1);
+        let source_dir = |source: &dyn Source| dir.join(crate::http_fetcher::source_dir(source));
+        let carto_dir = source_dir(&source);
+        let _ = cache.set_source(source);
+
+        let bounds = GeoBounds::new(3.2, 50.7, 7.3, 53.6);
+        let progress = run(cache.download_region(bounds, 0..=1));
+        assert!(matches!(
+            progress.last(),
+            Some(CacheMessage::DownloadProgress {
+                done: 2,
+                failed: 0,
+                total: 2
+            })
+        ));
+
+        // The tiles of the new source are kept apart from those of the original one
+        assert!(carto_dir.join("0/0/0").exists());
+        assert!(!source_dir(&OpenStreetMap).exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "http")]
+    fn downloads_without_disk_cache_fail() {
+        let cache = TileCache::builder(OpenStreetMap)
+            .http_client(TileServer)
+            .build();
+
+        let bounds = GeoBounds::new(3.2, 50.7, 7.3, 53.6);
+        let progress = run(cache.download_region(bounds, 0..=1));
+        assert!(matches!(
+            progress[..],
+            [CacheMessage::DownloadProgress {
+                done: 2,
+                failed: 2,
+                total: 2
+            }]
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "http")]
+    fn hidpi_tiles_from_threshold() {
+        let mut cache = TileCache::new(crate::sources::CartoLight(crate::sources::Scale::X1));
+        let handle = Handle::from_rgba(1, 1, vec![0; 4]);
+        let id = TileCoord::new(1, 1, 2);
+        let _ = cache.update(CacheMessage::Loaded { id, handle });
+        let _ = cache.update(CacheMessage::Visible { tiles: vec![id] });
+
+        // Fractional scaling is sharp enough with regular tiles
+        let _ = cache.update(CacheMessage::ScaleFactor { factor: 1.25 });
+        assert!(cache.is_loaded(&id));
+
+        // The tiles in view are fetched again at the higher density, and drawn until
+        // replaced, even when the map has been still for a while
+        cache.cache[&id]
+            .last_used
+            .set(Instant::now() - 2 * ALLOCATION_RETENTION);
+        let _ = cache.update(CacheMessage::ScaleFactor { factor: 2.0 });
+        assert!(cache.is_loaded(&id));
+        assert!(cache.cache[&id].refreshing);
+        assert!(cache.fetches.contains_key(&id));
     }
 }
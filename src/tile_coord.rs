@@ -31,6 +31,13 @@ impl TileCoord {
         }
     }
 
+    /// Create a tile without limiting it to the tile grid of its zoom level, for grids
+    /// which are not square.
+    #[cfg(feature = "decode")]
+    pub(crate) const fn new_unclamped(x: u32, y: u32, zoom: u8) -> Self {
+        TileCoord { x, y, zoom }
+    }
+
     pub fn x(&self) -> u32 {
         self.x
     }
@@ -48,11 +55,7 @@ impl TileCoord {
     }
 
     pub fn to_mercator(&self) -> Mercator {
-        let total_tiles = 2u32.pow(self.zoom as u32) as f64;
-        Mercator::new(
-            (self.x as f64 / total_tiles) * 2.0 - 1.0,
-            (self.y as f64 / total_tiles) * 2.0 - 1.0,
-        )
+        grid_corner(self.x, self.y, self.zoom)
     }
 
     /// Get the parent (lower zoom) for this tile.
@@ -127,3 +130,14 @@ impl TileCoord {
         [self.north(), self.east(), self.south(), self.west()]
     }
 }
+
+/// The top left corner of the tile at some position in the grid of a zoom level. This also
+/// gives the corners of tiles beyond the last row or column, which are the far edges of the
+/// tiles before them.
+pub(crate) fn grid_corner(x: u32, y: u32, zoom: u8) -> Mercator {
+    let total_tiles = 2u32.pow(zoom as u32) as f64;
+    Mercator::new(
+        (x as f64 / total_tiles) * 2.0 - 1.0,
+        (y as f64 / total_tiles) * 2.0 - 1.0,
+    )
+}
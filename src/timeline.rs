@@ -0,0 +1,349 @@
+//! Animation of time-dimension tile sources, such as weather radar, where every timestamp
+//! is a separate [`Source`] backed by its own [`TileCache`].
+
+use std::time::Duration;
+
+use iced::time::Instant;
+use iced::widget::canvas::{Frame, Image};
+use iced::widget::{button, row, slider, text};
+use iced::{Element, Length, Subscription, Task, alignment};
+use iced_core::image::FilterMethod;
+
+use crate::{CacheMessage, Projector, TileCache, sources::Source};
+
+/// The playback speeds cycled through by the speed button of [`Timeline::controls`],
+/// in frames per second.
+const SPEEDS: [f32; 4] = [0.5, 1.0, 2.0, 4.0];
+
+#[derive(Debug, Clone)]
+pub enum TimelineMessage {
+    Play,
+    Pause,
+    /// Set the playback speed in frames per second.
+    SetSpeed(f32),
+    /// Jump to a (fractional) frame position.
+    Seek(f32),
+    /// Advance the playback, produced by [`Timeline::subscription`].
+    Tick(Instant),
+    /// A message for the tile cache of a single frame.
+    Cache {
+        frame: usize,
+        message: CacheMessage,
+    },
+}
+
+#[derive(Debug)]
+struct TimelineFrame {
+    label: String,
+    cache: TileCache,
+}
+
+/// Plays back a sequence of tile sources, such as the timestamps of a weather radar.
+///
+/// Glue [`Timeline::update`] and [`Timeline::subscription`] into the application, and call
+/// [`Timeline::request`] whenever the viewpoint changes. The upcoming frames are fetched
+/// ahead of time, such that playback does not stall on loading tiles.
+#[derive(Debug)]
+pub struct Timeline {
+    frames: Vec<TimelineFrame>,
+    position: f32,
+    playing: bool,
+    speed: f32,
+    prefetch: usize,
+    fade: f32,
+    last_tick: Option<Instant>,
+    projector: Option<Projector>,
+}
+
+impl Timeline {
+    /// Create a timeline from labeled sources, in chronological order.
+    pub fn new<S: Source + 'static>(frames: impl IntoIterator<Item = (String, S)>) -> Self {
+        Self {
+            frames: frames
+                .into_iter()
+                .map(|(label, source)| TimelineFrame {
+                    label,
+                    cache: TileCache::new(source),
+                })
+                .collect(),
+            position: 0.0,
+            playing: false,
+            speed: 1.0,
+            prefetch: 3,
+            fade: 0.5,
+            last_tick: None,
+            projector: None,
+        }
+    }
+
+    /// The number of upcoming frames to fetch ahead of the current one.
+    pub fn prefetch(mut self, prefetch: usize) -> Self {
+        self.prefetch = prefetch;
+        self
+    }
+
+    /// The fraction of each frame spent cross-fading into the next one, from `0.0` for hard
+    /// cuts to `1.0` for a continuous blend.
+    pub fn fade(mut self, fade: f32) -> Self {
+        self.fade = fade.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    /// Playback speed in frames per second.
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    /// The current (fractional) frame position.
+    pub fn position(&self) -> f32 {
+        self.position
+    }
+
+    /// The label of the current frame.
+    pub fn label(&self) -> Option<&str> {
+        let (current, _, _) = self.blend()?;
+        Some(&self.frames[current].label)
+    }
+
+    /// The frames to draw at the current position, as `(current, next, weight of next)`.
+    fn blend(&self) -> Option<(usize, usize, f32)> {
+        if self.frames.is_empty() {
+            return None;
+        }
+
+        let current = (self.position.floor() as usize).min(self.frames.len() - 1);
+        let next = (current + 1) % self.frames.len();
+
+        // Hold the current frame, and only blend towards the end of it
+        let fraction = self.position.fract();
+        let weight = if self.fade > 0.0 {
+            ((fraction - (1.0 - self.fade)) / self.fade).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+
+        Some((current, next, weight))
+    }
+
+    /// Request the tiles of the current and upcoming frames for the given view.
+    pub fn request(&mut self, projector: Projector) -> Task<TimelineMessage> {
+        self.projector = Some(projector);
+        self.request_upcoming()
+    }
+
+    fn request_upcoming(&self) -> Task<TimelineMessage> {
+        let (Some(projector), Some((current, _, _))) = (&self.projector, self.blend()) else {
+            return Task::none();
+        };
+
+        let frames = self.frames.len();
+        Task::batch(
+            (current..=current + self.prefetch.min(frames - 1))
+                .map(|index| index % frames)
+                .flat_map(|index| {
+                    let cache = &self.frames[index].cache;
+                    let zoom = projector.tile_zoom(cache.tile_size(), cache.max_zoom());
+
+                    projector
+                        .tiles_in_view(zoom)
+                        .into_iter()
+                        .filter(move |id| cache.should_load(id))
+                        .map(move |id| {
+                            Task::done(TimelineMessage::Cache {
+                                frame: index,
+                                message: CacheMessage::Load { id },
+                            })
+                        })
+                }),
+        )
+    }
+
+    pub fn update(&mut self, message: TimelineMessage) -> Task<TimelineMessage> {
+        let previous = self.position.floor();
+
+        match message {
+            TimelineMessage::Play => {
+                self.playing = true;
+                self.last_tick = None;
+            }
+            TimelineMessage::Pause => {
+                self.playing = false;
+            }
+            TimelineMessage::SetSpeed(speed) => {
+                self.speed = speed.max(0.0);
+            }
+            TimelineMessage::Seek(position) => {
+                let last = self.frames.len().saturating_sub(1) as f32;
+                self.position = position.clamp(0.0, last);
+            }
+            TimelineMessage::Tick(now) => {
+                if self.playing && !self.frames.is_empty() {
+                    if let Some(last_tick) = self.last_tick {
+                        let elapsed = now.duration_since(last_tick).as_secs_f32();
+                        self.position = (self.position + elapsed * self.speed)
+                            .rem_euclid(self.frames.len() as f32);
+                    }
+                    self.last_tick = Some(now);
+                }
+            }
+            TimelineMessage::Cache { frame, message } => {
+                let Some(timeline_frame) = self.frames.get_mut(frame) else {
+                    return Task::none();
+                };
+
+                return timeline_frame
+                    .cache
+                    .update(message)
+                    .map(move |message| TimelineMessage::Cache { frame, message });
+            }
+        }
+
+        // Keep the upcoming frames loaded as the playback moves on
+        if self.position.floor() != previous {
+            return self.request_upcoming();
+        }
+
+        Task::none()
+    }
+
+    /// Advances the playback while playing.
+    pub fn subscription(&self) -> Subscription<TimelineMessage> {
+        if self.playing {
+            iced::time::every(Duration::from_millis(30)).map(TimelineMessage::Tick)
+        } else {
+            Subscription::none()
+        }
+    }
+
+    /// Play/pause and speed buttons, along with a scrub bar for seeking.
+    pub fn controls(&self) -> Element<'_, TimelineMessage> {
+        let (label, toggle) = if self.playing {
+            ("Pause", TimelineMessage::Pause)
+        } else {
+            ("Play", TimelineMessage::Play)
+        };
+
+        let next_speed = SPEEDS
+            .iter()
+            .copied()
+            .find(|&speed| speed > self.speed)
+            .unwrap_or(SPEEDS[0]);
+
+        let last = self.frames.len().saturating_sub(1) as f32;
+
+        row![
+            button(text(label)).on_press(toggle),
+            button(text(format!("{} fps", self.speed)))
+                .on_press(TimelineMessage::SetSpeed(next_speed)),
+            slider(0.0..=last, self.position, TimelineMessage::Seek)
+                .step(0.01)
+                .width(Length::Fill),
+            text(self.label().unwrap_or_default()),
+        ]
+        .spacing(10)
+        .align_y(alignment::Vertical::Center)
+        .into()
+    }
+
+    pub fn layer(&self) -> TimelineLayer<'_> {
+        TimelineLayer {
+            timeline: self,
+            opacity: 0.7,
+        }
+    }
+}
+
+/// Draws the current frame of a [`Timeline`], cross-fading into the next one.
+#[derive(Debug, Clone, Copy)]
+pub struct TimelineLayer<'a> {
+    timeline: &'a Timeline,
+    opacity: f32,
+}
+
+impl TimelineLayer<'_> {
+    pub fn opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity;
+        self
+    }
+
+    pub fn draw(&self, projector: &Projector, frame: &mut Frame<iced::Renderer>) {
+        let Some((current, next, weight)) = self.timeline.blend() else {
+            return;
+        };
+
+        let current = &self.timeline.frames[current].cache;
+        let next = &self.timeline.frames[next].cache;
+
+        let zoom = projector.tile_zoom(current.tile_size(), current.max_zoom());
+        for tile_id in projector.tiles_in_view(zoom) {
+            let bounds = projector.tile_bounds(&tile_id);
+
+            // Keep showing the current frame until the next one has loaded
+            let next_handle = next.get_loaded(&tile_id).filter(|_| weight > 0.0);
+            let weight = if next_handle.is_some() { weight } else { 0.0 };
+
+            if let Some(handle) = current.get_loaded(&tile_id) {
+                frame.draw_image(
+                    bounds,
+                    Image::new(handle)
+                        .filter_method(FilterMethod::Linear)
+                        .opacity(self.opacity * (1.0 - weight)),
+                );
+            }
+
+            if let Some(handle) = next_handle {
+                frame.draw_image(
+                    bounds,
+                    Image::new(handle)
+                        .filter_method(FilterMethod::Linear)
+                        .opacity(self.opacity * weight),
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sources::OpenStreetMap;
+
+    fn timeline(frames: usize) -> Timeline {
+        Timeline::new((0..frames).map(|i| (i.to_string(), OpenStreetMap)))
+    }
+
+    #[test]
+    fn cross_fade_weights() {
+        let mut timeline = timeline(3);
+
+        // The first half of each frame is held
+        timeline.position = 0.25;
+        assert_eq!(timeline.blend(), Some((0, 1, 0.0)));
+
+        timeline.position = 0.75;
+        assert_eq!(timeline.blend(), Some((0, 1, 0.5)));
+
+        // The last frame fades back into the first
+        timeline.position = 2.9;
+        let (current, next, weight) = timeline.blend().unwrap();
+        assert_eq!((current, next), (2, 0));
+        assert!((weight - 0.8).abs() < 1e-5);
+    }
+
+    #[test]
+    fn playback_wraps_around() {
+        let mut timeline = timeline(2);
+        let start = Instant::now();
+
+        let _ = timeline.update(TimelineMessage::Play);
+        let _ = timeline.update(TimelineMessage::Tick(start));
+        let _ = timeline.update(TimelineMessage::Tick(start + Duration::from_millis(2500)));
+
+        assert!((timeline.position() - 0.5).abs() < 1e-5);
+        assert_eq!(timeline.label(), Some("0"));
+    }
+}
@@ -0,0 +1,379 @@
+//! Live feeds of moving objects, such as aircraft, buses or ships, which report their
+//! position at irregular intervals.
+//!
+//! Between reports, the position is extrapolated from the last known speed and heading
+//! (dead reckoning), and corrections from new reports are blended in smoothly. Objects
+//! which have not reported for a while are faded out, and eventually removed.
+
+use std::{collections::HashMap, hash::Hash, time::Duration};
+
+use iced::time::Instant;
+use iced::widget::canvas::{self, Frame, Image, Path};
+use iced::{Color, Point, Radians, Rectangle, Size, Vector, alignment};
+use iced_core::image::Handle;
+
+use crate::legend::{LegendEntry, Symbol};
+use crate::{Geodetic, Mercator, Projector};
+
+/// A single position report of a vehicle.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VehicleReport {
+    pub position: Geodetic,
+    /// Heading in degrees, clockwise from north.
+    pub heading: f64,
+    /// Speed over ground in meters per second.
+    pub speed: f64,
+    pub label: Option<String>,
+}
+
+/// The estimated state of a vehicle at some point in time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VehicleState<'a> {
+    pub position: Geodetic,
+    /// Heading in degrees, clockwise from north.
+    pub heading: f64,
+    pub label: Option<&'a str>,
+    /// Ranges from `1.0` for recently updated vehicles down to `0.0` when about to expire.
+    pub freshness: f32,
+}
+
+#[derive(Debug, Clone)]
+struct Track {
+    report: VehicleReport,
+    received: Instant,
+    /// The estimated position and heading when the report was received, which is blended
+    /// towards the new report to avoid jumps.
+    correction_from: Option<(Geodetic, f64)>,
+}
+
+/// Keeps track of the vehicles of a live feed, identified by some key `K`.
+#[derive(Debug)]
+pub struct VehicleFeed<K> {
+    tracks: HashMap<K, Track>,
+    max_extrapolation: Duration,
+    smoothing: Duration,
+    stale_after: Duration,
+    expire_after: Duration,
+}
+
+impl<K> Default for VehicleFeed<K> {
+    fn default() -> Self {
+        Self {
+            tracks: HashMap::new(),
+            max_extrapolation: Duration::from_secs(30),
+            smoothing: Duration::from_secs(1),
+            stale_after: Duration::from_secs(30),
+            expire_after: Duration::from_secs(120),
+        }
+    }
+}
+
+impl<K: Hash + Eq> VehicleFeed<K> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stop extrapolating the position of a vehicle after this long without reports.
+    pub fn max_extrapolation(mut self, max_extrapolation: Duration) -> Self {
+        self.max_extrapolation = max_extrapolation;
+        self
+    }
+
+    /// The time over which a correction from a new report is blended in.
+    pub fn smoothing(mut self, smoothing: Duration) -> Self {
+        self.smoothing = smoothing;
+        self
+    }
+
+    /// Start fading out vehicles after this long without reports.
+    pub fn stale_after(mut self, stale_after: Duration) -> Self {
+        self.stale_after = stale_after;
+        self
+    }
+
+    /// Remove vehicles after this long without reports, see [`VehicleFeed::prune`].
+    pub fn expire_after(mut self, expire_after: Duration) -> Self {
+        self.expire_after = expire_after;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.tracks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tracks.is_empty()
+    }
+
+    /// Insert a new position report for a vehicle, received at `now`.
+    pub fn report(&mut self, id: K, report: VehicleReport, now: Instant) {
+        let correction_from = self
+            .tracks
+            .get(&id)
+            .map(|track| self.estimate(track, now))
+            .map(|state| (state.position, state.heading));
+
+        self.tracks.insert(
+            id,
+            Track {
+                report,
+                received: now,
+                correction_from,
+            },
+        );
+    }
+
+    pub fn remove(&mut self, id: &K) {
+        self.tracks.remove(id);
+    }
+
+    /// Remove all vehicles which have not reported within the expiry time.
+    pub fn prune(&mut self, now: Instant) {
+        let expire_after = self.expire_after;
+        self.tracks
+            .retain(|_, track| now.saturating_duration_since(track.received) < expire_after);
+    }
+
+    /// The estimated state of a vehicle at `now`.
+    pub fn get(&self, id: &K, now: Instant) -> Option<VehicleState<'_>> {
+        self.tracks.get(id).map(|track| self.estimate(track, now))
+    }
+
+    /// The estimated state of all vehicles at `now`.
+    pub fn iter(&self, now: Instant) -> impl Iterator<Item = (&K, VehicleState<'_>)> {
+        self.tracks
+            .iter()
+            .map(move |(id, track)| (id, self.estimate(track, now)))
+    }
+
+    pub fn layer(&self) -> VehicleLayer<'_, K> {
+        VehicleLayer {
+            feed: self,
+            style: VehicleStyle::default(),
+        }
+    }
+
+    fn estimate<'a>(&self, track: &'a Track, now: Instant) -> VehicleState<'a> {
+        let age = now.saturating_duration_since(track.received);
+
+        // Dead reckoning from the last report
+        let elapsed = age.min(self.max_extrapolation).as_secs_f64();
+        let report = &track.report;
+        let mut position = report
+            .position
+            .destination(report.heading, report.speed * elapsed);
+        let mut heading = report.heading;
+
+        // Blend in the correction from the previous estimate
+        if let Some((from_position, from_heading)) = track.correction_from
+            && age < self.smoothing
+        {
+            let t = age.as_secs_f64() / self.smoothing.as_secs_f64();
+            let (a, b) = (from_position.as_mercator(), position.as_mercator());
+            position = Mercator::new(
+                a.east_x() + (b.east_x() - a.east_x()) * t,
+                a.south_y() + (b.south_y() - a.south_y()) * t,
+            )
+            .as_geodetic();
+
+            // Turn the shortest way around
+            let turn = (heading - from_heading + 540.0).rem_euclid(360.0) - 180.0;
+            heading = (from_heading + turn * t).rem_euclid(360.0);
+        }
+
+        let freshness = match age.checked_sub(self.stale_after) {
+            None => 1.0,
+            Some(stale) => {
+                let fade = self.expire_after.saturating_sub(self.stale_after);
+                1.0 - (stale.as_secs_f32() / fade.as_secs_f32().max(f32::EPSILON)).min(1.0)
+            }
+        };
+
+        VehicleState {
+            position,
+            heading,
+            label: report.label.as_deref(),
+            freshness,
+        }
+    }
+}
+
+/// The visual appearance of a [`VehicleLayer`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct VehicleStyle {
+    pub color: Color,
+    /// Size of the icon in pixels.
+    pub size: f32,
+    /// An image pointing north, which is drawn instead of the default arrow.
+    pub icon: Option<Handle>,
+    /// Size of the labels, or `None` to not draw labels.
+    pub label_size: Option<f32>,
+    /// The opacity of vehicles which are just about to expire.
+    pub stale_opacity: f32,
+}
+
+impl Default for VehicleStyle {
+    fn default() -> Self {
+        Self {
+            color: Color::from_rgb(0.9, 0.35, 0.1),
+            size: 18.0,
+            icon: None,
+            label_size: Some(12.0),
+            stale_opacity: 0.2,
+        }
+    }
+}
+
+impl VehicleStyle {
+    /// An entry for a [`Legend`](crate::legend::Legend) explaining the vehicles.
+    pub fn legend(&self, label: impl Into<String>) -> LegendEntry {
+        let symbol = Symbol::Point {
+            color: self.color,
+            radius: self.size / 2.0,
+        };
+        LegendEntry::new(symbol, label)
+    }
+}
+
+/// Draws the vehicles of a [`VehicleFeed`] as icons rotated to their heading.
+///
+/// The positions are estimated at the time of drawing, so the application should request
+/// redraws regularly, for example through a [`iced::time::every`] subscription.
+#[derive(Debug, Clone)]
+pub struct VehicleLayer<'a, K> {
+    feed: &'a VehicleFeed<K>,
+    style: VehicleStyle,
+}
+
+impl<K: Hash + Eq> VehicleLayer<'_, K> {
+    pub fn style(mut self, style: VehicleStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    pub fn draw(&self, projector: &Projector, frame: &mut Frame<iced::Renderer>) {
+        let viewport = projector.bounds.expand(self.style.size);
+        let size = self.style.size;
+
+        // A simple arrow pointing north, centered on the origin
+        let arrow = Path::new(|builder| {
+            builder.move_to(Point::new(0.0, -size / 2.0));
+            builder.line_to(Point::new(size * 0.4, size / 2.0));
+            builder.line_to(Point::new(0.0, size * 0.25));
+            builder.line_to(Point::new(-size * 0.4, size / 2.0));
+            builder.close();
+        });
+
+        for (_, vehicle) in self.feed.iter(Instant::now()) {
+            let position = projector.geodetic_into_screen_space(vehicle.position);
+            if !viewport.contains(position) {
+                continue;
+            }
+
+            let opacity =
+                self.style.stale_opacity + (1.0 - self.style.stale_opacity) * vehicle.freshness;
+            let rotation = Radians(vehicle.heading.to_radians() as f32);
+
+            match &self.style.icon {
+                Some(icon) => frame.draw_image(
+                    Rectangle::new(
+                        position - Vector::new(size / 2.0, size / 2.0),
+                        Size::new(size, size),
+                    ),
+                    Image::new(icon.clone()).rotation(rotation).opacity(opacity),
+                ),
+                None => frame.with_save(|frame| {
+                    frame.translate(Vector::new(position.x, position.y));
+                    frame.rotate(rotation);
+                    frame.fill(&arrow, self.style.color.scale_alpha(opacity));
+                    frame.stroke(
+                        &arrow,
+                        canvas::Stroke::default()
+                            .with_color(Color::WHITE.scale_alpha(opacity))
+                            .with_width(1.5),
+                    );
+                }),
+            }
+
+            if let (Some(label), Some(label_size)) = (vehicle.label, self.style.label_size) {
+                frame.fill_text(canvas::Text {
+                    content: label.to_string(),
+                    position: position + Vector::new(size * 0.75, 0.0),
+                    color: Color::BLACK.scale_alpha(opacity),
+                    size: label_size.into(),
+                    align_y: alignment::Vertical::Center,
+                    ..Default::default()
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::location;
+
+    fn report(position: Geodetic, heading: f64, speed: f64) -> VehicleReport {
+        VehicleReport {
+            position,
+            heading,
+            speed,
+            label: None,
+        }
+    }
+
+    #[test]
+    fn dead_reckoning() {
+        let start = Instant::now();
+        let mut feed = VehicleFeed::new().max_extrapolation(Duration::from_secs(10));
+        feed.report(1, report(location::paris(), 90.0, 10.0), start);
+
+        // Moves east at 10 m/s
+        let state = feed.get(&1, start + Duration::from_secs(5)).unwrap();
+        approx::assert_relative_eq!(
+            state.position.distance_to(location::paris()),
+            50.0,
+            max_relative = 1e-3
+        );
+        assert!(state.position.longitude() > location::paris().longitude());
+
+        // Stops extrapolating after a while
+        let state = feed.get(&1, start + Duration::from_secs(60)).unwrap();
+        approx::assert_relative_eq!(
+            state.position.distance_to(location::paris()),
+            100.0,
+            max_relative = 1e-3
+        );
+    }
+
+    #[test]
+    fn smoothing_and_staleness() {
+        let start = Instant::now();
+        let mut feed = VehicleFeed::new()
+            .smoothing(Duration::from_secs(2))
+            .stale_after(Duration::from_secs(10))
+            .expire_after(Duration::from_secs(20));
+
+        feed.report("bus", report(location::paris(), 0.0, 0.0), start);
+        let moved = location::paris().destination(0.0, 100.0);
+        feed.report("bus", report(moved, 90.0, 0.0), start);
+
+        // The correction starts out at the previous estimate, turning the shortest way
+        let state = feed.get(&"bus", start + Duration::from_secs(1)).unwrap();
+        approx::assert_relative_eq!(
+            state.position.distance_to(location::paris()),
+            50.0,
+            max_relative = 1e-2
+        );
+        approx::assert_relative_eq!(state.heading, 45.0);
+        assert_eq!(state.freshness, 1.0);
+
+        let state = feed.get(&"bus", start + Duration::from_secs(15)).unwrap();
+        assert!(state.position.distance_to(moved) < 1e-3);
+        assert_eq!(state.freshness, 0.5);
+
+        feed.prune(start + Duration::from_secs(25));
+        assert!(feed.is_empty());
+    }
+}
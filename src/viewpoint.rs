@@ -1,6 +1,6 @@
-use iced::{Point, Rectangle, Vector};
+use iced::{Point, Rectangle, Size, Vector};
 
-use crate::{Geodetic, Mercator, Zoom};
+use crate::{Geodetic, Mercator, Zoom, map_widget::BASE_SIZE};
 
 /// The viewpoint of the [`MapWidget`] consists of a coordinate of
 /// the center of the viewport, and a zoom level.
@@ -13,12 +13,12 @@ pub struct Viewpoint {
 impl Viewpoint {
     /// Move the viewpoint to a different location defined by the a [`Mercator`] coordinate
     pub fn move_to_mercator(&mut self, mercator: Mercator) {
-        self.position = mercator;
+        self.position = mercator.clamped();
     }
 
     /// Move the viewpoint to a different location defined by the a [`Geodetic`] coordinate
     pub fn move_to_geodetic(&mut self, geodetic: Geodetic) {
-        self.position = geodetic.as_mercator();
+        self.position = geodetic.as_mercator().clamped();
     }
 
     /// Get the viewpoint position in the pixel space representation
@@ -62,4 +62,47 @@ impl Viewpoint {
     pub fn zoom_on_center(&mut self, zoom_amount: f64) {
         self.zoom.zoom_by(zoom_amount);
     }
+
+    /// Keep the world within the viewport of the given size. Along each axis where the world
+    /// is smaller than the viewport, it is centered, and otherwise it is kept from leaving
+    /// gaps at the edges of the viewport.
+    pub fn fit_world(&mut self, size: Size) {
+        // Half the viewport size, relative to half of the world size
+        let world = 2f64.powf(self.zoom.f64()) * BASE_SIZE as f64;
+        let fit = |position: f64, viewport: f32| {
+            let half = viewport as f64 / world;
+            if half >= 1.0 {
+                0.0
+            } else {
+                position.clamp(half - 1.0, 1.0 - half)
+            }
+        };
+
+        self.position = Mercator::new(
+            fit(self.position.east_x(), size.width),
+            fit(self.position.south_y(), size.height),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fit_world() {
+        let mut viewpoint = Viewpoint {
+            position: Mercator::new(0.9, -0.2),
+            zoom: Zoom::try_from(1.0).unwrap(),
+        };
+
+        // The world is 1024 pixels wide, so it is centered horizontally only
+        viewpoint.fit_world(Size::new(1280.0, 512.0));
+        assert_eq!(viewpoint.position, Mercator::new(0.0, -0.2));
+
+        // Gaps at the top edge are not allowed
+        viewpoint.position = Mercator::new(0.9, -0.9);
+        viewpoint.fit_world(Size::new(512.0, 512.0));
+        assert_eq!(viewpoint.position, Mercator::new(0.5, -0.5));
+    }
 }